@@ -0,0 +1,302 @@
+//! GOOSE (Generic Object Oriented Substation Event) implementation.
+//!
+//! The GOOSE messages are published directly over ethernet, see
+//! [`frame`] for the frame codec. The messages can also be read from pcap
+//! captures, see [`pcap`]. The [`subscriber`] supervises the received
+//! messages and the [`publisher`] sends them.
+
+use num_traits::cast::ToPrimitive as _;
+use rasn::{
+	ber,
+	error::strings::PermittedAlphabetError,
+	types::{Integer, VisibleString},
+};
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
+use time::OffsetDateTime;
+
+use crate::{
+	goose::{
+		ans1::goose::iec61850::{GOOSEpdu, IECGoosePdu},
+		frame::{EthernetFrame, FrameError, VlanTag},
+		pcap::PcapError,
+	},
+	iec61850::data::{Iec61850Data, Iec61850DataError},
+	mms::ans1::mms::asn1::Data,
+};
+
+pub mod ans1;
+pub mod frame;
+pub mod pcap;
+pub mod publisher;
+#[cfg(target_os = "linux")]
+pub mod socket;
+pub mod subscriber;
+
+/// The EtherType of the GOOSE messages.
+pub const GOOSE_ETHER_TYPE: u16 = 0x88b8;
+
+/// A GOOSE message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GooseMessage {
+	/// The reference of the GOOSE control block, e.g. `LD/LLN0$GO$gcb1`.
+	pub gocb_ref: String,
+	/// The time in milliseconds the message is valid for.
+	pub time_allowed_to_live: u32,
+	/// The reference of the published dataset.
+	pub dataset: String,
+	/// The id of the GOOSE control block.
+	pub go_id: Option<String>,
+	/// The time of the last state change.
+	pub timestamp: OffsetDateTime,
+	/// The state number, incremented on every state change.
+	pub st_num: u32,
+	/// The sequence number, incremented on every retransmission.
+	pub sq_num: u32,
+	/// Whether the message is a simulated one.
+	pub simulation: bool,
+	/// The configuration revision of the GOOSE control block.
+	pub config_rev: u32,
+	/// Whether the GOOSE control block needs commissioning.
+	pub needs_commissioning: bool,
+	/// The number of entries of the dataset.
+	pub number_of_entries: u32,
+	/// The values of the dataset entries.
+	pub data: Vec<Iec61850Data>,
+}
+
+impl TryFrom<IECGoosePdu> for GooseMessage {
+	type Error = GooseError;
+	fn try_from(value: IECGoosePdu) -> Result<Self, Self::Error> {
+		Ok(Self {
+			gocb_ref: value.gocb_ref.to_string(),
+			time_allowed_to_live: to_u32(&value.time_allowedto_live, "time_allowed_to_live")?,
+			dataset: value.dat_set.to_string(),
+			go_id: value.go_id.map(|go_id| go_id.to_string()),
+			timestamp: value.t.try_into().context(InvalidData)?,
+			st_num: to_u32(&value.st_num, "st_num")?,
+			sq_num: to_u32(&value.sq_num, "sq_num")?,
+			simulation: value.simulation,
+			config_rev: to_u32(&value.conf_rev, "config_rev")?,
+			needs_commissioning: value.nds_com,
+			number_of_entries: to_u32(&value.num_dat_set_entries, "number_of_entries")?,
+			data: value
+				.all_data
+				.into_iter()
+				.map(TryInto::try_into)
+				.collect::<Result<_, Iec61850DataError>>()
+				.context(InvalidData)?,
+		})
+	}
+}
+
+impl TryFrom<GooseMessage> for IECGoosePdu {
+	type Error = GooseError;
+	fn try_from(value: GooseMessage) -> Result<Self, Self::Error> {
+		Ok(Self::new(
+			to_visible_string(&value.gocb_ref, "gocb_ref")?,
+			value.time_allowed_to_live.into(),
+			to_visible_string(&value.dataset, "dataset")?,
+			value.go_id.as_deref().map(|go_id| to_visible_string(go_id, "go_id")).transpose()?,
+			value.timestamp.try_into().context(InvalidData)?,
+			value.st_num.into(),
+			value.sq_num.into(),
+			value.simulation,
+			value.config_rev.into(),
+			value.needs_commissioning,
+			value.number_of_entries.into(),
+			value
+				.data
+				.into_iter()
+				.map(TryInto::try_into)
+				.collect::<Result<Vec<Data>, Iec61850DataError>>()
+				.context(InvalidData)?,
+		))
+	}
+}
+
+impl GooseMessage {
+	/// Decode a GOOSE message from the bytes of a GOOSE PDU.
+	pub fn decode(bytes: &[u8]) -> Result<Self, GooseError> {
+		let GOOSEpdu::goosePdu(pdu) = ber::decode(bytes).context(Decode)?;
+		pdu.try_into()
+	}
+
+	/// Encode the GOOSE message into the bytes of a GOOSE PDU.
+	pub fn encode(self) -> Result<Vec<u8>, GooseError> {
+		ber::encode(&GOOSEpdu::goosePdu(self.try_into()?)).context(Encode)
+	}
+}
+
+/// A GOOSE message with the ethernet header it was sent with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GooseFrame {
+	/// The destination MAC address.
+	pub destination: [u8; 6],
+	/// The source MAC address.
+	pub source: [u8; 6],
+	/// The VLAN tag, if present.
+	pub vlan: Option<VlanTag>,
+	/// The application id.
+	pub app_id: u16,
+	/// The GOOSE message.
+	pub message: GooseMessage,
+}
+
+impl GooseFrame {
+	/// Decode a GOOSE frame from the bytes of an ethernet frame.
+	pub fn decode(bytes: &[u8]) -> Result<Self, GooseError> {
+		EthernetFrame::decode(bytes).context(Frame)?.try_into()
+	}
+
+	/// Encode the GOOSE frame into the bytes of an ethernet frame.
+	pub fn encode(self) -> Result<Vec<u8>, GooseError> {
+		EthernetFrame::try_from(self)?.encode().context(Frame)
+	}
+}
+
+impl TryFrom<EthernetFrame> for GooseFrame {
+	type Error = GooseError;
+	fn try_from(value: EthernetFrame) -> Result<Self, Self::Error> {
+		ensure!(value.ether_type == GOOSE_ETHER_TYPE, NotGoose { ether_type: value.ether_type });
+		let mut message = GooseMessage::decode(&value.apdu)?;
+		// Since edition 2 the simulation can also be signaled in the reserved 1
+		// field.
+		message.simulation |= value.simulation();
+		Ok(Self {
+			destination: value.destination,
+			source: value.source,
+			vlan: value.vlan,
+			app_id: value.app_id,
+			message,
+		})
+	}
+}
+
+impl TryFrom<GooseFrame> for EthernetFrame {
+	type Error = GooseError;
+	fn try_from(value: GooseFrame) -> Result<Self, Self::Error> {
+		let simulation = value.message.simulation;
+		let mut frame = Self {
+			destination: value.destination,
+			source: value.source,
+			vlan: value.vlan,
+			ether_type: GOOSE_ETHER_TYPE,
+			app_id: value.app_id,
+			reserved1: 0,
+			reserved2: 0,
+			apdu: value.message.encode()?,
+		};
+		frame.set_simulation(simulation);
+		Ok(frame)
+	}
+}
+
+/// Read the GOOSE frames of a pcap capture. The frames that are not GOOSE
+/// messages are skipped.
+pub fn read_pcap(bytes: &[u8]) -> Result<Vec<GooseFrame>, GooseError> {
+	pcap::read_pcap_ether_type(bytes, GOOSE_ETHER_TYPE)
+		.context(Pcap)?
+		.into_iter()
+		.map(|packet| EthernetFrame::decode(&packet.data).context(Frame)?.try_into())
+		.collect()
+}
+
+/// Convert an integer field of the GOOSE PDU.
+fn to_u32(value: &Integer, field: &str) -> Result<u32, GooseError> {
+	value.to_u32().context(InvalidInteger { field })
+}
+
+/// Convert a string field of the GOOSE message.
+fn to_visible_string(value: &str, field: &str) -> Result<VisibleString, GooseError> {
+	VisibleString::from_iso646_bytes(value.as_bytes()).context(InvalidString { field })
+}
+
+/// The error type for the GOOSE messages.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum GooseError {
+	#[snafu(display("Invalid ethernet frame"))]
+	Frame { source: FrameError },
+	#[snafu(display("Invalid pcap capture"))]
+	Pcap { source: PcapError },
+	#[snafu(display("Not a GOOSE message. EtherType: {:#06x}", ether_type))]
+	NotGoose { ether_type: u16 },
+	#[snafu(display("Error decoding the GOOSE PDU"))]
+	Decode { source: ber::de::DecodeError },
+	#[snafu(display("Error encoding the GOOSE PDU"))]
+	Encode { source: ber::enc::EncodeError },
+	#[snafu(display("Invalid integer for field: {}", field))]
+	InvalidInteger { field: String },
+	#[snafu(display("Invalid string for field: {}", field))]
+	InvalidString { field: String, source: PermittedAlphabetError },
+	#[snafu(display("Invalid GOOSE data"))]
+	InvalidData { source: Iec61850DataError },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::{pcap::PcapPacket, *};
+
+	/// A GOOSE frame with a VLAN tag and two values: a boolean and an integer.
+	const GOOSE_FRAME: [u8; 114] = [
+		0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01, 0x00, 0x1a, 0xb6, 0x03, 0x2f, 0x1c, 0x81, 0x00, 0x80,
+		0x00, 0x88, 0xb8, 0x10, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x00, 0x61, 0x56, 0x80, 0x14,
+		0x49, 0x45, 0x44, 0x31, 0x4c, 0x44, 0x30, 0x2f, 0x4c, 0x4c, 0x4e, 0x30, 0x24, 0x47, 0x4f,
+		0x24, 0x67, 0x63, 0x62, 0x31, 0x81, 0x02, 0x07, 0xd0, 0x82, 0x10, 0x49, 0x45, 0x44, 0x31,
+		0x4c, 0x44, 0x30, 0x2f, 0x4c, 0x4c, 0x4e, 0x30, 0x24, 0x44, 0x53, 0x31, 0x83, 0x04, 0x67,
+		0x63, 0x62, 0x31, 0x84, 0x08, 0x65, 0x53, 0xf1, 0x00, 0x80, 0x00, 0x00, 0x0a, 0x85, 0x01,
+		0x01, 0x86, 0x01, 0x00, 0x87, 0x01, 0x00, 0x88, 0x01, 0x01, 0x89, 0x01, 0x00, 0x8a, 0x01,
+		0x02, 0xab, 0x06, 0x83, 0x01, 0xff, 0x85, 0x01, 0x05,
+	];
+
+	#[test]
+	fn test_decode_goose_frame() {
+		let frame = GooseFrame::decode(&GOOSE_FRAME).unwrap();
+		assert_eq!(frame.destination, [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01]);
+		assert_eq!(frame.vlan, Some(VlanTag { priority: 4, drop_eligible: false, vlan_id: 0 }));
+		assert_eq!(frame.app_id, 0x1000);
+		assert_eq!(
+			frame.message,
+			GooseMessage {
+				gocb_ref: "IED1LD0/LLN0$GO$gcb1".to_owned(),
+				time_allowed_to_live: 2000,
+				dataset: "IED1LD0/LLN0$DS1".to_owned(),
+				go_id: Some("gcb1".to_owned()),
+				timestamp: OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_500_000_000)
+					.unwrap(),
+				st_num: 1,
+				sq_num: 0,
+				simulation: false,
+				config_rev: 1,
+				needs_commissioning: false,
+				number_of_entries: 2,
+				data: vec![Iec61850Data::Bool(true), Iec61850Data::Integer(5)],
+			}
+		);
+
+		// The encoded frame must decode to the same message.
+		let encoded = frame.clone().encode().unwrap();
+		assert_eq!(GooseFrame::decode(&encoded).unwrap(), frame);
+	}
+
+	#[test]
+	fn test_read_goose_pcap() {
+		let mut other = GOOSE_FRAME.to_vec();
+		// Sampled values EtherType
+		other[17] = 0xba;
+		// An ARP request and an IPv6 header, whose bytes are not a valid frame
+		// length.
+		let mut arp = GOOSE_FRAME[..12].to_vec();
+		arp.extend_from_slice(&[0x08, 0x06, 0x00, 0x01, 0x08, 0x00, 0x06, 0x04, 0x00, 0x01]);
+		arp.resize(60, 0);
+		let mut ipv6 = GOOSE_FRAME[..12].to_vec();
+		ipv6.extend_from_slice(&[0x86, 0xdd, 0x60, 0x00, 0x00, 0x00, 0xff, 0xff, 0x3a, 0x40]);
+		let packets = [&GOOSE_FRAME[..], &arp, &other, &ipv6, &GOOSE_FRAME]
+			.map(|data| PcapPacket { timestamp: OffsetDateTime::UNIX_EPOCH, data: data.to_vec() });
+		let frames = read_pcap(&pcap::write_pcap(&packets)).unwrap();
+		assert_eq!(frames.len(), 2);
+		assert_eq!(frames[0].message.gocb_ref, "IED1LD0/LLN0$GO$gcb1");
+	}
+}
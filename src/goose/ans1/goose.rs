@@ -0,0 +1,109 @@
+// This file was generated by the asn1-compiler tool.
+// *DO NOT EDIT THIS FILE MANUALLY*.
+// The asn1-compiler tool is used to compile the ASN.1 files into Rust modules
+// using the rasn-compiler crate.
+// The asn1-compiler tool is located in the src/mms/ans1/asn1-compiler.rs file.
+// For running the asn1-compiler tool, run the following command:
+// `cargo run --bin asn1-compiler`
+
+use crate::mms::ans1::mms::asn1;
+
+#[rustfmt::skip]
+#[allow(
+	missing_docs,
+	clippy::must_use_candidate,
+	clippy::missing_const_for_fn,
+	clippy::missing_docs_in_private_items,
+	clippy::unseparated_literal_suffix
+)]
+#[allow(
+	non_camel_case_types,
+	non_snake_case,
+	non_upper_case_globals,
+	unused,
+	clippy::too_many_arguments
+)]
+pub mod iec61850 {
+	extern crate alloc;
+	use core::borrow::Borrow;
+	use std::sync::LazyLock;
+
+	use rasn::prelude::*;
+
+	use super::asn1::{Data, UtcTime};
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice)]
+	pub enum GOOSEpdu {
+		#[rasn(tag(application, 1))]
+		goosePdu(IECGoosePdu),
+	}
+	impl From<IECGoosePdu> for GOOSEpdu {
+		fn from(value: IECGoosePdu) -> Self {
+			Self::goosePdu(value)
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct IECGoosePdu {
+		#[rasn(tag(context, 0), identifier = "gocbRef")]
+		pub gocb_ref: VisibleString,
+		#[rasn(tag(context, 1), identifier = "timeAllowedtoLive")]
+		pub time_allowedto_live: Integer,
+		#[rasn(tag(context, 2), identifier = "datSet")]
+		pub dat_set: VisibleString,
+		#[rasn(tag(context, 3), identifier = "goID")]
+		pub go_id: Option<VisibleString>,
+		#[rasn(tag(context, 4))]
+		pub t: UtcTime,
+		#[rasn(tag(context, 5), identifier = "stNum")]
+		pub st_num: Integer,
+		#[rasn(tag(context, 6), identifier = "sqNum")]
+		pub sq_num: Integer,
+		#[rasn(tag(context, 7), default = "iecgoose_pdu_simulation_default")]
+		pub simulation: bool,
+		#[rasn(tag(context, 8), identifier = "confRev")]
+		pub conf_rev: Integer,
+		#[rasn(tag(context, 9), default = "iecgoose_pdu_nds_com_default", identifier = "ndsCom")]
+		pub nds_com: bool,
+		#[rasn(tag(context, 10), identifier = "numDatSetEntries")]
+		pub num_dat_set_entries: Integer,
+		#[rasn(tag(context, 11), identifier = "allData")]
+		pub all_data: SequenceOf<Data>,
+	}
+	impl IECGoosePdu {
+		pub fn new(
+			gocb_ref: VisibleString,
+			time_allowedto_live: Integer,
+			dat_set: VisibleString,
+			go_id: Option<VisibleString>,
+			t: UtcTime,
+			st_num: Integer,
+			sq_num: Integer,
+			simulation: bool,
+			conf_rev: Integer,
+			nds_com: bool,
+			num_dat_set_entries: Integer,
+			all_data: SequenceOf<Data>,
+		) -> Self {
+			Self {
+				gocb_ref,
+				time_allowedto_live,
+				dat_set,
+				go_id,
+				t,
+				st_num,
+				sq_num,
+				simulation,
+				conf_rev,
+				nds_com,
+				num_dat_set_entries,
+				all_data,
+			}
+		}
+	}
+	fn iecgoose_pdu_simulation_default() -> bool {
+		false
+	}
+	fn iecgoose_pdu_nds_com_default() -> bool {
+		false
+	}
+}
@@ -0,0 +1,219 @@
+//! Ethernet frame codec for the IEC61850 multicast messages (GOOSE and SV).
+//!
+//! The frame has the following layout:
+//! | Destination (6) | Source (6) | [VLAN tag (4)] | EtherType (2) | APPID (2) |
+//! Length (2) | Reserved 1 (2) | Reserved 2 (2) | APDU |
+//!
+//! The length counts the bytes from the APPID to the end of the APDU.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, Snafu, ensure};
+
+/// The EtherType of the IEEE 802.1Q VLAN tag.
+pub const VLAN_ETHER_TYPE: u16 = 0x8100;
+/// The size of the ethernet header without the VLAN tag.
+const ETHERNET_HEADER_SIZE: usize = 14;
+/// The size of the header that precedes the APDU (APPID, length and the two
+/// reserved fields).
+const APDU_HEADER_SIZE: usize = 8;
+/// The simulation bit of the reserved 1 field. Only used since edition 2.
+const SIMULATION_BIT: u16 = 0x8000;
+
+/// An IEEE 802.1Q VLAN tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VlanTag {
+	/// The user priority, from 0 to 7.
+	pub priority: u8,
+	/// The drop eligible indicator.
+	pub drop_eligible: bool,
+	/// The VLAN id, from 0 to 4095.
+	pub vlan_id: u16,
+}
+
+impl VlanTag {
+	/// Create a VLAN tag from its tag control information.
+	#[must_use]
+	pub const fn from_tci(tci: u16) -> Self {
+		Self {
+			priority: (tci >> 13) as u8,
+			drop_eligible: tci & 0x1000 != 0,
+			vlan_id: tci & 0x0fff,
+		}
+	}
+
+	/// The tag control information of the VLAN tag.
+	#[must_use]
+	pub const fn tci(&self) -> u16 {
+		((self.priority as u16 & 0x07) << 13)
+			| ((self.drop_eligible as u16) << 12)
+			| (self.vlan_id & 0x0fff)
+	}
+}
+
+/// An ethernet frame carrying an IEC61850 multicast message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetFrame {
+	/// The destination MAC address.
+	pub destination: [u8; 6],
+	/// The source MAC address.
+	pub source: [u8; 6],
+	/// The VLAN tag, if present.
+	pub vlan: Option<VlanTag>,
+	/// The EtherType of the message.
+	pub ether_type: u16,
+	/// The application id.
+	pub app_id: u16,
+	/// The reserved 1 field.
+	pub reserved1: u16,
+	/// The reserved 2 field.
+	pub reserved2: u16,
+	/// The application protocol data unit.
+	pub apdu: Vec<u8>,
+}
+
+impl EthernetFrame {
+	/// Decode an ethernet frame. The padding added to short frames is
+	/// removed using the length field.
+	pub fn decode(bytes: &[u8]) -> Result<Self, FrameError> {
+		ensure!(bytes.len() >= ETHERNET_HEADER_SIZE, TooShort { length: bytes.len() });
+		let destination = read_mac(bytes, 0)?;
+		let source = read_mac(bytes, 6)?;
+
+		let mut offset = 12;
+		let mut ether_type = read_u16(bytes, offset)?;
+		let mut vlan = None;
+		if ether_type == VLAN_ETHER_TYPE {
+			vlan = Some(VlanTag::from_tci(read_u16(bytes, offset + 2)?));
+			offset += 4;
+			ether_type = read_u16(bytes, offset)?;
+		}
+		offset += 2;
+
+		let app_id = read_u16(bytes, offset)?;
+		let length = usize::from(read_u16(bytes, offset + 2)?);
+		let reserved1 = read_u16(bytes, offset + 4)?;
+		let reserved2 = read_u16(bytes, offset + 6)?;
+		ensure!(
+			length >= APDU_HEADER_SIZE && offset + length <= bytes.len(),
+			InvalidLength { length }
+		);
+		let apdu = bytes[offset + APDU_HEADER_SIZE..offset + length].to_vec();
+
+		Ok(Self { destination, source, vlan, ether_type, app_id, reserved1, reserved2, apdu })
+	}
+
+	/// Encode the ethernet frame.
+	pub fn encode(&self) -> Result<Vec<u8>, FrameError> {
+		let length = APDU_HEADER_SIZE + self.apdu.len();
+		let length_field =
+			u16::try_from(length).ok().context(InvalidLength { length })?.to_be_bytes();
+
+		let mut bytes = Vec::with_capacity(ETHERNET_HEADER_SIZE + 4 + length);
+		bytes.extend_from_slice(&self.destination);
+		bytes.extend_from_slice(&self.source);
+		if let Some(vlan) = self.vlan {
+			bytes.extend_from_slice(&VLAN_ETHER_TYPE.to_be_bytes());
+			bytes.extend_from_slice(&vlan.tci().to_be_bytes());
+		}
+		bytes.extend_from_slice(&self.ether_type.to_be_bytes());
+		bytes.extend_from_slice(&self.app_id.to_be_bytes());
+		bytes.extend_from_slice(&length_field);
+		bytes.extend_from_slice(&self.reserved1.to_be_bytes());
+		bytes.extend_from_slice(&self.reserved2.to_be_bytes());
+		bytes.extend_from_slice(&self.apdu);
+		Ok(bytes)
+	}
+
+	/// Whether the simulation bit of the reserved 1 field is set.
+	#[must_use]
+	pub const fn simulation(&self) -> bool {
+		self.reserved1 & SIMULATION_BIT != 0
+	}
+
+	/// Set the simulation bit of the reserved 1 field.
+	pub const fn set_simulation(&mut self, simulation: bool) {
+		if simulation {
+			self.reserved1 |= SIMULATION_BIT;
+		} else {
+			self.reserved1 &= !SIMULATION_BIT;
+		}
+	}
+}
+
+/// Read the EtherType of an ethernet frame, after its VLAN tag if any,
+/// without decoding the rest of the frame. Returns `None` if the frame is too
+/// short to have one.
+#[must_use]
+pub fn ether_type(bytes: &[u8]) -> Option<u16> {
+	match read_u16(bytes, 12).ok()? {
+		VLAN_ETHER_TYPE => read_u16(bytes, 16).ok(),
+		ether_type => Some(ether_type),
+	}
+}
+
+/// Read a MAC address at the given offset.
+fn read_mac(bytes: &[u8], offset: usize) -> Result<[u8; 6], FrameError> {
+	bytes
+		.get(offset..offset + 6)
+		.and_then(|mac| mac.try_into().ok())
+		.context(TooShort { length: bytes.len() })
+}
+
+/// Read a big endian u16 at the given offset.
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, FrameError> {
+	bytes
+		.get(offset..offset + 2)
+		.map(|value| u16::from_be_bytes([value[0], value[1]]))
+		.context(TooShort { length: bytes.len() })
+}
+
+/// The error type for the ethernet frame codec.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum FrameError {
+	#[snafu(display("Frame too short. Length: {}", length))]
+	TooShort { length: usize },
+	#[snafu(display("Invalid length field: {}", length))]
+	InvalidLength { length: usize },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_frame_roundtrip() {
+		let mut frame = EthernetFrame {
+			destination: [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01],
+			source: [0x00, 0x1a, 0xb6, 0x03, 0x2f, 0x1c],
+			vlan: Some(VlanTag { priority: 4, drop_eligible: false, vlan_id: 10 }),
+			ether_type: 0x88b8,
+			app_id: 0x1000,
+			reserved1: 0,
+			reserved2: 0,
+			apdu: vec![0x61, 0x00],
+		};
+		frame.set_simulation(true);
+		let bytes = frame.encode().unwrap();
+		assert_eq!(bytes[12..18], [0x81, 0x00, 0x80, 0x0a, 0x88, 0xb8]);
+		assert_eq!(bytes[18..26], [0x10, 0x00, 0x00, 0x0a, 0x80, 0x00, 0x00, 0x00]);
+
+		// Short frames are padded to the minimum ethernet size.
+		let mut padded = bytes.clone();
+		padded.resize(60, 0);
+		let decoded = EthernetFrame::decode(&padded).unwrap();
+		assert!(decoded.simulation());
+		assert_eq!(decoded, frame);
+
+		assert!(matches!(
+			EthernetFrame::decode(&bytes[..bytes.len() - 1]),
+			Err(FrameError::InvalidLength { length: 10 })
+		));
+
+		assert_eq!(ether_type(&bytes), Some(0x88b8));
+		assert_eq!(ether_type(&[&bytes[..12], &bytes[16..]].concat()), Some(0x88b8));
+		assert_eq!(ether_type(&bytes[..16]), None);
+	}
+}
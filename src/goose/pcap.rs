@@ -0,0 +1,156 @@
+//! A minimal reader and writer of pcap captures.
+//!
+//! Only the classic pcap format with ethernet link type is supported. This is
+//! enough to replay captures of GOOSE and SV messages without a network.
+
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
+use time::OffsetDateTime;
+
+use crate::goose::frame;
+
+/// The magic number of a capture with microsecond timestamps.
+const MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+/// The magic number of a capture with nanosecond timestamps.
+const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+/// The ethernet link type.
+const LINKTYPE_ETHERNET: u32 = 1;
+/// The size of the global header.
+const GLOBAL_HEADER_SIZE: usize = 24;
+/// The size of the packet record header.
+const RECORD_HEADER_SIZE: usize = 16;
+/// The snapshot length used when writing captures.
+const SNAPSHOT_LENGTH: u32 = 65_535;
+
+/// A packet of a pcap capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapPacket {
+	/// The time the packet was captured.
+	pub timestamp: OffsetDateTime,
+	/// The bytes of the captured ethernet frame.
+	pub data: Vec<u8>,
+}
+
+/// Read all the packets of a pcap capture.
+pub fn read_pcap(bytes: &[u8]) -> Result<Vec<PcapPacket>, PcapError> {
+	let magic = bytes.first_chunk::<4>().context(Truncated)?;
+	let (big_endian, nanoseconds) = match (u32::from_le_bytes(*magic), u32::from_be_bytes(*magic)) {
+		(MAGIC_MICROSECONDS, _) => (false, false),
+		(MAGIC_NANOSECONDS, _) => (false, true),
+		(_, MAGIC_MICROSECONDS) => (true, false),
+		(_, MAGIC_NANOSECONDS) => (true, true),
+		(magic, _) => return InvalidMagic { magic }.fail(),
+	};
+	let read_u32 = |offset: usize| -> Result<u32, PcapError> {
+		let value = *bytes.get(offset..).and_then(<[u8]>::first_chunk::<4>).context(Truncated)?;
+		Ok(if big_endian { u32::from_be_bytes(value) } else { u32::from_le_bytes(value) })
+	};
+
+	let link_type = read_u32(20)?;
+	ensure!(link_type == LINKTYPE_ETHERNET, UnsupportedLinkType { link_type });
+
+	let mut packets = Vec::new();
+	let mut offset = GLOBAL_HEADER_SIZE;
+	while offset < bytes.len() {
+		let seconds = read_u32(offset)?;
+		let fraction = read_u32(offset + 4)?;
+		let length = read_u32(offset + 8)? as usize;
+		offset += RECORD_HEADER_SIZE;
+		let data = bytes.get(offset..offset + length).context(Truncated)?.to_vec();
+		offset += length;
+
+		let fraction = if nanoseconds { i128::from(fraction) } else { i128::from(fraction) * 1000 };
+		let timestamp = OffsetDateTime::from_unix_timestamp_nanos(
+			i128::from(seconds) * 1_000_000_000 + fraction,
+		)
+		.context(InvalidTimestamp)?;
+		packets.push(PcapPacket { timestamp, data });
+	}
+	Ok(packets)
+}
+
+/// Read the packets of a pcap capture whose ethernet frame has the given
+/// EtherType. The other packets, e.g. ARP or IP traffic, are skipped without
+/// being decoded.
+pub fn read_pcap_ether_type(bytes: &[u8], ether_type: u16) -> Result<Vec<PcapPacket>, PcapError> {
+	let mut packets = read_pcap(bytes)?;
+	packets.retain(|packet| frame::ether_type(&packet.data) == Some(ether_type));
+	Ok(packets)
+}
+
+/// Write packets as a pcap capture with nanosecond timestamps.
+#[must_use]
+pub fn write_pcap(packets: &[PcapPacket]) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(
+		GLOBAL_HEADER_SIZE
+			+ packets.iter().map(|packet| RECORD_HEADER_SIZE + packet.data.len()).sum::<usize>(),
+	);
+	bytes.extend_from_slice(&MAGIC_NANOSECONDS.to_le_bytes());
+	// Version 2.4
+	bytes.extend_from_slice(&2_u16.to_le_bytes());
+	bytes.extend_from_slice(&4_u16.to_le_bytes());
+	// Time zone and timestamp accuracy
+	bytes.extend_from_slice(&[0; 8]);
+	bytes.extend_from_slice(&SNAPSHOT_LENGTH.to_le_bytes());
+	bytes.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+	for packet in packets {
+		let seconds = u32::try_from(packet.timestamp.unix_timestamp()).unwrap_or_default();
+		let length = u32::try_from(packet.data.len()).unwrap_or(u32::MAX);
+		bytes.extend_from_slice(&seconds.to_le_bytes());
+		bytes.extend_from_slice(&packet.timestamp.nanosecond().to_le_bytes());
+		bytes.extend_from_slice(&length.to_le_bytes());
+		bytes.extend_from_slice(&length.to_le_bytes());
+		bytes.extend_from_slice(&packet.data);
+	}
+	bytes
+}
+
+/// The error type for the pcap reader.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum PcapError {
+	#[snafu(display("Invalid pcap magic number: {:#x}", magic))]
+	InvalidMagic { magic: u32 },
+	#[snafu(display("Unsupported link type: {}", link_type))]
+	UnsupportedLinkType { link_type: u32 },
+	#[snafu(display("Truncated pcap capture"))]
+	Truncated,
+	#[snafu(display("Invalid packet timestamp"))]
+	InvalidTimestamp { source: time::error::ComponentRange },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_pcap_roundtrip() {
+		let packets = vec![
+			PcapPacket {
+				timestamp: OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_000_000_123)
+					.unwrap(),
+				data: vec![1, 2, 3],
+			},
+			PcapPacket { timestamp: OffsetDateTime::UNIX_EPOCH, data: vec![4, 5] },
+		];
+		let bytes = write_pcap(&packets);
+		assert_eq!(read_pcap(&bytes).unwrap(), packets);
+		assert!(matches!(read_pcap(&bytes[..bytes.len() - 1]), Err(PcapError::Truncated)));
+
+		// A big endian capture with microsecond timestamps.
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&MAGIC_MICROSECONDS.to_be_bytes());
+		bytes
+			.extend_from_slice(&[0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+		bytes.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1, 9]);
+		assert_eq!(
+			read_pcap(&bytes).unwrap(),
+			vec![PcapPacket {
+				timestamp: OffsetDateTime::from_unix_timestamp_nanos(1_000_002_000).unwrap(),
+				data: vec![9],
+			}]
+		);
+	}
+}
@@ -0,0 +1,327 @@
+//! GOOSE publisher.
+//!
+//! The publisher sends a new state as soon as the dataset changes and then
+//! retransmits it following a [`RetransmissionCurve`] until the heartbeat
+//! interval is reached. The frames are written to a [`FrameSink`].
+
+use std::{io, time::Duration};
+
+use ::time::OffsetDateTime;
+use async_trait::async_trait;
+use snafu::{ResultExt as _, Snafu};
+use tokio::{
+	select,
+	sync::mpsc,
+	time::{self, Instant},
+};
+use tracing::instrument;
+
+use crate::{
+	goose::{
+		GooseError, GooseFrame, GooseMessage,
+		frame::VlanTag,
+		pcap::{self, PcapPacket},
+	},
+	iec61850::{data::Iec61850Data, gocb::DestinationAddress},
+};
+
+/// A sink of ethernet frames.
+#[async_trait]
+pub trait FrameSink: Send {
+	/// Send an ethernet frame.
+	async fn send_frame(&mut self, frame: &[u8]) -> Result<(), io::Error>;
+}
+
+/// An in-memory sink of frames.
+#[async_trait]
+impl FrameSink for mpsc::Sender<Vec<u8>> {
+	async fn send_frame(&mut self, frame: &[u8]) -> Result<(), io::Error> {
+		self.send(frame.to_vec()).await.map_err(|_| io::ErrorKind::BrokenPipe.into())
+	}
+}
+
+/// A sink that records the frames as pcap packets.
+#[derive(Debug, Default)]
+pub struct PcapFrameSink {
+	/// The recorded packets.
+	packets: Vec<PcapPacket>,
+}
+
+impl PcapFrameSink {
+	/// The recorded packets.
+	#[must_use]
+	pub fn packets(&self) -> &[PcapPacket] {
+		&self.packets
+	}
+
+	/// The recorded packets as a pcap capture.
+	#[must_use]
+	pub fn to_pcap(&self) -> Vec<u8> {
+		pcap::write_pcap(&self.packets)
+	}
+}
+
+#[async_trait]
+impl FrameSink for PcapFrameSink {
+	async fn send_frame(&mut self, frame: &[u8]) -> Result<(), io::Error> {
+		self.packets
+			.push(PcapPacket { timestamp: OffsetDateTime::now_utc(), data: frame.to_vec() });
+		Ok(())
+	}
+}
+
+/// The retransmission curve of the GOOSE messages.
+/// After a state change the interval between the messages starts at the
+/// minimum time and doubles until the maximum time (the heartbeat) is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetransmissionCurve {
+	/// The interval after the first message of a new state.
+	pub min_time: Duration,
+	/// The interval between the messages once the state is stable.
+	pub max_time: Duration,
+}
+
+impl Default for RetransmissionCurve {
+	fn default() -> Self {
+		Self { min_time: Duration::from_millis(2), max_time: Duration::from_secs(1) }
+	}
+}
+
+impl RetransmissionCurve {
+	/// The interval between the message with the given sequence number and
+	/// the next one.
+	#[must_use]
+	pub fn interval(&self, sq_num: u32) -> Duration {
+		self.min_time
+			.checked_mul(2_u32.checked_pow(sq_num).unwrap_or(u32::MAX))
+			.map_or(self.max_time, |interval| interval.min(self.max_time))
+	}
+}
+
+/// The configuration of a GOOSE publisher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoosePublisherConfig {
+	/// The reference of the GOOSE control block, e.g. `LD/LLN0$GO$gcb1`.
+	pub gocb_ref: String,
+	/// The reference of the published dataset.
+	pub dataset: String,
+	/// The id of the GOOSE control block.
+	pub go_id: Option<String>,
+	/// The destination of the messages.
+	pub destination: DestinationAddress,
+	/// The source MAC address.
+	pub source: [u8; 6],
+	/// The configuration revision of the GOOSE control block.
+	pub config_rev: u32,
+	/// Whether the GOOSE control block needs commissioning.
+	pub needs_commissioning: bool,
+	/// Whether the messages are simulated ones.
+	pub simulation: bool,
+	/// The retransmission curve.
+	pub curve: RetransmissionCurve,
+}
+
+/// A GOOSE publisher.
+#[derive(Debug)]
+pub struct GoosePublisher<S> {
+	/// The configuration of the publisher.
+	config: GoosePublisherConfig,
+	/// The sink the frames are written to.
+	sink: S,
+	/// The values of the dataset.
+	data: Vec<Iec61850Data>,
+	/// The state number of the current state.
+	st_num: u32,
+	/// The sequence number of the last sent message.
+	sq_num: u32,
+	/// The time of the last state change.
+	timestamp: OffsetDateTime,
+	/// The time the next message must be sent.
+	next_send: Instant,
+}
+
+impl<S: FrameSink> GoosePublisher<S> {
+	/// Create a new GOOSE publisher with the initial values of the dataset.
+	/// Nothing is sent until [`Self::publish`] or [`Self::run`] is called.
+	#[must_use]
+	pub fn new(config: GoosePublisherConfig, sink: S, data: Vec<Iec61850Data>) -> Self {
+		Self {
+			config,
+			sink,
+			data,
+			st_num: 0,
+			sq_num: 0,
+			timestamp: OffsetDateTime::now_utc(),
+			next_send: Instant::now(),
+		}
+	}
+
+	/// The state number of the current state.
+	#[must_use]
+	pub const fn st_num(&self) -> u32 {
+		self.st_num
+	}
+
+	/// The sequence number of the last sent message.
+	#[must_use]
+	pub const fn sq_num(&self) -> u32 {
+		self.sq_num
+	}
+
+	/// The time the next retransmission must be sent.
+	#[must_use]
+	pub const fn next_retransmission(&self) -> Instant {
+		self.next_send
+	}
+
+	/// Publish a new state of the dataset. The state number is incremented and
+	/// the message is sent immediately.
+	#[instrument(skip(self))]
+	pub async fn publish(&mut self, data: Vec<Iec61850Data>) -> Result<(), GoosePublisherError> {
+		self.data = data;
+		self.timestamp = OffsetDateTime::now_utc();
+		// The state number wraps around to 1, 0 is only used before the first
+		// state.
+		self.st_num = self.st_num.checked_add(1).unwrap_or(1);
+		self.sq_num = 0;
+		self.send().await
+	}
+
+	/// Retransmit the current state. The sequence number is incremented.
+	#[instrument(skip(self))]
+	pub async fn retransmit(&mut self) -> Result<(), GoosePublisherError> {
+		// The sequence number wraps around to 1, 0 is only used for the first
+		// message of a state.
+		self.sq_num = self.sq_num.checked_add(1).unwrap_or(1);
+		self.send().await
+	}
+
+	/// Publish the initial state and then retransmit it following the
+	/// retransmission curve. Every value received from `updates` is published
+	/// as a new state. Returns when `updates` is closed.
+	#[instrument(skip_all)]
+	pub async fn run(
+		&mut self,
+		mut updates: mpsc::Receiver<Vec<Iec61850Data>>,
+	) -> Result<(), GoosePublisherError> {
+		self.publish(self.data.clone()).await?;
+		loop {
+			select! {
+				data = updates.recv() => match data {
+					Some(data) => self.publish(data).await?,
+					None => return Ok(()),
+				},
+				() = time::sleep_until(self.next_send) => self.retransmit().await?,
+			}
+		}
+	}
+
+	/// Encode and send the current message and schedule the next one.
+	async fn send(&mut self) -> Result<(), GoosePublisherError> {
+		let interval = self.config.curve.interval(self.sq_num);
+		let frame = self.frame(interval).encode().context(Encode)?;
+		self.sink.send_frame(&frame).await.context(Sink)?;
+		self.next_send = Instant::now() + interval;
+		Ok(())
+	}
+
+	/// Build the frame of the current message. The time allowed to live is
+	/// twice the interval to the next message.
+	fn frame(&self, interval: Duration) -> GooseFrame {
+		let destination = self.config.destination;
+		GooseFrame {
+			destination: destination.mac_address,
+			source: self.config.source,
+			vlan: Some(VlanTag {
+				priority: destination.priority,
+				drop_eligible: false,
+				vlan_id: destination.vlan_id,
+			}),
+			app_id: destination.app_id,
+			message: GooseMessage {
+				gocb_ref: self.config.gocb_ref.clone(),
+				time_allowed_to_live: u32::try_from(interval.as_millis() * 2).unwrap_or(u32::MAX),
+				dataset: self.config.dataset.clone(),
+				go_id: self.config.go_id.clone(),
+				timestamp: self.timestamp,
+				st_num: self.st_num,
+				sq_num: self.sq_num,
+				simulation: self.config.simulation,
+				config_rev: self.config.config_rev,
+				needs_commissioning: self.config.needs_commissioning,
+				number_of_entries: u32::try_from(self.data.len()).unwrap_or(u32::MAX),
+				data: self.data.clone(),
+			},
+		}
+	}
+}
+
+/// The error type for the GOOSE publisher.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum GoosePublisherError {
+	#[snafu(display("Error encoding the GOOSE message"))]
+	Encode { source: GooseError },
+	#[snafu(display("Error sending a frame"))]
+	Sink { source: io::Error },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config() -> GoosePublisherConfig {
+		GoosePublisherConfig {
+			gocb_ref: "LD/LLN0$GO$gcb1".to_owned(),
+			dataset: "LD/LLN0$DS1".to_owned(),
+			go_id: Some("gcb1".to_owned()),
+			destination: DestinationAddress {
+				mac_address: [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01],
+				priority: 4,
+				vlan_id: 0,
+				app_id: 0x1000,
+			},
+			source: [0x00, 0x1a, 0xb6, 0x03, 0x2f, 0x1c],
+			config_rev: 1,
+			needs_commissioning: false,
+			simulation: false,
+			curve: RetransmissionCurve {
+				min_time: Duration::from_millis(2),
+				max_time: Duration::from_millis(10),
+			},
+		}
+	}
+
+	#[test]
+	fn test_retransmission_curve() {
+		let curve = config().curve;
+		let intervals: Vec<u128> =
+			(0..6).map(|sq_num| curve.interval(sq_num).as_millis()).collect();
+		assert_eq!(intervals, [2, 4, 8, 10, 10, 10]);
+		assert_eq!(curve.interval(u32::MAX), Duration::from_millis(10));
+	}
+
+	#[tokio::test]
+	async fn test_publisher() {
+		let (tx, mut rx) = mpsc::channel(8);
+		let mut publisher = GoosePublisher::new(config(), tx, vec![Iec61850Data::Bool(false)]);
+		publisher.publish(vec![Iec61850Data::Bool(true)]).await.unwrap();
+		publisher.retransmit().await.unwrap();
+		publisher.publish(vec![Iec61850Data::Bool(false)]).await.unwrap();
+
+		let mut messages = Vec::new();
+		while let Ok(frame) = rx.try_recv() {
+			messages.push(GooseFrame::decode(&frame).unwrap().message);
+		}
+		let numbers: Vec<(u32, u32, u32)> = messages
+			.iter()
+			.map(|message| (message.st_num, message.sq_num, message.time_allowed_to_live))
+			.collect();
+		assert_eq!(numbers, [(1, 0, 4), (1, 1, 8), (2, 0, 4)]);
+		assert_eq!(messages[0].data, [Iec61850Data::Bool(true)]);
+		assert_eq!(messages[2].data, [Iec61850Data::Bool(false)]);
+		assert_eq!(messages[2].number_of_entries, 1);
+	}
+}
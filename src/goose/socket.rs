@@ -0,0 +1,149 @@
+//! Raw ethernet socket for Linux.
+//!
+//! The socket receives every frame with the given EtherType on an interface,
+//! including the multicast frames, and sends complete ethernet frames. Opening
+//! it requires the `CAP_NET_RAW` capability.
+//!
+//! Depending on the network card, the kernel may strip the VLAN tag of the
+//! received frames.
+
+use std::{
+	ffi::CString,
+	io, mem,
+	os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd},
+};
+
+use async_trait::async_trait;
+use tokio::io::unix::AsyncFd;
+
+use crate::goose::{publisher::FrameSink, subscriber::FrameSource};
+
+/// The maximum size of an ethernet frame, including the VLAN tag.
+const MAX_FRAME_SIZE: usize = 1522;
+
+/// A raw ethernet socket bound to an interface.
+#[derive(Debug)]
+pub struct RawSocket {
+	/// The socket file descriptor.
+	fd: AsyncFd<OwnedFd>,
+}
+
+impl RawSocket {
+	/// Open a raw socket that receives the frames with the given EtherType on
+	/// an interface, e.g. `eth0`.
+	pub fn bind(interface: &str, ether_type: u16) -> io::Result<Self> {
+		let name = CString::new(interface).map_err(io::Error::other)?;
+		// SAFETY: `name` is a valid null terminated string.
+		let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+		if index == 0 {
+			return Err(io::Error::last_os_error());
+		}
+		let index = i32::try_from(index).map_err(io::Error::other)?;
+		let protocol = ether_type.to_be();
+
+		// SAFETY: The arguments are valid constants. The returned descriptor is
+		// checked before use.
+		let fd = unsafe {
+			libc::socket(
+				libc::AF_PACKET,
+				libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+				i32::from(protocol),
+			)
+		};
+		if fd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		// SAFETY: `fd` is a valid descriptor owned by nobody else.
+		let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+		// SAFETY: `sockaddr_ll` is a plain C struct, all zeroes is a valid value.
+		let mut address: libc::sockaddr_ll = unsafe { mem::zeroed() };
+		address.sll_family = libc::AF_PACKET as u16;
+		address.sll_protocol = protocol;
+		address.sll_ifindex = index;
+		// SAFETY: `address` is a valid `sockaddr_ll` and the length matches its
+		// size.
+		let result = unsafe {
+			libc::bind(
+				fd.as_raw_fd(),
+				(&raw const address).cast(),
+				size_of::<libc::sockaddr_ll>() as u32,
+			)
+		};
+		if result < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		// Receive all the multicast frames of the interface.
+		// SAFETY: `packet_mreq` is a plain C struct, all zeroes is a valid value.
+		let mut membership: libc::packet_mreq = unsafe { mem::zeroed() };
+		membership.mr_ifindex = index;
+		membership.mr_type = libc::PACKET_MR_ALLMULTI as u16;
+		// SAFETY: `membership` is a valid `packet_mreq` and the length matches
+		// its size.
+		let result = unsafe {
+			libc::setsockopt(
+				fd.as_raw_fd(),
+				libc::SOL_PACKET,
+				libc::PACKET_ADD_MEMBERSHIP,
+				(&raw const membership).cast(),
+				size_of::<libc::packet_mreq>() as u32,
+			)
+		};
+		if result < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(Self { fd: AsyncFd::new(fd)? })
+	}
+
+	/// Receive an ethernet frame.
+	pub async fn recv(&self) -> io::Result<Vec<u8>> {
+		let mut buffer = vec![0; MAX_FRAME_SIZE];
+		loop {
+			let mut guard = self.fd.readable().await?;
+			let result = guard.try_io(|fd| {
+				// SAFETY: `buffer` is valid for writes of its length.
+				let length = unsafe {
+					libc::recv(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0)
+				};
+				usize::try_from(length).map_err(|_| io::Error::last_os_error())
+			});
+			if let Ok(result) = result {
+				buffer.truncate(result?);
+				return Ok(buffer);
+			}
+		}
+	}
+
+	/// Send an ethernet frame. The frame must contain the ethernet header.
+	pub async fn send(&self, frame: &[u8]) -> io::Result<()> {
+		loop {
+			let mut guard = self.fd.writable().await?;
+			let result = guard.try_io(|fd| {
+				// SAFETY: `frame` is valid for reads of its length.
+				let length =
+					unsafe { libc::send(fd.as_raw_fd(), frame.as_ptr().cast(), frame.len(), 0) };
+				usize::try_from(length).map_err(|_| io::Error::last_os_error())
+			});
+			if let Ok(result) = result {
+				result?;
+				return Ok(());
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl FrameSource for RawSocket {
+	async fn recv_frame(&mut self) -> Result<Option<Vec<u8>>, io::Error> {
+		self.recv().await.map(Some)
+	}
+}
+
+#[async_trait]
+impl FrameSink for RawSocket {
+	async fn send_frame(&mut self, frame: &[u8]) -> Result<(), io::Error> {
+		self.send(frame).await
+	}
+}
@@ -0,0 +1,552 @@
+//! GOOSE subscriber.
+//!
+//! The subscriber receives ethernet frames from a [`FrameSource`], keeps the
+//! state of every subscribed GOOSE control block and supervises the
+//! publication: message loss, duplicated and out of order messages, the
+//! expiration of the time allowed to live, the configuration revision and the
+//! needs commissioning flag.
+
+use std::{io, time::Duration};
+
+use async_trait::async_trait;
+use snafu::{ResultExt as _, Snafu};
+use tokio::{
+	select,
+	sync::mpsc,
+	time::{self, Instant},
+};
+use tracing::instrument;
+
+use crate::goose::{
+	GOOSE_ETHER_TYPE, GooseFrame, GooseMessage,
+	frame::EthernetFrame,
+	pcap::{self, PcapError, PcapPacket},
+};
+
+/// A source of ethernet frames.
+#[async_trait]
+pub trait FrameSource: Send {
+	/// Receive the next ethernet frame. Returns `None` when the source is
+	/// exhausted.
+	async fn recv_frame(&mut self) -> Result<Option<Vec<u8>>, io::Error>;
+}
+
+/// An in-memory source of frames.
+#[async_trait]
+impl FrameSource for mpsc::Receiver<Vec<u8>> {
+	async fn recv_frame(&mut self) -> Result<Option<Vec<u8>>, io::Error> {
+		Ok(self.recv().await)
+	}
+}
+
+/// A source of frames read from a pcap capture. The frames are delivered as
+/// fast as possible, the capture timestamps are ignored.
+#[derive(Debug)]
+pub struct PcapFrameSource {
+	/// The packets not delivered yet.
+	packets: std::vec::IntoIter<PcapPacket>,
+}
+
+impl PcapFrameSource {
+	/// Create a frame source from the bytes of a pcap capture.
+	pub fn new(bytes: &[u8]) -> Result<Self, PcapError> {
+		Ok(Self { packets: pcap::read_pcap(bytes)?.into_iter() })
+	}
+}
+
+#[async_trait]
+impl FrameSource for PcapFrameSource {
+	async fn recv_frame(&mut self) -> Result<Option<Vec<u8>>, io::Error> {
+		Ok(self.packets.next().map(|packet| packet.data))
+	}
+}
+
+/// A trait for reacting to the GOOSE events.
+#[async_trait]
+#[allow(missing_docs)]
+pub trait GooseCallback {
+	async fn on_event(&self, event: GooseEvent);
+}
+
+/// An event of a GOOSE subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GooseEvent {
+	/// The first message or a message with a new state was received.
+	StateChange(GooseMessage),
+	/// Messages were lost between the last received message and this one.
+	MessageLoss {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+		/// The state number of the received message.
+		st_num: u32,
+		/// The sequence number of the received message.
+		sq_num: u32,
+	},
+	/// The message was already received.
+	Duplicate {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+		/// The state number of the received message.
+		st_num: u32,
+		/// The sequence number of the received message.
+		sq_num: u32,
+	},
+	/// The message is older than the last received message. It is discarded.
+	OutOfOrder {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+		/// The state number of the received message.
+		st_num: u32,
+		/// The sequence number of the received message.
+		sq_num: u32,
+	},
+	/// No message was received within the time allowed to live of the last
+	/// message.
+	Expired {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+	},
+	/// The configuration revision of the message is not the expected one. The
+	/// message is discarded. Reported again only when the received revision
+	/// changes.
+	ConfigRevisionMismatch {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+		/// The expected configuration revision.
+		expected: u32,
+		/// The received configuration revision.
+		received: u32,
+	},
+	/// The publisher needs commissioning. The message is discarded. Reported
+	/// again only after a message that doesn't need commissioning.
+	NeedsCommissioning {
+		/// The reference of the GOOSE control block.
+		gocb_ref: String,
+	},
+}
+
+/// A subscription to a GOOSE control block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GooseSubscription {
+	/// The reference of the GOOSE control block, e.g. `LD/LLN0$GO$gcb1`.
+	pub gocb_ref: String,
+	/// The expected application id. Any application id is accepted if `None`.
+	pub app_id: Option<u16>,
+	/// The expected destination MAC address. Any address is accepted if
+	/// `None`.
+	pub destination: Option<[u8; 6]>,
+	/// The expected configuration revision. Any revision is accepted if
+	/// `None`.
+	pub config_rev: Option<u32>,
+}
+
+impl GooseSubscription {
+	/// Create a subscription to a GOOSE control block that accepts any
+	/// application id, destination and configuration revision.
+	#[must_use]
+	pub fn new(gocb_ref: impl Into<String>) -> Self {
+		Self { gocb_ref: gocb_ref.into(), app_id: None, destination: None, config_rev: None }
+	}
+
+	/// Whether the frame belongs to the subscription.
+	fn matches(&self, frame: &GooseFrame) -> bool {
+		self.gocb_ref == frame.message.gocb_ref
+			&& self.app_id.is_none_or(|app_id| app_id == frame.app_id)
+			&& self.destination.is_none_or(|destination| destination == frame.destination)
+	}
+}
+
+/// The state of a subscription.
+#[derive(Debug)]
+struct SubscriptionState {
+	/// The subscription.
+	subscription: GooseSubscription,
+	/// The state and sequence numbers of the last accepted message.
+	last: Option<(u32, u32)>,
+	/// The time the last accepted message expires.
+	deadline: Option<Instant>,
+	/// The configuration revision of the last message, if it was not the
+	/// expected one.
+	config_rev_mismatch: Option<u32>,
+	/// Whether the last message needed commissioning.
+	needs_commissioning: bool,
+}
+
+/// The position of a message relative to the last accepted one.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Sequence {
+	/// The next expected message.
+	Next,
+	/// A newer message, some messages were lost.
+	Gap,
+	/// The same message.
+	Duplicate,
+	/// An older message.
+	Older,
+}
+
+/// Compare two state or sequence numbers. They wrap around from `u32::MAX`
+/// to 1, as 0 is only used before the first state and by the first message
+/// of a state.
+const fn compare(received: u32, last: u32) -> Sequence {
+	match received.wrapping_sub(last) {
+		0 => Sequence::Duplicate,
+		1 => Sequence::Next,
+		2 if last == u32::MAX => Sequence::Next,
+		difference if difference < 1 << 31 => Sequence::Gap,
+		_ => Sequence::Older,
+	}
+}
+
+impl SubscriptionState {
+	/// Create the state of a subscription that received no message yet.
+	const fn new(subscription: GooseSubscription) -> Self {
+		Self {
+			subscription,
+			last: None,
+			deadline: None,
+			config_rev_mismatch: None,
+			needs_commissioning: false,
+		}
+	}
+
+	/// Supervise a message of the subscription and return its events.
+	fn handle_message(&mut self, message: GooseMessage, now: Instant) -> Vec<GooseEvent> {
+		let gocb_ref = message.gocb_ref.clone();
+		// The discarded messages are reported once, not on every retransmission.
+		if let Some(expected) = self.subscription.config_rev
+			&& expected != message.config_rev
+		{
+			if self.config_rev_mismatch.replace(message.config_rev) == Some(message.config_rev) {
+				return Vec::new();
+			}
+			return vec![GooseEvent::ConfigRevisionMismatch {
+				gocb_ref,
+				expected,
+				received: message.config_rev,
+			}];
+		}
+		self.config_rev_mismatch = None;
+		if message.needs_commissioning {
+			if std::mem::replace(&mut self.needs_commissioning, true) {
+				return Vec::new();
+			}
+			return vec![GooseEvent::NeedsCommissioning { gocb_ref }];
+		}
+		self.needs_commissioning = false;
+
+		let (st_num, sq_num) = (message.st_num, message.sq_num);
+		let mut events = Vec::new();
+		let state_change = match self.last {
+			None => true,
+			Some((last_st_num, last_sq_num)) if st_num == last_st_num => {
+				match compare(sq_num, last_sq_num) {
+					Sequence::Next => {}
+					Sequence::Gap => {
+						events.push(GooseEvent::MessageLoss { gocb_ref, st_num, sq_num });
+					}
+					Sequence::Duplicate => {
+						return vec![GooseEvent::Duplicate { gocb_ref, st_num, sq_num }];
+					}
+					Sequence::Older => {
+						return vec![GooseEvent::OutOfOrder { gocb_ref, st_num, sq_num }];
+					}
+				}
+				false
+			}
+			Some((last_st_num, _)) => match compare(st_num, last_st_num) {
+				// The first message of a new state has the sequence number 0.
+				Sequence::Next if sq_num == 0 => true,
+				Sequence::Next | Sequence::Gap => {
+					events.push(GooseEvent::MessageLoss { gocb_ref, st_num, sq_num });
+					true
+				}
+				// A restarted publisher starts again from the first state. Accept
+				// it once the last message has expired.
+				_ if self.deadline.is_none() => true,
+				_ => return vec![GooseEvent::OutOfOrder { gocb_ref, st_num, sq_num }],
+			},
+		};
+
+		self.last = Some((st_num, sq_num));
+		self.deadline = Some(now + Duration::from_millis(u64::from(message.time_allowed_to_live)));
+		if state_change {
+			events.push(GooseEvent::StateChange(message));
+		}
+		events
+	}
+}
+
+/// A GOOSE subscriber.
+pub struct GooseSubscriber {
+	/// The state of the subscriptions.
+	subscriptions: Vec<SubscriptionState>,
+	/// The callback for the events.
+	callback: Box<dyn GooseCallback + Send + Sync>,
+}
+
+impl std::fmt::Debug for GooseSubscriber {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("GooseSubscriber").field("subscriptions", &self.subscriptions).finish()
+	}
+}
+
+impl GooseSubscriber {
+	/// Create a new GOOSE subscriber.
+	#[must_use]
+	pub fn new(callback: Box<dyn GooseCallback + Send + Sync>) -> Self {
+		Self { subscriptions: Vec::new(), callback }
+	}
+
+	/// Subscribe to a GOOSE control block.
+	pub fn subscribe(&mut self, subscription: GooseSubscription) {
+		self.subscriptions.push(SubscriptionState::new(subscription));
+	}
+
+	/// Receive frames from the source until it is exhausted.
+	#[instrument(skip_all)]
+	pub async fn run(&mut self, mut source: impl FrameSource) -> Result<(), GooseSubscriberError> {
+		loop {
+			let next_deadline = self.next_deadline();
+			select! {
+				frame = source.recv_frame() => match frame.context(Source)? {
+					Some(frame) => self.handle_frame(&frame).await,
+					None => return Ok(()),
+				},
+				() = time::sleep_until(next_deadline.unwrap_or_else(Instant::now)),
+					if next_deadline.is_some() => self.handle_expirations(Instant::now()).await,
+			}
+		}
+	}
+
+	/// Handle a received ethernet frame. Frames that are not GOOSE messages or
+	/// don't belong to any subscription are ignored.
+	pub async fn handle_frame(&mut self, bytes: &[u8]) {
+		let frame = match EthernetFrame::decode(bytes) {
+			Ok(frame) if frame.ether_type == GOOSE_ETHER_TYPE => frame,
+			Ok(_) => return,
+			Err(e) => {
+				tracing::debug!("Invalid ethernet frame: {e}");
+				return;
+			}
+		};
+		let frame = match GooseFrame::try_from(frame) {
+			Ok(frame) => frame,
+			Err(e) => {
+				tracing::warn!("Invalid GOOSE message: {e}");
+				return;
+			}
+		};
+
+		let Some(state) =
+			self.subscriptions.iter_mut().find(|state| state.subscription.matches(&frame))
+		else {
+			return;
+		};
+		for event in state.handle_message(frame.message, Instant::now()) {
+			self.callback.on_event(event).await;
+		}
+	}
+
+	/// Get the earliest expiration of the subscriptions.
+	fn next_deadline(&self) -> Option<Instant> {
+		self.subscriptions.iter().filter_map(|state| state.deadline).min()
+	}
+
+	/// Report the subscriptions whose time allowed to live has expired at
+	/// `now`.
+	async fn handle_expirations(&mut self, now: Instant) {
+		for state in &mut self.subscriptions {
+			if state.deadline.is_some_and(|deadline| deadline <= now) {
+				state.deadline = None;
+				self.callback
+					.on_event(GooseEvent::Expired { gocb_ref: state.subscription.gocb_ref.clone() })
+					.await;
+			}
+		}
+	}
+}
+
+/// The error type for the GOOSE subscriber.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum GooseSubscriberError {
+	#[snafu(display("Error receiving a frame"))]
+	Source { source: io::Error },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use ::time::OffsetDateTime;
+	use tokio::sync::Mutex;
+
+	use super::*;
+
+	/// A callback that stores the received events.
+	struct TestCallback(Arc<Mutex<Vec<GooseEvent>>>);
+
+	#[async_trait]
+	impl GooseCallback for TestCallback {
+		async fn on_event(&self, event: GooseEvent) {
+			self.0.lock().await.push(event);
+		}
+	}
+
+	fn message(st_num: u32, sq_num: u32) -> GooseMessage {
+		GooseMessage {
+			gocb_ref: "LD/LLN0$GO$gcb1".to_owned(),
+			time_allowed_to_live: 20,
+			dataset: "LD/LLN0$DS1".to_owned(),
+			go_id: None,
+			timestamp: OffsetDateTime::UNIX_EPOCH,
+			st_num,
+			sq_num,
+			simulation: false,
+			config_rev: 1,
+			needs_commissioning: false,
+			number_of_entries: 0,
+			data: Vec::new(),
+		}
+	}
+
+	fn frame(message: GooseMessage) -> Vec<u8> {
+		GooseFrame {
+			destination: [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01],
+			source: [0; 6],
+			vlan: None,
+			app_id: 0x1000,
+			message,
+		}
+		.encode()
+		.unwrap()
+	}
+
+	#[test]
+	fn test_supervision() {
+		let mut state = SubscriptionState::new(GooseSubscription {
+			config_rev: Some(1),
+			..GooseSubscription::new("LD/LLN0$GO$gcb1")
+		});
+		let now = Instant::now();
+		let gocb_ref = "LD/LLN0$GO$gcb1".to_owned();
+
+		assert_eq!(
+			state.handle_message(message(1, 0), now),
+			[GooseEvent::StateChange(message(1, 0))]
+		);
+		assert_eq!(state.handle_message(message(1, 1), now), []);
+		assert_eq!(
+			state.handle_message(message(1, 1), now),
+			[GooseEvent::Duplicate { gocb_ref: gocb_ref.clone(), st_num: 1, sq_num: 1 }]
+		);
+		assert_eq!(
+			state.handle_message(message(1, 3), now),
+			[GooseEvent::MessageLoss { gocb_ref: gocb_ref.clone(), st_num: 1, sq_num: 3 }]
+		);
+		assert_eq!(
+			state.handle_message(message(1, 2), now),
+			[GooseEvent::OutOfOrder { gocb_ref: gocb_ref.clone(), st_num: 1, sq_num: 2 }]
+		);
+		assert_eq!(
+			state.handle_message(message(2, 0), now),
+			[GooseEvent::StateChange(message(2, 0))]
+		);
+		assert_eq!(
+			state.handle_message(message(4, 1), now),
+			[
+				GooseEvent::MessageLoss { gocb_ref: gocb_ref.clone(), st_num: 4, sq_num: 1 },
+				GooseEvent::StateChange(message(4, 1)),
+			]
+		);
+		assert_eq!(
+			state.handle_message(message(3, 0), now),
+			[GooseEvent::OutOfOrder { gocb_ref: gocb_ref.clone(), st_num: 3, sq_num: 0 }]
+		);
+
+		// The discarded messages are reported once, not on every retransmission.
+		let mut wrong_revision = message(5, 0);
+		wrong_revision.config_rev = 2;
+		assert_eq!(
+			state.handle_message(wrong_revision.clone(), now),
+			[GooseEvent::ConfigRevisionMismatch {
+				gocb_ref: gocb_ref.clone(),
+				expected: 1,
+				received: 2
+			}]
+		);
+		assert_eq!(state.handle_message(wrong_revision, now), []);
+		let mut needs_commissioning = message(5, 0);
+		needs_commissioning.needs_commissioning = true;
+		assert_eq!(
+			state.handle_message(needs_commissioning.clone(), now),
+			[GooseEvent::NeedsCommissioning { gocb_ref: gocb_ref.clone() }]
+		);
+		assert_eq!(state.handle_message(needs_commissioning.clone(), now), []);
+		assert_eq!(
+			state.handle_message(message(5, 0), now),
+			[GooseEvent::StateChange(message(5, 0))]
+		);
+		assert_eq!(
+			state.handle_message(needs_commissioning, now),
+			[GooseEvent::NeedsCommissioning { gocb_ref }]
+		);
+
+		// A restarted publisher is accepted once the last message has expired.
+		state.deadline = None;
+		assert_eq!(
+			state.handle_message(message(1, 0), now),
+			[GooseEvent::StateChange(message(1, 0))]
+		);
+	}
+
+	#[test]
+	fn test_rollover() {
+		let mut state = SubscriptionState::new(GooseSubscription::new("LD/LLN0$GO$gcb1"));
+		let now = Instant::now();
+
+		// The sequence number wraps around from u32::MAX to 1.
+		state.last = Some((1, u32::MAX));
+		assert_eq!(state.handle_message(message(1, 1), now), []);
+		assert_eq!(state.handle_message(message(1, 2), now), []);
+
+		// The state number wraps around from u32::MAX to 1.
+		state.last = Some((u32::MAX, 5));
+		assert_eq!(
+			state.handle_message(message(1, 0), now),
+			[GooseEvent::StateChange(message(1, 0))]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_subscriber_expiration() {
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let mut subscriber = GooseSubscriber::new(Box::new(TestCallback(events.clone())));
+		subscriber.subscribe(GooseSubscription {
+			app_id: Some(0x1000),
+			..GooseSubscription::new("LD/LLN0$GO$gcb1")
+		});
+
+		let mut other = message(1, 0);
+		other.gocb_ref = "LD/LLN0$GO$gcb2".to_owned();
+		subscriber.handle_frame(&frame(other)).await;
+		subscriber.handle_frame(&frame(message(1, 0))).await;
+
+		let deadline = subscriber.next_deadline().unwrap();
+		subscriber.handle_expirations(deadline - Duration::from_millis(1)).await;
+		subscriber.handle_expirations(deadline).await;
+		// The expiration is reported once.
+		subscriber.handle_expirations(deadline + Duration::from_millis(1)).await;
+		assert_eq!(subscriber.next_deadline(), None);
+		assert_eq!(
+			*events.lock().await,
+			[
+				GooseEvent::StateChange(message(1, 0)),
+				GooseEvent::Expired { gocb_ref: "LD/LLN0$GO$gcb1".to_owned() },
+			]
+		);
+	}
+}
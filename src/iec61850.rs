@@ -1,29 +1,67 @@
 //! IEC 61850 client implementation.
 
-use std::{collections::HashMap, fmt, str::Utf8Error};
+use std::{
+	collections::HashMap,
+	fmt,
+	str::Utf8Error,
+	sync::{Arc, Mutex, PoisonError, atomic::AtomicU8},
+};
 
 use rasn::prelude::VisibleString;
 use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use time::OffsetDateTime;
+use tokio::sync::watch;
 use tracing::instrument;
 
+pub mod control;
 pub mod data;
+pub mod gocb;
+pub mod lcb;
 pub mod model;
 pub mod rcb;
+pub mod reconnect;
 pub mod report;
+pub mod server;
+pub mod sgcb;
+pub mod svcb;
 
 use crate::{
 	iec61850::{
+		control::LastApplError,
 		data::{Iec61850Data, Iec61850DataError},
+		gocb::{DestinationAddress, GooseControlBlock, GooseControlBlockError},
+		lcb::{LogControlBlock, LogControlBlockError, LogEntry},
 		model::{IedModel, LogicalDevice, LogicalNode},
 		rcb::{OptionalFields, ReportControlBlock, ReportControlBlockError, TriggerOptions},
+		reconnect::{
+			RcbRestoreFailure, RcbSettings, RcbWrite, Reconnection, ReportTracker,
+			apply_restore_writes,
+		},
+		sgcb::{SGCB_PATH, SettingGroupControlBlock, SettingGroupControlBlockError},
+		svcb::{
+			SampleMode, SampledValueControlBlock, SampledValueControlBlockError,
+			SampledValueOptionalFields,
+		},
 	},
 	mms::{
 		ClientConfig, MmsObjectClass, ReportCallback,
 		ans1::mms::asn1::*,
-		client::{MmsClient, MmsClientError},
+		capabilities::{MmsService, ServerCapabilities},
+		client::{ConnectionState, MmsClient, MmsClientError, NegotiatedParameters},
+		error::{DataAccessError, RejectReason, ServiceError},
 	},
 };
 
+/// The functional constraints of the report control blocks.
+const RCB_FUNCTIONAL_CONSTRAINTS: &[&str] = &["BR", "RP"];
+/// The functional constraint of the log control blocks.
+const LCB_FUNCTIONAL_CONSTRAINTS: &[&str] = &["LG"];
+/// The functional constraint of the GOOSE control blocks.
+const GOCB_FUNCTIONAL_CONSTRAINTS: &[&str] = &["GO"];
+/// The functional constraints of the multicast and unicast sampled value
+/// control blocks.
+const SVCB_FUNCTIONAL_CONSTRAINTS: &[&str] = &["MS", "US"];
+
 /// An IEC 61850 client.
 #[derive(Debug)]
 pub struct Iec61850Client {
@@ -31,6 +69,17 @@ pub struct Iec61850Client {
 	client: MmsClient,
 	/// The IEC 61850 model.
 	ied_model: IedModel,
+	/// The client configuration, used to reconnect.
+	config: ClientConfig,
+	/// The report callback, keeping track of the last entry ids.
+	report_tracker: ReportTracker,
+	/// The report control block settings done by the application.
+	rcb_settings: Mutex<HashMap<String, RcbSettings>>,
+	/// The control number of the next control.
+	ctl_num: AtomicU8,
+	/// The control number of the last command selected or operated on each
+	/// control object, used again to cancel it.
+	command_ctl_nums: Mutex<HashMap<String, u8>>,
 }
 
 impl Iec61850Client {
@@ -38,15 +87,138 @@ impl Iec61850Client {
 	pub async fn new(
 		config: ClientConfig,
 		report_callback: Box<dyn ReportCallback + Send + Sync>,
+	) -> Result<Self, Iec61850ClientError> {
+		Self::connect(config, ReportTracker::new(Arc::from(report_callback))).await
+	}
+
+	/// Connect to the ied and load its model.
+	async fn connect(
+		config: ClientConfig,
+		report_tracker: ReportTracker,
 	) -> Result<Self, Iec61850ClientError> {
 		let mut client = Self {
-			client: MmsClient::connect(&config, report_callback).await?,
+			client: MmsClient::connect(&config, Box::new(report_tracker.clone())).await?,
 			ied_model: IedModel::default(),
+			config,
+			report_tracker,
+			rcb_settings: Mutex::default(),
+			ctl_num: AtomicU8::default(),
+			command_ctl_nums: Mutex::default(),
 		};
 		client.reload_ied_model().await?;
 		Ok(client)
 	}
 
+	/// Reconnect to the ied.
+	/// The model is reloaded and every report control block setting done
+	/// through the `set_rcb_*` methods is applied again. Buffered report
+	/// control blocks are resumed from the last received entry id.
+	/// The settings are not restored if the model of the ied changed, see
+	/// [`Reconnection::same_model`]. A setting that can not be restored
+	/// doesn't fail the reconnect, it is returned in
+	/// [`Reconnection::rcb_failures`].
+	#[instrument(skip(self))]
+	pub async fn reconnect(&mut self) -> Result<Reconnection, Iec61850ClientError> {
+		let connection = Self::connect(self.config.clone(), self.report_tracker.clone()).await?;
+		let same_model = self.replace_connection(connection);
+		Ok(self.resume(same_model).await)
+	}
+
+	/// Use the connection and the model of a client connected again to the
+	/// ied. Returns whether the model has the same structure as the cached
+	/// one.
+	fn replace_connection(&mut self, connection: Self) -> bool {
+		let same_model = self.ied_model.has_same_structure(&connection.ied_model);
+		if !same_model {
+			tracing::warn!("The model of the ied changed after reconnecting");
+		}
+		self.client = connection.client;
+		self.ied_model = connection.ied_model;
+		same_model
+	}
+
+	/// Restore the report control block settings after a reconnect, unless
+	/// the model of the ied changed.
+	async fn resume(&self, same_model: bool) -> Reconnection {
+		let rcb_failures = if same_model {
+			self.restore_rcbs().await
+		} else {
+			tracing::warn!("Report control block settings not restored, the model changed");
+			Vec::new()
+		};
+		Reconnection { same_model, rcb_failures }
+	}
+
+	/// Apply again the report control block settings done by the application.
+	/// Every report control block is read back first and only the attributes
+	/// that differ are written. Returns the attributes that could not be
+	/// restored.
+	#[instrument(skip(self))]
+	async fn restore_rcbs(&self) -> Vec<RcbRestoreFailure> {
+		let rcb_settings = self.rcb_settings.lock().unwrap_or_else(PoisonError::into_inner).clone();
+		let mut failures = Vec::new();
+		for (path, settings) in rcb_settings {
+			let path = ObjectPath::from(path);
+			let current = match self.get_rcb(&path).await {
+				Ok(current) => current,
+				Err(e) => {
+					tracing::warn!("Error reading report control block {path}, skipping: {e}");
+					failures.push(RcbRestoreFailure {
+						rcb: path.to_string(),
+						attribute: None,
+						error: Arc::new(e),
+					});
+					continue;
+				}
+			};
+			let entry_id = match &current {
+				ReportControlBlock::Buffered(rcb) if rcb.id.is_empty() => {
+					self.report_tracker.entry_id(&path.to_string())
+				}
+				ReportControlBlock::Buffered(rcb) => self.report_tracker.entry_id(&rcb.id),
+				ReportControlBlock::Unbuffered(_) => None,
+			};
+
+			let writes = settings.restore_writes(&current, entry_id);
+			for (attribute, e) in
+				apply_restore_writes(writes, |write| self.write_rcb_setting(&path, write)).await
+			{
+				tracing::warn!("Error restoring {attribute} of report control block {path}: {e}");
+				failures.push(RcbRestoreFailure {
+					rcb: path.to_string(),
+					attribute: Some(attribute),
+					error: Arc::new(e),
+				});
+			}
+		}
+		failures
+	}
+
+	/// Write a restored setting of a report control block.
+	async fn write_rcb_setting(
+		&self,
+		path: &ObjectPath,
+		write: RcbWrite,
+	) -> Result<(), Iec61850ClientError> {
+		match write {
+			RcbWrite::Dataset(dataset) => self.set_rcb_dataset(path, &dataset).await,
+			RcbWrite::OptionalFields(fields) => self.set_rcb_optional_fields(path, fields).await,
+			RcbWrite::TriggerOptions(options) => self.set_rcb_trigger_options(path, options).await,
+			RcbWrite::IntegrityPeriod(period) => self.set_rcb_integrity_period(path, period).await,
+			RcbWrite::BufferTime(buffer_time) => self.set_rcb_buffer_time(path, buffer_time).await,
+			RcbWrite::EntryId(entry_id) => self.set_rcb_entry_id(path, entry_id).await,
+			RcbWrite::Enabled(enabled) => self.set_rcb_enabled(path, enabled).await,
+			RcbWrite::Gi(gi) => self.set_rcb_gi(path, gi).await,
+		}
+	}
+
+	/// Record a report control block setting so it can be restored after a
+	/// reconnect.
+	fn record_rcb_setting(&self, path: &ObjectPath, update: impl FnOnce(&mut RcbSettings)) {
+		let mut rcb_settings = self.rcb_settings.lock().unwrap_or_else(PoisonError::into_inner);
+		update(rcb_settings.entry(path.to_string()).or_default());
+	}
+
 	/// Reload the model from the ied
 	pub async fn reload_ied_model(&mut self) -> Result<(), Iec61850ClientError> {
 		let model = self.get_ied_model().await?;
@@ -54,6 +226,43 @@ impl Iec61850Client {
 		Ok(())
 	}
 
+	/// Get a receiver for the connection state.
+	/// The receiver is notified every time the state changes.
+	#[must_use]
+	pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+		self.client.connection_state()
+	}
+
+	/// Check if the association is established.
+	#[must_use]
+	pub fn is_connected(&self) -> bool {
+		self.client.is_connected()
+	}
+
+	/// Get the parameters negotiated with the server.
+	#[must_use]
+	pub const fn negotiated_parameters(&self) -> NegotiatedParameters {
+		self.client.negotiated_parameters()
+	}
+
+	/// Get the services and parameters supported by the server.
+	#[must_use]
+	pub fn server_capabilities(&self) -> &ServerCapabilities {
+		self.client.server_capabilities()
+	}
+
+	/// Abort the association with the server.
+	pub async fn abort(&self) -> Result<(), Iec61850ClientError> {
+		Ok(self.client.abort().await?)
+	}
+
+	/// Gracefully close the association with the server.
+	/// The MMS context is concluded and the ACSE association released before
+	/// the connection is closed.
+	pub async fn close(&self) -> Result<(), Iec61850ClientError> {
+		Ok(self.client.conclude().await?)
+	}
+
 	/// Get the IED model.
 	#[must_use]
 	pub const fn model(&self) -> &IedModel {
@@ -71,11 +280,11 @@ impl Iec61850Client {
 			.collect::<Vec<_>>();
 
 		for ld in &mut logical_devices {
-			let mut logical_nodes = self
-				.get_logical_nodes_names(&ld.name)
-				.await?
-				.into_iter()
-				.map(|ln| LogicalNode::new(ln, &ld.name))
+			let names = self.get_variable_names(&ld.name).await?;
+			let mut logical_nodes = names
+				.iter()
+				.filter(|name| !name.contains('$'))
+				.map(|ln| LogicalNode::new(ln.clone(), &ld.name))
 				.collect::<Vec<_>>();
 
 			// Build the logical node tree.
@@ -83,17 +292,59 @@ impl Iec61850Client {
 				ln.parse_nodes(self.get_data_definition(&ld.name, &ln.name).await?);
 			}
 
+			// The setting group control block is part of LLN0.
+			let (sgcb_ln, sgcb_item) = SGCB_PATH.split_once('$').context(InvalidPath)?;
+			if let Some(ln) = logical_nodes
+				.iter_mut()
+				.find(|ln| ln.name == sgcb_ln && ln.find_node(sgcb_item).is_some())
+			{
+				match self.get_sgcb(&ld.name).await {
+					Ok(sgcb) => ln.setting_group_control_block = Some(sgcb),
+					Err(e) => tracing::warn!(
+						"Error reading the setting group control block of {}, skipping: {e}",
+						ld.name
+					),
+				}
+			}
+
 			// TODO: Rethink this to optimize memory allocation.
 			ld.logical_nodes.extend(logical_nodes);
 
-			let reports = self.get_rcbs(&ld.name).await?;
 			let mut report_rcbs = Vec::new();
-			for report in reports {
+			for report in control_block_names(&names, RCB_FUNCTIONAL_CONSTRAINTS) {
 				let rcb = self.get_rcb(&(&ld.name, &report).into()).await?;
 				report_rcbs.push((report, rcb));
 			}
 			ld.add_reports(report_rcbs).context(Model)?;
 
+			let lcbs = self
+				.read_control_blocks(
+					&ld.name,
+					&names,
+					LCB_FUNCTIONAL_CONSTRAINTS,
+					|path| async move { self.get_lcb(&path).await },
+				)
+				.await;
+			ld.add_control_blocks(lcbs).context(Model)?;
+			let gocbs = self
+				.read_control_blocks(
+					&ld.name,
+					&names,
+					GOCB_FUNCTIONAL_CONSTRAINTS,
+					|path| async move { self.get_gocb(&path).await },
+				)
+				.await;
+			ld.add_control_blocks(gocbs).context(Model)?;
+			let svcbs = self
+				.read_control_blocks(
+					&ld.name,
+					&names,
+					SVCB_FUNCTIONAL_CONSTRAINTS,
+					|path| async move { self.get_svcb(&path).await },
+				)
+				.await;
+			ld.add_control_blocks(svcbs).context(Model)?;
+
 			let datasets = self.get_datasets(Some(&ld.name)).await?;
 			let mut dataset_entries = HashMap::new();
 			for dataset in datasets {
@@ -105,6 +356,31 @@ impl Iec61850Client {
 		Ok(IedModel { logical_devices })
 	}
 
+	/// Read the control blocks with one of the functional constraints among
+	/// the variable names of a logical device. A control block that cannot
+	/// be read is logged and left out.
+	async fn read_control_blocks<T, F>(
+		&self,
+		logical_device: &str,
+		names: &[String],
+		functional_constraints: &[&str],
+		read: impl Fn(ObjectPath) -> F,
+	) -> Vec<T>
+	where
+		F: Future<Output = Result<T, Iec61850ClientError>>,
+	{
+		let mut control_blocks = Vec::new();
+		for name in control_block_names(names, functional_constraints) {
+			match read((logical_device, name.as_str()).into()).await {
+				Ok(control_block) => control_blocks.push(control_block),
+				Err(e) => tracing::warn!(
+					"Error reading control block {logical_device}/{name}, skipping: {e}"
+				),
+			}
+		}
+		control_blocks
+	}
+
 	/// Get the names of the logical devices.
 	#[instrument(skip(self))]
 	pub async fn get_logical_devices_names(&self) -> Result<Vec<String>, Iec61850ClientError> {
@@ -122,6 +398,18 @@ impl Iec61850Client {
 	pub async fn get_logical_nodes_names(
 		&self,
 		logical_device: &str,
+	) -> Result<Vec<String>, Iec61850ClientError> {
+		self.get_variable_names(logical_device)
+			.await
+			.map(|nodes| nodes.into_iter().filter(|node| !node.contains("$")).collect())
+	}
+
+	/// Get the names of all the variables in a logical device, from the
+	/// logical nodes to the data attributes.
+	#[instrument(skip(self))]
+	async fn get_variable_names(
+		&self,
+		logical_device: &str,
 	) -> Result<Vec<String>, Iec61850ClientError> {
 		self.client
 			.get_name_list(
@@ -130,7 +418,6 @@ impl Iec61850Client {
 			)
 			.await
 			.map_err(Into::into)
-			.map(|nodes| nodes.into_iter().filter(|node| !node.contains("$")).collect())
 	}
 
 	/// Get the datasets in a logical device.
@@ -280,43 +567,57 @@ impl Iec61850Client {
 		path: &ObjectPath,
 		data: Iec61850Data,
 	) -> Result<(), Iec61850ClientError> {
-		let path = path.get_split_path()?;
+		let result = self.set_data_values(&[(path.clone(), data)]).await?.pop();
+		result.context(InvalidDataLength)?.map_err(|error| DataAccess { error }.build())
+	}
 
-		let variable_access_specification = VariableDefs(vec![AnonymousVariableDefs::new(
-			VariableSpecification::name(ObjectName::domain_specific(
-				ObjectNameDomainSpecific::new(to_identifier(path.0)?, to_identifier(path.1)?),
-			)),
-			None,
-		)])
-		.into();
+	/// Set the data values of multiple paths.
+	/// The writes to the same logical device are sent in a single request.
+	/// Returns the result of every path, in the order they were given. If a
+	/// request fails, the requests to the previous logical devices have
+	/// already been written.
+	pub async fn set_data_values(
+		&self,
+		values: &[(ObjectPath, Iec61850Data)],
+	) -> Result<Vec<Result<(), DataAccessError>>, Iec61850ClientError> {
+		let mut batches: Vec<(&str, Vec<usize>)> = Vec::new();
+		for (index, (path, _)) in values.iter().enumerate() {
+			let logical_device = path.get_split_path()?.0;
+			match batches.iter_mut().find(|(ld, _)| *ld == logical_device) {
+				Some((_, indexes)) => indexes.push(index),
+				None => batches.push((logical_device, vec![index])),
+			}
+		}
 
-		let list_of_data = vec![data.try_into().context(ConvertDataToMmsData)?];
+		let mut results = vec![Ok(()); values.len()];
+		for (logical_device, indexes) in batches {
+			let mut variables = Vec::with_capacity(indexes.len());
+			let mut list_of_data = Vec::with_capacity(indexes.len());
+			for &index in &indexes {
+				let (path, data) = &values[index];
+				variables.push(make_variable_def(logical_device, path.get_split_path()?.1)?);
+				list_of_data.push(data.clone().try_into().context(ConvertDataToMmsData)?);
+			}
 
-		self.client.write(variable_access_specification, list_of_data).await.map_err(Into::into)
+			let batch_results =
+				self.client.write(VariableDefs(variables).into(), list_of_data).await?;
+			if batch_results.len() != indexes.len() {
+				return InvalidDataLength.fail();
+			}
+			for (index, result) in indexes.into_iter().zip(batch_results) {
+				results[index] = result;
+			}
+		}
+		Ok(results)
 	}
 
 	/// Get all the report control blocks in a logical device.
 	#[instrument(skip(self))]
 	pub async fn get_rcbs(&self, logical_device: &str) -> Result<Vec<String>, Iec61850ClientError> {
-		self.client
-			.get_name_list(
-				MmsObjectClass::NamedVariable as u8,
-				GetNameListRequestObjectScope::domainSpecific(to_identifier(logical_device)?),
-			)
-			.await
-			.map_err(Into::into)
-			.map(|nodes| {
-				nodes
-					.into_iter()
-					.filter(|node| {
-						let mut parts = node.splitn(4, '$');
-						matches!(
-							(parts.next(), parts.next(), parts.next(), parts.next()),
-							(Some(_), Some("BR" | "RP"), Some(_), None)
-						)
-					})
-					.collect()
-			})
+		Ok(control_block_names(
+			&self.get_variable_names(logical_device).await?,
+			RCB_FUNCTIONAL_CONSTRAINTS,
+		))
 	}
 
 	/// Get a report control block by its path in a logical device.
@@ -357,7 +658,9 @@ impl Iec61850Client {
 	#[instrument(skip(self))]
 	pub async fn set_rcb_gi(&self, path: &ObjectPath, gi: bool) -> Result<(), Iec61850ClientError> {
 		let data = Iec61850Data::Bool(gi);
-		self.set_data_value(&format!("{path}$GI").into(), data).await
+		self.set_data_value(&format!("{path}$GI").into(), data).await?;
+		self.record_rcb_setting(path, |settings| settings.gi = Some(gi));
+		Ok(())
 	}
 
 	/// Set the enabled state of a report control block.
@@ -368,7 +671,9 @@ impl Iec61850Client {
 		enabled: bool,
 	) -> Result<(), Iec61850ClientError> {
 		let data = Iec61850Data::Bool(enabled);
-		self.set_data_value(&format!("{path}$RptEna").into(), data).await
+		self.set_data_value(&format!("{path}$RptEna").into(), data).await?;
+		self.record_rcb_setting(path, |settings| settings.enabled = Some(enabled));
+		Ok(())
 	}
 
 	/// Set the dataset of a report control block.
@@ -378,8 +683,10 @@ impl Iec61850Client {
 		path: &ObjectPath,
 		dataset: &str,
 	) -> Result<(), Iec61850ClientError> {
-		let data = Iec61850Data::String(dataset.trim_start_matches("@").to_owned());
-		self.set_data_value(&format!("{path}$DatSet").into(), data).await
+		let data = Iec61850Data::String(dataset_reference(dataset).to_owned());
+		self.set_data_value(&format!("{path}$DatSet").into(), data).await?;
+		self.record_rcb_setting(path, |settings| settings.dataset = Some(dataset.to_owned()));
+		Ok(())
 	}
 
 	/// Set the integrity period of a report control block.
@@ -390,7 +697,11 @@ impl Iec61850Client {
 		integrity_period: u32,
 	) -> Result<(), Iec61850ClientError> {
 		let data = Iec61850Data::Unsigned(integrity_period);
-		self.set_data_value(&format!("{path}$IntgPd").into(), data).await
+		self.set_data_value(&format!("{path}$IntgPd").into(), data).await?;
+		self.record_rcb_setting(path, |settings| {
+			settings.integrity_period = Some(integrity_period);
+		});
+		Ok(())
 	}
 
 	/// Set the buffer time of a report control block.
@@ -401,7 +712,9 @@ impl Iec61850Client {
 		buffer_time: u32,
 	) -> Result<(), Iec61850ClientError> {
 		let data = Iec61850Data::Unsigned(buffer_time);
-		self.set_data_value(&format!("{path}$BufTm").into(), data).await
+		self.set_data_value(&format!("{path}$BufTm").into(), data).await?;
+		self.record_rcb_setting(path, |settings| settings.buffer_time = Some(buffer_time));
+		Ok(())
 	}
 
 	/// Set the trigger options of a report control block.
@@ -411,7 +724,10 @@ impl Iec61850Client {
 		path: &ObjectPath,
 		trigger_options: Vec<TriggerOptions>,
 	) -> Result<(), Iec61850ClientError> {
-		self.set_data_value(&format!("{path}$TrgOps").into(), trigger_options.into()).await
+		self.set_data_value(&format!("{path}$TrgOps").into(), trigger_options.clone().into())
+			.await?;
+		self.record_rcb_setting(path, |settings| settings.trigger_options = Some(trigger_options));
+		Ok(())
 	}
 
 	/// Set the optional fields of a report control block.
@@ -420,31 +736,424 @@ impl Iec61850Client {
 		&self,
 		path: &ObjectPath,
 		optional_fields: Vec<OptionalFields>,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&format!("{path}$OptFlds").into(), optional_fields.clone().into())
+			.await?;
+		self.record_rcb_setting(path, |settings| settings.optional_fields = Some(optional_fields));
+		Ok(())
+	}
+
+	/// Get all the log control blocks in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_lcbs(&self, logical_device: &str) -> Result<Vec<String>, Iec61850ClientError> {
+		Ok(control_block_names(
+			&self.get_variable_names(logical_device).await?,
+			LCB_FUNCTIONAL_CONSTRAINTS,
+		))
+	}
+
+	/// Get a log control block by its path in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_lcb(&self, path: &ObjectPath) -> Result<LogControlBlock, Iec61850ClientError> {
+		let (logical_device, log_control_block) = path.get_split_path()?;
+		match self.read_data_from_ld(logical_device, &[log_control_block]).await?.pop() {
+			Some(Iec61850Data::Structure(data)) => {
+				LogControlBlock::from_data(log_control_block.to_owned(), data)
+					.context(CreateLogControlBlock)
+			}
+			Some(_) => InvalidData.fail(),
+			None => InvalidDataLength.fail(),
+		}
+	}
+
+	/// Query the entries of a log added between two times.
+	/// The log reference is the `LogRef` of a log control block, e.g.
+	/// `LD/LLN0$EventLog`.
+	#[instrument(skip(self))]
+	pub async fn query_log_by_time(
+		&self,
+		log_ref: &ObjectPath,
+		start: OffsetDateTime,
+		end: OffsetDateTime,
+	) -> Result<Vec<LogEntry>, Iec61850ClientError> {
+		self.read_log(
+			log_ref,
+			Some(ReadJournalRequestRangeStartSpecification::startingTime(start.into())),
+			Some(ReadJournalRequestRangeStopSpecification::endingTime(end.into())),
+			None,
+		)
+		.await
+	}
+
+	/// Query the entries of a log added after an entry.
+	/// The time and the id are the ones of the entry to start after.
+	#[instrument(skip(self))]
+	pub async fn query_log_after_entry(
+		&self,
+		log_ref: &ObjectPath,
+		time: OffsetDateTime,
+		entry_id: Vec<u8>,
+	) -> Result<Vec<LogEntry>, Iec61850ClientError> {
+		let after = ReadJournalRequestEntryToStartAfter::new(time.into(), entry_id.into());
+		self.read_log(log_ref, None, None, Some(after)).await
+	}
+
+	/// Read the entries of a log.
+	/// While the server has more entries, they are requested starting after
+	/// the last received one.
+	async fn read_log(
+		&self,
+		log_ref: &ObjectPath,
+		mut start: Option<ReadJournalRequestRangeStartSpecification>,
+		stop: Option<ReadJournalRequestRangeStopSpecification>,
+		mut after: Option<ReadJournalRequestEntryToStartAfter>,
+	) -> Result<Vec<LogEntry>, Iec61850ClientError> {
+		let (logical_device, log) = log_ref.get_split_path()?;
+		let journal_name = ObjectName::domain_specific(ObjectNameDomainSpecific::new(
+			to_identifier(logical_device)?,
+			to_identifier(log)?,
+		));
+
+		let mut entries = Vec::new();
+		loop {
+			let response = self
+				.client
+				.read_journal(journal_name.clone(), start.take(), stop.clone(), after.take())
+				.await?;
+			let received = response.list_of_journal_entry.len();
+			for entry in response.list_of_journal_entry {
+				entries.push(LogEntry::try_from(entry).context(ConvertDataToMmsData)?);
+			}
+			let Some(last) = entries.last() else {
+				break;
+			};
+			if !response.more_follows || received == 0 {
+				break;
+			}
+			after = Some(ReadJournalRequestEntryToStartAfter::new(
+				last.time.into(),
+				last.entry_id.clone().into(),
+			));
+		}
+		Ok(entries)
+	}
+
+	/// Get all the GOOSE control blocks in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_gocbs(
+		&self,
+		logical_device: &str,
+	) -> Result<Vec<String>, Iec61850ClientError> {
+		Ok(control_block_names(
+			&self.get_variable_names(logical_device).await?,
+			GOCB_FUNCTIONAL_CONSTRAINTS,
+		))
+	}
+
+	/// Get a GOOSE control block by its path in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_gocb(
+		&self,
+		path: &ObjectPath,
+	) -> Result<GooseControlBlock, Iec61850ClientError> {
+		let (logical_device, goose_control_block) = path.get_split_path()?;
+		match self.read_data_from_ld(logical_device, &[goose_control_block]).await?.pop() {
+			Some(Iec61850Data::Structure(data)) => {
+				GooseControlBlock::from_data(goose_control_block.to_owned(), data)
+					.context(CreateGooseControlBlock)
+			}
+			Some(_) => InvalidData.fail(),
+			None => InvalidDataLength.fail(),
+		}
+	}
+
+	/// Enable or disable the publication of a GOOSE control block.
+	#[instrument(skip(self))]
+	pub async fn set_gocb_enabled(
+		&self,
+		path: &ObjectPath,
+		enabled: bool,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&format!("{path}$GoEna").into(), Iec61850Data::Bool(enabled)).await
+	}
+
+	/// Set the dataset published by a GOOSE control block.
+	/// The publication must be disabled to change the dataset.
+	#[instrument(skip(self))]
+	pub async fn set_gocb_dataset(
+		&self,
+		path: &ObjectPath,
+		dataset: &str,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(
+			&format!("{path}$DatSet").into(),
+			Iec61850Data::String(dataset_reference(dataset).to_owned()),
+		)
+		.await
+	}
+
+	/// Get all the sampled value control blocks in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_svcbs(
+		&self,
+		logical_device: &str,
+	) -> Result<Vec<String>, Iec61850ClientError> {
+		Ok(control_block_names(
+			&self.get_variable_names(logical_device).await?,
+			SVCB_FUNCTIONAL_CONSTRAINTS,
+		))
+	}
+
+	/// Get a sampled value control block by its path in a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_svcb(
+		&self,
+		path: &ObjectPath,
+	) -> Result<SampledValueControlBlock, Iec61850ClientError> {
+		let (logical_device, sampled_value_control_block) = path.get_split_path()?;
+		match self.read_data_from_ld(logical_device, &[sampled_value_control_block]).await?.pop() {
+			Some(Iec61850Data::Structure(data)) => {
+				SampledValueControlBlock::from_data(sampled_value_control_block.to_owned(), data)
+					.context(CreateSampledValueControlBlock)
+			}
+			Some(_) => InvalidData.fail(),
+			None => InvalidDataLength.fail(),
+		}
+	}
+
+	/// Set the enabled state of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_enabled(
+		&self,
+		path: &ObjectPath,
+		enabled: bool,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&format!("{path}$SvEna").into(), Iec61850Data::Bool(enabled)).await
+	}
+
+	/// Set the id of a sampled value control block.
+	/// The attribute is `MsvID` for multicast and `UsvID` for unicast control
+	/// blocks.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_id(
+		&self,
+		path: &ObjectPath,
+		id: &str,
+	) -> Result<(), Iec61850ClientError> {
+		let attribute = if path.to_string().contains("$US$") { "UsvID" } else { "MsvID" };
+		self.set_data_value(
+			&format!("{path}${attribute}").into(),
+			Iec61850Data::String(id.to_owned()),
+		)
+		.await
+	}
+
+	/// Set the dataset of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_dataset(
+		&self,
+		path: &ObjectPath,
+		dataset: &str,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(
+			&format!("{path}$DatSet").into(),
+			Iec61850Data::String(dataset_reference(dataset).to_owned()),
+		)
+		.await
+	}
+
+	/// Set the sample rate of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_sample_rate(
+		&self,
+		path: &ObjectPath,
+		sample_rate: u16,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(
+			&format!("{path}$SmpRate").into(),
+			Iec61850Data::Unsigned(sample_rate.into()),
+		)
+		.await
+	}
+
+	/// Set the optional fields of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_optional_fields(
+		&self,
+		path: &ObjectPath,
+		optional_fields: Vec<SampledValueOptionalFields>,
 	) -> Result<(), Iec61850ClientError> {
 		self.set_data_value(&format!("{path}$OptFlds").into(), optional_fields.into()).await
 	}
 
+	/// Set the sample mode of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_sample_mode(
+		&self,
+		path: &ObjectPath,
+		sample_mode: SampleMode,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&format!("{path}$SmpMod").into(), sample_mode.into()).await
+	}
+
+	/// Set the destination address of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_destination(
+		&self,
+		path: &ObjectPath,
+		destination: DestinationAddress,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&format!("{path}$DstAddress").into(), destination.into()).await
+	}
+
+	/// Set the number of ASDUs per message of a sampled value control block.
+	#[instrument(skip(self))]
+	pub async fn set_svcb_number_of_asdu(
+		&self,
+		path: &ObjectPath,
+		number_of_asdu: u16,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(
+			&format!("{path}$noASDU").into(),
+			Iec61850Data::Unsigned(number_of_asdu.into()),
+		)
+		.await
+	}
+
+	/// Get the setting group control block of a logical device.
+	#[instrument(skip(self))]
+	pub async fn get_sgcb(
+		&self,
+		logical_device: &str,
+	) -> Result<SettingGroupControlBlock, Iec61850ClientError> {
+		match self.read_data_from_ld(logical_device, &[SGCB_PATH]).await?.pop() {
+			Some(Iec61850Data::Structure(data)) => {
+				SettingGroupControlBlock::from_data(data).context(CreateSettingGroupControlBlock)
+			}
+			Some(_) => InvalidData.fail(),
+			None => InvalidDataLength.fail(),
+		}
+	}
+
+	/// Select the active setting group of a logical device.
+	/// The setting groups are numbered from 1.
+	#[instrument(skip(self))]
+	pub async fn set_active_setting_group(
+		&self,
+		logical_device: &str,
+		group: u8,
+	) -> Result<(), Iec61850ClientError> {
+		if group == 0 || self.number_of_setting_groups(logical_device).is_some_and(|n| group > n) {
+			return InvalidSettingGroup { group }.fail();
+		}
+		let data = Iec61850Data::Unsigned(group.into());
+		self.set_data_value(&(logical_device, format!("{SGCB_PATH}$ActSG")).into(), data).await
+	}
+
+	/// Select the setting group to edit in a logical device.
+	/// The values of the SE functional constraint then belong to this group
+	/// until the edition is confirmed. 0 cancels the edition.
+	#[instrument(skip(self))]
+	pub async fn set_edit_setting_group(
+		&self,
+		logical_device: &str,
+		group: u8,
+	) -> Result<(), Iec61850ClientError> {
+		if self.number_of_setting_groups(logical_device).is_some_and(|n| group > n) {
+			return InvalidSettingGroup { group }.fail();
+		}
+		let data = Iec61850Data::Unsigned(group.into());
+		self.set_data_value(&(logical_device, format!("{SGCB_PATH}$EditSG")).into(), data).await
+	}
+
+	/// Confirm the edition of the setting group of a logical device.
+	/// The values written to the SE functional constraint are stored in the
+	/// edited group.
+	#[instrument(skip(self))]
+	pub async fn confirm_setting_group_edit(
+		&self,
+		logical_device: &str,
+	) -> Result<(), Iec61850ClientError> {
+		let data = Iec61850Data::Bool(true);
+		self.set_data_value(&(logical_device, format!("{SGCB_PATH}$CnfEdit")).into(), data).await
+	}
+
+	/// Read a setting of the active setting group (SG functional constraint).
+	/// The path does not include the functional constraint, e.g.
+	/// `LD/PTOC1$StrVal$setMag$f`.
+	#[instrument(skip(self))]
+	pub async fn read_active_setting(
+		&self,
+		path: &ObjectPath,
+	) -> Result<Iec61850Data, Iec61850ClientError> {
+		self.read_setting(path, "SG").await
+	}
+
+	/// Read a setting of the edited setting group (SE functional constraint).
+	/// The path does not include the functional constraint.
+	#[instrument(skip(self))]
+	pub async fn read_edit_setting(
+		&self,
+		path: &ObjectPath,
+	) -> Result<Iec61850Data, Iec61850ClientError> {
+		self.read_setting(path, "SE").await
+	}
+
+	/// Write a setting of the edited setting group (SE functional constraint).
+	/// The path does not include the functional constraint. The value is only
+	/// applied once the edition is confirmed.
+	#[instrument(skip(self))]
+	pub async fn write_edit_setting(
+		&self,
+		path: &ObjectPath,
+		data: Iec61850Data,
+	) -> Result<(), Iec61850ClientError> {
+		self.set_data_value(&with_functional_constraint(path, "SE")?, data).await
+	}
+
+	/// Read a setting with a functional constraint.
+	async fn read_setting(
+		&self,
+		path: &ObjectPath,
+		functional_constraint: &str,
+	) -> Result<Iec61850Data, Iec61850ClientError> {
+		let path = with_functional_constraint(path, functional_constraint)?;
+		let (logical_device, item) = path.get_split_path()?;
+		self.read_data_from_ld(logical_device, &[item]).await?.pop().context(InvalidDataLength)
+	}
+
+	/// Get the number of setting groups of a logical device from the model.
+	fn number_of_setting_groups(&self, logical_device: &str) -> Option<u8> {
+		self.ied_model
+			.logical_devices
+			.iter()
+			.find(|ld| ld.name == logical_device)?
+			.logical_nodes
+			.iter()
+			.find_map(|ln| ln.setting_group_control_block.as_ref())
+			.map(|sgcb| sgcb.number_of_groups)
+	}
+
+	/// Set the entry id of a buffered report control block.
+	/// The ied will send the reports buffered after this entry once the
+	/// report control block is enabled.
+	#[instrument(skip(self))]
+	pub async fn set_rcb_entry_id(
+		&self,
+		path: &ObjectPath,
+		entry_id: Vec<u8>,
+	) -> Result<(), Iec61850ClientError> {
+		let data = Iec61850Data::OctetString(entry_id);
+		self.set_data_value(&format!("{path}$EntryID").into(), data).await
+	}
+
 	/// Read data from a logical device.
 	pub async fn read_data_from_ld(
 		&self,
 		logical_device: &str,
 		path: &[&str],
 	) -> Result<Vec<Iec61850Data>, Iec61850ClientError> {
-		let variable_defs = VariableDefs(
-			path.iter()
-				.map(|p| {
-					Ok(AnonymousVariableDefs::new(
-						VariableSpecification::name(ObjectName::domain_specific(
-							ObjectNameDomainSpecific::new(
-								to_identifier(logical_device)?,
-								to_identifier(p)?,
-							),
-						)),
-						None,
-					))
-				})
-				.collect::<Result<Vec<_>, Iec61850ClientError>>()?,
-		);
+		let variable_defs = make_variable_defs(logical_device, path)?;
 
 		self.client
 			.read(variable_defs.into(), false)
@@ -455,6 +1164,33 @@ impl Iec61850Client {
 			.context(ConvertDataToMmsData)
 	}
 
+	/// Read data from a logical device.
+	/// Returns the result of every path, so a path that can not be read does
+	/// not discard the values of the other paths. A value that can not be
+	/// converted is returned as [`DataAccessError::ObjectValueInvalid`].
+	pub async fn read_data_from_ld_with_results(
+		&self,
+		logical_device: &str,
+		path: &[&str],
+	) -> Result<Vec<Result<Iec61850Data, DataAccessError>>, Iec61850ClientError> {
+		let variable_defs = make_variable_defs(logical_device, path)?;
+
+		Ok(self
+			.client
+			.read_with_results(variable_defs.into(), false)
+			.await?
+			.into_iter()
+			.map(|result| {
+				result.and_then(|data| {
+					data.try_into().map_err(|e: Iec61850DataError| {
+						tracing::warn!("Error converting a read value: {e}");
+						DataAccessError::ObjectValueInvalid
+					})
+				})
+			})
+			.collect())
+	}
+
 	/// Read single data from a path.
 	/// The path is in the format <logical_device>/<logical_node>.
 	pub async fn read_data(&self, path: &str) -> Result<Vec<Iec61850Data>, Iec61850ClientError> {
@@ -488,6 +1224,29 @@ impl Iec61850Client {
 	}
 }
 
+/// Keep the names of the control blocks with one of the functional
+/// constraints among the variable names of a logical device. A control block
+/// name is like `LLN0$BR$brcb01`.
+fn control_block_names(names: &[String], functional_constraints: &[&str]) -> Vec<String> {
+	names
+		.iter()
+		.filter(|name| {
+			let mut parts = name.splitn(4, '$');
+			matches!(
+				(parts.next(), parts.next(), parts.next(), parts.next()),
+				(Some(_), Some(fc), Some(_), None) if functional_constraints.contains(&fc)
+			)
+		})
+		.cloned()
+		.collect()
+}
+
+/// The reference of a dataset as written in `DatSet`. The association
+/// specific datasets are written without their leading `@`.
+fn dataset_reference(dataset: &str) -> &str {
+	dataset.trim_start_matches('@')
+}
+
 /// Convert a string to an identifier.
 fn to_identifier<T: AsRef<str>>(value: T) -> Result<Identifier, Iec61850ClientError> {
 	Ok(Identifier(
@@ -496,6 +1255,42 @@ fn to_identifier<T: AsRef<str>>(value: T) -> Result<Identifier, Iec61850ClientEr
 	))
 }
 
+/// Make the variable definitions of paths in a logical device.
+fn make_variable_defs(
+	logical_device: &str,
+	path: &[&str],
+) -> Result<VariableDefs, Iec61850ClientError> {
+	Ok(VariableDefs(
+		path.iter()
+			.map(|p| make_variable_def(logical_device, p))
+			.collect::<Result<Vec<_>, Iec61850ClientError>>()?,
+	))
+}
+
+/// Make the variable definition of a path in a logical device.
+fn make_variable_def(
+	logical_device: &str,
+	path: &str,
+) -> Result<AnonymousVariableDefs, Iec61850ClientError> {
+	Ok(AnonymousVariableDefs::new(
+		VariableSpecification::name(ObjectName::domain_specific(ObjectNameDomainSpecific::new(
+			to_identifier(logical_device)?,
+			to_identifier(path)?,
+		))),
+		None,
+	))
+}
+
+/// Insert a functional constraint after the logical node of a path.
+fn with_functional_constraint(
+	path: &ObjectPath,
+	functional_constraint: &str,
+) -> Result<ObjectPath, Iec61850ClientError> {
+	let (logical_device, item) = path.get_split_path()?;
+	let (logical_node, rest) = item.split_once('$').context(InvalidPath)?;
+	Ok((logical_device, format!("{logical_node}${functional_constraint}${rest}")).into())
+}
+
 /// Split a path into a logical device and a logical node.
 fn split_path(path: &str) -> Result<(&str, &str), Iec61850ClientError> {
 	let split_path = path.split('/').collect::<Vec<&str>>();
@@ -528,6 +1323,14 @@ pub enum Iec61850ClientError {
 	InvalidPath,
 	/// Error on the MMS client.
 	Client { source: MmsClientError },
+	/// The server answered the request with a service error: {error}
+	Service { error: ServiceError },
+	/// The server rejected the request: {reason}
+	Rejected { reason: RejectReason },
+	/// The service {service} is not supported by the server
+	ServiceNotSupported { service: MmsService },
+	/// The server could not access the variable: {error}
+	DataAccess { error: DataAccessError },
 	/// Invalid data.
 	InvalidData,
 	/// Invalid data length.
@@ -540,11 +1343,51 @@ pub enum Iec61850ClientError {
 	Model { source: model::ModelError },
 	/// Error converting to string
 	ConvertToString { source: Utf8Error },
+	/// Error creating log control block.
+	CreateLogControlBlock { source: LogControlBlockError },
+	/// Error creating GOOSE control block.
+	CreateGooseControlBlock { source: GooseControlBlockError },
+	/// Error creating sampled value control block.
+	CreateSampledValueControlBlock { source: SampledValueControlBlockError },
+	/// Error creating setting group control block.
+	CreateSettingGroupControlBlock { source: SettingGroupControlBlockError },
+	/// Invalid setting group: {group}
+	InvalidSettingGroup { group: u8 },
+	/// Invalid control model: {value}
+	InvalidControlModel { value: i32 },
+	/// Control object not found in the model: {path}
+	ControlObjectNotFound { path: String },
+	/// Unsupported attribute {name} in the control structure {path}
+	UnsupportedControlAttribute { path: String, name: String },
+	/// The data object is status only and can not be controlled: {path}
+	ControlNotSupported { path: String },
+	/// The selection of {path} failed
+	SelectFailed { path: String },
+	/// The control {path} was rejected: {access_error}
+	ControlRejected {
+		path: String,
+		access_error: DataAccessError,
+		last_appl_error: Option<LastApplError>,
+	},
+	/// The command termination of {path} was negative
+	CommandTerminationFailed { path: String, last_appl_error: Option<LastApplError> },
+	/// Timed out waiting for the command termination of {path}
+	CommandTerminationTimeout { path: String },
 }
 
 impl From<MmsClientError> for Iec61850ClientError {
 	fn from(error: MmsClientError) -> Self {
-		Iec61850ClientError::Client { source: error }
+		match error {
+			MmsClientError::ServiceError { error, .. } => Iec61850ClientError::Service { error },
+			MmsClientError::Rejected { reason, .. } => Iec61850ClientError::Rejected { reason },
+			MmsClientError::ServiceNotSupported { service, .. } => {
+				Iec61850ClientError::ServiceNotSupported { service }
+			}
+			MmsClientError::DataAccessError { error, .. } => {
+				Iec61850ClientError::DataAccess { error }
+			}
+			error => Iec61850ClientError::Client { source: error },
+		}
 	}
 }
 
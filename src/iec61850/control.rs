@@ -0,0 +1,941 @@
+//! The IEC 61850 control model.
+//!
+//! Controllable data objects are operated through the `Oper`, `SBOw` and
+//! `Cancel` structures of the CO functional constraint. The `ctlModel`
+//! attribute of the CF functional constraint defines if the object must be
+//! selected before being operated and if the server reports the end of the
+//! command with a `CommandTermination`.
+
+use std::{
+	fmt,
+	sync::{PoisonError, atomic::Ordering},
+	time::Duration,
+};
+
+use snafu::{OptionExt as _, ResultExt as _};
+use time::OffsetDateTime;
+use tokio::sync::broadcast::{
+	self,
+	error::{RecvError, TryRecvError},
+};
+use tracing::instrument;
+
+use crate::{
+	Iec61850Client,
+	iec61850::{
+		CommandTerminationFailed, CommandTerminationTimeout, ControlNotSupported,
+		ControlObjectNotFound, ControlRejected, ConvertDataToMmsData, Iec61850ClientError,
+		InvalidControlModel, InvalidDataLength, InvalidPath, ObjectPath, SelectFailed,
+		UnsupportedControlAttribute,
+		data::{Bitstring, Iec61850Data, Iec61850DataError, InvalidData, MissingData},
+		model::Node,
+	},
+	mms::{
+		ans1::mms::asn1::{
+			AccessResult, InformationReport, VariableAccessSpecification, VariableSpecification,
+		},
+		client::ConnectionLost,
+	},
+};
+
+/// The name of the `LastApplError` variable.
+pub(crate) const LAST_APPL_ERROR: &str = "LastApplError";
+/// The default time to wait for the command termination.
+const DEFAULT_TERMINATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The control model of a controllable data object (`ctlModel`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlModel {
+	/// The data object can not be controlled.
+	StatusOnly,
+	/// Direct control with normal security.
+	DirectNormal,
+	/// Select before operate with normal security.
+	SboNormal,
+	/// Direct control with enhanced security.
+	DirectEnhanced,
+	/// Select before operate with enhanced security.
+	SboEnhanced,
+}
+
+impl ControlModel {
+	/// Check if the data object must be selected before being operated.
+	#[must_use]
+	pub const fn is_sbo(self) -> bool {
+		matches!(self, Self::SboNormal | Self::SboEnhanced)
+	}
+
+	/// Check if the server reports the end of the command.
+	#[must_use]
+	pub const fn is_enhanced(self) -> bool {
+		matches!(self, Self::DirectEnhanced | Self::SboEnhanced)
+	}
+}
+
+impl TryFrom<i32> for ControlModel {
+	type Error = Iec61850ClientError;
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::StatusOnly),
+			1 => Ok(Self::DirectNormal),
+			2 => Ok(Self::SboNormal),
+			3 => Ok(Self::DirectEnhanced),
+			4 => Ok(Self::SboEnhanced),
+			value => InvalidControlModel { value }.fail(),
+		}
+	}
+}
+
+/// The category of the originator of a control (`orCat`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginatorCategory {
+	/// Not supported.
+	NotSupported,
+	/// Control from the bay level.
+	BayControl,
+	/// Control from the station level.
+	StationControl,
+	/// Control from a remote control center.
+	RemoteControl,
+	/// Automatic control from the bay level.
+	AutomaticBay,
+	/// Automatic control from the station level.
+	AutomaticStation,
+	/// Automatic control from a remote control center.
+	AutomaticRemote,
+	/// Control from a maintenance tool.
+	Maintenance,
+	/// Control from the process.
+	Process,
+	/// An unknown category.
+	Unknown(i32),
+}
+
+impl From<i32> for OriginatorCategory {
+	fn from(value: i32) -> Self {
+		match value {
+			0 => Self::NotSupported,
+			1 => Self::BayControl,
+			2 => Self::StationControl,
+			3 => Self::RemoteControl,
+			4 => Self::AutomaticBay,
+			5 => Self::AutomaticStation,
+			6 => Self::AutomaticRemote,
+			7 => Self::Maintenance,
+			8 => Self::Process,
+			value => Self::Unknown(value),
+		}
+	}
+}
+
+impl From<OriginatorCategory> for i32 {
+	fn from(value: OriginatorCategory) -> Self {
+		match value {
+			OriginatorCategory::NotSupported => 0,
+			OriginatorCategory::BayControl => 1,
+			OriginatorCategory::StationControl => 2,
+			OriginatorCategory::RemoteControl => 3,
+			OriginatorCategory::AutomaticBay => 4,
+			OriginatorCategory::AutomaticStation => 5,
+			OriginatorCategory::AutomaticRemote => 6,
+			OriginatorCategory::Maintenance => 7,
+			OriginatorCategory::Process => 8,
+			OriginatorCategory::Unknown(value) => value,
+		}
+	}
+}
+
+/// The originator of a control (`origin`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Originator {
+	/// The category of the originator.
+	pub category: OriginatorCategory,
+	/// The identifier of the originator.
+	pub identifier: Vec<u8>,
+}
+
+impl Default for Originator {
+	fn default() -> Self {
+		Self { category: OriginatorCategory::RemoteControl, identifier: Vec::new() }
+	}
+}
+
+impl From<Originator> for Iec61850Data {
+	fn from(value: Originator) -> Self {
+		Self::Structure(vec![
+			Self::Integer(value.category.into()),
+			Self::OctetString(value.identifier),
+		])
+	}
+}
+
+impl TryFrom<Iec61850Data> for Originator {
+	type Error = Iec61850DataError;
+	fn try_from(value: Iec61850Data) -> Result<Self, Self::Error> {
+		let Iec61850Data::Structure(values) = value else {
+			return Err(Iec61850DataError::InvalidData);
+		};
+		let mut values = values.into_iter();
+		let category: i32 = values.next().context(MissingData)?.try_into()?;
+		let identifier = values.next().context(MissingData)?.try_into()?;
+		Ok(Self { category: category.into(), identifier })
+	}
+}
+
+/// The options of a control.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlOptions {
+	/// The originator of the control.
+	pub originator: Originator,
+	/// The control is a test and must not be executed by the process.
+	pub test: bool,
+	/// Ask the server to perform the synchrocheck.
+	pub synchrocheck: bool,
+	/// Ask the server to perform the interlock check.
+	pub interlock_check: bool,
+	/// When the server must execute the control. `None` executes it
+	/// immediately.
+	pub operate_time: Option<OffsetDateTime>,
+	/// How long to wait for the command termination in the enhanced security
+	/// control models.
+	pub termination_timeout: Duration,
+}
+
+impl Default for ControlOptions {
+	fn default() -> Self {
+		Self {
+			originator: Originator::default(),
+			test: false,
+			synchrocheck: false,
+			interlock_check: false,
+			operate_time: None,
+			termination_timeout: DEFAULT_TERMINATION_TIMEOUT,
+		}
+	}
+}
+
+/// The error of a failed control (`LastApplError.Error`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlError {
+	/// No error.
+	NoError,
+	/// An unknown error.
+	Unknown,
+	/// The timeout test failed.
+	TimeoutTestNotOk,
+	/// The operator test failed.
+	OperatorTestNotOk,
+	/// An error code not defined by the standard.
+	Other(i32),
+}
+
+impl From<i32> for ControlError {
+	fn from(value: i32) -> Self {
+		match value {
+			0 => Self::NoError,
+			1 => Self::Unknown,
+			2 => Self::TimeoutTestNotOk,
+			3 => Self::OperatorTestNotOk,
+			value => Self::Other(value),
+		}
+	}
+}
+
+impl From<ControlError> for i32 {
+	fn from(value: ControlError) -> Self {
+		match value {
+			ControlError::NoError => 0,
+			ControlError::Unknown => 1,
+			ControlError::TimeoutTestNotOk => 2,
+			ControlError::OperatorTestNotOk => 3,
+			ControlError::Other(value) => value,
+		}
+	}
+}
+
+impl fmt::Display for ControlError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NoError => write!(f, "no error"),
+			Self::Unknown => write!(f, "unknown"),
+			Self::TimeoutTestNotOk => write!(f, "timeout test not ok"),
+			Self::OperatorTestNotOk => write!(f, "operator test not ok"),
+			Self::Other(value) => write!(f, "error {value}"),
+		}
+	}
+}
+
+/// The additional cause of a failed control (`LastApplError.AddCause`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddCause {
+	/// Unknown cause.
+	Unknown,
+	/// The control is not supported.
+	NotSupported,
+	/// Blocked by the switching hierarchy.
+	BlockedBySwitchingHierarchy,
+	/// The selection failed.
+	SelectFailed,
+	/// The position is invalid.
+	InvalidPosition,
+	/// The position is already reached.
+	PositionReached,
+	/// A parameter changed during the execution.
+	ParameterChangeInExecution,
+	/// The step limit is reached.
+	StepLimit,
+	/// Blocked by the mode.
+	BlockedByMode,
+	/// Blocked by the process.
+	BlockedByProcess,
+	/// Blocked by the interlocking.
+	BlockedByInterlocking,
+	/// Blocked by the synchrocheck.
+	BlockedBySynchrocheck,
+	/// A command is already being executed.
+	CommandAlreadyInExecution,
+	/// Blocked by the health.
+	BlockedByHealth,
+	/// Only one of N controls can be executed at a time.
+	OneOfNControl,
+	/// Aborted by a cancel.
+	AbortionByCancel,
+	/// The time limit is over.
+	TimeLimitOver,
+	/// Aborted by a trip.
+	AbortionByTrip,
+	/// The object is not selected.
+	ObjectNotSelected,
+	/// The object is already selected.
+	ObjectAlreadySelected,
+	/// The client has no access authority.
+	NoAccessAuthority,
+	/// The command ended with an overshoot.
+	EndedWithOvershoot,
+	/// Aborted due to a deviation.
+	AbortionDueToDeviation,
+	/// Aborted by a communication loss.
+	AbortionByCommunicationLoss,
+	/// Blocked by a command.
+	BlockedByCommand,
+	/// No cause.
+	None,
+	/// The parameters are inconsistent.
+	InconsistentParameters,
+	/// Locked by another client.
+	LockedByOtherClient,
+	/// A cause not defined by the standard.
+	Other(i32),
+}
+
+impl From<i32> for AddCause {
+	fn from(value: i32) -> Self {
+		match value {
+			0 => Self::Unknown,
+			1 => Self::NotSupported,
+			2 => Self::BlockedBySwitchingHierarchy,
+			3 => Self::SelectFailed,
+			4 => Self::InvalidPosition,
+			5 => Self::PositionReached,
+			6 => Self::ParameterChangeInExecution,
+			7 => Self::StepLimit,
+			8 => Self::BlockedByMode,
+			9 => Self::BlockedByProcess,
+			10 => Self::BlockedByInterlocking,
+			11 => Self::BlockedBySynchrocheck,
+			12 => Self::CommandAlreadyInExecution,
+			13 => Self::BlockedByHealth,
+			14 => Self::OneOfNControl,
+			15 => Self::AbortionByCancel,
+			16 => Self::TimeLimitOver,
+			17 => Self::AbortionByTrip,
+			18 => Self::ObjectNotSelected,
+			19 => Self::ObjectAlreadySelected,
+			20 => Self::NoAccessAuthority,
+			21 => Self::EndedWithOvershoot,
+			22 => Self::AbortionDueToDeviation,
+			23 => Self::AbortionByCommunicationLoss,
+			24 => Self::BlockedByCommand,
+			25 => Self::None,
+			26 => Self::InconsistentParameters,
+			27 => Self::LockedByOtherClient,
+			value => Self::Other(value),
+		}
+	}
+}
+
+impl From<AddCause> for i32 {
+	fn from(value: AddCause) -> Self {
+		match value {
+			AddCause::Unknown => 0,
+			AddCause::NotSupported => 1,
+			AddCause::BlockedBySwitchingHierarchy => 2,
+			AddCause::SelectFailed => 3,
+			AddCause::InvalidPosition => 4,
+			AddCause::PositionReached => 5,
+			AddCause::ParameterChangeInExecution => 6,
+			AddCause::StepLimit => 7,
+			AddCause::BlockedByMode => 8,
+			AddCause::BlockedByProcess => 9,
+			AddCause::BlockedByInterlocking => 10,
+			AddCause::BlockedBySynchrocheck => 11,
+			AddCause::CommandAlreadyInExecution => 12,
+			AddCause::BlockedByHealth => 13,
+			AddCause::OneOfNControl => 14,
+			AddCause::AbortionByCancel => 15,
+			AddCause::TimeLimitOver => 16,
+			AddCause::AbortionByTrip => 17,
+			AddCause::ObjectNotSelected => 18,
+			AddCause::ObjectAlreadySelected => 19,
+			AddCause::NoAccessAuthority => 20,
+			AddCause::EndedWithOvershoot => 21,
+			AddCause::AbortionDueToDeviation => 22,
+			AddCause::AbortionByCommunicationLoss => 23,
+			AddCause::BlockedByCommand => 24,
+			AddCause::None => 25,
+			AddCause::InconsistentParameters => 26,
+			AddCause::LockedByOtherClient => 27,
+			AddCause::Other(value) => value,
+		}
+	}
+}
+
+impl fmt::Display for AddCause {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Other(value) => write!(f, "cause {value}"),
+			cause => write!(f, "{cause:?}"),
+		}
+	}
+}
+
+/// The error reported by the server when a control fails (`LastApplError`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastApplError {
+	/// The reference of the control structure, e.g. `LD/CSWI1$CO$Pos$Oper`.
+	pub control_object: String,
+	/// The error.
+	pub error: ControlError,
+	/// The originator of the failed control.
+	pub originator: Originator,
+	/// The control number of the failed control.
+	pub ctl_num: u8,
+	/// The additional cause of the error.
+	pub add_cause: AddCause,
+}
+
+impl TryFrom<Iec61850Data> for LastApplError {
+	type Error = Iec61850DataError;
+	fn try_from(value: Iec61850Data) -> Result<Self, Self::Error> {
+		let Iec61850Data::Structure(values) = value else {
+			return Err(Iec61850DataError::InvalidData);
+		};
+		let mut values = values.into_iter();
+		let control_object = values.next().context(MissingData)?.try_into()?;
+		let error: i32 = values.next().context(MissingData)?.try_into()?;
+		let originator = values.next().context(MissingData)?.try_into()?;
+		let ctl_num: u32 = values.next().context(MissingData)?.try_into()?;
+		let add_cause: i32 = values.next().context(MissingData)?.try_into()?;
+		Ok(Self {
+			control_object,
+			error: error.into(),
+			originator,
+			ctl_num: u8::try_from(ctl_num).ok().context(InvalidData)?,
+			add_cause: add_cause.into(),
+		})
+	}
+}
+
+impl From<LastApplError> for Iec61850Data {
+	fn from(value: LastApplError) -> Self {
+		Self::Structure(vec![
+			Self::String(value.control_object),
+			Self::Integer(value.error.into()),
+			value.originator.into(),
+			Self::Unsigned(value.ctl_num.into()),
+			Self::Integer(value.add_cause.into()),
+		])
+	}
+}
+
+impl fmt::Display for LastApplError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} (error: {}, additional cause: {}, control number: {})",
+			self.control_object, self.error, self.add_cause, self.ctl_num
+		)
+	}
+}
+
+/// An event of the control model, sent by the server in an information
+/// report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEvent {
+	/// A control failed.
+	LastApplError(LastApplError),
+	/// The command of a control ended. The `LastApplError` is only present
+	/// when the command failed.
+	CommandTermination {
+		/// The reference of the control structure.
+		control_object: String,
+		/// The error of a negative command termination.
+		last_appl_error: Option<LastApplError>,
+	},
+}
+
+impl ControlEvent {
+	/// Parse an information report of the control model.
+	/// Returns `None` for any other information report.
+	pub(crate) fn parse(report: &InformationReport) -> Option<Self> {
+		let names = match &report.variable_access_specification {
+			VariableAccessSpecification::listOfVariable(variables) => variables
+				.0
+				.iter()
+				.map(|variable| {
+					let VariableSpecification::name(name) = &variable.variable_specification;
+					name.to_string()
+				})
+				.collect(),
+			VariableAccessSpecification::variableListName(name) => vec![name.to_string()],
+		};
+		let mut control_object = None;
+		let mut last_appl_error = None;
+		for (name, result) in names.into_iter().zip(&report.list_of_access_result) {
+			if name == LAST_APPL_ERROR {
+				if let AccessResult::success(data) = result {
+					last_appl_error = Iec61850Data::try_from(data.clone())
+						.and_then(LastApplError::try_from)
+						.inspect_err(|e| tracing::warn!("Invalid LastApplError: {e}"))
+						.ok();
+				}
+			} else if name.contains("$CO$") && name.ends_with("$Oper") {
+				control_object = Some(name);
+			}
+		}
+		match (control_object, last_appl_error) {
+			(Some(control_object), last_appl_error) => {
+				Some(Self::CommandTermination { control_object, last_appl_error })
+			}
+			(None, Some(last_appl_error)) => Some(Self::LastApplError(last_appl_error)),
+			(None, None) => None,
+		}
+	}
+}
+
+/// A controllable data object.
+struct ControlObject<'a> {
+	/// The logical device.
+	logical_device: &'a str,
+	/// The logical node.
+	logical_node: &'a str,
+	/// The data object, relative to the logical node.
+	data_object: &'a str,
+}
+
+impl<'a> ControlObject<'a> {
+	/// Create a control object from the path of the data object, e.g.
+	/// `LD/CSWI1$Pos`.
+	fn new(path: &'a ObjectPath) -> Result<Self, Iec61850ClientError> {
+		let (logical_device, path) = path.get_split_path()?;
+		let (logical_node, data_object) = path.split_once('$').context(InvalidPath)?;
+		Ok(Self { logical_device, logical_node, data_object })
+	}
+
+	/// Get the reference of the data object, e.g. `LD/CSWI1$Pos`.
+	fn reference(&self) -> String {
+		format!("{}/{}${}", self.logical_device, self.logical_node, self.data_object)
+	}
+
+	/// Get the item of an attribute, relative to the logical device.
+	fn item(&self, functional_constraint: &str, attribute: &str) -> String {
+		format!("{}${functional_constraint}${}${attribute}", self.logical_node, self.data_object)
+	}
+
+	/// Get the full path of an attribute.
+	fn path(&self, functional_constraint: &str, attribute: &str) -> String {
+		format!("{}/{}", self.logical_device, self.item(functional_constraint, attribute))
+	}
+}
+
+impl Iec61850Client {
+	/// Read the control model of a controllable data object.
+	/// The path is the path of the data object, e.g. `LD/CSWI1$Pos`.
+	#[instrument(skip(self))]
+	pub async fn control_model(
+		&self,
+		path: &ObjectPath,
+	) -> Result<ControlModel, Iec61850ClientError> {
+		self.read_control_model(&ControlObject::new(path)?).await
+	}
+
+	/// Operate a controllable data object.
+	/// The data object is selected first when its control model requires it.
+	/// With an enhanced security control model, waits for the command
+	/// termination.
+	#[instrument(skip(self))]
+	pub async fn control(
+		&self,
+		path: &ObjectPath,
+		ctl_val: Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		let object = ControlObject::new(path)?;
+		let model = self.read_control_model(&object).await?;
+		if model.is_sbo() {
+			self.select_with_model(&object, model, &ctl_val, options).await?;
+		}
+		self.operate_with_model(&object, model, ctl_val, options).await
+	}
+
+	/// Select a controllable data object.
+	/// The `SBO` attribute is read with the normal security and the `SBOw`
+	/// structure is written with the enhanced security.
+	#[instrument(skip(self))]
+	pub async fn select(
+		&self,
+		path: &ObjectPath,
+		ctl_val: &Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		let object = ControlObject::new(path)?;
+		let model = self.read_control_model(&object).await?;
+		self.select_with_model(&object, model, ctl_val, options).await
+	}
+
+	/// Operate a controllable data object without selecting it.
+	/// With an enhanced security control model, waits for the command
+	/// termination.
+	#[instrument(skip(self))]
+	pub async fn operate(
+		&self,
+		path: &ObjectPath,
+		ctl_val: Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		let object = ControlObject::new(path)?;
+		let model = self.read_control_model(&object).await?;
+		self.operate_with_model(&object, model, ctl_val, options).await
+	}
+
+	/// Cancel the selection or the pending operation of a controllable data
+	/// object.
+	#[instrument(skip(self))]
+	pub async fn cancel(
+		&self,
+		path: &ObjectPath,
+		ctl_val: Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		let object = ControlObject::new(path)?;
+		let ctl_num = self
+			.command_ctl_nums
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.get(&object.reference())
+			.copied()
+			.unwrap_or_else(|| self.ctl_num.load(Ordering::Relaxed));
+		self.write_control(&object, "Cancel", ctl_val, ctl_num, options).await?;
+		Ok(())
+	}
+
+	/// Read the control model of a control object.
+	async fn read_control_model(
+		&self,
+		object: &ControlObject<'_>,
+	) -> Result<ControlModel, Iec61850ClientError> {
+		let item = object.item("CF", "ctlModel");
+		let value: i32 = self
+			.read_data_from_ld(object.logical_device, &[&item])
+			.await?
+			.pop()
+			.context(InvalidDataLength)?
+			.try_into()
+			.context(ConvertDataToMmsData)?;
+		ControlModel::try_from(value)
+	}
+
+	/// Select a control object with a known control model.
+	async fn select_with_model(
+		&self,
+		object: &ControlObject<'_>,
+		model: ControlModel,
+		ctl_val: &Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		match model {
+			ControlModel::StatusOnly => {
+				ControlNotSupported { path: object.path("CF", "ctlModel") }.fail()
+			}
+			ControlModel::DirectNormal | ControlModel::DirectEnhanced => Ok(()),
+			ControlModel::SboNormal => {
+				let item = object.item("CO", "SBO");
+				let selected: String = self
+					.read_data_from_ld(object.logical_device, &[&item])
+					.await?
+					.pop()
+					.context(InvalidDataLength)?
+					.try_into()
+					.context(ConvertDataToMmsData)?;
+				if selected.is_empty() {
+					return SelectFailed { path: object.path("CO", "SBO") }.fail();
+				}
+				Ok(())
+			}
+			ControlModel::SboEnhanced => {
+				let ctl_num = self.ctl_num.load(Ordering::Relaxed);
+				self.write_control(object, "SBOw", ctl_val.clone(), ctl_num, options).await?;
+				Ok(())
+			}
+		}
+	}
+
+	/// Operate a control object with a known control model.
+	async fn operate_with_model(
+		&self,
+		object: &ControlObject<'_>,
+		model: ControlModel,
+		ctl_val: Iec61850Data,
+		options: &ControlOptions,
+	) -> Result<(), Iec61850ClientError> {
+		if model == ControlModel::StatusOnly {
+			return ControlNotSupported { path: object.path("CF", "ctlModel") }.fail();
+		}
+		let ctl_num = self.ctl_num.fetch_add(1, Ordering::Relaxed);
+		let events = self.write_control(object, "Oper", ctl_val, ctl_num, options).await?;
+		if model.is_enhanced() {
+			wait_for_termination(events, object.path("CO", "Oper"), options.termination_timeout)
+				.await?;
+		}
+		Ok(())
+	}
+
+	/// Write a control structure.
+	/// Returns the receiver of the control events, subscribed before the
+	/// write so that no event sent by the server is missed.
+	async fn write_control(
+		&self,
+		object: &ControlObject<'_>,
+		structure: &str,
+		ctl_val: Iec61850Data,
+		ctl_num: u8,
+		options: &ControlOptions,
+	) -> Result<broadcast::Receiver<ControlEvent>, Iec61850ClientError> {
+		let path = object.path("CO", structure);
+		let node = self
+			.ied_model
+			.find_node(&path)
+			.context(ControlObjectNotFound { path: path.clone() })?;
+		let data = make_control_structure(&path, node.nodes(), ctl_val, ctl_num, options)?;
+		if structure != "Cancel" {
+			self.command_ctl_nums
+				.lock()
+				.unwrap_or_else(PoisonError::into_inner)
+				.insert(object.reference(), ctl_num);
+		}
+
+		let mut events = self.client.subscribe_control_events()?;
+		match self.set_data_value(&path.clone().into(), data).await {
+			Ok(()) => Ok(events),
+			Err(Iec61850ClientError::DataAccess { error }) => {
+				let last_appl_error = find_last_appl_error(&mut events, &path);
+				ControlRejected { path, access_error: error, last_appl_error }.fail()
+			}
+			Err(e) => Err(e),
+		}
+	}
+}
+
+/// Build a control structure following the attributes of its model.
+fn make_control_structure(
+	path: &str,
+	attributes: &[Node],
+	ctl_val: Iec61850Data,
+	ctl_num: u8,
+	options: &ControlOptions,
+) -> Result<Iec61850Data, Iec61850ClientError> {
+	let now = OffsetDateTime::now_utc();
+	let mut ctl_val = Some(ctl_val);
+	attributes
+		.iter()
+		.map(|attribute| {
+			Ok(match attribute.name() {
+				"ctlVal" => ctl_val
+					.take()
+					.context(UnsupportedControlAttribute { path, name: attribute.name() })?,
+				"operTm" => Iec61850Data::UtcTime(options.operate_time.unwrap_or(now)),
+				"origin" => options.originator.clone().into(),
+				"ctlNum" => Iec61850Data::Unsigned(ctl_num.into()),
+				"T" => Iec61850Data::UtcTime(now),
+				"Test" => Iec61850Data::Bool(options.test),
+				"Check" => Iec61850Data::BitString(Bitstring {
+					bytes: vec![
+						u8::from(options.synchrocheck) | u8::from(options.interlock_check) << 1,
+					],
+					padding: 6,
+				}),
+				name => return UnsupportedControlAttribute { path, name }.fail(),
+			})
+		})
+		.collect::<Result<_, _>>()
+		.map(Iec61850Data::Structure)
+}
+
+/// Find the `LastApplError` of a control structure in the events already
+/// received.
+fn find_last_appl_error(
+	events: &mut broadcast::Receiver<ControlEvent>,
+	path: &str,
+) -> Option<LastApplError> {
+	loop {
+		match events.try_recv() {
+			Ok(ControlEvent::LastApplError(error)) if error.control_object == path => {
+				return Some(error);
+			}
+			Ok(_) => {}
+			Err(TryRecvError::Lagged(count)) => {
+				tracing::warn!("Missed {count} control events");
+			}
+			Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+		}
+	}
+}
+
+/// Wait for the command termination of a control structure.
+async fn wait_for_termination(
+	mut events: broadcast::Receiver<ControlEvent>,
+	path: String,
+	timeout: Duration,
+) -> Result<(), Iec61850ClientError> {
+	let termination = async {
+		loop {
+			match events.recv().await {
+				Ok(ControlEvent::CommandTermination { control_object, last_appl_error })
+					if control_object == path =>
+				{
+					return Ok(last_appl_error);
+				}
+				Ok(_) => {}
+				Err(RecvError::Lagged(count)) => {
+					tracing::warn!("Missed {count} control events");
+				}
+				Err(RecvError::Closed) => return Err(ConnectionLost.build().into()),
+			}
+		}
+	};
+	match tokio::time::timeout(timeout, termination).await {
+		Ok(Ok(None)) => Ok(()),
+		Ok(Ok(Some(last_appl_error))) => {
+			CommandTerminationFailed { path, last_appl_error: Some(last_appl_error) }.fail()
+		}
+		Ok(Err(e)) => Err(e),
+		Err(_) => CommandTerminationTimeout { path }.fail(),
+	}
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mms::ans1::mms::asn1::{
+		AnonymousVariableDefs, Data, Identifier, ObjectName, ObjectNameDomainSpecific, VariableDefs,
+	};
+
+	fn attribute(name: &str) -> Node {
+		Node::DataAttribute { name: name.to_owned(), path: String::new(), r#type: String::new() }
+	}
+
+	fn variable(name: ObjectName) -> AnonymousVariableDefs {
+		AnonymousVariableDefs::new(VariableSpecification::name(name), None)
+	}
+
+	#[test]
+	fn test_make_control_structure() {
+		let attributes =
+			["ctlVal", "origin", "ctlNum", "T", "Test", "Check"].map(attribute).to_vec();
+		let options = ControlOptions { interlock_check: true, ..Default::default() };
+		let data = make_control_structure(
+			"LD/CSWI1$CO$Pos$Oper",
+			&attributes,
+			Iec61850Data::Bool(true),
+			7,
+			&options,
+		)
+		.unwrap();
+		let Iec61850Data::Structure(values) = data else { panic!("Expected a structure") };
+		assert_eq!(values.len(), 6);
+		assert_eq!(values[0], Iec61850Data::Bool(true));
+		assert_eq!(
+			values[1],
+			Iec61850Data::Structure(vec![
+				Iec61850Data::Integer(3),
+				Iec61850Data::OctetString(vec![])
+			])
+		);
+		assert_eq!(values[2], Iec61850Data::Unsigned(7));
+		assert_eq!(values[4], Iec61850Data::Bool(false));
+		assert_eq!(values[5], Iec61850Data::BitString(Bitstring { bytes: vec![0x02], padding: 6 }));
+
+		let attributes = [attribute("ctlVal"), attribute("unknown")];
+		assert!(
+			make_control_structure(
+				"LD/CSWI1$CO$Pos$Oper",
+				&attributes,
+				Iec61850Data::Bool(true),
+				0,
+				&options
+			)
+			.is_err()
+		);
+	}
+
+	#[test]
+	fn test_parse_negative_command_termination() {
+		let last_appl_error = Data::try_from(Iec61850Data::Structure(vec![
+			Iec61850Data::String("LD/CSWI1$CO$Pos$Oper".to_owned()),
+			Iec61850Data::Integer(1),
+			Originator::default().into(),
+			Iec61850Data::Unsigned(7),
+			Iec61850Data::Integer(5),
+		]))
+		.unwrap();
+		let variables = VariableDefs(vec![
+			variable(ObjectName::vmd_specific(Identifier(LAST_APPL_ERROR.try_into().unwrap()))),
+			variable(ObjectName::domain_specific(ObjectNameDomainSpecific::new(
+				Identifier("LD".try_into().unwrap()),
+				Identifier("CSWI1$CO$Pos$Oper".try_into().unwrap()),
+			))),
+		]);
+		let oper = Data::try_from(Iec61850Data::Structure(vec![Iec61850Data::Bool(true)])).unwrap();
+		let mut report = InformationReport::new(
+			VariableAccessSpecification::listOfVariable(variables.clone()),
+			vec![AccessResult::success(last_appl_error.clone()), AccessResult::success(oper)],
+		);
+
+		let Some(ControlEvent::CommandTermination { control_object, last_appl_error: Some(error) }) =
+			ControlEvent::parse(&report)
+		else {
+			panic!("Expected a negative command termination");
+		};
+		assert_eq!(control_object, "LD/CSWI1$CO$Pos$Oper");
+		assert_eq!(error.control_object, control_object);
+		assert_eq!(error.ctl_num, 7);
+		assert_eq!(error.add_cause, AddCause::PositionReached);
+		assert_eq!(error.error, ControlError::Unknown);
+
+		report.variable_access_specification =
+			VariableAccessSpecification::listOfVariable(VariableDefs(vec![variables.0[0].clone()]));
+		report.list_of_access_result = vec![AccessResult::success(last_appl_error)];
+		assert!(matches!(ControlEvent::parse(&report), Some(ControlEvent::LastApplError(_))));
+
+		report.variable_access_specification = VariableAccessSpecification::variableListName(
+			ObjectName::vmd_specific(Identifier(LAST_APPL_ERROR.try_into().unwrap())),
+		);
+		assert!(matches!(ControlEvent::parse(&report), Some(ControlEvent::LastApplError(_))));
+
+		report.variable_access_specification = VariableAccessSpecification::variableListName(
+			ObjectName::vmd_specific(Identifier("RPT".try_into().unwrap())),
+		);
+		assert_eq!(ControlEvent::parse(&report), None);
+	}
+}
@@ -15,6 +15,8 @@ use crate::mms::ans1::mms::asn1::{Data, FloatingPoint, MMSString, TimeOfDay, Utc
 const MMS_TO_UNIX_EPOCH_OFFSET: i64 = 441_763_200_000;
 /// The number of milliseconds in a day.
 const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+/// The number of nanoseconds in a second.
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
 
 /// The IEC61850 data types.
 #[derive(Debug, Clone, PartialEq)]
@@ -163,7 +165,7 @@ impl TryFrom<Iec61850Data> for Data {
 				VisibleString::from_iso646_bytes(value.as_bytes())
 					.context(InvalidStringConversion)?,
 			)),
-			Iec61850Data::UtcTime(value) => Data::utc_time(value.into()),
+			Iec61850Data::UtcTime(value) => Data::utc_time(value.try_into()?),
 		})
 	}
 }
@@ -207,22 +209,22 @@ impl TryFrom<UtcTime> for OffsetDateTime {
 
 	#[instrument(level = "debug")]
 	fn try_from(value: UtcTime) -> Result<Self, Self::Error> {
-		let seconds = i32::from_le_bytes(*value.0.first_chunk().context(MissingData)?);
-		let milliseconds = i32::from_le_bytes([
-			0,
-			*value.0.get(4).context(MissingData)?,
-			*value.0.get(5).context(MissingData)?,
-			*value.0.get(6).context(MissingData)?,
-		]) / 16777;
+		// The first 4 bytes are the seconds since the epoch and the next 3 bytes
+		// the fraction of the second, both in big endian.
+		let seconds = u32::from_be_bytes(*value.0.first_chunk().context(MissingData)?);
+		let fraction = u32::from_be_bytes([0, value.0[4], value.0[5], value.0[6]]);
+		let nanoseconds = (u64::from(fraction) * NANOSECONDS_PER_SECOND) >> 24;
 
 		//TODO: Fix it
-		let quality = value.0.get(7).context(MissingData)?;
+		let quality = value.0[7];
 		let _leap_second_known = (quality & 0x80) != 0;
 		let _clock_failure = (quality & 0x40) != 0;
 		let _not_synchronized = (quality & 0x20) != 0;
 
-		let timestamp = i64::from(seconds) * 1000 + i64::from(milliseconds);
-		OffsetDateTime::from_unix_timestamp(timestamp).context(InvalidTimestamp)
+		OffsetDateTime::from_unix_timestamp_nanos(
+			i128::from(seconds) * i128::from(NANOSECONDS_PER_SECOND) + i128::from(nanoseconds),
+		)
+		.context(InvalidTimestamp)
 	}
 }
 
@@ -243,25 +245,32 @@ impl From<OffsetDateTime> for TimeOfDay {
 	}
 }
 
-impl From<OffsetDateTime> for UtcTime {
-	fn from(value: OffsetDateTime) -> Self {
-		let seconds = (value.unix_timestamp() as i32).to_le_bytes();
-		let millisecond = value.millisecond();
-		let seconds_fraction =
-			i32::from((millisecond) * 16777 + ((millisecond * 216) / 1000)).to_le_bytes();
+impl TryFrom<OffsetDateTime> for UtcTime {
+	type Error = Iec61850DataError;
+
+	#[instrument(level = "debug")]
+	fn try_from(value: OffsetDateTime) -> Result<Self, Self::Error> {
+		// The seconds since the epoch are unsigned, a time before the epoch
+		// can't be encoded.
+		let seconds = u32::try_from(value.unix_timestamp())
+			.ok()
+			.context(TimestampOutOfRange { timestamp: value })?
+			.to_be_bytes();
+		let fraction =
+			((u64::from(value.nanosecond()) << 24) / NANOSECONDS_PER_SECOND).to_be_bytes();
 
 		//TODO: Fix it
 		let quality = 0x00;
-		UtcTime(FixedOctetString::from([
+		Ok(UtcTime(FixedOctetString::from([
 			seconds[0],
 			seconds[1],
 			seconds[2],
 			seconds[3],
-			seconds_fraction[0],
-			seconds_fraction[1],
-			seconds_fraction[2],
+			fraction[5],
+			fraction[6],
+			fraction[7],
 			quality,
-		]))
+		])))
 	}
 }
 
@@ -402,6 +411,8 @@ pub enum Iec61850DataError {
 	MissingData,
 	/// Invalid timestamp.
 	InvalidTimestamp { source: time::error::ComponentRange },
+	/// Timestamp out of the range of the data type: {timestamp}.
+	TimestampOutOfRange { timestamp: OffsetDateTime },
 }
 
 #[allow(clippy::unwrap_used, clippy::print_stdout)]
@@ -416,6 +427,24 @@ mod tests {
 		let utc_time = UtcTime(FixedOctetString::from([0, 0, 0, 0, 0, 0, 0, 0]));
 		let offset_date_time = OffsetDateTime::try_from(utc_time).unwrap();
 		assert_eq!(offset_date_time, OffsetDateTime::from_unix_timestamp(0).unwrap());
+
+		// 2023-11-14T22:13:20.5Z
+		let utc_time =
+			UtcTime(FixedOctetString::from([0x65, 0x53, 0xf1, 0x00, 0x80, 0x00, 0x00, 0x0a]));
+		let offset_date_time = OffsetDateTime::try_from(utc_time).unwrap();
+		assert_eq!(
+			offset_date_time,
+			OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_500_000_000).unwrap()
+		);
+		let from_offset_date_time = UtcTime::try_from(offset_date_time).unwrap();
+		assert_eq!(from_offset_date_time.0[..7], [0x65, 0x53, 0xf1, 0x00, 0x80, 0x00, 0x00]);
+
+		// A time before the epoch can't be encoded.
+		let before_epoch = OffsetDateTime::from_unix_timestamp(-1).unwrap();
+		assert!(matches!(
+			UtcTime::try_from(before_epoch),
+			Err(Iec61850DataError::TimestampOutOfRange { .. })
+		));
 	}
 	#[test]
 	fn test_from_bitstring_to_bit_string() {
@@ -0,0 +1,230 @@
+//! IEC61850 GOOSE control block.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+
+use crate::iec61850::data::{Iec61850Data, Iec61850DataError};
+
+/// A representation of a GOOSE control block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GooseControlBlock {
+	/// The name of the GOOSE control block.
+	pub name: String,
+	/// Whether the publication is enabled.
+	pub enabled: bool, // Index 0
+	/// The id of the GOOSE control block.
+	pub id: String, // Index 1
+	/// The dataset of the GOOSE control block.
+	pub dataset: String, // Index 2
+	/// The configuration revision of the GOOSE control block.
+	pub config_rev: u32, // Index 3
+	/// Whether the GOOSE control block needs commissioning.
+	pub needs_commissioning: bool, // Index 4
+	/// The destination of the GOOSE messages.
+	pub destination: DestinationAddress, // Index 5
+	/// The minimum retransmission time in milliseconds. Only present since
+	/// edition 2.
+	pub min_time: Option<u32>, // Index 6
+	/// The maximum retransmission time in milliseconds. Only present since
+	/// edition 2.
+	pub max_time: Option<u32>, // Index 7
+	/// Whether the encoding of the data has a fixed offset. Only present
+	/// since edition 2.
+	pub fixed_offsets: Option<bool>, // Index 8
+}
+
+/// The destination address of a multicast publication (`DstAddress`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationAddress {
+	/// The destination MAC address.
+	pub mac_address: [u8; 6], // Index 0
+	/// The VLAN priority.
+	pub priority: u8, // Index 1
+	/// The VLAN id.
+	pub vlan_id: u16, // Index 2
+	/// The application id.
+	pub app_id: u16, // Index 3
+}
+
+impl GooseControlBlock {
+	/// Create a GOOSE control block from data.
+	pub fn from_data(
+		name: String,
+		data: Vec<Iec61850Data>,
+	) -> Result<Self, GooseControlBlockError> {
+		if !(6..=9).contains(&data.len()) {
+			return InvalidDataLength { length: data.len() }.fail();
+		}
+		// The values come in a specific order
+		let mut data = data.into_iter();
+		Ok(Self {
+			name,
+			enabled: data
+				.next()
+				.context(MissingField { field: "enabled" })?
+				.try_into()
+				.context(InvalidConversion { field: "enabled" })?,
+			id: data
+				.next()
+				.context(MissingField { field: "id" })?
+				.try_into()
+				.context(InvalidConversion { field: "id" })?,
+			dataset: data
+				.next()
+				.context(MissingField { field: "dataset" })?
+				.try_into()
+				.context(InvalidConversion { field: "dataset" })?,
+			config_rev: data
+				.next()
+				.context(MissingField { field: "config_rev" })?
+				.try_into()
+				.context(InvalidConversion { field: "config_rev" })?,
+			needs_commissioning: data
+				.next()
+				.context(MissingField { field: "needs_commissioning" })?
+				.try_into()
+				.context(InvalidConversion { field: "needs_commissioning" })?,
+			destination: DestinationAddress::from_data(
+				data.next().context(MissingField { field: "destination" })?,
+			)?,
+			min_time: data
+				.next()
+				.map(TryInto::try_into)
+				.transpose()
+				.context(InvalidConversion { field: "min_time" })?,
+			max_time: data
+				.next()
+				.map(TryInto::try_into)
+				.transpose()
+				.context(InvalidConversion { field: "max_time" })?,
+			fixed_offsets: data
+				.next()
+				.map(TryInto::try_into)
+				.transpose()
+				.context(InvalidConversion { field: "fixed_offsets" })?,
+		})
+	}
+}
+
+impl DestinationAddress {
+	/// Create a destination address from data.
+	pub fn from_data(data: Iec61850Data) -> Result<Self, GooseControlBlockError> {
+		let Iec61850Data::Structure(data) = data else {
+			return InvalidDestination.fail();
+		};
+		if data.len() != 4 {
+			return InvalidDataLength { length: data.len() }.fail();
+		}
+		let mut data = data.into_iter();
+		let mac_address: Vec<u8> = data
+			.next()
+			.context(MissingField { field: "mac_address" })?
+			.try_into()
+			.context(InvalidConversion { field: "mac_address" })?;
+		Ok(Self {
+			mac_address: <[u8; 6]>::try_from(mac_address.as_slice())
+				.ok()
+				.context(InvalidMacAddress { length: mac_address.len() })?,
+			priority: to_unsigned(data.next(), "priority")?,
+			vlan_id: to_unsigned(data.next(), "vlan_id")?,
+			app_id: to_unsigned(data.next(), "app_id")?,
+		})
+	}
+}
+
+impl From<DestinationAddress> for Iec61850Data {
+	fn from(value: DestinationAddress) -> Self {
+		Self::Structure(vec![
+			Self::OctetString(value.mac_address.to_vec()),
+			Self::Unsigned(value.priority.into()),
+			Self::Unsigned(value.vlan_id.into()),
+			Self::Unsigned(value.app_id.into()),
+		])
+	}
+}
+
+/// Convert the data of an unsigned field.
+fn to_unsigned<T: TryFrom<u32>>(
+	data: Option<Iec61850Data>,
+	field: &str,
+) -> Result<T, GooseControlBlockError> {
+	let value: u32 =
+		data.context(MissingField { field })?.try_into().context(InvalidConversion { field })?;
+	T::try_from(value).ok().context(OutOfRange { field, value })
+}
+
+/// The error type for the GOOSE control block.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum GooseControlBlockError {
+	#[snafu(display("Missing field: {}", field))]
+	MissingField { field: String },
+	#[snafu(display("Invalid conversion for field: {}", field))]
+	InvalidConversion { field: String, source: Iec61850DataError },
+	#[snafu(display("Value out of range for field {}: {}", field, value))]
+	OutOfRange { field: String, value: u32 },
+	#[snafu(display("Invalid destination address"))]
+	InvalidDestination,
+	#[snafu(display("Invalid MAC address length: {}", length))]
+	InvalidMacAddress { length: usize },
+	#[snafu(display("Invalid data length for GOOSE control block. Length: {}", length))]
+	InvalidDataLength { length: usize },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn gocb_data() -> Vec<Iec61850Data> {
+		vec![
+			Iec61850Data::Bool(true),
+			Iec61850Data::String("LD/LLN0.gcbEvents".to_owned()),
+			Iec61850Data::String("LD/LLN0$Events".to_owned()),
+			Iec61850Data::Unsigned(3),
+			Iec61850Data::Bool(false),
+			DestinationAddress {
+				mac_address: [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01],
+				priority: 4,
+				vlan_id: 0,
+				app_id: 0x1000,
+			}
+			.into(),
+		]
+	}
+
+	#[test]
+	fn test_gocb_from_data() {
+		let gocb =
+			GooseControlBlock::from_data("LLN0$GO$gcbEvents".to_owned(), gocb_data()).unwrap();
+		assert!(gocb.enabled);
+		assert_eq!(gocb.config_rev, 3);
+		assert_eq!(gocb.destination.mac_address, [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01]);
+		assert_eq!(gocb.destination.app_id, 0x1000);
+		assert_eq!(gocb.min_time, None);
+
+		let mut data = gocb_data();
+		data.extend([
+			Iec61850Data::Unsigned(4),
+			Iec61850Data::Unsigned(1000),
+			Iec61850Data::Bool(true),
+		]);
+		let gocb = GooseControlBlock::from_data(String::new(), data).unwrap();
+		assert_eq!(gocb.min_time, Some(4));
+		assert_eq!(gocb.max_time, Some(1000));
+		assert_eq!(gocb.fixed_offsets, Some(true));
+
+		let mut data = gocb_data();
+		data[5] = Iec61850Data::Structure(vec![
+			Iec61850Data::OctetString(vec![0x01, 0x0c]),
+			Iec61850Data::Unsigned(4),
+			Iec61850Data::Unsigned(0),
+			Iec61850Data::Unsigned(0x1000),
+		]);
+		assert!(matches!(
+			GooseControlBlock::from_data(String::new(), data),
+			Err(GooseControlBlockError::InvalidMacAddress { length: 2 })
+		));
+	}
+}
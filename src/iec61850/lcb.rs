@@ -0,0 +1,243 @@
+//! IEC61850 log control block and log entries.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use time::OffsetDateTime;
+
+use crate::{
+	iec61850::{
+		data::{Bitstring, Iec61850Data, Iec61850DataError, MissingData},
+		rcb::TriggerOptions,
+	},
+	mms::ans1::mms::asn1::{EntryContentEntryForm, JournalEntry},
+};
+
+/// The variable tag of the reason code of a log entry value.
+const REASON_CODE: &str = "ReasonCode";
+
+/// A representation of a log control block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogControlBlock {
+	/// The name of the log control block.
+	pub name: String,
+	/// Whether the log control block is enabled.
+	pub enabled: bool, // Index 0
+	/// The reference of the log, e.g. `LD/LLN0$EventLog`.
+	pub log_ref: String, // Index 1
+	/// The dataset of the log control block.
+	pub dataset: String, // Index 2
+	/// The time of the oldest entry in the log.
+	pub old_entry_time: OffsetDateTime, // Index 3
+	/// The time of the newest entry in the log.
+	pub new_entry_time: OffsetDateTime, // Index 4
+	/// The id of the oldest entry in the log.
+	pub old_entry: Vec<u8>, // Index 5
+	/// The id of the newest entry in the log.
+	pub new_entry: Vec<u8>, // Index 6
+	/// The trigger options of the log control block.
+	pub trigger_options: Vec<TriggerOptions>, // Index 7
+	/// The integrity period of the log control block.
+	pub integrity_period: u32, // Index 8
+}
+
+impl LogControlBlock {
+	/// Create a log control block from data.
+	pub fn from_data(
+		name: String,
+		mut data: Vec<Iec61850Data>,
+	) -> Result<Self, LogControlBlockError> {
+		if data.len() != 9 {
+			return InvalidDataLength { length: data.len() }.fail();
+		}
+		Ok(Self {
+			name,
+			// The values come in a specific order
+			integrity_period: data
+				.pop()
+				.context(MissingField { field: "integrity_period" })?
+				.try_into()
+				.context(InvalidConversion { field: "integrity_period" })?,
+			trigger_options: data
+				.pop()
+				.context(MissingField { field: "trigger_options" })?
+				.try_into()
+				.context(InvalidConversion { field: "trigger_options" })?,
+			new_entry: data
+				.pop()
+				.context(MissingField { field: "new_entry" })?
+				.try_into()
+				.context(InvalidConversion { field: "new_entry" })?,
+			old_entry: data
+				.pop()
+				.context(MissingField { field: "old_entry" })?
+				.try_into()
+				.context(InvalidConversion { field: "old_entry" })?,
+			new_entry_time: data
+				.pop()
+				.context(MissingField { field: "new_entry_time" })?
+				.try_into()
+				.context(InvalidConversion { field: "new_entry_time" })?,
+			old_entry_time: data
+				.pop()
+				.context(MissingField { field: "old_entry_time" })?
+				.try_into()
+				.context(InvalidConversion { field: "old_entry_time" })?,
+			dataset: data
+				.pop()
+				.context(MissingField { field: "dataset" })?
+				.try_into()
+				.context(InvalidConversion { field: "dataset" })?,
+			log_ref: data
+				.pop()
+				.context(MissingField { field: "log_ref" })?
+				.try_into()
+				.context(InvalidConversion { field: "log_ref" })?,
+			enabled: data
+				.pop()
+				.context(MissingField { field: "enabled" })?
+				.try_into()
+				.context(InvalidConversion { field: "enabled" })?,
+		})
+	}
+}
+
+/// An entry of a log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+	/// The id of the entry.
+	pub entry_id: Vec<u8>,
+	/// The time the entry was added to the log.
+	pub time: OffsetDateTime,
+	/// The values logged in the entry.
+	pub values: Vec<LogEntryValue>,
+}
+
+/// A value logged in a log entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntryValue {
+	/// The reference of the logged data, e.g. `LD/GGIO1$ST$Ind1$stVal`.
+	pub reference: String,
+	/// The logged value.
+	pub value: Iec61850Data,
+	/// The reason why the value was logged.
+	pub reason_code: Option<Bitstring>,
+}
+
+impl TryFrom<JournalEntry> for LogEntry {
+	type Error = Iec61850DataError;
+	fn try_from(value: JournalEntry) -> Result<Self, Self::Error> {
+		let mut values: Vec<LogEntryValue> = Vec::new();
+		if let EntryContentEntryForm::data(data) = value.entry_content.entry_form {
+			for variable in data.list_of_variables.unwrap_or_default() {
+				let reference = variable.variable_tag.0.to_string();
+				let data = Iec61850Data::try_from(variable.value_specification)?;
+				if reference == REASON_CODE {
+					let last = values.last_mut().context(MissingData)?;
+					last.reason_code = Some(data.try_into()?);
+				} else {
+					values.push(LogEntryValue { reference, value: data, reason_code: None });
+				}
+			}
+		}
+		Ok(Self {
+			entry_id: value.entry_identifier.to_vec(),
+			time: value.entry_content.occurrence_time.try_into()?,
+			values,
+		})
+	}
+}
+
+/// The error type for the log control block.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum LogControlBlockError {
+	#[snafu(display("Missing field: {}", field))]
+	MissingField { field: String },
+	#[snafu(display("Invalid conversion for field: {}", field))]
+	InvalidConversion { field: String, source: Iec61850DataError },
+	#[snafu(display("Invalid data length for log control block. Length: {}", length))]
+	InvalidDataLength { length: usize },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use rasn::{ber, prelude::*};
+
+	use super::*;
+	use crate::mms::ans1::mms::asn1::{
+		ApplicationReference, Data, EntryContent, EntryContentEntryFormData, JournalVariable,
+		MMS255String, ReadJournalResponse, TimeOfDay,
+	};
+
+	fn variable(tag: &str, data: Iec61850Data) -> JournalVariable {
+		JournalVariable::new(
+			MMS255String(VisibleString::try_from(tag).unwrap()),
+			Data::try_from(data).unwrap(),
+		)
+	}
+
+	#[test]
+	fn test_log_entry_from_journal_entry() {
+		let time = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+		let reason_code = Bitstring { bytes: vec![0x02], padding: 2 };
+		let entry = JournalEntry::new(
+			OctetString::from(vec![0, 0, 0, 1]),
+			ApplicationReference::new(None, None, None, None),
+			EntryContent::new(
+				TimeOfDay::from(time),
+				EntryContentEntryForm::data(EntryContentEntryFormData::new(
+					None,
+					Some(vec![
+						variable("LD/GGIO1$ST$Ind1$stVal", Iec61850Data::Bool(true)),
+						variable(REASON_CODE, Iec61850Data::BitString(reason_code.clone())),
+					]),
+				)),
+			),
+		);
+
+		// Check that the entry survives the BER encoding of the response.
+		let response = ReadJournalResponse::new(vec![entry], true);
+		let response: ReadJournalResponse = ber::decode(&ber::encode(&response).unwrap()).unwrap();
+		assert!(response.more_follows);
+
+		let entry = LogEntry::try_from(response.list_of_journal_entry[0].clone()).unwrap();
+		assert_eq!(entry.entry_id, vec![0, 0, 0, 1]);
+		assert_eq!(entry.time, time);
+		assert_eq!(
+			entry.values,
+			vec![LogEntryValue {
+				reference: "LD/GGIO1$ST$Ind1$stVal".to_owned(),
+				value: Iec61850Data::Bool(true),
+				reason_code: Some(reason_code),
+			}]
+		);
+	}
+
+	#[test]
+	fn test_lcb_from_data() {
+		let time = OffsetDateTime::UNIX_EPOCH;
+		let data = vec![
+			Iec61850Data::Bool(true),
+			Iec61850Data::String("LD/LLN0$EventLog".to_owned()),
+			Iec61850Data::String("LD/LLN0$Events".to_owned()),
+			Iec61850Data::BinaryTime(time),
+			Iec61850Data::BinaryTime(time),
+			Iec61850Data::OctetString(vec![0; 8]),
+			Iec61850Data::OctetString(vec![1; 8]),
+			Iec61850Data::BitString(vec![TriggerOptions::DataChange].into()),
+			Iec61850Data::Unsigned(1000),
+		];
+		let lcb = LogControlBlock::from_data("LLN0$LG$EventLog".to_owned(), data.clone()).unwrap();
+		assert!(lcb.enabled);
+		assert_eq!(lcb.log_ref, "LD/LLN0$EventLog");
+		assert_eq!(lcb.new_entry, vec![1; 8]);
+		assert_eq!(lcb.trigger_options, vec![TriggerOptions::DataChange]);
+		assert_eq!(lcb.integrity_period, 1000);
+		assert!(matches!(
+			LogControlBlock::from_data(String::new(), data[..8].to_vec()),
+			Err(LogControlBlockError::InvalidDataLength { length: 8 })
+		));
+	}
+}
@@ -3,12 +3,19 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use snafu::{OptionExt as _, Snafu};
+use snafu::OptionExt as _;
 
-use crate::{iec61850::rcb::ReportControlBlock, mms::ans1::mms::asn1::TypeSpecification};
+pub use self::error::*;
+use crate::{
+	iec61850::{
+		gocb::GooseControlBlock, lcb::LogControlBlock, rcb::ReportControlBlock,
+		sgcb::SettingGroupControlBlock, svcb::SampledValueControlBlock,
+	},
+	mms::ans1::mms::asn1::TypeSpecification,
+};
 
 /// A dataset in the IEC61850 ied model.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Dataset {
 	/// The name of the dataset.
 	pub name: String,
@@ -40,6 +47,65 @@ pub struct IedModel {
 	pub logical_devices: Vec<LogicalDevice>,
 }
 
+impl IedModel {
+	/// Check if two models have the same structure.
+	/// Values that change at runtime, like the state of the report control
+	/// blocks, are not compared.
+	#[must_use]
+	pub fn has_same_structure(&self, other: &Self) -> bool {
+		self.logical_devices.len() == other.logical_devices.len()
+			&& self
+				.logical_devices
+				.iter()
+				.zip(&other.logical_devices)
+				.all(|(ld, other)| ld.has_same_structure(other))
+	}
+
+	/// Fill the paths of the logical nodes, nodes, reports and datasets from
+	/// their names. The paths are not serialized, so a deserialized model
+	/// needs them filled before looking nodes up by path.
+	pub fn fill_paths(&mut self) {
+		for ld in &mut self.logical_devices {
+			for ln in &mut ld.logical_nodes {
+				ln.path = format!("{}/{}", ld.name, ln.name);
+				for node in &mut ln.nodes {
+					node.fill_paths(&ln.path);
+				}
+				for report in ln.reports.values_mut() {
+					report.path = format!("{}/{}", ld.name, report.name);
+				}
+				for dataset in ln.datasets.values_mut() {
+					dataset.path = format!("{}/{}", ld.name, dataset.name);
+				}
+			}
+		}
+	}
+
+	/// Find a report by its path.
+	#[must_use]
+	pub fn find_report(&self, path: &str) -> Option<&Report> {
+		self.logical_devices
+			.iter()
+			.flat_map(|ld| &ld.logical_nodes)
+			.flat_map(|ln| ln.reports.values())
+			.find(|report| report.path == path)
+	}
+
+	/// Find a node by its path, e.g. `LD/LN$CO$Pos$Oper`.
+	#[must_use]
+	pub fn find_node(&self, path: &str) -> Option<&Node> {
+		let (ld, rest) = path.split_once('/')?;
+		let (ln, item) = rest.split_once('$')?;
+		self.logical_devices
+			.iter()
+			.find(|logical_device| logical_device.name == ld)?
+			.logical_nodes
+			.iter()
+			.find(|logical_node| logical_node.name == ln)?
+			.find_node(item)
+	}
+}
+
 /// A logical device in the IEC61850 ied model.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogicalDevice {
@@ -55,6 +121,18 @@ impl LogicalDevice {
 	pub const fn new(name: String) -> Self {
 		Self { name, logical_nodes: Vec::new() }
 	}
+
+	/// Check if two logical devices have the same structure.
+	#[must_use]
+	pub fn has_same_structure(&self, other: &Self) -> bool {
+		self.name == other.name
+			&& self.logical_nodes.len() == other.logical_nodes.len()
+			&& self
+				.logical_nodes
+				.iter()
+				.zip(&other.logical_nodes)
+				.all(|(ln, other)| ln.has_same_structure(other))
+	}
 }
 
 /// A logical node in the IEC61850 ied model.
@@ -71,6 +149,18 @@ pub struct LogicalNode {
 	pub reports: HashMap<String, Report>,
 	/// The nodes in the logical node.
 	pub nodes: Vec<Node>,
+	/// The setting group control block, only present in `LLN0`.
+	#[serde(default)]
+	pub setting_group_control_block: Option<SettingGroupControlBlock>,
+	/// The log control blocks in the logical node.
+	#[serde(default)]
+	pub log_control_blocks: HashMap<String, LogControlBlock>,
+	/// The GOOSE control blocks in the logical node.
+	#[serde(default)]
+	pub goose_control_blocks: HashMap<String, GooseControlBlock>,
+	/// The sampled value control blocks in the logical node.
+	#[serde(default)]
+	pub sampled_value_control_blocks: HashMap<String, SampledValueControlBlock>,
 }
 
 impl LogicalNode {
@@ -83,12 +173,107 @@ impl LogicalNode {
 			datasets: HashMap::new(),
 			reports: HashMap::new(),
 			nodes: Vec::new(),
+			setting_group_control_block: None,
+			log_control_blocks: HashMap::new(),
+			goose_control_blocks: HashMap::new(),
+			sampled_value_control_blocks: HashMap::new(),
+		}
+	}
+
+	/// Find a node by its path in the logical node, e.g. `CO$Pos$Oper`.
+	#[must_use]
+	pub fn find_node(&self, item: &str) -> Option<&Node> {
+		let mut nodes: &[Node] = &self.nodes;
+		let mut node = None;
+		for name in item.split('$') {
+			let found = nodes.iter().find(|node| node.name() == name)?;
+			nodes = found.nodes();
+			node = Some(found);
 		}
+		node
+	}
+
+	/// Check if two logical nodes have the same structure.
+	#[must_use]
+	pub fn has_same_structure(&self, other: &Self) -> bool {
+		self.name == other.name
+			&& self.nodes == other.nodes
+			&& self.datasets == other.datasets
+			&& self.reports.len() == other.reports.len()
+			&& self.reports.iter().all(|(name, report)| {
+				other.reports.get(name).is_some_and(|other| other.buffered == report.buffered)
+			}) && self.setting_group_control_block.as_ref().map(|sgcb| sgcb.number_of_groups)
+			== other.setting_group_control_block.as_ref().map(|sgcb| sgcb.number_of_groups)
+			&& self.log_control_blocks.len() == other.log_control_blocks.len()
+			&& self
+				.log_control_blocks
+				.keys()
+				.all(|name| other.log_control_blocks.contains_key(name))
+			&& self.goose_control_blocks.len() == other.goose_control_blocks.len()
+			&& self
+				.goose_control_blocks
+				.keys()
+				.all(|name| other.goose_control_blocks.contains_key(name))
+			&& self.sampled_value_control_blocks.len() == other.sampled_value_control_blocks.len()
+			&& self
+				.sampled_value_control_blocks
+				.keys()
+				.all(|name| other.sampled_value_control_blocks.contains_key(name))
+	}
+}
+
+/// A control block stored in a logical node, e.g. a log control block.
+pub trait ControlBlock: Sized {
+	/// The name of the control block in its logical device, e.g.
+	/// `LLN0$LG$log01`.
+	fn name(&self) -> &str;
+
+	/// The control blocks of the same kind in a logical node.
+	fn control_blocks(ln: &mut LogicalNode) -> &mut HashMap<String, Self>;
+}
+
+impl ControlBlock for Report {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn control_blocks(ln: &mut LogicalNode) -> &mut HashMap<String, Self> {
+		&mut ln.reports
+	}
+}
+
+impl ControlBlock for LogControlBlock {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn control_blocks(ln: &mut LogicalNode) -> &mut HashMap<String, Self> {
+		&mut ln.log_control_blocks
+	}
+}
+
+impl ControlBlock for GooseControlBlock {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn control_blocks(ln: &mut LogicalNode) -> &mut HashMap<String, Self> {
+		&mut ln.goose_control_blocks
+	}
+}
+
+impl ControlBlock for SampledValueControlBlock {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn control_blocks(ln: &mut LogicalNode) -> &mut HashMap<String, Self> {
+		&mut ln.sampled_value_control_blocks
 	}
 }
 
 /// A node in the IEC61850 ied model.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum Node {
 	/// A data attribute.
@@ -119,19 +304,33 @@ impl LogicalDevice {
 		&mut self,
 		reports: Vec<(String, ReportControlBlock)>,
 	) -> Result<(), ModelError> {
-		for (report, rcb) in reports {
-			let buffered = matches!(rcb, ReportControlBlock::Buffered(_));
+		let reports = reports
+			.into_iter()
+			.map(|(report, rcb)| Report {
+				path: format!("{}/{}", self.name, report),
+				name: report,
+				buffered: matches!(rcb, ReportControlBlock::Buffered(_)),
+				rcb,
+			})
+			.collect();
+		self.add_control_blocks(reports)
+	}
+
+	/// Add control blocks to the logical nodes of the logical device.
+	pub fn add_control_blocks<T: ControlBlock>(
+		&mut self,
+		control_blocks: Vec<T>,
+	) -> Result<(), ModelError> {
+		for control_block in control_blocks {
+			let name = control_block.name().to_owned();
 			let ln_name =
-				report.split_once("$").with_context(|| InvalidReport { report: report.clone() })?.0;
+				name.split_once("$").with_context(|| InvalidControlBlock { name: &name })?.0;
 			let ln = self
 				.logical_nodes
 				.iter_mut()
 				.find(|ln| ln.name == ln_name)
 				.with_context(|| LogicalNodeNotFound { ln_name })?;
-			ln.reports.insert(
-				report.clone(),
-				Report { path: format!("{}/{}", self.name, report), name: report, buffered, rcb },
-			);
+			T::control_blocks(ln).insert(name, control_block);
 		}
 		Ok(())
 	}
@@ -179,6 +378,24 @@ impl LogicalNode {
 						continue;
 					}
 
+					// LG is a special node that represents log control blocks.
+					if name == "LG" {
+						tracing::debug!("Found LG node. Skipping...");
+						continue;
+					}
+
+					// GO is a special node that represents GOOSE control blocks.
+					if name == "GO" {
+						tracing::debug!("Found GO node. Skipping...");
+						continue;
+					}
+
+					// MS and US are special nodes that represent sampled value control blocks.
+					if name == "MS" || name == "US" {
+						tracing::debug!("Found MS or US node. Skipping...");
+						continue;
+					}
+
 					let path = format!("{}${name}", self.path);
 
 					let sub_node = Node::to_nodes(name, path, component.component_type);
@@ -191,6 +408,20 @@ impl LogicalNode {
 }
 
 impl Node {
+	/// Fill the path of the node and its sub nodes from the path of its
+	/// parent.
+	fn fill_paths(&mut self, parent: &str) {
+		match self {
+			Self::DataAttribute { name, path, .. } => *path = format!("{parent}${name}"),
+			Self::DataObject { name, path, nodes } => {
+				*path = format!("{parent}${name}");
+				for node in nodes {
+					node.fill_paths(path);
+				}
+			}
+		}
+	}
+
 	/// Convert the type specification to a node.
 	pub fn to_nodes(name: String, path: String, value: TypeSpecification) -> Self {
 		match value {
@@ -250,6 +481,31 @@ impl Node {
 			}
 		}
 	}
+
+	/// Get the name of the node.
+	#[must_use]
+	pub fn name(&self) -> &str {
+		match self {
+			Self::DataAttribute { name, .. } | Self::DataObject { name, .. } => name,
+		}
+	}
+
+	/// Get the path of the node.
+	#[must_use]
+	pub fn path(&self) -> &str {
+		match self {
+			Self::DataAttribute { path, .. } | Self::DataObject { path, .. } => path,
+		}
+	}
+
+	/// Get the child nodes. A data attribute has none.
+	#[must_use]
+	pub fn nodes(&self) -> &[Self] {
+		match self {
+			Self::DataAttribute { .. } => &[],
+			Self::DataObject { nodes, .. } => nodes,
+		}
+	}
 }
 
 impl std::fmt::Display for IedModel {
@@ -262,17 +518,27 @@ impl std::fmt::Display for IedModel {
 	}
 }
 
-#[allow(missing_docs)]
-/// The error type for the IEC61850 ied model.
-#[derive(Debug, Snafu)]
-#[snafu(visibility(pub), context(suffix(false)))]
-pub enum ModelError {
-	#[snafu(display("Invalid report path: {}", report))]
-	InvalidReport { report: String },
-	#[snafu(display("Logical node not found: {}", ln_name))]
-	LogicalNodeNotFound { ln_name: String },
-	#[snafu(display("Invalid dataset path: {}", dataset))]
-	InvalidDataset { dataset: String },
-	#[snafu(display("Dataset not found: {}", dataset_name))]
-	DatasetNotFound { dataset_name: String },
+/// The error of the ied model, in its own module so that the deprecated
+/// variant can be allowed in the code derived for it.
+#[allow(deprecated)]
+mod error {
+	use snafu::Snafu;
+
+	#[allow(missing_docs)]
+	/// The error type for the IEC61850 ied model.
+	#[derive(Debug, Snafu)]
+	#[snafu(visibility(pub), context(suffix(false)))]
+	pub enum ModelError {
+		#[snafu(display("Invalid control block path: {}", name))]
+		InvalidControlBlock { name: String },
+		#[deprecated(note = "no longer returned, see `InvalidControlBlock`")]
+		#[snafu(display("Invalid report path: {}", report))]
+		InvalidReport { report: String },
+		#[snafu(display("Logical node not found: {}", ln_name))]
+		LogicalNodeNotFound { ln_name: String },
+		#[snafu(display("Invalid dataset path: {}", dataset))]
+		InvalidDataset { dataset: String },
+		#[snafu(display("Dataset not found: {}", dataset_name))]
+		DatasetNotFound { dataset_name: String },
+	}
 }
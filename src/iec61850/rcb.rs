@@ -6,6 +6,32 @@ use time::OffsetDateTime;
 
 use crate::iec61850::data::{Bitstring, Iec61850Data, Iec61850DataError};
 
+/// The attributes of a buffered report control block, in the order of its
+/// MMS structure.
+pub const BUFFERED_ATTRIBUTES: [&str; 14] = [
+	"RptID",
+	"RptEna",
+	"DatSet",
+	"ConfRev",
+	"OptFlds",
+	"BufTm",
+	"SqNum",
+	"TrgOps",
+	"IntgPd",
+	"GI",
+	"PurgeBuf",
+	"EntryID",
+	"TimeofEntry",
+	"ResvTms",
+];
+
+/// The attributes of an unbuffered report control block, in the order of its
+/// MMS structure.
+pub const UNBUFFERED_ATTRIBUTES: [&str; 11] = [
+	"RptID", "RptEna", "Resv", "DatSet", "ConfRev", "OptFlds", "BufTm", "SqNum", "TrgOps",
+	"IntgPd", "GI",
+];
+
 /// A representation of a report control block.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ReportControlBlock {
@@ -202,6 +228,76 @@ impl BufferedReportControlBlock {
 	}
 }
 
+impl BufferedReportControlBlock {
+	/// Convert the report control block to data, in the order of
+	/// [`BUFFERED_ATTRIBUTES`].
+	#[must_use]
+	pub fn to_data(&self) -> Vec<Iec61850Data> {
+		vec![
+			Iec61850Data::String(self.id.clone()),
+			Iec61850Data::Bool(self.enabled),
+			Iec61850Data::String(self.dataset.clone()),
+			Iec61850Data::Unsigned(self.config_rev),
+			self.optional_fields.clone().into(),
+			Iec61850Data::Unsigned(self.buffer_time),
+			Iec61850Data::Unsigned(self.sequence_number),
+			self.trigger_options.clone().into(),
+			Iec61850Data::Unsigned(self.integrity_period),
+			Iec61850Data::Bool(self.gi),
+			Iec61850Data::Bool(self.purge_buffer),
+			Iec61850Data::OctetString(self.entry_id.clone()),
+			Iec61850Data::BinaryTime(self.time_of_entry),
+			Iec61850Data::Integer(self.reservation_time),
+		]
+	}
+}
+
+#[cfg(test)]
+impl BufferedReportControlBlock {
+	/// A disabled `LLN0$BR$brcb01` on `LD/LLN0$DS1`, for the tests to update
+	/// with the attributes they exercise.
+	pub(crate) fn fixture() -> Self {
+		Self {
+			name: "LLN0$BR$brcb01".to_owned(),
+			id: "brcb01".to_owned(),
+			enabled: false,
+			dataset: "LD/LLN0$DS1".to_owned(),
+			config_rev: 1,
+			optional_fields: Vec::new(),
+			buffer_time: 0,
+			sequence_number: 0,
+			trigger_options: vec![TriggerOptions::DataChange],
+			integrity_period: 0,
+			gi: false,
+			purge_buffer: false,
+			entry_id: vec![0; 8],
+			time_of_entry: OffsetDateTime::UNIX_EPOCH,
+			reservation_time: 0,
+		}
+	}
+}
+
+impl UnbufferedReportControlBlock {
+	/// Convert the report control block to data, in the order of
+	/// [`UNBUFFERED_ATTRIBUTES`].
+	#[must_use]
+	pub fn to_data(&self) -> Vec<Iec61850Data> {
+		vec![
+			Iec61850Data::String(self.id.clone()),
+			Iec61850Data::Bool(self.enabled),
+			Iec61850Data::Bool(self.reservation),
+			Iec61850Data::String(self.dataset.clone()),
+			Iec61850Data::Unsigned(self.config_rev),
+			self.optional_fields.clone().into(),
+			Iec61850Data::Unsigned(self.buffer_time),
+			Iec61850Data::Unsigned(self.sequence_number),
+			self.trigger_options.clone().into(),
+			Iec61850Data::Unsigned(self.integrity_period),
+			Iec61850Data::Bool(self.gi),
+		]
+	}
+}
+
 impl UnbufferedReportControlBlock {
 	/// Create a report control block from data.
 	pub fn from_data(
@@ -284,6 +380,16 @@ impl ReportControlBlock {
 			InvalidDataLength { length: data.len() }.fail()
 		}
 	}
+
+	/// Convert the report control block to data, in the order of its
+	/// attributes.
+	#[must_use]
+	pub fn to_data(&self) -> Vec<Iec61850Data> {
+		match self {
+			ReportControlBlock::Buffered(rcb) => rcb.to_data(),
+			ReportControlBlock::Unbuffered(rcb) => rcb.to_data(),
+		}
+	}
 }
 
 /// The error type for the report control block.
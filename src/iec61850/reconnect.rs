@@ -0,0 +1,540 @@
+//! Automatic reconnection of the IEC61850 client.
+
+use std::{
+	collections::HashMap,
+	fmt,
+	sync::{Arc, Mutex, PoisonError},
+	time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+	sync::{RwLock, broadcast, watch},
+	task::JoinHandle,
+	time,
+};
+
+use crate::{
+	Iec61850Client,
+	iec61850::{
+		Iec61850ClientError, dataset_reference,
+		rcb::{OptionalFields, ReportControlBlock, TriggerOptions},
+		report::Report,
+	},
+	mms::{
+		ReportCallback,
+		client::{ConnectionLost, ConnectionState},
+	},
+};
+
+/// The capacity of the reconnect event channel.
+const RECONNECT_EVENT_CAPACITY: usize = 16;
+
+/// The reconnect configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ReconnectConfig {
+	/// The delay before the first reconnect attempt in milliseconds.
+	pub initial_delay_ms: u64,
+	/// The maximum delay between reconnect attempts in milliseconds.
+	pub max_delay_ms: u64,
+	/// The maximum number of consecutive attempts. `None` retries forever.
+	pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+	fn default() -> Self {
+		Self { initial_delay_ms: 500, max_delay_ms: 30_000, max_attempts: None }
+	}
+}
+
+/// The settings written by the application to a report control block.
+/// Only the settings that were set are restored after a reconnect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct RcbSettings {
+	/// The dataset.
+	pub(crate) dataset: Option<String>,
+	/// The optional fields.
+	pub(crate) optional_fields: Option<Vec<OptionalFields>>,
+	/// The trigger options.
+	pub(crate) trigger_options: Option<Vec<TriggerOptions>>,
+	/// The integrity period.
+	pub(crate) integrity_period: Option<u32>,
+	/// The buffer time.
+	pub(crate) buffer_time: Option<u32>,
+	/// The enabled state.
+	pub(crate) enabled: Option<bool>,
+	/// The GI state.
+	pub(crate) gi: Option<bool>,
+}
+
+/// A write restoring an attribute of a report control block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RcbWrite {
+	/// Write `DatSet`.
+	Dataset(String),
+	/// Write `OptFlds`.
+	OptionalFields(Vec<OptionalFields>),
+	/// Write `TrgOps`.
+	TriggerOptions(Vec<TriggerOptions>),
+	/// Write `IntgPd`.
+	IntegrityPeriod(u32),
+	/// Write `BufTm`.
+	BufferTime(u32),
+	/// Write `EntryID`.
+	EntryId(Vec<u8>),
+	/// Write `RptEna`.
+	Enabled(bool),
+	/// Write `GI`.
+	Gi(bool),
+}
+
+impl RcbWrite {
+	/// The name of the written attribute.
+	const fn attribute(&self) -> &'static str {
+		match self {
+			Self::Dataset(_) => "DatSet",
+			Self::OptionalFields(_) => "OptFlds",
+			Self::TriggerOptions(_) => "TrgOps",
+			Self::IntegrityPeriod(_) => "IntgPd",
+			Self::BufferTime(_) => "BufTm",
+			Self::EntryId(_) => "EntryID",
+			Self::Enabled(_) => "RptEna",
+			Self::Gi(_) => "GI",
+		}
+	}
+}
+
+/// The outcome of a reconnect.
+#[derive(Debug, Clone)]
+pub struct Reconnection {
+	/// Whether the reloaded model has the same structure as the cached one.
+	/// The report control block settings are only restored on the same model.
+	pub same_model: bool,
+	/// The report control block settings that could not be restored.
+	pub rcb_failures: Vec<RcbRestoreFailure>,
+}
+
+/// An event of the reconnect supervisor.
+#[derive(Debug, Clone)]
+pub enum ReconnectEvent {
+	/// The client reconnected.
+	Reconnected {
+		/// The number of attempts it took.
+		attempts: u32,
+		/// The outcome of the reconnect, e.g. whether the model changed.
+		reconnection: Reconnection,
+	},
+	/// The supervisor gave up reconnecting after the maximum number of
+	/// attempts.
+	GaveUp {
+		/// The number of attempts made.
+		attempts: u32,
+	},
+}
+
+/// A report control block attribute that could not be restored after a
+/// reconnect.
+#[derive(Debug, Clone)]
+pub struct RcbRestoreFailure {
+	/// The path of the report control block.
+	pub rcb: String,
+	/// The attribute that could not be restored, e.g. `RptEna`. `None` if the
+	/// report control block could not be read back.
+	pub attribute: Option<&'static str>,
+	/// The error of the read or the write.
+	pub error: Arc<Iec61850ClientError>,
+}
+
+impl RcbSettings {
+	/// The writes restoring the settings on a report control block read back
+	/// from the ied. Only the attributes whose value differs are written, as
+	/// writing `DatSet` purges the buffer of a buffered report control block.
+	/// The entry id is written after every attribute that can purge the
+	/// buffer and before the report control block is enabled.
+	/// See [`apply_restore_writes`] for the failed writes.
+	pub(crate) fn restore_writes(
+		&self,
+		current: &ReportControlBlock,
+		entry_id: Option<Vec<u8>>,
+	) -> Vec<RcbWrite> {
+		let (dataset, optional_fields, trigger_options, integrity_period, buffer_time, enabled) =
+			match current {
+				ReportControlBlock::Buffered(rcb) => (
+					&rcb.dataset,
+					&rcb.optional_fields,
+					&rcb.trigger_options,
+					rcb.integrity_period,
+					rcb.buffer_time,
+					rcb.enabled,
+				),
+				ReportControlBlock::Unbuffered(rcb) => (
+					&rcb.dataset,
+					&rcb.optional_fields,
+					&rcb.trigger_options,
+					rcb.integrity_period,
+					rcb.buffer_time,
+					rcb.enabled,
+				),
+			};
+		let current_entry_id = match current {
+			ReportControlBlock::Buffered(rcb) => Some(&rcb.entry_id),
+			ReportControlBlock::Unbuffered(_) => None,
+		};
+
+		let mut writes = Vec::new();
+		if let Some(value) = &self.dataset
+			&& dataset_reference(value) != dataset
+		{
+			writes.push(RcbWrite::Dataset(value.clone()));
+		}
+		if let Some(value) = &self.optional_fields
+			&& !same_elements(value, optional_fields)
+		{
+			writes.push(RcbWrite::OptionalFields(value.clone()));
+		}
+		if let Some(value) = &self.trigger_options
+			&& !same_elements(value, trigger_options)
+		{
+			writes.push(RcbWrite::TriggerOptions(value.clone()));
+		}
+		if let Some(value) = self.integrity_period
+			&& value != integrity_period
+		{
+			writes.push(RcbWrite::IntegrityPeriod(value));
+		}
+		if let Some(value) = self.buffer_time
+			&& value != buffer_time
+		{
+			writes.push(RcbWrite::BufferTime(value));
+		}
+		if let Some(value) = entry_id
+			&& current_entry_id.is_some_and(|current| *current != value)
+		{
+			writes.push(RcbWrite::EntryId(value));
+		}
+		if let Some(value) = self.enabled
+			&& value != enabled
+		{
+			writes.push(RcbWrite::Enabled(value));
+		}
+		if let Some(true) = self.gi {
+			writes.push(RcbWrite::Gi(true));
+		}
+		writes
+	}
+}
+
+/// Apply the writes restoring a report control block with `write` and
+/// return the attributes whose write failed.
+/// A failed write doesn't stop the next ones. In particular an ied that
+/// restarted rejects the stale entry id: the report control block is then
+/// enabled without it and reports from the start of its buffer.
+pub(crate) async fn apply_restore_writes<F, E>(
+	writes: Vec<RcbWrite>,
+	mut write: impl FnMut(RcbWrite) -> F,
+) -> Vec<(&'static str, E)>
+where
+	F: Future<Output = Result<(), E>>,
+{
+	let mut failures = Vec::new();
+	for rcb_write in writes {
+		let attribute = rcb_write.attribute();
+		if let Err(e) = write(rcb_write).await {
+			failures.push((attribute, e));
+		}
+	}
+	failures
+}
+
+/// Whether two lists hold the same elements, in any order.
+fn same_elements<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+	a.iter().all(|element| b.contains(element)) && b.iter().all(|element| a.contains(element))
+}
+
+/// A report callback that keeps track of the last entry id of every report
+/// before forwarding the report to the application.
+#[derive(Clone)]
+pub(crate) struct ReportTracker {
+	/// The application callback.
+	callback: Arc<dyn ReportCallback + Send + Sync>,
+	/// The last entry id received for every report id.
+	entry_ids: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl ReportTracker {
+	/// Create a new report tracker.
+	pub(crate) fn new(callback: Arc<dyn ReportCallback + Send + Sync>) -> Self {
+		Self { callback, entry_ids: Arc::default() }
+	}
+
+	/// Get the last entry id received for a report id.
+	pub(crate) fn entry_id(&self, report_id: &str) -> Option<Vec<u8>> {
+		self.entry_ids.lock().unwrap_or_else(PoisonError::into_inner).get(report_id).cloned()
+	}
+}
+
+impl fmt::Debug for ReportTracker {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReportTracker").field("entry_ids", &self.entry_ids).finish_non_exhaustive()
+	}
+}
+
+#[async_trait::async_trait]
+impl ReportCallback for ReportTracker {
+	async fn on_report(&self, report: Report) {
+		if let Some(entry_id) = &report.entry_id {
+			self.entry_ids
+				.lock()
+				.unwrap_or_else(PoisonError::into_inner)
+				.insert(report.id.clone(), entry_id.clone());
+		}
+		self.callback.on_report(report).await;
+	}
+}
+
+/// A supervisor that reconnects the client when the association is lost.
+///
+/// After a reconnect the model is reloaded and compared with the cached one
+/// and, if it didn't change, every report control block setting done through
+/// the `set_rcb_*` methods is applied again. Buffered report control blocks
+/// are resumed from the last received entry id. The outcome is published as a
+/// [`ReconnectEvent`].
+///
+/// The new connection is established and its model loaded without locking
+/// the client, so the application can keep reading it in the meantime.
+#[derive(Debug)]
+pub struct ReconnectSupervisor {
+	/// The supervised client.
+	client: Arc<RwLock<Iec61850Client>>,
+	/// The connection state, kept across reconnects.
+	state: watch::Receiver<ConnectionState>,
+	/// The sender for the reconnect events.
+	events: broadcast::Sender<ReconnectEvent>,
+	/// The supervisor task.
+	task: JoinHandle<()>,
+}
+
+impl ReconnectSupervisor {
+	/// Start supervising a client.
+	#[must_use]
+	pub fn new(client: Iec61850Client, config: ReconnectConfig) -> Self {
+		let (state_tx, state) = watch::channel(client.connection_state().borrow().clone());
+		let (events, _) = broadcast::channel(RECONNECT_EVENT_CAPACITY);
+		let client = Arc::new(RwLock::new(client));
+		let task = tokio::spawn(supervise(client.clone(), config, state_tx, events.clone()));
+		Self { client, state, events, task }
+	}
+
+	/// Get the supervised client.
+	#[must_use]
+	pub fn client(&self) -> Arc<RwLock<Iec61850Client>> {
+		self.client.clone()
+	}
+
+	/// Get a receiver for the connection state.
+	/// Unlike the client one, this receiver stays valid across reconnects.
+	#[must_use]
+	pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+		self.state.clone()
+	}
+
+	/// Get a receiver for the reconnect events.
+	/// Only the events sent after this call are received.
+	#[must_use]
+	pub fn events(&self) -> broadcast::Receiver<ReconnectEvent> {
+		self.events.subscribe()
+	}
+}
+
+impl Drop for ReconnectSupervisor {
+	fn drop(&mut self) {
+		self.task.abort();
+	}
+}
+
+/// The supervisor loop.
+/// Forwards the connection state and reconnects when the association is
+/// aborted. Stops when the association is closed by the application.
+async fn supervise(
+	client: Arc<RwLock<Iec61850Client>>,
+	config: ReconnectConfig,
+	state_tx: watch::Sender<ConnectionState>,
+	events: broadcast::Sender<ReconnectEvent>,
+) {
+	loop {
+		let mut state = client.read().await.connection_state();
+		let cause = loop {
+			let current = state.borrow_and_update().clone();
+			state_tx.send_replace(current.clone());
+			match current {
+				ConnectionState::Aborted(cause) => break cause,
+				ConnectionState::Closed => return,
+				ConnectionState::Connected
+				| ConnectionState::Troubled(_)
+				| ConnectionState::Closing => {}
+			}
+			if state.changed().await.is_err() {
+				break Arc::new(ConnectionLost.build());
+			}
+		};
+		tracing::warn!("Association lost: {cause}. Reconnecting...");
+
+		let (client_config, report_tracker) = {
+			let client = client.read().await;
+			(client.config.clone(), client.report_tracker.clone())
+		};
+		let mut delay = Duration::from_millis(config.initial_delay_ms);
+		let mut attempt: u32 = 0;
+		let connection = loop {
+			time::sleep(delay).await;
+			attempt += 1;
+			match Iec61850Client::connect(client_config.clone(), report_tracker.clone()).await {
+				Ok(connection) => break connection,
+				Err(e) => {
+					tracing::warn!("Reconnect attempt {attempt} failed: {e}");
+					if config.max_attempts.is_some_and(|max| attempt >= max) {
+						tracing::error!("Giving up reconnecting after {attempt} attempts");
+						let _ = events.send(ReconnectEvent::GaveUp { attempts: attempt });
+						return;
+					}
+					delay = next_delay(delay, &config);
+				}
+			}
+		};
+		tracing::info!("Reconnected after {attempt} attempts");
+
+		let same_model = client.write().await.replace_connection(connection);
+		let reconnection = client.read().await.resume(same_model).await;
+		let _ = events.send(ReconnectEvent::Reconnected { attempts: attempt, reconnection });
+	}
+}
+
+/// Get the delay for the next reconnect attempt.
+fn next_delay(delay: Duration, config: &ReconnectConfig) -> Duration {
+	delay.saturating_mul(2).min(Duration::from_millis(config.max_delay_ms))
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use tokio::sync::mpsc;
+
+	use super::*;
+	use crate::iec61850::{data::Bitstring, rcb::BufferedReportControlBlock};
+
+	/// A report callback forwarding the report ids to a channel.
+	struct ForwardReports(mpsc::UnboundedSender<String>);
+
+	#[async_trait::async_trait]
+	impl ReportCallback for ForwardReports {
+		async fn on_report(&self, report: Report) {
+			self.0.send(report.id).unwrap();
+		}
+	}
+
+	fn report(id: &str, entry_id: Option<Vec<u8>>) -> Report {
+		Report {
+			id: id.to_owned(),
+			optional_fields: Vec::new(),
+			sequence_number: None,
+			time_of_entry: None,
+			dataset: None,
+			buffer_overflow: None,
+			entry_id,
+			configuration_revision: None,
+			sub_sequence_number: None,
+			more_segments_follows: None,
+			inclusion: Bitstring { bytes: Vec::new(), padding: 0 },
+			data_reference: None,
+			values: Vec::new(),
+			reason_for_transmission: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn test_report_tracker() {
+		let (sender, mut receiver) = mpsc::unbounded_channel();
+		let tracker = ReportTracker::new(Arc::new(ForwardReports(sender)));
+		tracker.on_report(report("brcb01", Some(vec![1]))).await;
+		tracker.on_report(report("urcb01", None)).await;
+		tracker.on_report(report("brcb01", Some(vec![2]))).await;
+		assert_eq!(receiver.recv().await.unwrap(), "brcb01");
+		assert_eq!(receiver.recv().await.unwrap(), "urcb01");
+		assert_eq!(receiver.recv().await.unwrap(), "brcb01");
+		assert_eq!(tracker.entry_id("brcb01"), Some(vec![2]));
+		assert_eq!(tracker.entry_id("urcb01"), None);
+	}
+
+	#[test]
+	fn test_restore_writes() {
+		let current = ReportControlBlock::Buffered(BufferedReportControlBlock {
+			optional_fields: vec![OptionalFields::SequenceNumber, OptionalFields::EntryID],
+			buffer_time: 100,
+			..BufferedReportControlBlock::fixture()
+		});
+		let settings = RcbSettings {
+			dataset: Some("LD/LLN0$DS1".to_owned()),
+			optional_fields: Some(vec![OptionalFields::EntryID, OptionalFields::SequenceNumber]),
+			trigger_options: Some(vec![TriggerOptions::DataChange, TriggerOptions::Gi]),
+			integrity_period: None,
+			buffer_time: Some(100),
+			enabled: Some(true),
+			gi: Some(true),
+		};
+		// The unchanged attributes are not written and the entry id is
+		// written after the trigger options, before enabling.
+		assert_eq!(
+			settings.restore_writes(&current, Some(vec![0, 0, 0, 0, 0, 0, 0, 5])),
+			[
+				RcbWrite::TriggerOptions(vec![TriggerOptions::DataChange, TriggerOptions::Gi]),
+				RcbWrite::EntryId(vec![0, 0, 0, 0, 0, 0, 0, 5]),
+				RcbWrite::Enabled(true),
+				RcbWrite::Gi(true),
+			]
+		);
+		let settings = RcbSettings { dataset: Some("LD/LLN0$DS2".to_owned()), ..settings };
+		assert_eq!(
+			settings.restore_writes(&current, None)[..2],
+			[
+				RcbWrite::Dataset("LD/LLN0$DS2".to_owned()),
+				RcbWrite::TriggerOptions(vec![TriggerOptions::DataChange, TriggerOptions::Gi]),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_apply_restore_writes() {
+		let writes = vec![
+			RcbWrite::EntryId(vec![0, 0, 0, 0, 0, 0, 0, 5]),
+			RcbWrite::Enabled(true),
+			RcbWrite::Gi(true),
+		];
+		// The ied restarted and rejects the stale entry id.
+		let mut written = Vec::new();
+		let failures = apply_restore_writes(writes, |write| {
+			let result = match write {
+				RcbWrite::EntryId(_) => Err("object value invalid"),
+				_ => Ok(()),
+			};
+			written.push(write);
+			std::future::ready(result)
+		})
+		.await;
+		assert_eq!(failures, [("EntryID", "object value invalid")]);
+		assert_eq!(written[1..], [RcbWrite::Enabled(true), RcbWrite::Gi(true)]);
+	}
+
+	#[test]
+	fn test_next_delay() {
+		let config =
+			ReconnectConfig { initial_delay_ms: 500, max_delay_ms: 3000, max_attempts: None };
+		let mut delay = Duration::from_millis(config.initial_delay_ms);
+		let mut delays = Vec::new();
+		for _ in 0..4 {
+			delay = next_delay(delay, &config);
+			delays.push(delay.as_millis());
+		}
+		assert_eq!(delays, vec![1000, 2000, 3000, 3000]);
+	}
+}
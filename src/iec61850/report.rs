@@ -1,5 +1,6 @@
 //! IEC61850 report.
 
+use rasn::prelude::VisibleString;
 use snafu::{OptionExt as _, ResultExt as _, Snafu};
 use time::OffsetDateTime;
 
@@ -9,7 +10,13 @@ use super::{
 };
 use crate::{
 	iec61850::data::{Bitstring, Iec61850DataError},
-	mms::ans1::mms::asn1::{AccessResult, DataAccessError, InformationReport},
+	mms::{
+		ans1::mms::asn1::{
+			AccessResult, Data, Identifier, InformationReport, ObjectName,
+			VariableAccessSpecification,
+		},
+		error::DataAccessError,
+	},
 };
 
 /// A representation of a report.
@@ -55,7 +62,8 @@ impl TryFrom<InformationReport> for Report {
 			.map(|access_result| match access_result {
 				AccessResult::success(data) => data.try_into().context(FailedToConvertData),
 				AccessResult::failure(e) => {
-					FailedToConvertAccessResults { data_access_error: e }.fail()
+					FailedToConvertAccessResults { data_access_error: DataAccessError::from(e) }
+						.fail()
 				}
 			})
 			.collect::<Result<Vec<Iec61850Data>, ReportError>>()?;
@@ -231,6 +239,83 @@ impl TryFrom<InformationReport> for Report {
 	}
 }
 
+/// The name of the variable list of the information reports sent by an RCB.
+pub const REPORT_LIST_NAME: &str = "RPT";
+
+impl TryFrom<Report> for InformationReport {
+	type Error = ReportError;
+	fn try_from(report: Report) -> Result<Self, Self::Error> {
+		let has = |field| report.optional_fields.contains(&field);
+		let mut values =
+			vec![Iec61850Data::String(report.id.clone()), report.optional_fields.clone().into()];
+		if has(OptionalFields::SequenceNumber) {
+			let sequence_number =
+				report.sequence_number.context(MissingField { field: "sequence_number" })?;
+			values.push(Iec61850Data::Unsigned(sequence_number));
+		}
+		if has(OptionalFields::ReportTimestamp) {
+			let time_of_entry =
+				report.time_of_entry.context(MissingField { field: "time_of_entry" })?;
+			values.push(Iec61850Data::BinaryTime(time_of_entry));
+		}
+		if has(OptionalFields::DataSetName) {
+			let dataset = report.dataset.clone().context(MissingField { field: "dataset" })?;
+			values.push(Iec61850Data::String(dataset));
+		}
+		if has(OptionalFields::BufferOverflow) {
+			let buffer_overflow =
+				report.buffer_overflow.context(MissingField { field: "buffer_overflow" })?;
+			values.push(Iec61850Data::Bool(buffer_overflow));
+		}
+		if has(OptionalFields::EntryID) {
+			let entry_id = report.entry_id.clone().context(MissingField { field: "entry_id" })?;
+			values.push(Iec61850Data::OctetString(entry_id));
+		}
+		if has(OptionalFields::ConfigurationRevision) {
+			let configuration_revision = report
+				.configuration_revision
+				.context(MissingField { field: "configuration_revision" })?;
+			values.push(Iec61850Data::Unsigned(configuration_revision));
+		}
+		if has(OptionalFields::Segmentation) {
+			let sub_sequence_number = report
+				.sub_sequence_number
+				.context(MissingField { field: "sub_sequence_number" })?;
+			let more_segments_follows = report
+				.more_segments_follows
+				.context(MissingField { field: "more_segments_follows" })?;
+			values.push(Iec61850Data::Unsigned(sub_sequence_number));
+			values.push(Iec61850Data::Bool(more_segments_follows));
+		}
+		values.push(Iec61850Data::BitString(report.inclusion));
+		if has(OptionalFields::DataReference) {
+			let data_reference =
+				report.data_reference.context(MissingField { field: "data_reference" })?;
+			values.extend(data_reference.into_iter().map(Iec61850Data::String));
+		}
+		values.extend(report.values);
+		if has(OptionalFields::ReasonForTransmission) {
+			let reasons = report
+				.reason_for_transmission
+				.context(MissingField { field: "reason_for_transmission" })?;
+			values.extend(reasons.into_iter().map(Iec61850Data::from));
+		}
+
+		let list_of_access_result = values
+			.into_iter()
+			.map(|value| Data::try_from(value).map(AccessResult::success))
+			.collect::<Result<Vec<_>, _>>()
+			.context(FailedToConvertData)?;
+		Ok(Self::new(
+			VariableAccessSpecification::variableListName(ObjectName::vmd_specific(Identifier(
+				VisibleString::try_from(REPORT_LIST_NAME)
+					.map_err(|_| ReportError::InvalidReport)?,
+			))),
+			list_of_access_result,
+		))
+	}
+}
+
 /// The error type for the report.
 #[allow(missing_docs)]
 #[derive(Debug, Snafu)]
@@ -0,0 +1,1794 @@
+//! IEC 61850 server implementation.
+//!
+//! The [`Iec61850Server`] serves an [`IedModel`] over the MMS server stack:
+//! the name lists, the types and the values of the data, the datasets, the
+//! report control blocks and the controls. The application updates the
+//! values and receives the controls through an [`Iec61850ServerHandle`].
+//!
+//! The log, GOOSE, sampled value and setting group control blocks of the
+//! model are not served.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	net::SocketAddr,
+	sync::{Arc, Mutex, MutexGuard, PoisonError},
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use rasn::prelude::{Integer, VisibleString};
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use time::OffsetDateTime;
+use tokio::{
+	select,
+	sync::{Notify, broadcast},
+	time::{Instant, sleep_until},
+};
+use tracing::instrument;
+
+pub mod buffer;
+pub mod reporting;
+
+use crate::{
+	iec61850::{
+		control::{
+			AddCause, ControlError, ControlModel, LAST_APPL_ERROR, LastApplError, Originator,
+		},
+		data::{Bitstring, Iec61850Data},
+		model::{IedModel, Node},
+		rcb::{BUFFERED_ATTRIBUTES, ReportControlBlock, TriggerOptions, UNBUFFERED_ATTRIBUTES},
+		server::reporting::{OutgoingReport, ReportDataSource, ReportEngine},
+	},
+	mms::{
+		MmsObjectClass, ServerConfig,
+		ans1::mms::asn1::{
+			AccessResult, AnonymousTypeSpecificationStructureComponents, AnonymousVariableDefs,
+			AnonymousWriteResponse, ConfirmedServiceRequest, ConfirmedServiceResponse, Data,
+			DefineNamedVariableListRequest, DefineNamedVariableListResponse,
+			DeleteNamedVariableListRequest, DeleteNamedVariableListResponse, GetNameListRequest,
+			GetNameListRequestObjectScope, GetNameListResponse,
+			GetNamedVariableListAttributesResponse, GetVariableAccessAttributesRequest,
+			GetVariableAccessAttributesResponse, Identifier, InformationReport, Integer32,
+			ObjectClass, ObjectName, ObjectNameDomainSpecific, ReadRequest, ReadResponse,
+			ServiceError, ServiceErrorErrorClass, TypeSpecification, TypeSpecificationArray,
+			TypeSpecificationFloatingPoint, TypeSpecificationStructure,
+			TypeSpecificationStructureComponents, Unsigned8, Unsigned32,
+			VariableAccessSpecification, VariableDefs, VariableSpecification, WriteRequest,
+			WriteResponse,
+		},
+		error::DataAccessError,
+		server::{
+			MmsServer, MmsServerError, MmsServerHandler, RequestOutcome, ServerConnection,
+			service_error,
+		},
+	},
+};
+
+/// The functional constraints of the attributes the clients can write.
+const WRITABLE_FUNCTIONAL_CONSTRAINTS: [&str; 6] = ["CF", "DC", "SP", "SV", "SE", "BL"];
+/// The functional constraints of the report control blocks.
+const REPORT_FUNCTIONAL_CONSTRAINTS: [&str; 2] = ["BR", "RP"];
+/// The capacity of the control request channel.
+const CONTROL_CAPACITY: usize = 64;
+/// The bytes of a name list response besides the names.
+const NAME_LIST_OVERHEAD: usize = 64;
+/// The bytes of a name in a name list response besides its characters.
+const NAME_OVERHEAD: usize = 4;
+/// The object access unsupported code of the access error class.
+const ACCESS_OBJECT_ACCESS_UNSUPPORTED: u8 = 1;
+/// The object non existent code of the access error class.
+const ACCESS_OBJECT_NON_EXISTENT: u8 = 2;
+/// The object exists code of the definition error class.
+const DEFINITION_OBJECT_EXISTS: u8 = 5;
+/// The scope of delete of the named variable lists given by name.
+const DELETE_SPECIFIC: u8 = 0;
+/// The scope of delete of all the association specific named variable lists.
+const DELETE_AA_SPECIFIC: u8 = 1;
+/// The scope of delete of all the named variable lists of a domain.
+const DELETE_DOMAIN: u8 = 2;
+/// How long a data object stays selected when its `sboTimeout` is not set.
+const DEFAULT_SBO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A control received from a client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlRequest {
+	/// The reference of the data object, e.g. `LD/CSWI1$Pos`.
+	pub object: String,
+	/// The control value.
+	pub ctl_val: Iec61850Data,
+	/// The originator of the control.
+	pub originator: Option<Originator>,
+	/// The control number.
+	pub ctl_num: Option<u8>,
+	/// Whether the control is a test.
+	pub test: bool,
+}
+
+/// An IEC 61850 server.
+#[derive(Debug)]
+pub struct Iec61850Server {
+	/// The MMS server.
+	server: MmsServer,
+	/// The handle to the state of the server.
+	handle: Iec61850ServerHandle,
+}
+
+impl Iec61850Server {
+	/// Bind the server to the address of the configuration and serve the
+	/// model. Every data attribute starts with the default value of its
+	/// type and every report control block starts disabled.
+	#[instrument(skip(model))]
+	pub async fn bind(
+		config: ServerConfig,
+		mut model: IedModel,
+	) -> Result<Self, Iec61850ServerError> {
+		model.fill_paths();
+		let state = ServerState::new(model)?;
+		let (controls, _) = broadcast::channel(CONTROL_CAPACITY);
+		let shared = Arc::new(Shared { state: Mutex::new(state), wakeup: Notify::new(), controls });
+		let server = MmsServer::bind(config, shared.clone()).await.context(Mms)?;
+		Ok(Self { server, handle: Iec61850ServerHandle { shared } })
+	}
+
+	/// The handle to update the values and receive the controls.
+	#[must_use]
+	pub fn handle(&self) -> Iec61850ServerHandle {
+		self.handle.clone()
+	}
+
+	/// The local address of the server.
+	pub fn local_addr(&self) -> Result<SocketAddr, Iec61850ServerError> {
+		self.server.local_addr().context(Mms)
+	}
+
+	/// Serve the clients and send the reports until the listener fails.
+	pub async fn run(self) -> Result<(), Iec61850ServerError> {
+		select! {
+			result = self.server.run() => result.context(Mms),
+			() = self.handle.shared.run_reports() => Ok(()),
+		}
+	}
+}
+
+/// A handle to the state of an IEC 61850 server.
+#[derive(Debug, Clone)]
+pub struct Iec61850ServerHandle {
+	/// The state shared with the server.
+	shared: Arc<Shared>,
+}
+
+impl Iec61850ServerHandle {
+	/// The value of a data object, data attribute or report control block,
+	/// e.g. `LD/GGIO1$ST$Ind1$stVal`.
+	#[must_use]
+	pub fn value(&self, path: &str) -> Option<Iec61850Data> {
+		let state = self.shared.lock();
+		state.reports.read(path).or_else(|| state.store.value(path))
+	}
+
+	/// Update the value of a data object or data attribute and send the
+	/// reports it triggers. A structure updates every attribute of a data
+	/// object.
+	#[instrument(skip(self, value))]
+	pub fn update(&self, path: &str, value: Iec61850Data) -> Result<(), Iec61850ServerError> {
+		let controls = {
+			let mut state = self.shared.lock();
+			let mut effects = Effects::default();
+			state.set_values(path, value, &mut effects).map_err(|error| match error {
+				DataAccessError::TypeInconsistent => TypeMismatch { path }.build(),
+				_ => NodeNotFound { path }.build(),
+			})?;
+			state.deliver(effects)
+		};
+		self.shared.dispatch(controls);
+		Ok(())
+	}
+
+	/// Change the number of reports kept by the buffer of every buffered
+	/// report control block, [`DEFAULT_BUFFER_CAPACITY`] by default.
+	///
+	/// [`DEFAULT_BUFFER_CAPACITY`]: buffer::DEFAULT_BUFFER_CAPACITY
+	pub fn set_report_buffer_capacity(&self, capacity: usize) {
+		self.shared.lock().reports.set_buffer_capacity(capacity);
+	}
+
+	/// Subscribe to the controls operated by the clients.
+	#[must_use]
+	pub fn subscribe_controls(&self) -> broadcast::Receiver<ControlRequest> {
+		self.shared.controls.subscribe()
+	}
+}
+
+/// The state of the server shared with the MMS server and the handles.
+#[derive(Debug)]
+struct Shared {
+	/// The state of the server.
+	state: Mutex<ServerState>,
+	/// Notified when the deadlines of the reports may have changed.
+	wakeup: Notify,
+	/// The sender for the controls.
+	controls: broadcast::Sender<ControlRequest>,
+}
+
+impl Shared {
+	/// Lock the state of the server.
+	fn lock(&self) -> MutexGuard<'_, ServerState> {
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Send the buffered and integrity reports when their time comes.
+	async fn run_reports(&self) {
+		loop {
+			let deadline = {
+				let mut state = self.lock();
+				let state = &mut *state;
+				let mut effects = Effects::default();
+				effects.add_reports(state.reports.poll(&state.store, Instant::now()));
+				state.deliver(effects);
+				state.reports.next_deadline()
+			};
+			match deadline {
+				Some(deadline) => select! {
+					() = sleep_until(deadline) => {}
+					() = self.wakeup.notified() => {}
+				},
+				None => self.wakeup.notified().await,
+			}
+		}
+	}
+
+	/// Send the controls of a request or an update and wake up the reporting
+	/// loop.
+	fn dispatch(&self, controls: Vec<ControlRequest>) {
+		for control in controls {
+			if self.controls.send(control).is_err() {
+				tracing::debug!("Control request dropped, no subscribers");
+			}
+		}
+		self.wakeup.notify_one();
+	}
+}
+
+#[async_trait]
+impl MmsServerHandler for Shared {
+	async fn on_connect(&self, connection: &ServerConnection) {
+		self.lock().connections.insert(connection.id(), connection.clone());
+	}
+
+	async fn handle_request(
+		&self,
+		connection: &ServerConnection,
+		request: ConfirmedServiceRequest,
+	) -> RequestOutcome {
+		let (outcome, controls) = {
+			let mut state = self.lock();
+			let mut effects = Effects::default();
+			let result = state.handle_request(connection, request, &mut effects);
+			let outcome = RequestOutcome {
+				result,
+				reports_before: std::mem::take(&mut effects.reports_before),
+				reports_after: std::mem::take(&mut effects.reports_after),
+			};
+			(outcome, state.deliver(effects))
+		};
+		self.dispatch(controls);
+		outcome
+	}
+
+	async fn on_disconnect(&self, connection: &ServerConnection) {
+		{
+			let mut state = self.lock();
+			let id = connection.id();
+			state.connections.remove(&id);
+			state.reports.release(id);
+			state.store.aa_datasets.remove(&id);
+			state.selections.retain(|_, selection| selection.connection != id);
+		}
+		self.wakeup.notify_one();
+	}
+}
+
+/// The side effects of a request or an update.
+#[derive(Debug, Default)]
+struct Effects {
+	/// The information reports to send by connection id.
+	reports: Vec<(u64, InformationReport)>,
+	/// The reports to send to the client of the request before its response.
+	reports_before: Vec<InformationReport>,
+	/// The reports to send to the client of the request after its response.
+	reports_after: Vec<InformationReport>,
+	/// The controls operated by the clients.
+	controls: Vec<ControlRequest>,
+}
+
+impl Effects {
+	/// Add the reports of the report control blocks.
+	fn add_reports(&mut self, reports: Vec<OutgoingReport>) {
+		for (connection, report) in reports {
+			match InformationReport::try_from(report) {
+				Ok(report) => self.reports.push((connection, report)),
+				Err(e) => tracing::warn!("Error encoding report: {e}"),
+			}
+		}
+	}
+}
+
+/// A named variable list.
+#[derive(Debug)]
+struct NamedVariableList {
+	/// The members of the list, e.g. `LD/GGIO1$ST$Ind1`.
+	members: Vec<String>,
+	/// Whether the list was defined by a client.
+	deletable: bool,
+}
+
+/// The values and datasets of the model.
+#[derive(Debug)]
+struct DataStore {
+	/// The model served.
+	model: IedModel,
+	/// The values of the data attributes by reference.
+	values: HashMap<String, Iec61850Data>,
+	/// The domain specific named variable lists by reference, e.g.
+	/// `LD/LLN0$DS1`.
+	datasets: BTreeMap<String, NamedVariableList>,
+	/// The association specific named variable lists by connection id and
+	/// name.
+	aa_datasets: HashMap<u64, BTreeMap<String, Vec<String>>>,
+}
+
+impl DataStore {
+	/// Create the store of a model with the default values.
+	fn new(model: IedModel) -> Result<Self, Iec61850ServerError> {
+		let mut values = HashMap::new();
+		let logical_nodes = model.logical_devices.iter().flat_map(|ld| &ld.logical_nodes);
+		for node in logical_nodes.clone().flat_map(|ln| &ln.nodes) {
+			add_default_values(node, &mut values)?;
+		}
+		let datasets = logical_nodes
+			.flat_map(|ln| ln.datasets.values())
+			.map(|dataset| {
+				let list = NamedVariableList { members: dataset.entries.clone(), deletable: false };
+				(dataset.path.clone(), list)
+			})
+			.collect();
+		Ok(Self { model, values, datasets, aa_datasets: HashMap::new() })
+	}
+
+	/// The value of a node.
+	fn node_value(&self, node: &Node) -> Iec61850Data {
+		match node {
+			Node::DataAttribute { path, r#type, .. } => self
+				.values
+				.get(path)
+				.cloned()
+				.or_else(|| default_value(r#type))
+				.unwrap_or(Iec61850Data::Structure(Vec::new())),
+			Node::DataObject { nodes, .. } => {
+				Iec61850Data::Structure(nodes.iter().map(|node| self.node_value(node)).collect())
+			}
+		}
+	}
+
+	/// The type specification of a node.
+	fn node_type(&self, node: &Node) -> Option<TypeSpecification> {
+		match node {
+			Node::DataAttribute { path, r#type, .. } => {
+				attribute_type(r#type, self.values.get(path))
+			}
+			Node::DataObject { nodes, .. } => structure(
+				nodes.iter().map(|node| Some((node.name(), self.node_type(node)?))).collect(),
+			),
+		}
+	}
+
+	/// Set the value of a node. The changed attributes are added with the
+	/// reason of the change.
+	fn set(
+		&mut self,
+		reference: &str,
+		value: Iec61850Data,
+		changes: &mut Vec<(String, TriggerOptions)>,
+	) -> Result<(), DataAccessError> {
+		let node = self.model.find_node(reference).ok_or(DataAccessError::ObjectNonExistent)?;
+		if !node_matches(node, &value) {
+			return Err(DataAccessError::TypeInconsistent);
+		}
+		let mut leaves = Vec::new();
+		collect_leaves(node, value, &mut leaves);
+		for (path, value) in leaves {
+			let reason = match self.values.insert(path.to_owned(), value.clone()) {
+				Some(old) if old == value => TriggerOptions::DataUpdate,
+				_ if path.ends_with("$q") => TriggerOptions::QualityChange,
+				_ => TriggerOptions::DataChange,
+			};
+			changes.push((path.to_owned(), reason));
+		}
+		Ok(())
+	}
+
+	/// The named variable list of a connection with the given key: the
+	/// reference of a domain specific list or the name of an association
+	/// specific list prefixed with `@`.
+	fn list(&self, connection: u64, key: &str) -> Option<(&[String], bool)> {
+		match key.strip_prefix('@') {
+			Some(name) => self
+				.aa_datasets
+				.get(&connection)?
+				.get(name)
+				.map(|members| (members.as_slice(), true)),
+			None => self.datasets.get(key).map(|list| (list.members.as_slice(), list.deletable)),
+		}
+	}
+}
+
+impl ReportDataSource for DataStore {
+	fn dataset_members(&self, connection: u64, dataset: &str) -> Option<Vec<String>> {
+		self.list(connection, &dataset_key(dataset)).map(|(members, _)| members.to_vec())
+	}
+
+	fn value(&self, reference: &str) -> Option<Iec61850Data> {
+		self.model.find_node(reference).map(|node| self.node_value(node))
+	}
+}
+
+/// The selection of a data object by a connection.
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+	/// The id of the connection.
+	connection: u64,
+	/// The time the selection expires, after the `sboTimeout` of the data
+	/// object.
+	expires: Instant,
+}
+
+/// The state of the server.
+#[derive(Debug)]
+struct ServerState {
+	/// The values and datasets.
+	store: DataStore,
+	/// The report control blocks.
+	reports: ReportEngine,
+	/// The selections by data object, e.g. `LD/CSWI1$Pos`.
+	selections: HashMap<String, Selection>,
+	/// The open connections by id.
+	connections: HashMap<u64, ServerConnection>,
+}
+
+impl ServerState {
+	/// Create the state of a server serving the model.
+	fn new(model: IedModel) -> Result<Self, Iec61850ServerError> {
+		Ok(Self {
+			reports: ReportEngine::new(&model),
+			store: DataStore::new(model)?,
+			selections: HashMap::new(),
+			connections: HashMap::new(),
+		})
+	}
+
+	/// Queue the reports of the effects on their connections and return the
+	/// controls. The reports are queued while the state is locked, so every
+	/// connection receives them in the order of their sequence numbers.
+	fn deliver(&self, effects: Effects) -> Vec<ControlRequest> {
+		for (id, report) in effects.reports {
+			let Some(connection) = self.connections.get(&id) else {
+				continue;
+			};
+			if let Err(e) = connection.try_send_information_report(report) {
+				tracing::warn!("Report dropped: {e}");
+			}
+		}
+		effects.controls
+	}
+
+	/// Handle a confirmed service request of a client.
+	fn handle_request(
+		&mut self,
+		connection: &ServerConnection,
+		request: ConfirmedServiceRequest,
+		effects: &mut Effects,
+	) -> Result<ConfirmedServiceResponse, ServiceError> {
+		let id = connection.id();
+		Ok(match request {
+			ConfirmedServiceRequest::getNameList(request) => {
+				let max_pdu_size = connection.negotiated_parameters().max_pdu_size;
+				let max_size = usize::try_from(max_pdu_size).unwrap_or_default();
+				ConfirmedServiceResponse::getNameList(self.get_name_list(id, request, max_size))
+			}
+			ConfirmedServiceRequest::read(request) => {
+				ConfirmedServiceResponse::read(self.read(id, request)?)
+			}
+			ConfirmedServiceRequest::write(request) => {
+				ConfirmedServiceResponse::write(self.write(id, request, effects)?)
+			}
+			ConfirmedServiceRequest::getVariableAccessAttributes(request) => {
+				ConfirmedServiceResponse::getVariableAccessAttributes(
+					self.get_variable_access_attributes(&request)?,
+				)
+			}
+			ConfirmedServiceRequest::defineNamedVariableList(request) => {
+				self.define_named_variable_list(id, request)?;
+				ConfirmedServiceResponse::defineNamedVariableList(DefineNamedVariableListResponse(
+					(),
+				))
+			}
+			ConfirmedServiceRequest::getNamedVariableListAttributes(request) => {
+				let key = list_key(&request.0).ok_or_else(non_existent)?;
+				let (members, deletable) = self.store.list(id, &key).ok_or_else(non_existent)?;
+				ConfirmedServiceResponse::getNamedVariableListAttributes(
+					GetNamedVariableListAttributesResponse::new(
+						deletable,
+						VariableDefs(
+							members.iter().filter_map(|member| variable(member)).collect(),
+						),
+					),
+				)
+			}
+			ConfirmedServiceRequest::deleteNamedVariableList(request) => {
+				ConfirmedServiceResponse::deleteNamedVariableList(
+					self.delete_named_variable_lists(id, &request),
+				)
+			}
+			_ => return Err(access_error(ACCESS_OBJECT_ACCESS_UNSUPPORTED)),
+		})
+	}
+
+	/// Get the names of the logical devices, of the variables or of the named
+	/// variable lists. The list is cut to fit in a PDU.
+	fn get_name_list(
+		&self,
+		connection: u64,
+		request: GetNameListRequest,
+		max_size: usize,
+	) -> GetNameListResponse {
+		let ObjectClass::basicObjectClass(class) = request.object_class;
+		let class = i64::try_from(class).unwrap_or(-1);
+		let names = match request.object_scope {
+			GetNameListRequestObjectScope::vmdSpecific(())
+				if class == MmsObjectClass::Domain as i64 =>
+			{
+				self.store.model.logical_devices.iter().map(|ld| ld.name.clone()).collect()
+			}
+			GetNameListRequestObjectScope::domainSpecific(domain)
+				if class == MmsObjectClass::NamedVariable as i64 =>
+			{
+				self.variable_names(&domain.0.to_string())
+			}
+			GetNameListRequestObjectScope::domainSpecific(domain)
+				if class == MmsObjectClass::NamedVariableList as i64 =>
+			{
+				let prefix = format!("{}/", domain.0);
+				self.store
+					.datasets
+					.keys()
+					.filter_map(|reference| reference.strip_prefix(&prefix))
+					.map(str::to_owned)
+					.collect()
+			}
+			GetNameListRequestObjectScope::aaSpecific(())
+				if class == MmsObjectClass::NamedVariableList as i64 =>
+			{
+				self.store
+					.aa_datasets
+					.get(&connection)
+					.map(|lists| lists.keys().cloned().collect())
+					.unwrap_or_default()
+			}
+			_ => Vec::new(),
+		};
+
+		let start = match request.continue_after {
+			Some(after) => {
+				let after = after.0.to_string();
+				names.iter().position(|name| *name == after).map_or(names.len(), |index| index + 1)
+			}
+			None => 0,
+		};
+		let mut size = NAME_LIST_OVERHEAD;
+		let mut list_of_identifier = Vec::new();
+		let mut more_follows = false;
+		for name in &names[start..] {
+			size += name.len() + NAME_OVERHEAD;
+			if size > max_size && !list_of_identifier.is_empty() {
+				more_follows = true;
+				break;
+			}
+			list_of_identifier.extend(identifier(name));
+		}
+		GetNameListResponse::new(list_of_identifier, more_follows)
+	}
+
+	/// The names of the variables of a logical device, relative to the
+	/// logical device: the logical nodes, their data and their report control
+	/// blocks.
+	fn variable_names(&self, logical_device: &str) -> Vec<String> {
+		let Some(ld) = self.store.model.logical_devices.iter().find(|ld| ld.name == logical_device)
+		else {
+			return Vec::new();
+		};
+		let prefix_length = ld.name.len() + 1;
+		let mut names = Vec::new();
+		for ln in &ld.logical_nodes {
+			names.push(ln.name.clone());
+			for node in &ln.nodes {
+				add_node_names(node, prefix_length, &mut names);
+			}
+			for fc in REPORT_FUNCTIONAL_CONSTRAINTS {
+				let prefix = format!("{}/{}${fc}$", ld.name, ln.name);
+				let mut rcbs = self
+					.reports
+					.rcbs()
+					.filter(|(reference, _)| reference.starts_with(&prefix))
+					.peekable();
+				if rcbs.peek().is_none() {
+					continue;
+				}
+				names.push(format!("{}${fc}", ln.name));
+				for (reference, rcb) in rcbs {
+					let item = &reference[prefix_length..];
+					names.push(item.to_owned());
+					names.extend(
+						rcb_attributes(rcb).iter().map(|attribute| format!("{item}${attribute}")),
+					);
+				}
+			}
+		}
+		names
+	}
+
+	/// Get the type of a variable.
+	fn get_variable_access_attributes(
+		&self,
+		request: &GetVariableAccessAttributesRequest,
+	) -> Result<GetVariableAccessAttributesResponse, ServiceError> {
+		let GetVariableAccessAttributesRequest::name(ObjectName::domain_specific(name)) = request
+		else {
+			return Err(non_existent());
+		};
+		let type_specification = self
+			.type_specification(&name.domain_id.0.to_string(), &name.item_id.0.to_string())
+			.ok_or_else(non_existent)?;
+		Ok(GetVariableAccessAttributesResponse::new(false, type_specification))
+	}
+
+	/// The type specification of a variable of a logical device.
+	fn type_specification(&self, logical_device: &str, item: &str) -> Option<TypeSpecification> {
+		let reference = format!("{logical_device}/{item}");
+		if let Some((rcb, attribute)) = self.reports.split_reference(&reference) {
+			return self.rcb_type(rcb, attribute);
+		}
+		match item.split_once('$') {
+			None => {
+				let ln = self
+					.store
+					.model
+					.logical_devices
+					.iter()
+					.find(|ld| ld.name == logical_device)?
+					.logical_nodes
+					.iter()
+					.find(|ln| ln.name == item)?;
+				let mut components = ln
+					.nodes
+					.iter()
+					.map(|node| Some((node.name(), self.store.node_type(node)?)))
+					.collect::<Option<Vec<_>>>()?;
+				for fc in REPORT_FUNCTIONAL_CONSTRAINTS {
+					if let Some(rcbs) = self.rcb_fc_type(logical_device, item, fc) {
+						components.push((fc, rcbs));
+					}
+				}
+				structure(components.into_iter().map(Some).collect())
+			}
+			Some((ln, fc)) if REPORT_FUNCTIONAL_CONSTRAINTS.contains(&fc) => {
+				self.rcb_fc_type(logical_device, ln, fc)
+			}
+			Some(_) => {
+				self.store.model.find_node(&reference).and_then(|node| self.store.node_type(node))
+			}
+		}
+	}
+
+	/// The type specification of the report control blocks of a logical node
+	/// with a functional constraint.
+	fn rcb_fc_type(&self, logical_device: &str, ln: &str, fc: &str) -> Option<TypeSpecification> {
+		let prefix = format!("{logical_device}/{ln}${fc}$");
+		let components = self
+			.reports
+			.rcbs()
+			.filter_map(|(reference, _)| {
+				let name = reference.strip_prefix(&prefix)?;
+				Some(Some((name, self.rcb_type(reference, None)?)))
+			})
+			.collect::<Vec<_>>();
+		(!components.is_empty()).then(|| structure(components)).flatten()
+	}
+
+	/// The type specification of a report control block or one of its
+	/// attributes.
+	fn rcb_type(&self, reference: &str, attribute: Option<&str>) -> Option<TypeSpecification> {
+		let (_, rcb) = self.reports.rcbs().find(|(rcb, _)| *rcb == reference)?;
+		let buffered = matches!(rcb, ReportControlBlock::Buffered(_));
+		let attributes = rcb_attributes(rcb);
+		match attribute {
+			Some(attribute) => {
+				attributes.contains(&attribute).then(|| rcb_attribute_type(attribute, buffered))
+			}
+			None => structure(
+				attributes
+					.iter()
+					.map(|attribute| Some((*attribute, rcb_attribute_type(attribute, buffered))))
+					.collect(),
+			),
+		}
+	}
+
+	/// The references of the variables of an access specification.
+	fn references(
+		&self,
+		connection: u64,
+		specification: &VariableAccessSpecification,
+	) -> Result<Vec<Result<String, DataAccessError>>, ServiceError> {
+		match specification {
+			VariableAccessSpecification::listOfVariable(variables) => Ok(variables
+				.0
+				.iter()
+				.map(|variable| {
+					let VariableSpecification::name(name) = &variable.variable_specification;
+					match name {
+						_ if variable.alternate_access.is_some() => {
+							Err(DataAccessError::ObjectAccessUnsupported)
+						}
+						ObjectName::domain_specific(name) => {
+							Ok(format!("{}/{}", name.domain_id.0, name.item_id.0))
+						}
+						_ => Err(DataAccessError::ObjectNonExistent),
+					}
+				})
+				.collect()),
+			VariableAccessSpecification::variableListName(name) => {
+				let key = list_key(name).ok_or_else(non_existent)?;
+				let (members, _) = self.store.list(connection, &key).ok_or_else(non_existent)?;
+				Ok(members.iter().cloned().map(Ok).collect())
+			}
+		}
+	}
+
+	/// Read variables.
+	fn read(
+		&mut self,
+		connection: u64,
+		request: ReadRequest,
+	) -> Result<ReadResponse, ServiceError> {
+		let references = self.references(connection, &request.variable_access_specification)?;
+		let mut results = Vec::with_capacity(references.len());
+		for reference in references {
+			let value =
+				reference.and_then(|reference| self.read_value(connection, &reference)).and_then(
+					|value| Data::try_from(value).map_err(|_| DataAccessError::ObjectValueInvalid),
+				);
+			results.push(match value {
+				Ok(value) => AccessResult::success(value),
+				Err(error) => AccessResult::failure(error.into()),
+			});
+		}
+		let specification =
+			request.specification_with_result.then_some(request.variable_access_specification);
+		Ok(ReadResponse::new(specification, results))
+	}
+
+	/// Read a variable. Reading the `SBO` attribute selects the data object.
+	fn read_value(
+		&mut self,
+		connection: u64,
+		reference: &str,
+	) -> Result<Iec61850Data, DataAccessError> {
+		if let Some(value) = self.reports.read(reference) {
+			return Ok(value);
+		}
+		if let Some((target, "SBO")) = ControlTarget::parse(reference)
+			&& self.store.model.find_node(reference).is_some()
+		{
+			let selected = self.control_model(&target) == ControlModel::SboNormal
+				&& self.selection(&target).is_none_or(|owner| owner == connection);
+			if !selected {
+				return Ok(Iec61850Data::String(String::new()));
+			}
+			self.select(&target, connection);
+			return Ok(Iec61850Data::String(target.path("CO", "")));
+		}
+		self.store.value(reference).ok_or(DataAccessError::ObjectNonExistent)
+	}
+
+	/// Write variables.
+	fn write(
+		&mut self,
+		connection: u64,
+		request: WriteRequest,
+		effects: &mut Effects,
+	) -> Result<WriteResponse, ServiceError> {
+		let references = self.references(connection, &request.variable_access_specification)?;
+		let mut data = request.list_of_data.into_iter();
+		let mut results = Vec::with_capacity(references.len());
+		for reference in references {
+			let value = data.next().ok_or(DataAccessError::TypeInconsistent).and_then(|value| {
+				Iec61850Data::try_from(value).map_err(|_| DataAccessError::TypeInconsistent)
+			});
+			let result = reference
+				.and_then(|reference| self.write_value(connection, &reference, value?, effects));
+			results.push(match result {
+				Ok(()) => AnonymousWriteResponse::success(()),
+				Err(error) => AnonymousWriteResponse::failure(error.into()),
+			});
+		}
+		Ok(WriteResponse(results))
+	}
+
+	/// Write a variable: an attribute of a report control block, a control
+	/// structure or an attribute with a writable functional constraint.
+	fn write_value(
+		&mut self,
+		connection: u64,
+		reference: &str,
+		value: Iec61850Data,
+		effects: &mut Effects,
+	) -> Result<(), DataAccessError> {
+		if self.reports.split_reference(reference).is_some() {
+			let reports =
+				self.reports.write(connection, reference, value, &self.store, Instant::now())?;
+			effects.add_reports(reports);
+			return Ok(());
+		}
+		if let Some((target, structure @ ("Oper" | "SBOw" | "Cancel"))) =
+			ControlTarget::parse(reference)
+		{
+			return self.control(connection, &target, structure, value, effects);
+		}
+
+		let fc = reference.split('$').nth(1).ok_or(DataAccessError::ObjectNonExistent)?;
+		if self.store.model.find_node(reference).is_none() {
+			return Err(DataAccessError::ObjectNonExistent);
+		}
+		if !WRITABLE_FUNCTIONAL_CONSTRAINTS.contains(&fc) {
+			return Err(DataAccessError::ObjectAccessDenied);
+		}
+		self.set_values(reference, value, effects)
+	}
+
+	/// Set the value of a node and report its changes.
+	fn set_values(
+		&mut self,
+		reference: &str,
+		value: Iec61850Data,
+		effects: &mut Effects,
+	) -> Result<(), DataAccessError> {
+		let mut changes = Vec::new();
+		self.store.set(reference, value, &mut changes)?;
+		effects.add_reports(self.reports.data_changed(&changes, &self.store, Instant::now()));
+		Ok(())
+	}
+
+	/// The control model of a data object. The data objects without
+	/// `ctlModel` are operated directly with the normal security.
+	fn control_model(&self, target: &ControlTarget<'_>) -> ControlModel {
+		match self.store.value(&target.path("CF", "ctlModel")) {
+			Some(Iec61850Data::Integer(value)) => {
+				ControlModel::try_from(value).unwrap_or(ControlModel::StatusOnly)
+			}
+			_ => ControlModel::DirectNormal,
+		}
+	}
+
+	/// The connection selecting a data object. An expired selection is
+	/// removed.
+	fn selection(&mut self, target: &ControlTarget<'_>) -> Option<u64> {
+		let object = target.object();
+		let selection = self.selections.get(&object)?;
+		if selection.expires <= Instant::now() {
+			self.selections.remove(&object);
+			return None;
+		}
+		Some(selection.connection)
+	}
+
+	/// Select a data object for a connection until its `sboTimeout` expires.
+	/// A `sboTimeout` of 0, the default value of the attribute, uses
+	/// [`DEFAULT_SBO_TIMEOUT`].
+	fn select(&mut self, target: &ControlTarget<'_>, connection: u64) {
+		let timeout = match self.store.value(&target.path("CF", "sboTimeout")) {
+			Some(Iec61850Data::Unsigned(timeout)) if timeout > 0 => {
+				Duration::from_millis(timeout.into())
+			}
+			_ => DEFAULT_SBO_TIMEOUT,
+		};
+		let selection = Selection { connection, expires: Instant::now() + timeout };
+		self.selections.insert(target.object(), selection);
+	}
+
+	/// Handle the write of a control structure.
+	fn control(
+		&mut self,
+		connection: u64,
+		target: &ControlTarget<'_>,
+		structure: &str,
+		value: Iec61850Data,
+		effects: &mut Effects,
+	) -> Result<(), DataAccessError> {
+		let reference = target.path("CO", structure);
+		let node =
+			self.store.model.find_node(&reference).ok_or(DataAccessError::ObjectNonExistent)?;
+		if !node_matches(node, &value) {
+			return Err(DataAccessError::TypeInconsistent);
+		}
+		let request = ControlRequest::parse(target.object(), node, value.clone());
+		let model = self.control_model(target);
+		let selection = self.selection(target);
+
+		let rejection = match structure {
+			"Oper" if model == ControlModel::StatusOnly => {
+				Some((AddCause::NotSupported, DataAccessError::ObjectAccessDenied))
+			}
+			"Oper" if model.is_sbo() && selection != Some(connection) => {
+				Some((AddCause::ObjectNotSelected, DataAccessError::ObjectAccessDenied))
+			}
+			"SBOw" if model != ControlModel::SboEnhanced => {
+				Some((AddCause::NotSupported, DataAccessError::ObjectAccessDenied))
+			}
+			"SBOw" if selection.is_some_and(|owner| owner != connection) => {
+				Some((AddCause::ObjectAlreadySelected, DataAccessError::TemporarilyUnavailable))
+			}
+			_ => None,
+		};
+		if let Some((add_cause, error)) = rejection {
+			let last_appl_error = LastApplError {
+				control_object: reference,
+				error: ControlError::Unknown,
+				originator: request.originator.unwrap_or_default(),
+				ctl_num: request.ctl_num.unwrap_or_default(),
+				add_cause,
+			};
+			if let Some(report) = last_appl_error_report(last_appl_error) {
+				effects.reports_before.push(report);
+			}
+			return Err(error);
+		}
+
+		match structure {
+			"SBOw" => self.select(target, connection),
+			"Cancel" => {
+				if selection == Some(connection) {
+					self.selections.remove(&target.object());
+				}
+			}
+			_ => {
+				self.selections.remove(&target.object());
+				let status = target.path("ST", "stVal");
+				if let Some(Node::DataAttribute { r#type, .. }) =
+					self.store.model.find_node(&status)
+					&& let Some(value) = status_value(r#type, &request.ctl_val)
+					&& let Err(e) = self.set_values(&status, value, effects)
+				{
+					tracing::warn!("Error updating {status}: {e}");
+				}
+				effects.controls.push(request);
+				if model.is_enhanced()
+					&& let Some(report) = command_termination_report(target, &reference, value)
+				{
+					effects.reports_after.push(report);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Define a named variable list.
+	fn define_named_variable_list(
+		&mut self,
+		connection: u64,
+		request: DefineNamedVariableListRequest,
+	) -> Result<(), ServiceError> {
+		let mut members = Vec::with_capacity(request.list_of_variable.0.len());
+		for variable in &request.list_of_variable.0 {
+			let VariableSpecification::name(ObjectName::domain_specific(name)) =
+				&variable.variable_specification
+			else {
+				return Err(non_existent());
+			};
+			let member = format!("{}/{}", name.domain_id.0, name.item_id.0);
+			if self.store.model.find_node(&member).is_none() {
+				return Err(non_existent());
+			}
+			members.push(member);
+		}
+
+		let key = list_key(&request.variable_list_name)
+			.ok_or_else(|| access_error(ACCESS_OBJECT_ACCESS_UNSUPPORTED))?;
+		if self.store.list(connection, &key).is_some() {
+			return Err(service_error(ServiceErrorErrorClass::definition(Integer::from(
+				DEFINITION_OBJECT_EXISTS,
+			))));
+		}
+		match key.strip_prefix('@') {
+			Some(name) => {
+				self.store
+					.aa_datasets
+					.entry(connection)
+					.or_default()
+					.insert(name.to_owned(), members);
+			}
+			None => {
+				let domain = key.split_once('/').map(|(domain, _)| domain).unwrap_or_default();
+				if !self.store.model.logical_devices.iter().any(|ld| ld.name == domain) {
+					return Err(non_existent());
+				}
+				self.store.datasets.insert(key, NamedVariableList { members, deletable: true });
+			}
+		}
+		Ok(())
+	}
+
+	/// Delete named variable lists. The lists of the model and the lists
+	/// used by a report control block are not deleted.
+	fn delete_named_variable_lists(
+		&mut self,
+		connection: u64,
+		request: &DeleteNamedVariableListRequest,
+	) -> DeleteNamedVariableListResponse {
+		let scope = i64::try_from(&request.scope_of_delete).unwrap_or(-1);
+		let keys: Vec<String> = match u8::try_from(scope) {
+			Ok(DELETE_SPECIFIC) => {
+				request.list_of_variable_list_name.iter().flatten().filter_map(list_key).collect()
+			}
+			Ok(DELETE_AA_SPECIFIC) => self
+				.store
+				.aa_datasets
+				.get(&connection)
+				.map(|lists| lists.keys().map(|name| format!("@{name}")).collect())
+				.unwrap_or_default(),
+			Ok(DELETE_DOMAIN) => {
+				let prefix = request.domain_name.as_ref().map(|domain| format!("{}/", domain.0));
+				self.store
+					.datasets
+					.keys()
+					.filter(|key| prefix.as_ref().is_some_and(|prefix| key.starts_with(prefix)))
+					.cloned()
+					.collect()
+			}
+			_ => Vec::new(),
+		};
+
+		let mut matched = 0;
+		let mut deleted = 0;
+		for key in keys {
+			let Some((_, deletable)) = self.store.list(connection, &key) else {
+				continue;
+			};
+			matched += 1;
+			let used = self.reports.datasets().any(|(dataset, owner)| {
+				dataset_key(dataset) == key && (!key.starts_with('@') || owner == Some(connection))
+			});
+			if !deletable || used {
+				continue;
+			}
+			match key.strip_prefix('@') {
+				Some(name) => {
+					self.store.aa_datasets.get_mut(&connection).map(|lists| lists.remove(name));
+				}
+				None => {
+					self.store.datasets.remove(&key);
+				}
+			}
+			deleted += 1;
+		}
+		DeleteNamedVariableListResponse::new(Unsigned32(matched), Unsigned32(deleted))
+	}
+}
+
+/// A controllable data object.
+#[derive(Debug)]
+struct ControlTarget<'a> {
+	/// The logical device.
+	logical_device: &'a str,
+	/// The logical node.
+	logical_node: &'a str,
+	/// The data object, relative to the logical node.
+	data_object: &'a str,
+}
+
+impl<'a> ControlTarget<'a> {
+	/// Parse the reference of a control attribute, e.g. `LD/CSWI1$CO$Pos$Oper`,
+	/// into the data object and the attribute.
+	fn parse(reference: &'a str) -> Option<(Self, &'a str)> {
+		let (logical_device, item) = reference.split_once('/')?;
+		let (logical_node, item) = item.split_once('$')?;
+		let (data_object, attribute) = item.strip_prefix("CO$")?.rsplit_once('$')?;
+		Some((Self { logical_device, logical_node, data_object }, attribute))
+	}
+
+	/// The reference of the data object, e.g. `LD/CSWI1$Pos`.
+	fn object(&self) -> String {
+		format!("{}/{}${}", self.logical_device, self.logical_node, self.data_object)
+	}
+
+	/// The reference of an attribute of the data object. Without attribute,
+	/// the reference of the data object with the functional constraint.
+	fn path(&self, functional_constraint: &str, attribute: &str) -> String {
+		let path = format!(
+			"{}/{}${functional_constraint}${}",
+			self.logical_device, self.logical_node, self.data_object
+		);
+		if attribute.is_empty() { path } else { format!("{path}${attribute}") }
+	}
+}
+
+impl ControlRequest {
+	/// Parse a control structure following the attributes of its model.
+	fn parse(object: String, node: &Node, value: Iec61850Data) -> Self {
+		let Iec61850Data::Structure(values) = value else {
+			return Self { object, ctl_val: value, originator: None, ctl_num: None, test: false };
+		};
+		let mut request = Self {
+			object,
+			ctl_val: Iec61850Data::Structure(Vec::new()),
+			originator: None,
+			ctl_num: None,
+			test: false,
+		};
+		for (attribute, value) in node.nodes().iter().zip(values) {
+			match (attribute.name(), value) {
+				("ctlVal", value) => request.ctl_val = value,
+				("origin", value) => request.originator = Originator::try_from(value).ok(),
+				("ctlNum", Iec61850Data::Unsigned(ctl_num)) => {
+					request.ctl_num = u8::try_from(ctl_num).ok();
+				}
+				("Test", Iec61850Data::Bool(test)) => request.test = test,
+				_ => {}
+			}
+		}
+		request
+	}
+}
+
+/// The information report of a `LastApplError`.
+fn last_appl_error_report(last_appl_error: LastApplError) -> Option<InformationReport> {
+	let value = Data::try_from(Iec61850Data::from(last_appl_error)).ok()?;
+	Some(InformationReport::new(
+		VariableAccessSpecification::listOfVariable(VariableDefs(vec![
+			AnonymousVariableDefs::new(
+				VariableSpecification::name(ObjectName::vmd_specific(identifier(LAST_APPL_ERROR)?)),
+				None,
+			),
+		])),
+		vec![AccessResult::success(value)],
+	))
+}
+
+/// The information report of a positive command termination.
+fn command_termination_report(
+	target: &ControlTarget<'_>,
+	reference: &str,
+	value: Iec61850Data,
+) -> Option<InformationReport> {
+	let item = reference.split_once('/')?.1;
+	let name = ObjectNameDomainSpecific::new(identifier(target.logical_device)?, identifier(item)?);
+	Some(InformationReport::new(
+		VariableAccessSpecification::listOfVariable(VariableDefs(vec![
+			AnonymousVariableDefs::new(
+				VariableSpecification::name(ObjectName::domain_specific(name)),
+				None,
+			),
+		])),
+		vec![AccessResult::success(Data::try_from(value).ok()?)],
+	))
+}
+
+/// The status value of a control value: the same value, or the double point
+/// position of a boolean.
+fn status_value(r#type: &str, ctl_val: &Iec61850Data) -> Option<Iec61850Data> {
+	match (r#type, ctl_val) {
+		(_, value) if type_matches(r#type, value) => Some(value.clone()),
+		("bit_string", Iec61850Data::Bool(on)) => Some(Iec61850Data::BitString(Bitstring {
+			bytes: vec![if *on { 0b01 } else { 0b10 }],
+			padding: 6,
+		})),
+		_ => None,
+	}
+}
+
+/// Add the default values of the attributes of a node.
+fn add_default_values(
+	node: &Node,
+	values: &mut HashMap<String, Iec61850Data>,
+) -> Result<(), Iec61850ServerError> {
+	match node {
+		Node::DataAttribute { path, r#type, .. } => {
+			let value = default_value(r#type).context(UnknownType { path, r#type })?;
+			values.insert(path.clone(), value);
+		}
+		Node::DataObject { nodes, .. } => {
+			for node in nodes {
+				add_default_values(node, values)?;
+			}
+		}
+	}
+	Ok(())
+}
+
+/// The default value of a type of the model.
+fn default_value(r#type: &str) -> Option<Iec61850Data> {
+	if r#type.starts_with('[') {
+		return Some(Iec61850Data::Array(Vec::new()));
+	}
+	Some(match r#type {
+		"bool" => Iec61850Data::Bool(false),
+		"bit_string" => Iec61850Data::BitString(Bitstring { bytes: Vec::new(), padding: 0 }),
+		"integer" => Iec61850Data::Integer(0),
+		"unsigned" => Iec61850Data::Unsigned(0),
+		"floating_point" => Iec61850Data::FloatingPoint(0.0),
+		"octet_string" => Iec61850Data::OctetString(Vec::new()),
+		"visible_string" => Iec61850Data::String(String::new()),
+		"mMSString" => Iec61850Data::MMSString(String::new()),
+		"binary_time" => Iec61850Data::BinaryTime(OffsetDateTime::UNIX_EPOCH),
+		"utc_time" => Iec61850Data::UtcTime(OffsetDateTime::UNIX_EPOCH),
+		_ => return None,
+	})
+}
+
+/// Whether a value matches the type of a node.
+fn node_matches(node: &Node, value: &Iec61850Data) -> bool {
+	match (node, value) {
+		(Node::DataAttribute { r#type, .. }, value) => type_matches(r#type, value),
+		(Node::DataObject { nodes, .. }, Iec61850Data::Structure(values)) => {
+			nodes.len() == values.len()
+				&& nodes.iter().zip(values).all(|(node, value)| node_matches(node, value))
+		}
+		(Node::DataObject { .. }, _) => false,
+	}
+}
+
+/// Whether a value matches a type of the model.
+fn type_matches(r#type: &str, value: &Iec61850Data) -> bool {
+	if let Some(element) = r#type.strip_prefix('[').and_then(|r#type| r#type.strip_suffix(']')) {
+		return matches!(value, Iec61850Data::Array(values)
+			if values.iter().all(|value| type_matches(element, value)));
+	}
+	matches!(
+		(r#type, value),
+		("bool", Iec61850Data::Bool(_))
+			| ("bit_string", Iec61850Data::BitString(_))
+			| ("integer", Iec61850Data::Integer(_))
+			| ("unsigned", Iec61850Data::Unsigned(_))
+			| ("floating_point", Iec61850Data::FloatingPoint(_))
+			| ("octet_string", Iec61850Data::OctetString(_))
+			| ("visible_string", Iec61850Data::String(_))
+			| ("mMSString", Iec61850Data::MMSString(_))
+			| ("binary_time", Iec61850Data::BinaryTime(_))
+			| ("utc_time", Iec61850Data::UtcTime(_))
+	)
+}
+
+/// Split the value of a node into the values of its attributes.
+fn collect_leaves<'a>(
+	node: &'a Node,
+	value: Iec61850Data,
+	leaves: &mut Vec<(&'a str, Iec61850Data)>,
+) {
+	match (node, value) {
+		(Node::DataObject { nodes, .. }, Iec61850Data::Structure(values)) => {
+			for (node, value) in nodes.iter().zip(values) {
+				collect_leaves(node, value, leaves);
+			}
+		}
+		(node, value) => leaves.push((node.path(), value)),
+	}
+}
+
+/// Add the names of a node and its sub nodes, relative to the logical
+/// device.
+fn add_node_names(node: &Node, prefix_length: usize, names: &mut Vec<String>) {
+	names.push(node.path().get(prefix_length..).unwrap_or_default().to_owned());
+	for node in node.nodes() {
+		add_node_names(node, prefix_length, names);
+	}
+}
+
+/// The type specification of an attribute of the model. The number of
+/// elements of an array is the one of its current value.
+fn attribute_type(r#type: &str, value: Option<&Iec61850Data>) -> Option<TypeSpecification> {
+	if let Some(element) = r#type.strip_prefix('[').and_then(|r#type| r#type.strip_suffix(']')) {
+		let elements = match value {
+			Some(Iec61850Data::Array(elements)) => elements.as_slice(),
+			_ => &[],
+		};
+		return Some(TypeSpecification::array(Box::new(TypeSpecificationArray::new(
+			false,
+			Unsigned32(u32::try_from(elements.len()).ok()?),
+			attribute_type(element, elements.first())?,
+		))));
+	}
+	Some(match r#type {
+		"bool" => TypeSpecification::bool(()),
+		"bit_string" => {
+			let bits = match value {
+				Some(Iec61850Data::BitString(bits)) if !bits.bytes.is_empty() => {
+					i32::try_from(bits.bytes.len() * 8).ok()? - i32::from(bits.padding)
+				}
+				_ => -64,
+			};
+			TypeSpecification::bit_string(Integer32(bits))
+		}
+		"integer" => TypeSpecification::integer(Unsigned8(32)),
+		"unsigned" => TypeSpecification::unsigned(Unsigned8(32)),
+		"floating_point" => TypeSpecification::floating_point(TypeSpecificationFloatingPoint::new(
+			Unsigned8(32),
+			Unsigned8(8),
+		)),
+		"octet_string" => TypeSpecification::octet_string(Integer32(-64)),
+		"visible_string" => TypeSpecification::visible_string(Integer32(-255)),
+		"mMSString" => TypeSpecification::mMSString(Integer32(-255)),
+		"binary_time" => TypeSpecification::binary_time(true),
+		"utc_time" => TypeSpecification::utc_time(()),
+		_ => return None,
+	})
+}
+
+/// The type specification of an attribute of a report control block.
+fn rcb_attribute_type(attribute: &str, buffered: bool) -> TypeSpecification {
+	match attribute {
+		"RptID" | "DatSet" => TypeSpecification::visible_string(Integer32(-129)),
+		"RptEna" | "Resv" | "GI" | "PurgeBuf" => TypeSpecification::bool(()),
+		"SqNum" => TypeSpecification::unsigned(Unsigned8(if buffered { 16 } else { 8 })),
+		"OptFlds" => TypeSpecification::bit_string(Integer32(10)),
+		"TrgOps" => TypeSpecification::bit_string(Integer32(6)),
+		"EntryID" => TypeSpecification::octet_string(Integer32(8)),
+		"TimeofEntry" => TypeSpecification::binary_time(true),
+		"ResvTms" => TypeSpecification::integer(Unsigned8(16)),
+		_ => TypeSpecification::unsigned(Unsigned8(32)),
+	}
+}
+
+/// The attributes of a report control block.
+const fn rcb_attributes(rcb: &ReportControlBlock) -> &'static [&'static str] {
+	match rcb {
+		ReportControlBlock::Buffered(_) => &BUFFERED_ATTRIBUTES,
+		ReportControlBlock::Unbuffered(_) => &UNBUFFERED_ATTRIBUTES,
+	}
+}
+
+/// Make a structure type specification from named components.
+fn structure(components: Vec<Option<(&str, TypeSpecification)>>) -> Option<TypeSpecification> {
+	let components = components
+		.into_iter()
+		.map(|component| {
+			let (name, component_type) = component?;
+			Some(AnonymousTypeSpecificationStructureComponents::new(
+				Some(identifier(name)?),
+				component_type,
+			))
+		})
+		.collect::<Option<Vec<_>>>()?;
+	Some(TypeSpecification::structure(TypeSpecificationStructure::new(
+		false,
+		TypeSpecificationStructureComponents(components),
+	)))
+}
+
+/// The key of a named variable list: the reference of a domain specific list
+/// or the name of an association specific list prefixed with `@`.
+fn list_key(name: &ObjectName) -> Option<String> {
+	match name {
+		ObjectName::domain_specific(name) => {
+			Some(format!("{}/{}", name.domain_id.0, name.item_id.0))
+		}
+		ObjectName::aa_specific(name) => Some(format!("@{}", name.0)),
+		ObjectName::vmd_specific(_) => None,
+	}
+}
+
+/// The key of the named variable list of a `DatSet`. The association
+/// specific lists are written with or without the `@`.
+fn dataset_key(dataset: &str) -> String {
+	if dataset.contains('/') {
+		dataset.to_owned()
+	} else {
+		format!("@{}", dataset.trim_start_matches('@'))
+	}
+}
+
+/// The variable definition of a member of a named variable list.
+fn variable(member: &str) -> Option<AnonymousVariableDefs> {
+	let (domain, item) = member.split_once('/')?;
+	let name = ObjectNameDomainSpecific::new(identifier(domain)?, identifier(item)?);
+	Some(AnonymousVariableDefs::new(
+		VariableSpecification::name(ObjectName::domain_specific(name)),
+		None,
+	))
+}
+
+/// Convert a name to an MMS identifier.
+fn identifier(name: &str) -> Option<Identifier> {
+	VisibleString::from_iso646_bytes(name.as_bytes()).ok().map(Identifier)
+}
+
+/// Make a service error of the access class.
+fn access_error(code: u8) -> ServiceError {
+	service_error(ServiceErrorErrorClass::access(Integer::from(code)))
+}
+
+/// Make the service error of an object that doesn't exist.
+fn non_existent() -> ServiceError {
+	access_error(ACCESS_OBJECT_NON_EXISTENT)
+}
+
+/// The error type for the IEC 61850 server.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum Iec61850ServerError {
+	#[snafu(display("Error on the MMS server"))]
+	Mms { source: MmsServerError },
+	#[snafu(display("Node not found in the model: {}", path))]
+	NodeNotFound { path: String },
+	#[snafu(display("The value doesn't match the type of {}", path))]
+	TypeMismatch { path: String },
+	#[snafu(display("Unknown type {} of {}", r#type, path))]
+	UnknownType { path: String, r#type: String },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use tokio::sync::mpsc;
+
+	use super::*;
+	use crate::{
+		Iec61850Client,
+		iec61850::{
+			Iec61850ClientError,
+			control::ControlOptions,
+			model::{LogicalDevice, LogicalNode},
+			rcb::{BufferedReportControlBlock, OptionalFields, UnbufferedReportControlBlock},
+			report::Report,
+		},
+		mms::{ClientConfig, ReportCallback, client::MmsClient},
+	};
+
+	/// A report callback forwarding the reports to a channel.
+	struct ForwardReports(mpsc::UnboundedSender<Report>);
+
+	#[async_trait]
+	impl ReportCallback for ForwardReports {
+		async fn on_report(&self, report: Report) {
+			self.0.send(report).unwrap();
+		}
+	}
+
+	fn attribute(name: &str, r#type: &str) -> Node {
+		Node::DataAttribute {
+			name: name.to_owned(),
+			path: String::new(),
+			r#type: r#type.to_owned(),
+		}
+	}
+
+	fn object(name: &str, nodes: Vec<Node>) -> Node {
+		Node::DataObject { name: name.to_owned(), path: String::new(), nodes }
+	}
+
+	/// A control structure. `Cancel` has no `Check`.
+	fn control_structure(name: &str) -> Node {
+		let mut nodes = vec![
+			attribute("ctlVal", "bool"),
+			object(
+				"origin",
+				vec![attribute("orCat", "integer"), attribute("orIdent", "octet_string")],
+			),
+			attribute("ctlNum", "unsigned"),
+			attribute("T", "utc_time"),
+			attribute("Test", "bool"),
+		];
+		if name != "Cancel" {
+			nodes.push(attribute("Check", "bit_string"));
+		}
+		object(name, nodes)
+	}
+
+	fn test_model() -> IedModel {
+		let mut ld = LogicalDevice::new("LD0".to_owned());
+		ld.logical_nodes.push(LogicalNode::new("LLN0".to_owned(), "LD0"));
+		let mut ggio = LogicalNode::new("GGIO1".to_owned(), "LD0");
+		ggio.nodes = vec![
+			object(
+				"ST",
+				vec![
+					object("Ind1", vec![attribute("stVal", "bool"), attribute("q", "bit_string")]),
+					object("SPCSO1", vec![attribute("stVal", "bool")]),
+				],
+			),
+			object(
+				"CF",
+				vec![object(
+					"SPCSO1",
+					vec![attribute("ctlModel", "integer"), attribute("sboTimeout", "unsigned")],
+				)],
+			),
+			object(
+				"CO",
+				vec![object(
+					"SPCSO1",
+					vec![
+						attribute("SBO", "visible_string"),
+						control_structure("SBOw"),
+						control_structure("Oper"),
+						control_structure("Cancel"),
+					],
+				)],
+			),
+		];
+		ld.logical_nodes.push(ggio);
+		ld.add_datasets(HashMap::from([(
+			"LLN0$DS1".to_owned(),
+			vec!["LD0/GGIO1$ST$Ind1".to_owned()],
+		)]))
+		.unwrap();
+		let urcb = UnbufferedReportControlBlock {
+			name: "LLN0$RP$urcb01".to_owned(),
+			id: "urcb01".to_owned(),
+			enabled: false,
+			reservation: false,
+			dataset: "LD0/LLN0$DS1".to_owned(),
+			config_rev: 1,
+			optional_fields: vec![
+				OptionalFields::SequenceNumber,
+				OptionalFields::ReasonForTransmission,
+				OptionalFields::DataSetName,
+				OptionalFields::DataReference,
+			],
+			buffer_time: 0,
+			sequence_number: 0,
+			trigger_options: vec![TriggerOptions::DataChange, TriggerOptions::Gi],
+			integrity_period: 0,
+			gi: false,
+		};
+		let brcb = BufferedReportControlBlock {
+			dataset: "LD0/LLN0$DS1".to_owned(),
+			optional_fields: vec![
+				OptionalFields::SequenceNumber,
+				OptionalFields::DataSetName,
+				OptionalFields::BufferOverflow,
+				OptionalFields::EntryID,
+			],
+			..BufferedReportControlBlock::fixture()
+		};
+		ld.add_reports(vec![
+			(urcb.name.clone(), ReportControlBlock::Unbuffered(urcb)),
+			(brcb.name.clone(), ReportControlBlock::Buffered(brcb)),
+		])
+		.unwrap();
+		let mut model = IedModel { logical_devices: vec![ld] };
+		model.fill_paths();
+		model
+	}
+
+	/// Start a server of the test model on a free port.
+	async fn start_server() -> (Iec61850ServerHandle, u16) {
+		let config =
+			ServerConfig { address: "127.0.0.1".to_owned(), port: 0, ..ServerConfig::default() };
+		let server = Iec61850Server::bind(config, test_model()).await.unwrap();
+		let port = server.local_addr().unwrap().port();
+		let handle = server.handle();
+		tokio::spawn(server.run());
+		(handle, port)
+	}
+
+	/// Connect a client, with the receiver of its reports.
+	async fn connect(port: u16) -> (Iec61850Client, mpsc::UnboundedReceiver<Report>) {
+		let (sender, reports) = mpsc::unbounded_channel();
+		let config =
+			ClientConfig { address: "127.0.0.1".to_owned(), port, ..ClientConfig::default() };
+		let client = Iec61850Client::new(config, Box::new(ForwardReports(sender))).await.unwrap();
+		(client, reports)
+	}
+
+	#[tokio::test]
+	async fn test_client_server() {
+		let (handle, port) = start_server().await;
+		handle.update("LD0/GGIO1$CF$SPCSO1$ctlModel", Iec61850Data::Integer(3)).unwrap();
+		assert!(matches!(
+			handle.update("LD0/GGIO1$ST$Ind1$stVal", Iec61850Data::Integer(1)),
+			Err(Iec61850ServerError::TypeMismatch { .. })
+		));
+		let mut controls = handle.subscribe_controls();
+
+		// The client loads the same model from the server.
+		let (client, mut reports) = connect(port).await;
+		assert!(client.model().has_same_structure(&test_model()));
+
+		// A general interrogation, then a data change.
+		let rcb = "LD0/LLN0$RP$urcb01".into();
+		client.set_rcb_enabled(&rcb, true).await.unwrap();
+		client.set_rcb_gi(&rcb, true).await.unwrap();
+		let report = reports.recv().await.unwrap();
+		assert_eq!(report.id, "urcb01");
+		assert_eq!(report.sequence_number, Some(0));
+		assert_eq!(report.dataset.as_deref(), Some("LD0/LLN0$DS1"));
+		assert_eq!(report.data_reference.unwrap(), ["LD0/GGIO1$ST$Ind1"]);
+		assert_eq!(report.reason_for_transmission.unwrap(), [[TriggerOptions::Gi]]);
+
+		handle.update("LD0/GGIO1$ST$Ind1$stVal", Iec61850Data::Bool(true)).unwrap();
+		let report = reports.recv().await.unwrap();
+		assert_eq!(report.sequence_number, Some(1));
+		assert_eq!(report.reason_for_transmission.unwrap(), [[TriggerOptions::DataChange]]);
+		let Iec61850Data::Structure(values) = &report.values[0] else {
+			panic!("Unexpected value {:?}", report.values[0]);
+		};
+		assert_eq!(values[0], Iec61850Data::Bool(true));
+
+		// A direct control with enhanced security.
+		client
+			.control(
+				&"LD0/GGIO1$SPCSO1".into(),
+				Iec61850Data::Bool(true),
+				&ControlOptions::default(),
+			)
+			.await
+			.unwrap();
+		let control = controls.recv().await.unwrap();
+		assert_eq!(control.object, "LD0/GGIO1$SPCSO1");
+		assert_eq!(control.ctl_val, Iec61850Data::Bool(true));
+		assert_eq!(control.ctl_num, Some(0));
+		assert_eq!(handle.value("LD0/GGIO1$ST$SPCSO1$stVal"), Some(Iec61850Data::Bool(true)));
+		assert_eq!(handle.value("LD0/LLN0$RP$urcb01$RptEna"), Some(Iec61850Data::Bool(true)));
+	}
+
+	#[tokio::test]
+	async fn test_select_before_operate() {
+		let (handle, port) = start_server().await;
+		let mut controls = handle.subscribe_controls();
+		let (client, _reports) = connect(port).await;
+		let (other, _other_reports) = connect(port).await;
+		let path = "LD0/GGIO1$SPCSO1".into();
+		let options = ControlOptions::default();
+
+		// Normal security: reading SBO selects the object for the client.
+		handle.update("LD0/GGIO1$CF$SPCSO1$ctlModel", Iec61850Data::Integer(2)).unwrap();
+		client.select(&path, &Iec61850Data::Bool(true), &options).await.unwrap();
+		assert!(matches!(
+			other.select(&path, &Iec61850Data::Bool(true), &options).await,
+			Err(Iec61850ClientError::SelectFailed { .. })
+		));
+		let Err(Iec61850ClientError::ControlRejected { last_appl_error, .. }) =
+			other.operate(&path, Iec61850Data::Bool(true), &options).await
+		else {
+			panic!("The operation of an object selected by another client was accepted");
+		};
+		assert_eq!(last_appl_error.unwrap().add_cause, AddCause::ObjectNotSelected);
+		client.operate(&path, Iec61850Data::Bool(true), &options).await.unwrap();
+		assert_eq!(controls.recv().await.unwrap().ctl_val, Iec61850Data::Bool(true));
+
+		// The operation ends the selection.
+		assert!(matches!(
+			client.operate(&path, Iec61850Data::Bool(false), &options).await,
+			Err(Iec61850ClientError::ControlRejected { .. })
+		));
+
+		// Enhanced security: SBOw selects, Cancel releases the selection.
+		handle.update("LD0/GGIO1$CF$SPCSO1$ctlModel", Iec61850Data::Integer(4)).unwrap();
+		client.select(&path, &Iec61850Data::Bool(false), &options).await.unwrap();
+		let Err(Iec61850ClientError::ControlRejected { last_appl_error, .. }) =
+			other.select(&path, &Iec61850Data::Bool(false), &options).await
+		else {
+			panic!("The object was selected by two clients");
+		};
+		assert_eq!(last_appl_error.unwrap().add_cause, AddCause::ObjectAlreadySelected);
+		client.cancel(&path, Iec61850Data::Bool(false), &options).await.unwrap();
+		other.control(&path, Iec61850Data::Bool(false), &options).await.unwrap();
+		assert_eq!(controls.recv().await.unwrap().ctl_val, Iec61850Data::Bool(false));
+		assert_eq!(handle.value("LD0/GGIO1$ST$SPCSO1$stVal"), Some(Iec61850Data::Bool(false)));
+
+		// A selection expires after the sboTimeout, so a client that selects
+		// and goes silent doesn't hold the object.
+		handle.update("LD0/GGIO1$CF$SPCSO1$sboTimeout", Iec61850Data::Unsigned(1)).unwrap();
+		client.select(&path, &Iec61850Data::Bool(true), &options).await.unwrap();
+		tokio::time::sleep(Duration::from_millis(5)).await;
+		other.select(&path, &Iec61850Data::Bool(true), &options).await.unwrap();
+		assert!(matches!(
+			client.operate(&path, Iec61850Data::Bool(true), &options).await,
+			Err(Iec61850ClientError::ControlRejected { .. })
+		));
+	}
+
+	#[tokio::test]
+	async fn test_buffered_reports() {
+		let (handle, port) = start_server().await;
+		let stval = "LD0/GGIO1$ST$Ind1$stVal";
+		let brcb = "LD0/LLN0$BR$brcb01".into();
+		let (client, mut reports) = connect(port).await;
+		client.set_rcb_enabled(&brcb, true).await.unwrap();
+		handle.update(stval, Iec61850Data::Bool(true)).unwrap();
+		let first = reports.recv().await.unwrap();
+		assert_eq!(first.id, "brcb01");
+		assert_eq!(first.sequence_number, Some(0));
+		assert_eq!(first.entry_id.as_deref(), Some([0, 0, 0, 0, 0, 0, 0, 1].as_slice()));
+
+		// The changes are buffered while no client listens.
+		client.set_rcb_enabled(&brcb, false).await.unwrap();
+		client.close().await.unwrap();
+		handle.update(stval, Iec61850Data::Bool(false)).unwrap();
+		handle.update(stval, Iec61850Data::Bool(true)).unwrap();
+
+		// Another client gets them on enable, in order.
+		let (client, mut reports) = connect(port).await;
+		client.set_rcb_enabled(&brcb, true).await.unwrap();
+		for sequence_number in 1..3 {
+			let report = reports.recv().await.unwrap();
+			assert_eq!(report.sequence_number, Some(sequence_number));
+			assert_eq!(report.buffer_overflow, Some(false));
+		}
+
+		// A resync replays the entries after the one written in EntryID.
+		client.set_rcb_enabled(&brcb, false).await.unwrap();
+		client.set_rcb_entry_id(&brcb, first.entry_id.unwrap()).await.unwrap();
+		client.set_rcb_enabled(&brcb, true).await.unwrap();
+		for (entry, value) in [(2, false), (3, true)] {
+			let report = reports.recv().await.unwrap();
+			assert_eq!(report.entry_id, Some(vec![0, 0, 0, 0, 0, 0, 0, entry]));
+			let Iec61850Data::Structure(values) = &report.values[0] else {
+				panic!("Unexpected value {:?}", report.values[0]);
+			};
+			assert_eq!(values[0], Iec61850Data::Bool(value));
+		}
+	}
+
+	#[tokio::test]
+	async fn test_named_variable_lists() {
+		let (_handle, port) = start_server().await;
+		let (client, _reports) = connect(port).await;
+		client
+			.create_dataset(&"LD0/LLN0$DS2".into(), vec!["LD0/GGIO1$ST$SPCSO1".to_owned()])
+			.await
+			.unwrap();
+		client.create_dataset(&"@DS3".into(), vec!["LD0/GGIO1$ST$Ind1".to_owned()]).await.unwrap();
+		assert!(
+			client
+				.create_dataset(&"LD0/LLN0$DS2".into(), vec!["LD0/GGIO1$ST$Ind1".to_owned()])
+				.await
+				.is_err()
+		);
+		assert_eq!(
+			client.get_dataset("LLN0$DS2", Some("LD0")).await.unwrap(),
+			["LD0/GGIO1$ST$SPCSO1"]
+		);
+		assert_eq!(client.get_datasets(None).await.unwrap(), ["DS3"]);
+
+		// The lists of the model are not deletable.
+		let name = |item: &str| {
+			ObjectName::domain_specific(ObjectNameDomainSpecific::new(
+				identifier("LD0").unwrap(),
+				identifier(item).unwrap(),
+			))
+		};
+		let (sender, _) = mpsc::unbounded_channel();
+		let config =
+			ClientConfig { address: "127.0.0.1".to_owned(), port, ..ClientConfig::default() };
+		let mms = MmsClient::connect(&config, Box::new(ForwardReports(sender))).await.unwrap();
+		let response = mms
+			.delete_named_variable_list(
+				DELETE_SPECIFIC.into(),
+				Some(vec![name("LLN0$DS1"), name("LLN0$DS2")]),
+				None,
+			)
+			.await
+			.unwrap();
+		assert_eq!((response.number_matched.0, response.number_deleted.0), (2, 1));
+		assert_eq!(client.get_datasets(Some("LD0")).await.unwrap(), ["LLN0$DS1"]);
+
+		// The association lists are only seen by their connection.
+		let response =
+			mms.delete_named_variable_list(DELETE_AA_SPECIFIC.into(), None, None).await.unwrap();
+		assert_eq!(response.number_matched.0, 0);
+		assert_eq!(client.get_datasets(None).await.unwrap(), ["DS3"]);
+	}
+}
@@ -0,0 +1,232 @@
+//! Buffer of a buffered report control block.
+//!
+//! The [`ReportBuffer`] keeps the reports of a buffered report control block
+//! while no client is listening, so that a client reconnecting after a
+//! communication loss gets the events it missed. Every entry gets an
+//! `EntryID` and a `TimeOfEntry`, the oldest entries are dropped once the
+//! buffer is full and the next report sent is flagged with `BufOvfl`.
+
+use std::collections::VecDeque;
+
+use time::OffsetDateTime;
+
+use crate::{
+	iec61850::{rcb::BufferedReportControlBlock, report::Report},
+	mms::error::DataAccessError,
+};
+
+/// The default number of reports kept by a buffer.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 256;
+/// The modulo of the sequence number of the buffered reports.
+const SEQUENCE_MODULO: u32 = 65_536;
+/// The size of an `EntryID`.
+const ENTRY_ID_SIZE: usize = 8;
+
+/// The reports of a buffered report control block.
+#[derive(Debug)]
+pub struct ReportBuffer {
+	/// The reports, oldest first.
+	entries: VecDeque<Report>,
+	/// The maximum number of reports kept.
+	capacity: usize,
+	/// The index of the first entry not sent yet.
+	next: usize,
+	/// The number of the next entry. The `EntryID` zero is reserved for the
+	/// start of the buffer.
+	next_entry: u64,
+	/// Whether entries not sent yet were dropped since the last report sent.
+	overflow: bool,
+}
+
+impl ReportBuffer {
+	/// Create an empty buffer keeping up to `capacity` reports.
+	#[must_use]
+	pub const fn new(capacity: usize) -> Self {
+		Self { entries: VecDeque::new(), capacity, next: 0, next_entry: 1, overflow: false }
+	}
+
+	/// The maximum number of reports kept.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Change the maximum number of reports kept. The oldest entries are
+	/// dropped if the buffer holds more.
+	pub fn set_capacity(&mut self, capacity: usize) {
+		self.capacity = capacity;
+		self.shrink();
+	}
+
+	/// The number of reports in the buffer.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Whether the buffer is empty.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// The number of reports not sent yet.
+	#[must_use]
+	pub fn pending(&self) -> usize {
+		self.entries.len() - self.next
+	}
+
+	/// Add a report to the buffer. The report gets the next `EntryID` and its
+	/// time becomes the `TimeOfEntry`.
+	pub fn push(&mut self, mut report: Report) {
+		report.entry_id = Some(self.next_entry.to_be_bytes().to_vec());
+		report.time_of_entry.get_or_insert_with(OffsetDateTime::now_utc);
+		self.next_entry = self.next_entry.wrapping_add(1).max(1);
+		self.entries.push_back(report);
+		self.shrink();
+	}
+
+	/// Take the next report to send. It gets the next sequence number of the
+	/// report control block, whose `EntryID` and `TimeofEntry` become the
+	/// ones of the report.
+	pub fn next_report(&mut self, rcb: &mut BufferedReportControlBlock) -> Option<Report> {
+		let mut report = self.entries.get(self.next)?.clone();
+		self.next += 1;
+		report.sequence_number = Some(rcb.sequence_number);
+		rcb.sequence_number = (rcb.sequence_number + 1) % SEQUENCE_MODULO;
+		report.buffer_overflow = Some(std::mem::take(&mut self.overflow));
+		rcb.entry_id = report.entry_id.clone().unwrap_or_default();
+		if let Some(time_of_entry) = report.time_of_entry {
+			rcb.time_of_entry = time_of_entry;
+		}
+		Some(report)
+	}
+
+	/// Resume the reports after the entry written by a client in `EntryID`.
+	/// An `EntryID` of zeros resumes from the oldest entry.
+	/// Fails with `ObjectValueInvalid` if the entry is not in the buffer.
+	pub fn resync(
+		&mut self,
+		rcb: &mut BufferedReportControlBlock,
+		entry_id: Vec<u8>,
+	) -> Result<(), DataAccessError> {
+		if entry_id.iter().all(|byte| *byte == 0) {
+			self.next = 0;
+		} else {
+			let index = self
+				.entries
+				.iter()
+				.position(|report| report.entry_id.as_ref() == Some(&entry_id))
+				.ok_or(DataAccessError::ObjectValueInvalid)?;
+			self.next = index + 1;
+		}
+		self.overflow = false;
+		rcb.entry_id = entry_id;
+		Ok(())
+	}
+
+	/// Drop every report of the buffer, as asked by a client through
+	/// `PurgeBuf`.
+	pub fn purge(&mut self, rcb: &mut BufferedReportControlBlock) {
+		self.entries.clear();
+		self.next = 0;
+		self.overflow = false;
+		rcb.entry_id = vec![0; ENTRY_ID_SIZE];
+		rcb.time_of_entry = OffsetDateTime::UNIX_EPOCH;
+	}
+
+	/// Drop the oldest entries beyond the capacity. Dropping an entry not
+	/// sent yet is an overflow.
+	fn shrink(&mut self) {
+		while self.entries.len() > self.capacity {
+			self.entries.pop_front();
+			if self.next == 0 {
+				self.overflow = true;
+			} else {
+				self.next -= 1;
+			}
+		}
+	}
+}
+
+impl Default for ReportBuffer {
+	fn default() -> Self {
+		Self::new(DEFAULT_BUFFER_CAPACITY)
+	}
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::iec61850::data::{Bitstring, Iec61850Data};
+
+	fn report(value: i32) -> Report {
+		Report {
+			id: "brcb01".to_owned(),
+			optional_fields: Vec::new(),
+			sequence_number: None,
+			time_of_entry: Some(OffsetDateTime::UNIX_EPOCH),
+			dataset: None,
+			buffer_overflow: None,
+			entry_id: None,
+			configuration_revision: None,
+			sub_sequence_number: None,
+			more_segments_follows: None,
+			inclusion: Bitstring { bytes: vec![0b1], padding: 7 },
+			data_reference: None,
+			values: vec![Iec61850Data::Integer(value)],
+			reason_for_transmission: None,
+		}
+	}
+
+	fn brcb() -> BufferedReportControlBlock {
+		BufferedReportControlBlock {
+			sequence_number: 65_535,
+			..BufferedReportControlBlock::fixture()
+		}
+	}
+
+	#[test]
+	fn test_report_buffer() {
+		let mut rcb = brcb();
+		let mut buffer = ReportBuffer::new(3);
+		for value in 0..2 {
+			buffer.push(report(value));
+		}
+		let first = buffer.next_report(&mut rcb).unwrap();
+		assert_eq!(first.entry_id, Some(vec![0, 0, 0, 0, 0, 0, 0, 1]));
+		assert_eq!(first.sequence_number, Some(65_535));
+		assert_eq!(first.buffer_overflow, Some(false));
+		assert_eq!(rcb.entry_id, [0, 0, 0, 0, 0, 0, 0, 1]);
+		assert_eq!(rcb.sequence_number, 0);
+
+		// The connection is lost: the sent entries are kept for a resync and
+		// the overflow drops the oldest entry not sent yet.
+		for value in 2..5 {
+			buffer.push(report(value));
+		}
+		assert_eq!((buffer.len(), buffer.pending()), (3, 3));
+		let report = buffer.next_report(&mut rcb).unwrap();
+		assert_eq!(report.values, [Iec61850Data::Integer(2)]);
+		assert_eq!(report.buffer_overflow, Some(true));
+		assert_eq!(buffer.next_report(&mut rcb).unwrap().buffer_overflow, Some(false));
+
+		// A client resyncs after the last entry it received.
+		let entry_id = report.entry_id.unwrap();
+		assert_eq!(
+			buffer.resync(&mut rcb, first.entry_id.unwrap()),
+			Err(DataAccessError::ObjectValueInvalid)
+		);
+		buffer.resync(&mut rcb, entry_id.clone()).unwrap();
+		assert_eq!(rcb.entry_id, entry_id);
+		assert_eq!(buffer.next_report(&mut rcb).unwrap().values, [Iec61850Data::Integer(3)]);
+		buffer.resync(&mut rcb, vec![0; ENTRY_ID_SIZE]).unwrap();
+		assert_eq!(buffer.pending(), 3);
+
+		buffer.purge(&mut rcb);
+		assert!(buffer.is_empty());
+		assert!(buffer.next_report(&mut rcb).is_none());
+		assert_eq!(rcb.entry_id, [0; ENTRY_ID_SIZE]);
+	}
+}
@@ -0,0 +1,847 @@
+//! Report control blocks of the IEC 61850 server.
+//!
+//! The [`ReportEngine`] holds the state of the report control blocks of the
+//! model and decides when a report is sent: on the data changes selected by
+//! the trigger options, at the end of the buffer time, on a general
+//! interrogation and on every integrity period. It doesn't know about the
+//! connections, so it is driven and tested without sockets.
+//!
+//! Once enabled, a buffered report control block keeps reporting into its
+//! [`ReportBuffer`] while disabled, and replays the reports on the next
+//! enable.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use time::OffsetDateTime;
+use tokio::time::Instant;
+
+use crate::{
+	iec61850::{
+		data::{Bitstring, Iec61850Data},
+		model::IedModel,
+		rcb::{
+			BUFFERED_ATTRIBUTES, OptionalFields, ReportControlBlock, TriggerOptions,
+			UNBUFFERED_ATTRIBUTES,
+		},
+		report::Report,
+		server::buffer::ReportBuffer,
+	},
+	mms::error::DataAccessError,
+};
+
+/// The modulo of the sequence number of the unbuffered reports.
+const UNBUFFERED_SEQUENCE_MODULO: u32 = 256;
+
+/// A report to send to the connection with the given id.
+pub type OutgoingReport = (u64, Report);
+
+/// Access a field common to the buffered and unbuffered report control
+/// blocks.
+macro_rules! field {
+	(mut $rcb:expr, $field:ident) => {
+		match $rcb {
+			ReportControlBlock::Buffered(rcb) => &mut rcb.$field,
+			ReportControlBlock::Unbuffered(rcb) => &mut rcb.$field,
+		}
+	};
+	($rcb:expr, $field:ident) => {
+		match $rcb {
+			ReportControlBlock::Buffered(rcb) => &rcb.$field,
+			ReportControlBlock::Unbuffered(rcb) => &rcb.$field,
+		}
+	};
+}
+
+/// The datasets and values the reports are made of.
+pub trait ReportDataSource {
+	/// The members of a dataset, given its reference as written in `DatSet`.
+	/// The association specific datasets are only visible to their
+	/// connection.
+	fn dataset_members(&self, connection: u64, dataset: &str) -> Option<Vec<String>>;
+
+	/// The value of a member of a dataset, e.g. `LD/GGIO1$ST$Ind1`.
+	fn value(&self, reference: &str) -> Option<Iec61850Data>;
+}
+
+/// The state of a report control block.
+#[derive(Debug)]
+struct RcbState {
+	/// The reference of the report control block, e.g. `LD/LLN0$RP$urcb01`.
+	reference: String,
+	/// The attributes of the report control block.
+	rcb: ReportControlBlock,
+	/// The connection that enabled or reserved the report control block.
+	owner: Option<u64>,
+	/// The members of the dataset, resolved when the report control block is
+	/// enabled.
+	members: Vec<String>,
+	/// The reasons of the members waiting for the end of the buffer time.
+	pending: Vec<Option<Vec<TriggerOptions>>>,
+	/// The end of the buffer time.
+	buffer_deadline: Option<Instant>,
+	/// The time of the next integrity report.
+	next_integrity: Option<Instant>,
+	/// The buffer of a buffered report control block.
+	buffer: Option<ReportBuffer>,
+	/// Whether a buffered report control block reports into its buffer, from
+	/// its first enable until its dataset changes.
+	buffering: bool,
+}
+
+impl RcbState {
+	/// Create the state of a report control block, disabled and without
+	/// owner.
+	fn new(reference: String, mut rcb: ReportControlBlock) -> Self {
+		*field!(mut &mut rcb, enabled) = false;
+		*field!(mut &mut rcb, gi) = false;
+		let buffer = match &mut rcb {
+			ReportControlBlock::Buffered(_) => Some(ReportBuffer::default()),
+			ReportControlBlock::Unbuffered(rcb) => {
+				rcb.reservation = false;
+				None
+			}
+		};
+		Self {
+			reference,
+			rcb,
+			owner: None,
+			members: Vec::new(),
+			pending: Vec::new(),
+			buffer_deadline: None,
+			next_integrity: None,
+			buffer,
+			buffering: false,
+		}
+	}
+
+	/// Whether the report control block is enabled.
+	const fn is_enabled(&self) -> bool {
+		*field!(&self.rcb, enabled)
+	}
+
+	/// Whether the report control block makes reports, to send or to buffer.
+	const fn is_active(&self) -> bool {
+		self.is_enabled() || self.buffering
+	}
+
+	/// Whether the trigger options contain the option.
+	fn triggers(&self, option: TriggerOptions) -> bool {
+		field!(&self.rcb, trigger_options).contains(&option)
+	}
+
+	/// The integrity period, if the integrity reports are enabled.
+	fn integrity_period(&self) -> Option<Duration> {
+		let period = *field!(&self.rcb, integrity_period);
+		(period > 0 && self.triggers(TriggerOptions::Integrity))
+			.then(|| Duration::from_millis(period.into()))
+	}
+
+	/// Write an attribute of the report control block.
+	fn write(
+		&mut self,
+		connection: u64,
+		attribute: &str,
+		value: Iec61850Data,
+		source: &dyn ReportDataSource,
+		now: Instant,
+	) -> Result<Vec<OutgoingReport>, DataAccessError> {
+		let buffered = matches!(self.rcb, ReportControlBlock::Buffered(_));
+		let attributes: &[&str] =
+			if buffered { &BUFFERED_ATTRIBUTES } else { &UNBUFFERED_ATTRIBUTES };
+		if !attributes.contains(&attribute) {
+			return Err(DataAccessError::ObjectNonExistent);
+		}
+		if self.owner.is_some_and(|owner| owner != connection) {
+			return Err(DataAccessError::TemporarilyUnavailable);
+		}
+
+		match attribute {
+			"RptEna" => {
+				if convert(value)? {
+					return self.enable(connection, source, now);
+				}
+				self.disable();
+				return Ok(Vec::new());
+			}
+			"GI" => {
+				let gi: bool = convert(value)?;
+				if !gi || !self.is_enabled() || !self.triggers(TriggerOptions::Gi) {
+					return Ok(Vec::new());
+				}
+				let mut reports = self.flush(source);
+				let reasons = vec![Some(vec![TriggerOptions::Gi]); self.members.len()];
+				reports.extend(self.report(reasons, source));
+				return Ok(reports);
+			}
+			"Resv" => {
+				let ReportControlBlock::Unbuffered(rcb) = &mut self.rcb else {
+					return Err(DataAccessError::ObjectNonExistent);
+				};
+				rcb.reservation = convert(value)?;
+				self.owner = (rcb.reservation || rcb.enabled).then_some(connection);
+				return Ok(Vec::new());
+			}
+			"ConfRev" | "SqNum" | "TimeofEntry" => {
+				return Err(DataAccessError::ObjectAccessDenied);
+			}
+			_ if self.is_enabled() => return Err(DataAccessError::TemporarilyUnavailable),
+			_ => {}
+		}
+
+		match (attribute, &mut self.rcb) {
+			("RptID", rcb) => *field!(mut rcb, id) = convert(value)?,
+			("DatSet", rcb) => {
+				let dataset: String = convert(value)?;
+				if !dataset.is_empty() && source.dataset_members(connection, &dataset).is_none() {
+					return Err(DataAccessError::ObjectValueInvalid);
+				}
+				*field!(mut rcb, dataset) = dataset;
+				// The buffered reports are of the previous dataset.
+				if let (Some(buffer), ReportControlBlock::Buffered(rcb)) = (&mut self.buffer, rcb) {
+					buffer.purge(rcb);
+				}
+				self.buffering = false;
+			}
+			("OptFlds", rcb) => *field!(mut rcb, optional_fields) = convert(value)?,
+			("BufTm", rcb) => *field!(mut rcb, buffer_time) = convert(value)?,
+			("TrgOps", rcb) => *field!(mut rcb, trigger_options) = convert(value)?,
+			("IntgPd", rcb) => *field!(mut rcb, integrity_period) = convert(value)?,
+			("PurgeBuf", ReportControlBlock::Buffered(rcb)) => {
+				if convert(value)?
+					&& let Some(buffer) = &mut self.buffer
+				{
+					buffer.purge(rcb);
+				}
+			}
+			("EntryID", ReportControlBlock::Buffered(rcb)) => {
+				let entry_id = convert(value)?;
+				match &mut self.buffer {
+					Some(buffer) => buffer.resync(rcb, entry_id)?,
+					None => rcb.entry_id = entry_id,
+				}
+			}
+			("ResvTms", ReportControlBlock::Buffered(rcb)) => {
+				rcb.reservation_time = convert(value)?;
+			}
+			_ => return Err(DataAccessError::ObjectNonExistent),
+		}
+		Ok(Vec::new())
+	}
+
+	/// Enable the report control block for a connection. Returns the
+	/// buffered reports not sent yet. The integrity period and the trigger
+	/// options may have changed while disabled, so the integrity deadline
+	/// and the pending changes of a buffering report control block are
+	/// computed again.
+	fn enable(
+		&mut self,
+		connection: u64,
+		source: &dyn ReportDataSource,
+		now: Instant,
+	) -> Result<Vec<OutgoingReport>, DataAccessError> {
+		if self.is_enabled() {
+			return Ok(Vec::new());
+		}
+		if self.buffering {
+			let trigger_options = field!(&self.rcb, trigger_options);
+			for reasons in &mut self.pending {
+				*reasons = reasons.take().and_then(|mut reasons| {
+					reasons.retain(|reason| trigger_options.contains(reason));
+					(!reasons.is_empty()).then_some(reasons)
+				});
+			}
+			if self.pending.iter().all(Option::is_none) {
+				self.buffer_deadline = None;
+			}
+		} else {
+			self.members = source
+				.dataset_members(connection, field!(&self.rcb, dataset))
+				.ok_or(DataAccessError::ObjectValueInvalid)?;
+			self.pending = vec![None; self.members.len()];
+			self.buffer_deadline = None;
+		}
+		self.next_integrity = self.integrity_period().map(|period| now + period);
+		self.buffering = self.buffer.is_some();
+		self.owner = Some(connection);
+		*field!(mut &mut self.rcb, enabled) = true;
+		Ok(self.send_buffered())
+	}
+
+	/// Disable the report control block. An unbuffered report control block
+	/// stays reserved by its owner and a buffered one keeps buffering.
+	fn disable(&mut self) {
+		*field!(mut &mut self.rcb, enabled) = false;
+		if self.buffering {
+			self.owner = None;
+			return;
+		}
+		self.pending.clear();
+		self.buffer_deadline = None;
+		self.next_integrity = None;
+		let reserved = matches!(&self.rcb, ReportControlBlock::Unbuffered(rcb) if rcb.reservation);
+		if !reserved {
+			self.owner = None;
+		}
+	}
+
+	/// Release the report control block owned by a closed connection.
+	fn release(&mut self) {
+		if let ReportControlBlock::Unbuffered(rcb) = &mut self.rcb {
+			rcb.reservation = false;
+		}
+		self.disable();
+	}
+
+	/// Handle a batch of data changes. The members changed twice while the
+	/// buffer time runs are sent before being changed again.
+	fn data_changed(
+		&mut self,
+		changes: &[(String, TriggerOptions)],
+		source: &dyn ReportDataSource,
+		now: Instant,
+	) -> Vec<OutgoingReport> {
+		if !self.is_active() {
+			return Vec::new();
+		}
+		let buffer_time = Duration::from_millis((*field!(&self.rcb, buffer_time)).into());
+		let mut reports = Vec::new();
+		for (reference, reason) in changes {
+			if !self.triggers(*reason) {
+				continue;
+			}
+			for index in 0..self.members.len() {
+				if !is_member_of(reference, &self.members[index]) {
+					continue;
+				}
+				if !buffer_time.is_zero()
+					&& self.pending[index].as_ref().is_some_and(|reasons| !reasons.is_empty())
+				{
+					reports.extend(self.flush(source));
+				}
+				let reasons = self.pending[index].get_or_insert_with(Vec::new);
+				if !reasons.contains(reason) {
+					reasons.push(*reason);
+				}
+				self.buffer_deadline.get_or_insert(now + buffer_time);
+			}
+		}
+		if buffer_time.is_zero() {
+			reports.extend(self.flush(source));
+		}
+		reports
+	}
+
+	/// Send the reports whose time has come.
+	fn poll(&mut self, source: &dyn ReportDataSource, now: Instant) -> Vec<OutgoingReport> {
+		let mut reports = Vec::new();
+		if self.buffer_deadline.is_some_and(|deadline| deadline <= now) {
+			reports.extend(self.flush(source));
+		}
+		if let Some(next_integrity) = self.next_integrity
+			&& next_integrity <= now
+		{
+			reports.extend(self.flush(source));
+			let reasons = vec![Some(vec![TriggerOptions::Integrity]); self.members.len()];
+			reports.extend(self.report(reasons, source));
+			let period = self.integrity_period().unwrap_or_default();
+			self.next_integrity = Some((next_integrity + period).max(now));
+		}
+		reports
+	}
+
+	/// The next time the report control block needs to be polled.
+	fn next_deadline(&self) -> Option<Instant> {
+		[self.buffer_deadline, self.next_integrity].into_iter().flatten().min()
+	}
+
+	/// Send the members waiting for the end of the buffer time.
+	fn flush(&mut self, source: &dyn ReportDataSource) -> Vec<OutgoingReport> {
+		self.buffer_deadline = None;
+		if self.pending.iter().all(Option::is_none) {
+			return Vec::new();
+		}
+		let reasons = std::mem::replace(&mut self.pending, vec![None; self.members.len()]);
+		self.report(reasons, source)
+	}
+
+	/// Report the members with a reason. The report of a buffered report
+	/// control block goes through its buffer. Nothing is sent without an
+	/// owner.
+	fn report(
+		&mut self,
+		reasons: Vec<Option<Vec<TriggerOptions>>>,
+		source: &dyn ReportDataSource,
+	) -> Vec<OutgoingReport> {
+		let report = self.make_report(reasons, source);
+		match (&mut self.buffer, &mut self.rcb) {
+			(Some(buffer), _) => {
+				buffer.push(report);
+				self.send_buffered()
+			}
+			(None, rcb) => {
+				let Some(owner) = self.owner else {
+					return Vec::new();
+				};
+				let sequence_number = field!(mut rcb, sequence_number);
+				let report = Report { sequence_number: Some(*sequence_number), ..report };
+				*sequence_number = (*sequence_number + 1) % UNBUFFERED_SEQUENCE_MODULO;
+				vec![(owner, report)]
+			}
+		}
+	}
+
+	/// Send the buffered reports not sent yet, if the report control block is
+	/// enabled.
+	fn send_buffered(&mut self) -> Vec<OutgoingReport> {
+		let (Some(owner), Some(buffer), ReportControlBlock::Buffered(rcb)) =
+			(self.owner.filter(|_| self.is_enabled()), &mut self.buffer, &mut self.rcb)
+		else {
+			return Vec::new();
+		};
+		std::iter::from_fn(|| buffer.next_report(rcb)).map(|report| (owner, report)).collect()
+	}
+
+	/// Make a report of the members with a reason. The sequence number and
+	/// the entry id are given when it is sent or buffered.
+	fn make_report(
+		&self,
+		reasons: Vec<Option<Vec<TriggerOptions>>>,
+		source: &dyn ReportDataSource,
+	) -> Report {
+		let mut inclusion = Bitstring {
+			bytes: vec![0; self.members.len().div_ceil(8)],
+			padding: u8::try_from((8 - self.members.len() % 8) % 8).unwrap_or_default(),
+		};
+		let mut data_reference = Vec::new();
+		let mut values = Vec::new();
+		let mut reason_for_transmission = Vec::new();
+		for (index, (member, reasons)) in self.members.iter().zip(reasons).enumerate() {
+			let (Some(reasons), Some(value)) = (reasons, source.value(member)) else {
+				continue;
+			};
+			inclusion.bytes[index / 8] |= 1 << (index % 8);
+			data_reference.push(member.clone());
+			values.push(value);
+			reason_for_transmission.push(reasons);
+		}
+
+		let buffered = self.buffer.is_some();
+		let mut optional_fields = field!(&self.rcb, optional_fields).clone();
+		// Reports are never segmented and only the buffered reports have an
+		// entry id and a buffer overflow.
+		optional_fields.retain(|field| {
+			*field != OptionalFields::Segmentation
+				&& (buffered
+					|| !matches!(field, OptionalFields::BufferOverflow | OptionalFields::EntryID))
+		});
+		let id = field!(&self.rcb, id);
+		Report {
+			id: if id.is_empty() { self.reference.clone() } else { id.clone() },
+			optional_fields,
+			sequence_number: None,
+			time_of_entry: Some(OffsetDateTime::now_utc()),
+			dataset: Some(field!(&self.rcb, dataset).clone()),
+			buffer_overflow: Some(false),
+			entry_id: None,
+			configuration_revision: Some(*field!(&self.rcb, config_rev)),
+			sub_sequence_number: None,
+			more_segments_follows: None,
+			inclusion,
+			data_reference: Some(data_reference),
+			values,
+			reason_for_transmission: Some(reason_for_transmission),
+		}
+	}
+}
+
+/// The report control blocks of a server.
+#[derive(Debug)]
+pub struct ReportEngine {
+	/// The report control blocks by reference.
+	rcbs: BTreeMap<String, RcbState>,
+}
+
+impl ReportEngine {
+	/// Create the report control blocks of the model. They start disabled,
+	/// whatever their state in the model.
+	#[must_use]
+	pub fn new(model: &IedModel) -> Self {
+		let rcbs = model
+			.logical_devices
+			.iter()
+			.flat_map(|ld| {
+				ld.logical_nodes.iter().flat_map(|ln| ln.reports.values()).map(|report| {
+					let reference = format!("{}/{}", ld.name, report.name);
+					(reference.clone(), RcbState::new(reference, report.rcb.clone()))
+				})
+			})
+			.collect();
+		Self { rcbs }
+	}
+
+	/// The report control blocks by reference.
+	pub fn rcbs(&self) -> impl Iterator<Item = (&str, &ReportControlBlock)> {
+		self.rcbs.iter().map(|(reference, state)| (reference.as_str(), &state.rcb))
+	}
+
+	/// Split a reference into the reference of a report control block and
+	/// the name of one of its attributes.
+	/// Returns `None` if the reference is not part of a report control block.
+	#[must_use]
+	pub fn split_reference<'a>(&self, reference: &'a str) -> Option<(&'a str, Option<&'a str>)> {
+		let mut parts = reference.splitn(4, '$');
+		let length =
+			[parts.next()?, parts.next()?, parts.next()?].iter().map(|p| p.len()).sum::<usize>();
+		let rcb = &reference[..length + 2];
+		self.rcbs.contains_key(rcb).then_some((rcb, parts.next()))
+	}
+
+	/// Read a report control block or one of its attributes, e.g.
+	/// `LD/LLN0$RP$urcb01$RptEna`.
+	#[must_use]
+	pub fn read(&self, reference: &str) -> Option<Iec61850Data> {
+		let (rcb, attribute) = self.split_reference(reference)?;
+		let state = self.rcbs.get(rcb)?;
+		let data = state.rcb.to_data();
+		let Some(attribute) = attribute else {
+			return Some(Iec61850Data::Structure(data));
+		};
+		let attributes: &[&str] = match state.rcb {
+			ReportControlBlock::Buffered(_) => &BUFFERED_ATTRIBUTES,
+			ReportControlBlock::Unbuffered(_) => &UNBUFFERED_ATTRIBUTES,
+		};
+		let index = attributes.iter().position(|name| *name == attribute)?;
+		data.into_iter().nth(index)
+	}
+
+	/// Write an attribute of a report control block for a connection, e.g.
+	/// `LD/LLN0$RP$urcb01$RptEna`. Returns the reports to send, e.g. the
+	/// report of a general interrogation.
+	pub fn write(
+		&mut self,
+		connection: u64,
+		reference: &str,
+		value: Iec61850Data,
+		source: &dyn ReportDataSource,
+		now: Instant,
+	) -> Result<Vec<OutgoingReport>, DataAccessError> {
+		let Some((rcb, Some(attribute))) = self.split_reference(reference) else {
+			return Err(DataAccessError::ObjectAccessDenied);
+		};
+		let state = self.rcbs.get_mut(rcb).ok_or(DataAccessError::ObjectNonExistent)?;
+		state.write(connection, attribute, value, source, now)
+	}
+
+	/// Handle a batch of data changes, given as the reference of the changed
+	/// attribute and the reason of the change. Returns the reports to send.
+	pub fn data_changed(
+		&mut self,
+		changes: &[(String, TriggerOptions)],
+		source: &dyn ReportDataSource,
+		now: Instant,
+	) -> Vec<OutgoingReport> {
+		self.rcbs.values_mut().flat_map(|state| state.data_changed(changes, source, now)).collect()
+	}
+
+	/// Send the buffered and integrity reports whose time has come.
+	pub fn poll(&mut self, source: &dyn ReportDataSource, now: Instant) -> Vec<OutgoingReport> {
+		self.rcbs.values_mut().flat_map(|state| state.poll(source, now)).collect()
+	}
+
+	/// The next time the engine needs to be polled.
+	#[must_use]
+	pub fn next_deadline(&self) -> Option<Instant> {
+		self.rcbs.values().filter_map(RcbState::next_deadline).min()
+	}
+
+	/// Change the number of reports kept by the buffer of every buffered
+	/// report control block.
+	pub fn set_buffer_capacity(&mut self, capacity: usize) {
+		for buffer in self.rcbs.values_mut().filter_map(|state| state.buffer.as_mut()) {
+			buffer.set_capacity(capacity);
+		}
+	}
+
+	/// Release the report control blocks owned by a closed connection.
+	pub fn release(&mut self, connection: u64) {
+		for state in self.rcbs.values_mut() {
+			if state.owner == Some(connection) {
+				state.release();
+			}
+		}
+	}
+
+	/// The datasets of the report control blocks and their owners.
+	pub fn datasets(&self) -> impl Iterator<Item = (&str, Option<u64>)> {
+		self.rcbs.values().map(|state| (field!(&state.rcb, dataset).as_str(), state.owner))
+	}
+}
+
+/// Whether a changed attribute is part of a dataset member.
+fn is_member_of(reference: &str, member: &str) -> bool {
+	reference.strip_prefix(member).is_some_and(|rest| rest.is_empty() || rest.starts_with('$'))
+}
+
+/// Convert the written value to the type of an attribute.
+fn convert<T: TryFrom<Iec61850Data>>(value: Iec61850Data) -> Result<T, DataAccessError> {
+	T::try_from(value).map_err(|_| DataAccessError::TypeInconsistent)
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+	use crate::iec61850::{
+		model::{LogicalDevice, LogicalNode},
+		rcb::{BufferedReportControlBlock, UnbufferedReportControlBlock},
+	};
+
+	/// A data source with one dataset of two members.
+	struct TestSource(HashMap<String, Iec61850Data>);
+
+	impl ReportDataSource for TestSource {
+		fn dataset_members(&self, _connection: u64, dataset: &str) -> Option<Vec<String>> {
+			(dataset == "LD/LLN0$DS1")
+				.then(|| vec!["LD/GGIO1$ST$Ind1".to_owned(), "LD/GGIO1$ST$Ind2".to_owned()])
+		}
+
+		fn value(&self, reference: &str) -> Option<Iec61850Data> {
+			self.0.get(reference).cloned()
+		}
+	}
+
+	fn engine_with(rcb: ReportControlBlock) -> ReportEngine {
+		let mut ln = LogicalNode::new("LLN0".to_owned(), "LD");
+		let (name, buffered) = match &rcb {
+			ReportControlBlock::Buffered(rcb) => (rcb.name.clone(), true),
+			ReportControlBlock::Unbuffered(rcb) => (rcb.name.clone(), false),
+		};
+		ln.reports.insert(
+			name.clone(),
+			crate::iec61850::model::Report { name, path: String::new(), buffered, rcb },
+		);
+		let mut ld = LogicalDevice::new("LD".to_owned());
+		ld.logical_nodes.push(ln);
+		ReportEngine::new(&IedModel { logical_devices: vec![ld] })
+	}
+
+	fn engine() -> ReportEngine {
+		engine_with(ReportControlBlock::Unbuffered(UnbufferedReportControlBlock {
+			name: "LLN0$RP$urcb01".to_owned(),
+			id: String::new(),
+			enabled: true,
+			reservation: false,
+			dataset: "LD/LLN0$DS1".to_owned(),
+			config_rev: 1,
+			optional_fields: vec![OptionalFields::SequenceNumber, OptionalFields::EntryID],
+			buffer_time: 100,
+			sequence_number: 255,
+			trigger_options: vec![
+				TriggerOptions::DataChange,
+				TriggerOptions::Integrity,
+				TriggerOptions::Gi,
+			],
+			integrity_period: 1000,
+			gi: false,
+		}))
+	}
+
+	#[test]
+	fn test_report_engine() {
+		let source = TestSource(HashMap::from([
+			("LD/GGIO1$ST$Ind1".to_owned(), Iec61850Data::Bool(true)),
+			("LD/GGIO1$ST$Ind2".to_owned(), Iec61850Data::Bool(false)),
+		]));
+		let mut engine = engine();
+		let rcb = "LD/LLN0$RP$urcb01";
+		let now = Instant::now();
+		assert_eq!(engine.read(&format!("{rcb}$RptEna")), Some(Iec61850Data::Bool(false)));
+		assert_eq!(engine.split_reference("LD/LLN0$RP$urcb01$GI"), Some((rcb, Some("GI"))));
+		assert_eq!(engine.split_reference("LD/LLN0$RP$other"), None);
+
+		// Enabling reserves the RCB for the connection.
+		let enable = |engine: &mut ReportEngine, connection| {
+			engine.write(
+				connection,
+				&format!("{rcb}$RptEna"),
+				Iec61850Data::Bool(true),
+				&source,
+				now,
+			)
+		};
+		enable(&mut engine, 1).unwrap();
+		assert_eq!(enable(&mut engine, 2).unwrap_err(), DataAccessError::TemporarilyUnavailable);
+		assert_eq!(
+			engine
+				.write(1, &format!("{rcb}$BufTm"), Iec61850Data::Unsigned(0), &source, now)
+				.unwrap_err(),
+			DataAccessError::TemporarilyUnavailable
+		);
+		assert_eq!(
+			engine
+				.write(1, &format!("{rcb}$SqNum"), Iec61850Data::Unsigned(0), &source, now)
+				.unwrap_err(),
+			DataAccessError::ObjectAccessDenied
+		);
+		assert_eq!(engine.next_deadline(), Some(now + Duration::from_secs(1)));
+
+		// A general interrogation reports every member.
+		let reports =
+			engine.write(1, &format!("{rcb}$GI"), Iec61850Data::Bool(true), &source, now).unwrap();
+		let [(1, report)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(report.id, rcb);
+		assert_eq!(report.sequence_number, Some(255));
+		assert_eq!(report.optional_fields, [OptionalFields::SequenceNumber]);
+		assert_eq!(report.inclusion, Bitstring { bytes: vec![0b11], padding: 6 });
+		assert_eq!(report.values, [Iec61850Data::Bool(true), Iec61850Data::Bool(false)]);
+
+		// The changes are buffered, a second change of a member flushes the
+		// first one.
+		let change = |reference: &str| vec![(reference.to_owned(), TriggerOptions::DataChange)];
+		assert!(engine.data_changed(&change("LD/GGIO1$ST$Ind2$stVal"), &source, now).is_empty());
+		assert!(engine.data_changed(&change("LD/GGIO1$ST$Ind20"), &source, now).is_empty());
+		assert!(engine.data_changed(&change("LD/GGIO1$ST$Ind1$q"), &source, now).is_empty());
+		let reports = engine.data_changed(&change("LD/GGIO1$ST$Ind1$stVal"), &source, now);
+		let [(1, report)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(report.sequence_number, Some(0));
+		assert_eq!(report.inclusion, Bitstring { bytes: vec![0b11], padding: 6 });
+		assert_eq!(
+			report.reason_for_transmission,
+			Some(vec![vec![TriggerOptions::DataChange], vec![TriggerOptions::DataChange]])
+		);
+
+		// The buffer time ends before the integrity period.
+		let reports = engine.poll(&source, now + Duration::from_millis(100));
+		let [(1, report)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(report.inclusion, Bitstring { bytes: vec![0b01], padding: 6 });
+		let reports = engine.poll(&source, now + Duration::from_secs(1));
+		let [(1, report)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(
+			report.reason_for_transmission.as_ref().unwrap()[0],
+			[TriggerOptions::Integrity]
+		);
+		assert_eq!(engine.next_deadline(), Some(now + Duration::from_secs(2)));
+
+		// Closing the connection releases the RCB.
+		engine.release(1);
+		assert_eq!(engine.next_deadline(), None);
+		enable(&mut engine, 2).unwrap();
+	}
+
+	#[test]
+	fn test_buffered_reports() {
+		let source = TestSource(HashMap::from([
+			("LD/GGIO1$ST$Ind1".to_owned(), Iec61850Data::Bool(true)),
+			("LD/GGIO1$ST$Ind2".to_owned(), Iec61850Data::Bool(false)),
+		]));
+		let mut engine = engine_with(ReportControlBlock::Buffered(BufferedReportControlBlock {
+			optional_fields: vec![OptionalFields::BufferOverflow, OptionalFields::EntryID],
+			..BufferedReportControlBlock::fixture()
+		}));
+		let rcb = "LD/LLN0$BR$brcb01";
+		let now = Instant::now();
+		let write = |engine: &mut ReportEngine, connection, attribute: &str, value| {
+			engine.write(connection, &format!("{rcb}${attribute}"), value, &source, now)
+		};
+		let change = |engine: &mut ReportEngine, reference: &str| {
+			let changes = [(reference.to_owned(), TriggerOptions::DataChange)];
+			engine.data_changed(&changes, &source, now)
+		};
+
+		// Nothing is buffered before the first enable.
+		assert!(change(&mut engine, "LD/GGIO1$ST$Ind1$stVal").is_empty());
+		assert!(write(&mut engine, 1, "RptEna", Iec61850Data::Bool(true)).unwrap().is_empty());
+		let reports = change(&mut engine, "LD/GGIO1$ST$Ind1$stVal");
+		let [(1, first)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(first.entry_id, Some(vec![0, 0, 0, 0, 0, 0, 0, 1]));
+		assert_eq!(
+			first.optional_fields,
+			[OptionalFields::BufferOverflow, OptionalFields::EntryID]
+		);
+
+		// The connection is lost, the changes are buffered.
+		engine.release(1);
+		assert!(change(&mut engine, "LD/GGIO1$ST$Ind2$stVal").is_empty());
+		assert!(change(&mut engine, "LD/GGIO1$ST$Ind1$q").is_empty());
+
+		// The client reconnects, resyncs and gets the reports it missed.
+		let entry_id = Iec61850Data::OctetString(first.entry_id.clone().unwrap());
+		write(&mut engine, 2, "EntryID", entry_id).unwrap();
+		let reports = write(&mut engine, 2, "RptEna", Iec61850Data::Bool(true)).unwrap();
+		let [(2, second), (2, third)] = reports.as_slice() else {
+			panic!("unexpected reports: {reports:?}")
+		};
+		assert_eq!(second.sequence_number, Some(1));
+		assert_eq!(second.inclusion, Bitstring { bytes: vec![0b10], padding: 6 });
+		assert_eq!(third.entry_id, Some(vec![0, 0, 0, 0, 0, 0, 0, 3]));
+		assert_eq!(
+			engine.read(&format!("{rcb}$EntryID")),
+			Some(Iec61850Data::OctetString(vec![0, 0, 0, 0, 0, 0, 0, 3]))
+		);
+
+		// An entry no longer in the buffer can't be resynced.
+		write(&mut engine, 2, "RptEna", Iec61850Data::Bool(false)).unwrap();
+		write(&mut engine, 2, "PurgeBuf", Iec61850Data::Bool(true)).unwrap();
+		assert_eq!(
+			write(
+				&mut engine,
+				2,
+				"EntryID",
+				Iec61850Data::OctetString(vec![0, 0, 0, 0, 0, 0, 0, 3])
+			)
+			.unwrap_err(),
+			DataAccessError::ObjectValueInvalid
+		);
+
+		// The oldest reports are dropped once the buffer is full.
+		engine.set_buffer_capacity(1);
+		assert!(change(&mut engine, "LD/GGIO1$ST$Ind1$stVal").is_empty());
+		assert!(change(&mut engine, "LD/GGIO1$ST$Ind2$stVal").is_empty());
+		let reports = write(&mut engine, 2, "RptEna", Iec61850Data::Bool(true)).unwrap();
+		let [(2, report)] = reports.as_slice() else { panic!("unexpected reports: {reports:?}") };
+		assert_eq!(report.buffer_overflow, Some(true));
+		assert_eq!(report.entry_id, Some(vec![0, 0, 0, 0, 0, 0, 0, 5]));
+	}
+
+	#[test]
+	fn test_buffered_reenable() {
+		let source = TestSource(HashMap::from([
+			("LD/GGIO1$ST$Ind1".to_owned(), Iec61850Data::Bool(true)),
+			("LD/GGIO1$ST$Ind2".to_owned(), Iec61850Data::Bool(false)),
+		]));
+		let mut engine = engine_with(ReportControlBlock::Buffered(BufferedReportControlBlock {
+			buffer_time: 100,
+			..BufferedReportControlBlock::fixture()
+		}));
+		let rcb = "LD/LLN0$BR$brcb01";
+		let now = Instant::now();
+		let write = |engine: &mut ReportEngine, attribute: &str, value, now| {
+			engine.write(1, &format!("{rcb}${attribute}"), value, &source, now).unwrap();
+			engine.next_deadline()
+		};
+
+		// A change waits for the end of the buffer time.
+		assert_eq!(write(&mut engine, "RptEna", Iec61850Data::Bool(true), now), None);
+		let changes = [("LD/GGIO1$ST$Ind1$stVal".to_owned(), TriggerOptions::DataChange)];
+		assert!(engine.data_changed(&changes, &source, now).is_empty());
+		assert_eq!(engine.next_deadline(), Some(now + Duration::from_millis(100)));
+
+		// The trigger options change while disabled: the pending change is
+		// dropped and the integrity reports start on the next enable.
+		write(&mut engine, "RptEna", Iec61850Data::Bool(false), now);
+		write(&mut engine, "IntgPd", Iec61850Data::Unsigned(1000), now);
+		write(&mut engine, "TrgOps", vec![TriggerOptions::Integrity].into(), now);
+		let later = now + Duration::from_secs(5);
+		assert_eq!(
+			write(&mut engine, "RptEna", Iec61850Data::Bool(true), later),
+			Some(later + Duration::from_secs(1))
+		);
+
+		// Without the integrity trigger, there is no integrity deadline.
+		write(&mut engine, "RptEna", Iec61850Data::Bool(false), later);
+		write(&mut engine, "TrgOps", vec![TriggerOptions::DataChange].into(), later);
+		assert_eq!(write(&mut engine, "RptEna", Iec61850Data::Bool(true), later), None);
+	}
+}
@@ -0,0 +1,131 @@
+//! IEC61850 setting group control block.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use time::OffsetDateTime;
+
+use crate::iec61850::data::{Iec61850Data, Iec61850DataError};
+
+/// The path of the setting group control block in a logical device.
+pub const SGCB_PATH: &str = "LLN0$SP$SGCB";
+
+/// A representation of a setting group control block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingGroupControlBlock {
+	/// The number of setting groups.
+	pub number_of_groups: u8, // Index 0
+	/// The active setting group.
+	pub active_group: u8, // Index 1
+	/// The setting group selected for edition. 0 if none is selected.
+	pub edit_group: u8, // Index 2
+	/// Whether the edition has been confirmed.
+	pub confirm_edit: bool, // Index 3
+	/// The last time a setting group was activated.
+	pub last_activation_time: OffsetDateTime, // Index 4
+	/// The reservation time of the edition in seconds. Only present since
+	/// edition 2.
+	pub reservation_time: Option<u16>, // Index 5
+}
+
+impl SettingGroupControlBlock {
+	/// Create a setting group control block from data.
+	pub fn from_data(mut data: Vec<Iec61850Data>) -> Result<Self, SettingGroupControlBlockError> {
+		let reservation_time = match data.len() {
+			5 => None,
+			6 => {
+				let value: u32 = data
+					.pop()
+					.context(MissingField { field: "reservation_time" })?
+					.try_into()
+					.context(InvalidConversion { field: "reservation_time" })?;
+				Some(
+					u16::try_from(value)
+						.ok()
+						.context(OutOfRange { field: "reservation_time", value })?,
+				)
+			}
+			length => return InvalidDataLength { length }.fail(),
+		};
+		Ok(Self {
+			reservation_time,
+			// The values come in a specific order
+			last_activation_time: data
+				.pop()
+				.context(MissingField { field: "last_activation_time" })?
+				.try_into()
+				.context(InvalidConversion { field: "last_activation_time" })?,
+			confirm_edit: data
+				.pop()
+				.context(MissingField { field: "confirm_edit" })?
+				.try_into()
+				.context(InvalidConversion { field: "confirm_edit" })?,
+			edit_group: to_group(data.pop(), "edit_group")?,
+			active_group: to_group(data.pop(), "active_group")?,
+			number_of_groups: to_group(data.pop(), "number_of_groups")?,
+		})
+	}
+}
+
+/// Convert the data of a setting group number.
+fn to_group(data: Option<Iec61850Data>, field: &str) -> Result<u8, SettingGroupControlBlockError> {
+	let value: u32 =
+		data.context(MissingField { field })?.try_into().context(InvalidConversion { field })?;
+	u8::try_from(value).ok().context(OutOfRange { field, value })
+}
+
+/// The error type for the setting group control block.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum SettingGroupControlBlockError {
+	#[snafu(display("Missing field: {}", field))]
+	MissingField { field: String },
+	#[snafu(display("Invalid conversion for field: {}", field))]
+	InvalidConversion { field: String, source: Iec61850DataError },
+	#[snafu(display("Value out of range for field {}: {}", field, value))]
+	OutOfRange { field: String, value: u32 },
+	#[snafu(display("Invalid data length for setting group control block. Length: {}", length))]
+	InvalidDataLength { length: usize },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sgcb_data() -> Vec<Iec61850Data> {
+		vec![
+			Iec61850Data::Unsigned(4),
+			Iec61850Data::Unsigned(2),
+			Iec61850Data::Unsigned(0),
+			Iec61850Data::Bool(false),
+			Iec61850Data::UtcTime(OffsetDateTime::UNIX_EPOCH),
+		]
+	}
+
+	#[test]
+	fn test_sgcb_from_data() {
+		let sgcb = SettingGroupControlBlock::from_data(sgcb_data()).unwrap();
+		assert_eq!(sgcb.number_of_groups, 4);
+		assert_eq!(sgcb.active_group, 2);
+		assert_eq!(sgcb.edit_group, 0);
+		assert!(!sgcb.confirm_edit);
+		assert_eq!(sgcb.reservation_time, None);
+
+		let mut data = sgcb_data();
+		data.push(Iec61850Data::Unsigned(30));
+		let sgcb = SettingGroupControlBlock::from_data(data).unwrap();
+		assert_eq!(sgcb.reservation_time, Some(30));
+
+		let mut data = sgcb_data();
+		data[1] = Iec61850Data::Unsigned(256);
+		assert!(matches!(
+			SettingGroupControlBlock::from_data(data),
+			Err(SettingGroupControlBlockError::OutOfRange { value: 256, .. })
+		));
+		assert!(matches!(
+			SettingGroupControlBlock::from_data(sgcb_data()[..4].to_vec()),
+			Err(SettingGroupControlBlockError::InvalidDataLength { length: 4 })
+		));
+	}
+}
@@ -0,0 +1,279 @@
+//! IEC61850 sampled value control block.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+
+use crate::iec61850::{
+	data::{Bitstring, Iec61850Data, Iec61850DataError},
+	gocb::{DestinationAddress, GooseControlBlockError},
+};
+
+/// A representation of a sampled value control block.
+/// The multicast (`MS`) and unicast (`US`) control blocks share the same
+/// attributes, the unicast one has an additional reservation attribute.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampledValueControlBlock {
+	/// The name of the sampled value control block.
+	pub name: String,
+	/// Whether the publication is enabled.
+	pub enabled: bool, // Index 0
+	/// Whether the sampled value control block is reserved. Only present in
+	/// unicast sampled value control blocks.
+	pub reservation: Option<bool>, // Index 1 (unicast only)
+	/// The id of the sampled value control block.
+	pub id: String, // Index 1
+	/// The dataset of the sampled value control block.
+	pub dataset: String, // Index 2
+	/// The configuration revision of the sampled value control block.
+	pub config_rev: u32, // Index 3
+	/// The sample rate, in the unit given by the sample mode.
+	pub sample_rate: u16, // Index 4
+	/// The optional fields of the sampled value control block.
+	pub optional_fields: Vec<SampledValueOptionalFields>, // Index 5
+	/// The sample mode of the sampled value control block.
+	pub sample_mode: SampleMode, // Index 6
+	/// The destination of the sampled value messages.
+	pub destination: DestinationAddress, // Index 7
+	/// The number of ASDUs in each message.
+	pub number_of_asdu: u16, // Index 8
+}
+
+/// A sampled value optional field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum SampledValueOptionalFields {
+	/// The refresh time optional field.
+	RefreshTime = 0x01,
+	/// The sample synchronized optional field. Always present since edition
+	/// 2.
+	SampleSynchronized = 0x02,
+	/// The sample rate optional field.
+	SampleRate = 0x04,
+	/// The dataset name optional field.
+	DataSetName = 0x08,
+	/// The security optional field.
+	Security = 0x10,
+}
+
+/// The unit of the sample rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SampleMode {
+	/// Samples per nominal period.
+	SamplesPerPeriod,
+	/// Samples per second.
+	SamplesPerSecond,
+	/// Seconds per sample.
+	SecondsPerSample,
+}
+
+impl SampledValueControlBlock {
+	/// Create a sampled value control block from data.
+	pub fn from_data(
+		name: String,
+		data: Vec<Iec61850Data>,
+	) -> Result<Self, SampledValueControlBlockError> {
+		let unicast = match data.len() {
+			9 => false,
+			10 => true,
+			length => return InvalidDataLength { length }.fail(),
+		};
+		// The values come in a specific order
+		let mut data = data.into_iter();
+		Ok(Self {
+			name,
+			enabled: data
+				.next()
+				.context(MissingField { field: "enabled" })?
+				.try_into()
+				.context(InvalidConversion { field: "enabled" })?,
+			reservation: if unicast {
+				Some(
+					data.next()
+						.context(MissingField { field: "reservation" })?
+						.try_into()
+						.context(InvalidConversion { field: "reservation" })?,
+				)
+			} else {
+				None
+			},
+			id: data
+				.next()
+				.context(MissingField { field: "id" })?
+				.try_into()
+				.context(InvalidConversion { field: "id" })?,
+			dataset: data
+				.next()
+				.context(MissingField { field: "dataset" })?
+				.try_into()
+				.context(InvalidConversion { field: "dataset" })?,
+			config_rev: data
+				.next()
+				.context(MissingField { field: "config_rev" })?
+				.try_into()
+				.context(InvalidConversion { field: "config_rev" })?,
+			sample_rate: to_u16(data.next(), "sample_rate")?,
+			optional_fields: data
+				.next()
+				.context(MissingField { field: "optional_fields" })?
+				.try_into()
+				.context(InvalidConversion { field: "optional_fields" })?,
+			sample_mode: data
+				.next()
+				.context(MissingField { field: "sample_mode" })?
+				.try_into()
+				.context(InvalidConversion { field: "sample_mode" })?,
+			destination: DestinationAddress::from_data(
+				data.next().context(MissingField { field: "destination" })?,
+			)
+			.context(InvalidDestination)?,
+			number_of_asdu: to_u16(data.next(), "number_of_asdu")?,
+		})
+	}
+
+	/// Whether the sampled value control block is a multicast one.
+	#[must_use]
+	pub const fn is_multicast(&self) -> bool {
+		self.reservation.is_none()
+	}
+}
+
+/// Convert the data of an `INT16U` field.
+fn to_u16(data: Option<Iec61850Data>, field: &str) -> Result<u16, SampledValueControlBlockError> {
+	let value: u32 =
+		data.context(MissingField { field })?.try_into().context(InvalidConversion { field })?;
+	u16::try_from(value).ok().context(OutOfRange { field, value })
+}
+
+/// The error type for the sampled value control block.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum SampledValueControlBlockError {
+	#[snafu(display("Missing field: {}", field))]
+	MissingField { field: String },
+	#[snafu(display("Invalid conversion for field: {}", field))]
+	InvalidConversion { field: String, source: Iec61850DataError },
+	#[snafu(display("Value out of range for field {}: {}", field, value))]
+	OutOfRange { field: String, value: u32 },
+	#[snafu(display("Invalid destination address"))]
+	InvalidDestination { source: GooseControlBlockError },
+	#[snafu(display("Invalid data length for sampled value control block. Length: {}", length))]
+	InvalidDataLength { length: usize },
+}
+
+impl TryFrom<Iec61850Data> for Vec<SampledValueOptionalFields> {
+	type Error = Iec61850DataError;
+	fn try_from(value: Iec61850Data) -> Result<Self, Self::Error> {
+		match value {
+			Iec61850Data::BitString(value) => {
+				let mut options = Vec::new();
+				if value.len() != 1 {
+					return Err(Iec61850DataError::InvalidConversion);
+				}
+				let option_byte = value.bytes[0];
+
+				for option in [
+					SampledValueOptionalFields::RefreshTime,
+					SampledValueOptionalFields::SampleSynchronized,
+					SampledValueOptionalFields::SampleRate,
+					SampledValueOptionalFields::DataSetName,
+					SampledValueOptionalFields::Security,
+				] {
+					if option_byte & option as u8 != 0 {
+						options.push(option);
+					}
+				}
+				Ok(options)
+			}
+			_ => Err(Iec61850DataError::InvalidConversion),
+		}
+	}
+}
+
+impl From<Vec<SampledValueOptionalFields>> for Bitstring {
+	fn from(value: Vec<SampledValueOptionalFields>) -> Self {
+		Bitstring {
+			bytes: vec![value.into_iter().fold(0_u8, |byte, option| byte | option as u8)],
+			padding: 3,
+		}
+	}
+}
+
+impl From<Vec<SampledValueOptionalFields>> for Iec61850Data {
+	fn from(value: Vec<SampledValueOptionalFields>) -> Self {
+		Iec61850Data::BitString(value.into())
+	}
+}
+
+impl TryFrom<Iec61850Data> for SampleMode {
+	type Error = Iec61850DataError;
+	fn try_from(value: Iec61850Data) -> Result<Self, Self::Error> {
+		match i32::try_from(value)? {
+			0 => Ok(Self::SamplesPerPeriod),
+			1 => Ok(Self::SamplesPerSecond),
+			2 => Ok(Self::SecondsPerSample),
+			_ => Err(Iec61850DataError::InvalidConversion),
+		}
+	}
+}
+
+impl From<SampleMode> for Iec61850Data {
+	fn from(value: SampleMode) -> Self {
+		Iec61850Data::Integer(match value {
+			SampleMode::SamplesPerPeriod => 0,
+			SampleMode::SamplesPerSecond => 1,
+			SampleMode::SecondsPerSample => 2,
+		})
+	}
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn msvcb_data() -> Vec<Iec61850Data> {
+		vec![
+			Iec61850Data::Bool(false),
+			Iec61850Data::String("MU01".to_owned()),
+			Iec61850Data::String("LD/LLN0$PhsMeas".to_owned()),
+			Iec61850Data::Unsigned(1),
+			Iec61850Data::Unsigned(4000),
+			vec![SampledValueOptionalFields::SampleSynchronized].into(),
+			SampleMode::SamplesPerSecond.into(),
+			DestinationAddress {
+				mac_address: [0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01],
+				priority: 4,
+				vlan_id: 0,
+				app_id: 0x4000,
+			}
+			.into(),
+			Iec61850Data::Unsigned(1),
+		]
+	}
+
+	#[test]
+	fn test_svcb_from_data() {
+		let svcb = SampledValueControlBlock::from_data("LLN0$MS$MSVCB01".to_owned(), msvcb_data())
+			.unwrap();
+		assert!(svcb.is_multicast());
+		assert_eq!(svcb.id, "MU01");
+		assert_eq!(svcb.sample_rate, 4000);
+		assert_eq!(svcb.optional_fields, vec![SampledValueOptionalFields::SampleSynchronized]);
+		assert_eq!(svcb.sample_mode, SampleMode::SamplesPerSecond);
+		assert_eq!(svcb.destination.app_id, 0x4000);
+		assert_eq!(svcb.number_of_asdu, 1);
+
+		let mut data = msvcb_data();
+		data.insert(1, Iec61850Data::Bool(true));
+		let svcb = SampledValueControlBlock::from_data("LLN0$US$USVCB01".to_owned(), data).unwrap();
+		assert!(!svcb.is_multicast());
+		assert_eq!(svcb.reservation, Some(true));
+		assert_eq!(svcb.id, "MU01");
+
+		assert!(matches!(
+			SampledValueControlBlock::from_data(String::new(), msvcb_data()[..8].to_vec()),
+			Err(SampledValueControlBlockError::InvalidDataLength { length: 8 })
+		));
+	}
+}
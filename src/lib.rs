@@ -1,15 +1,24 @@
 //! IEC61850 protocol implementation in pure rust.
 //!
-//! This crate provides a client implementation for the IEC61850 protocol.
-//! It is a pure rust implementation of the protocol and does not depend on
-//! any external libraries.
+//! This crate provides a client implementation for the IEC61850 protocol, a
+//! server serving a model with reporting and controls, built on an MMS server
+//! stack, to build simulators and test the client in-process, and
+//! encoders/decoders for the GOOSE and sampled values messages, over ethernet
+//! or routed over UDP (IEC 61850-90-5).
+//! The protocols are implemented in rust and do not bind to an external
+//! IEC61850 library. The raw ethernet socket of the GOOSE and sampled values
+//! messages, [`goose::socket`], is only available on Linux and calls the
+//! system through `libc`.
 //!
 //! The implementation is based on the MMS stack protocol and the IEC61850
 //! model.
 //!
 //! For an example of how to use the client see the examples folder.
 
+pub mod goose;
 pub mod iec61850;
 pub mod mms;
-pub use iec61850::Iec61850Client;
-pub use mms::ClientConfig;
+pub mod routable;
+pub mod sv;
+pub use iec61850::{Iec61850Client, server::Iec61850Server};
+pub use mms::{ClientConfig, ServerConfig};
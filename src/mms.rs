@@ -12,11 +12,14 @@ use crate::iec61850::report::Report;
 
 pub mod acse;
 pub mod ans1;
+pub mod capabilities;
 pub mod cotp;
+pub mod error;
 pub mod presentation;
 pub mod session;
 
 pub mod client;
+pub mod server;
 
 //TODO: Split this into multiple configs
 /// The client configuration.
@@ -69,6 +72,11 @@ pub struct ConnectionConfig {
 	pub data_structure_nesting_level: i8,
 	/// The maximum PDU size.
 	pub max_pdu_size: i32,
+	/// How long to wait for the response of a request in milliseconds.
+	/// `None` waits forever.
+	pub request_timeout_ms: Option<u64>,
+	/// Send a cancel request to the server when a request times out.
+	pub cancel_on_timeout: bool,
 }
 
 /// The client TLS configuration
@@ -126,6 +134,54 @@ impl Default for ConnectionConfig {
 			max_serv_outstanding_called: 10,
 			data_structure_nesting_level: 10,
 			max_pdu_size: 8192,
+			request_timeout_ms: Some(10_000),
+			cancel_on_timeout: false,
+		}
+	}
+}
+
+/// The server configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ServerConfig {
+	/// The address the server listens on.
+	pub address: String,
+	/// The port the server listens on.
+	pub port: u16,
+	/// The maximum TPDU size.
+	pub tpdu_size: u32,
+	/// The local session selector.
+	pub local_s_sel: Vec<u8>,
+	/// The local presentation selector.
+	pub local_p_sel: Vec<u8>,
+	/// The local AP title.
+	pub local_ap_title: Option<Vec<u32>>,
+	/// The local AE qualifier.
+	pub local_ae_qualifier: Option<u32>,
+	/// The maximum number of outstanding calling services.
+	pub max_serv_outstanding_calling: i16,
+	/// The maximum number of outstanding called services.
+	pub max_serv_outstanding_called: i16,
+	/// The data structure nesting level.
+	pub data_structure_nesting_level: i8,
+	/// The maximum PDU size.
+	pub max_pdu_size: i32,
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Self {
+			address: "0.0.0.0".to_owned(),
+			port: 102,
+			tpdu_size: COTP_MAX_TPDU_SIZE,
+			local_s_sel: vec![0x00, 0x01],
+			local_p_sel: vec![0x00, 0x00, 0x00, 0x01],
+			local_ap_title: Some(vec![1, 1, 1, 999, 1]),
+			local_ae_qualifier: Some(12),
+			max_serv_outstanding_calling: 10,
+			max_serv_outstanding_called: 10,
+			data_structure_nesting_level: 10,
+			max_pdu_size: 8192,
 		}
 	}
 }
@@ -1,18 +1,40 @@
 //! ISO ACSE Layer Implementation (ISO 8327)
 
+use std::fmt;
+
 use async_trait::async_trait;
 use rasn::{ber, prelude::*};
 use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use tokio::net::TcpStream;
 use tracing::instrument;
 
 use crate::mms::{
-	ClientConfig, ReadHalfConnection, SpanTraceWrapper, WriteHalfConnection,
+	ClientConfig, ReadHalfConnection, ServerConfig, SpanTraceWrapper, WriteHalfConnection,
 	ans1::acse::acse_1::*,
-	presentation::{Presentation, PresentationError, PresentationReadHalf, PresentationWriteHalf},
+	presentation::{
+		Presentation, PresentationError, PresentationEvent, PresentationReadHalf,
+		PresentationWriteHalf,
+	},
 };
 
 /// The ASO context name.
 const ASO_CONTEXT_NAME: [u32; 5] = [1, 0, 9506, 2, 3];
+/// The normal release request and release response reason.
+const RELEASE_REASON_NORMAL: u8 = 0;
+/// The ACSE service user abort source.
+const ABORT_SOURCE_SERVICE_USER: u8 = 0;
+/// The accepted associate result.
+const RESULT_ACCEPTED: u8 = 0;
+/// The rejected (permanent) associate result.
+const RESULT_REJECTED_PERMANENT: u8 = 1;
+/// The null service user diagnostic of an accepted association.
+const DIAGNOSTIC_NULL: u8 = 0;
+/// The no reason given service user diagnostic.
+const DIAGNOSTIC_NO_REASON_GIVEN: u8 = 1;
+/// The application context name not supported service user diagnostic.
+const DIAGNOSTIC_APPLICATION_CONTEXT_NOT_SUPPORTED: u8 = 2;
+/// The presentation context ID of the MMS data in the user information.
+const MMS_INDIRECT_REFERENCE: u8 = 3;
 
 /// The ACSE layer.
 #[derive(Debug)]
@@ -71,7 +93,7 @@ impl Acse {
 			None,
 			Some(AssociationData(vec![Myexternal::new(
 				None,
-				Some(Integer::from(3)),
+				Some(Integer::from(MMS_INDIRECT_REFERENCE)),
 				MyexternalEncoding::single_ASN1_type(Any::from(data)),
 			)])),
 		);
@@ -97,6 +119,99 @@ impl Acse {
 		}
 	}
 
+	/// Accept the connection of a client and wait for its AARQ APDU.
+	/// Returns the ACSE and the MMS data of the AARQ APDU. An association with
+	/// an unsupported application context is rejected.
+	#[instrument(skip_all)]
+	pub async fn receive_connect(
+		stream: TcpStream,
+		config: &ServerConfig,
+	) -> Result<(Self, Vec<u8>), AcseError> {
+		let (presentation, data) = Presentation::receive_connect(stream, config).await?;
+		let aarq: AARQApdu = ber::decode(&data).context(DecodeAarq)?;
+		let mut acse = Self {
+			presentation,
+			local_ap_title: config.local_ap_title.clone(),
+			local_ae_qualifier: config.local_ae_qualifier,
+			remote_ap_title: aarq
+				.calling_ap_title
+				.map(|APTitle::ap_title_form2(title)| title.0.to_vec()),
+			remote_ae_qualifier: aarq.calling_ae_qualifier.and_then(
+				|AEQualifier(ASOQualifier::aso_qualifier_form2(qualifier))| {
+					qualifier.0.try_into().ok()
+				},
+			),
+		};
+
+		if aarq.application_context_name
+			!= ObjectIdentifier::new(&ASO_CONTEXT_NAME).context(CreateObjectIdentifier)?
+		{
+			acse.send_aare(
+				RESULT_REJECTED_PERMANENT,
+				DIAGNOSTIC_APPLICATION_CONTEXT_NOT_SUPPORTED,
+				None,
+			)
+			.await?;
+			return UnsupportedApplicationContext.fail();
+		}
+
+		let user_data = aarq
+			.user_information
+			.and_then(|mut data| data.0.pop())
+			.context(MissingUserInformation)?;
+		match user_data.encoding {
+			MyexternalEncoding::single_ASN1_type(data) => Ok((acse, data.into_bytes())),
+			_ => WrongUserInformationEncoding.fail(),
+		}
+	}
+
+	/// Accept the association with an AARE APDU carrying the MMS data.
+	#[instrument(skip(self))]
+	pub async fn accept(&mut self, data: Vec<u8>) -> Result<(), AcseError> {
+		self.send_aare(RESULT_ACCEPTED, DIAGNOSTIC_NULL, Some(data)).await
+	}
+
+	/// Reject the association without giving a reason.
+	#[instrument(skip(self))]
+	pub async fn reject(&mut self) -> Result<(), AcseError> {
+		self.send_aare(RESULT_REJECTED_PERMANENT, DIAGNOSTIC_NO_REASON_GIVEN, None).await
+	}
+
+	/// Send an AARE APDU with the result of the association.
+	async fn send_aare(
+		&mut self,
+		result: u8,
+		diagnostic: u8,
+		data: Option<Vec<u8>>,
+	) -> Result<(), AcseError> {
+		let aare = AAREApdu::new(
+			[true].into_iter().collect(),
+			ObjectIdentifier::new(&ASO_CONTEXT_NAME).context(CreateObjectIdentifier)?,
+			AssociateResult(Integer::from(result)),
+			AssociateSourceDiagnostic::service_user(Integer::from(diagnostic)),
+			self.local_ap_title.as_ref().and_then(|title| {
+				ObjectIdentifier::new(title.clone()).map(APTitleForm2).map(APTitle::from)
+			}),
+			self.local_ae_qualifier
+				.map(|q| AEQualifier(ASOQualifier::from(ASOQualifierForm2(q.into())))),
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			None,
+			data.map(|data| {
+				AssociationData(vec![Myexternal::new(
+					None,
+					Some(Integer::from(MMS_INDIRECT_REFERENCE)),
+					MyexternalEncoding::single_ASN1_type(Any::from(data)),
+				)])
+			}),
+		);
+		Ok(self.presentation.accept(ber::encode(&aare).context(EncodeApdu)?).await?)
+	}
+
 	/// Split the ACSE layer connection into a read half and a write half.
 	#[must_use]
 	pub fn split(self) -> (AcseReadHalf, AcseWriteHalf) {
@@ -142,6 +257,30 @@ impl AcseWriteHalf {
 	) -> Result<(), AcseError> {
 		Ok(presentation.send_data(data).await?)
 	}
+
+	/// Request the release of the association.
+	#[instrument(skip(self))]
+	pub async fn send_release_request(&mut self) -> Result<(), AcseError> {
+		let rlrq = RLRQApdu::new(Some(ReleaseRequestReason(RELEASE_REASON_NORMAL.into())), None);
+		let data = ber::encode(&rlrq).context(EncodeApdu)?;
+		Ok(self.presentation.send_release_request(data).await?)
+	}
+
+	/// Accept the release of the association.
+	#[instrument(skip(self))]
+	pub async fn send_release_response(&mut self) -> Result<(), AcseError> {
+		let rlre = RLREApdu::new(Some(ReleaseResponseReason(RELEASE_REASON_NORMAL.into())), None);
+		let data = ber::encode(&rlre).context(EncodeApdu)?;
+		Ok(self.presentation.send_release_response(data).await?)
+	}
+
+	/// Abort the association.
+	#[instrument(skip(self))]
+	pub async fn send_abort(&mut self) -> Result<(), AcseError> {
+		let abrt = ABRTApdu::new(ABRTSource(ABORT_SOURCE_SERVICE_USER.into()), None, None);
+		let data = ber::encode(&abrt).context(EncodeApdu)?;
+		Ok(self.presentation.send_abort(data).await?)
+	}
 }
 
 #[async_trait]
@@ -168,6 +307,130 @@ impl AcseReadHalf {
 	) -> Result<Vec<u8>, AcseError> {
 		Ok(presentation.receive_data().await?)
 	}
+
+	/// Receive an event from the remote ACSE.
+	#[instrument(skip(self))]
+	pub async fn receive_event(&mut self) -> Result<AcseEvent, AcseError> {
+		match self.presentation.receive_event().await? {
+			PresentationEvent::Data { data, .. } => Ok(AcseEvent::Data(data)),
+			PresentationEvent::ReleaseRequest(data) => {
+				let rlrq: RLRQApdu = ber::decode(&data).context(DecodeApdu)?;
+				tracing::debug!("Release request received: {:?}", rlrq);
+				Ok(AcseEvent::ReleaseRequest)
+			}
+			PresentationEvent::ReleaseResponse(data) => {
+				let rlre: RLREApdu = ber::decode(&data).context(DecodeApdu)?;
+				tracing::debug!("Release response received: {:?}", rlre);
+				Ok(AcseEvent::ReleaseResponse)
+			}
+			PresentationEvent::UserAbort(data) => Ok(AcseEvent::Abort(decode_abort(data))),
+			PresentationEvent::ProviderAbort { reason } => Ok(AcseEvent::Abort(Abort {
+				source: AbortSource::ServiceProvider,
+				diagnostic: reason.map(AbortDiagnostic::PresentationProvider),
+			})),
+		}
+	}
+}
+
+/// Decode the ABRT APDU of a user abort.
+/// The abort is reported even if the APDU is missing or can not be decoded.
+fn decode_abort(data: Option<Vec<u8>>) -> Abort {
+	let abrt = data.map(|data| ber::decode::<ABRTApdu>(&data));
+	match abrt {
+		Some(Ok(abrt)) => Abort {
+			source: AbortSource::from(i64::try_from(abrt.abort_source.0).unwrap_or(i64::MAX)),
+			diagnostic: abrt.abort_diagnostic.map(AbortDiagnostic::from),
+		},
+		Some(Err(e)) => {
+			tracing::warn!("Error decoding abort APDU: {e}");
+			Abort { source: AbortSource::ServiceUser, diagnostic: None }
+		}
+		None => Abort { source: AbortSource::ServiceUser, diagnostic: None },
+	}
+}
+
+/// An event received from the remote ACSE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcseEvent {
+	/// User data.
+	Data(Vec<u8>),
+	/// The remote requested the release of the association.
+	ReleaseRequest,
+	/// The remote accepted the release of the association.
+	ReleaseResponse,
+	/// The association was aborted.
+	Abort(Abort),
+}
+
+/// An abort of the association.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abort {
+	/// Who aborted the association.
+	pub source: AbortSource,
+	/// Why the association was aborted, if given.
+	pub diagnostic: Option<AbortDiagnostic>,
+}
+
+impl fmt::Display for Abort {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "aborted by the {:?}", self.source)?;
+		if let Some(diagnostic) = &self.diagnostic {
+			write!(f, ": {diagnostic:?}")?;
+		}
+		Ok(())
+	}
+}
+
+/// The source of an abort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AbortSource {
+	/// The remote ACSE user, e.g. the IED application.
+	ServiceUser,
+	/// The ACSE or one of the underlying layers.
+	ServiceProvider,
+	/// A source not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for AbortSource {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::ServiceUser,
+			1 => Self::ServiceProvider,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// The diagnostic of an abort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum AbortDiagnostic {
+	NoReasonGiven,
+	ProtocolError,
+	AuthenticationMechanismNameNotRecognized,
+	AuthenticationMechanismNameRequired,
+	AuthenticationFailure,
+	AuthenticationRequired,
+	/// A presentation provider abort with its reason code.
+	PresentationProvider(i64),
+}
+
+impl From<ABRTDiagnostic> for AbortDiagnostic {
+	fn from(value: ABRTDiagnostic) -> Self {
+		match value {
+			ABRTDiagnostic::no_reason_given => Self::NoReasonGiven,
+			ABRTDiagnostic::protocol_error => Self::ProtocolError,
+			ABRTDiagnostic::authentication_mechanism_name_not_recognized => {
+				Self::AuthenticationMechanismNameNotRecognized
+			}
+			ABRTDiagnostic::authentication_mechanism_name_required => {
+				Self::AuthenticationMechanismNameRequired
+			}
+			ABRTDiagnostic::authentication_failure => Self::AuthenticationFailure,
+			ABRTDiagnostic::authentication_required => Self::AuthenticationRequired,
+		}
+	}
 }
 
 #[async_trait]
@@ -223,6 +486,29 @@ pub enum AcseError {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("Error encoding APDU"))]
+	EncodeApdu {
+		source: ber::enc::EncodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error decoding APDU"))]
+	DecodeApdu {
+		source: ber::de::DecodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error decoding AARQ"))]
+	DecodeAarq {
+		source: ber::de::DecodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Unsupported application context"))]
+	UnsupportedApplicationContext {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
 }
 
 impl AcseError {
@@ -237,6 +523,10 @@ impl AcseError {
 			AcseError::AareResultNotSuccessful { context } => context,
 			AcseError::EncodeAarq { context, .. } => context,
 			AcseError::CreateObjectIdentifier { context } => context,
+			AcseError::EncodeApdu { context, .. } => context,
+			AcseError::DecodeApdu { context, .. } => context,
+			AcseError::DecodeAarq { context, .. } => context,
+			AcseError::UnsupportedApplicationContext { context } => context,
 		}
 	}
 }
@@ -249,3 +539,31 @@ impl From<PresentationError> for AcseError {
 		}
 	}
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_abort() {
+		let abrt = ABRTApdu::new(
+			ABRTSource(Integer::from(1)),
+			Some(ABRTDiagnostic::authentication_failure),
+			None,
+		);
+		let data = ber::encode(&abrt).unwrap();
+		assert_eq!(data, vec![0x64, 0x06, 0x80, 0x01, 0x01, 0x81, 0x01, 0x05]);
+		assert_eq!(
+			decode_abort(Some(data)),
+			Abort {
+				source: AbortSource::ServiceProvider,
+				diagnostic: Some(AbortDiagnostic::AuthenticationFailure),
+			}
+		);
+		assert_eq!(
+			decode_abort(Some(vec![0x00])),
+			Abort { source: AbortSource::ServiceUser, diagnostic: None }
+		);
+	}
+}
@@ -175,6 +175,39 @@ pub mod acse_1 {
 			}
 		}
 	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(tag(application, 4), identifier = "ABRT-apdu")]
+	pub struct ABRTApdu {
+		#[rasn(tag(context, 0), identifier = "abort-source")]
+		pub abort_source: ABRTSource,
+		#[rasn(tag(context, 1), identifier = "abort-diagnostic")]
+		pub abort_diagnostic: Option<ABRTDiagnostic>,
+		#[rasn(tag(context, 30), identifier = "user-information")]
+		pub user_information: Option<AssociationData>,
+	}
+	impl ABRTApdu {
+		pub fn new(
+			abort_source: ABRTSource,
+			abort_diagnostic: Option<ABRTDiagnostic>,
+			user_information: Option<AssociationData>,
+		) -> Self {
+			Self { abort_source, abort_diagnostic, user_information }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Copy, Decode, Encode, PartialEq, Eq, Hash, PartialOrd, Ord)]
+	#[rasn(enumerated, identifier = "ABRT-diagnostic")]
+	#[non_exhaustive]
+	pub enum ABRTDiagnostic {
+		no_reason_given = 1,
+		protocol_error = 2,
+		authentication_mechanism_name_not_recognized = 3,
+		authentication_mechanism_name_required = 4,
+		authentication_failure = 5,
+		authentication_required = 6,
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "ABRT-source")]
+	pub struct ABRTSource(pub Integer);
 	#[doc = "  may be used to identify the Association Control ASE."]
 	#[doc = "  top level CHOICE"]
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
@@ -188,6 +221,8 @@ pub mod acse_1 {
 		rlrq(RLRQApdu),
 		#[rasn(tag(application, 3))]
 		rlre(RLREApdu),
+		#[rasn(tag(application, 4))]
+		abrt(ABRTApdu),
 	}
 	impl From<AARQApdu> for ACSEApdu {
 		fn from(value: AARQApdu) -> Self {
@@ -209,6 +244,11 @@ pub mod acse_1 {
 			Self::rlre(value)
 		}
 	}
+	impl From<ABRTApdu> for ACSEApdu {
+		fn from(value: ABRTApdu) -> Self {
+			Self::abrt(value)
+		}
+	}
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate, identifier = "ACSE-requirements")]
 	pub struct ACSERequirements(pub BitString);
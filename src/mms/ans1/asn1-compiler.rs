@@ -1,4 +1,4 @@
-//! ASN.1 compiler for the IEC61850 MMS part of the protocol.
+//! ASN.1 compiler for the IEC61850 MMS, GOOSE and SV parts of the protocol.
 //! This is used to compile the ASN.1 files into Rust modules using the
 //! rasn-compiler crate.
 
@@ -6,8 +6,16 @@ use std::error::Error;
 
 use rasn_compiler::prelude::*;
 
-/// The base path for the ASN.1 files.
+/// The base path for the MMS ASN.1 files.
 const BASE_PATH: &str = "src/mms/ans1";
+/// The base path for the GOOSE ASN.1 files.
+const GOOSE_BASE_PATH: &str = "src/goose/ans1";
+/// The base path for the SV ASN.1 files.
+const SV_BASE_PATH: &str = "src/sv/ans1";
+
+/// The imports needed by the modules that import types, e.g. `Data` or
+/// `UtcTime`, from the MMS module.
+const MMS_IMPORTS: &str = "use crate::mms::ans1::mms::asn1;\n\n";
 
 /// The header for the generated Rust module file.
 const FILE_HEADER: &str = r#"// This file was generated by the asn1-compiler tool.
@@ -18,7 +26,10 @@ const FILE_HEADER: &str = r#"// This file was generated by the asn1-compiler too
 // For running the asn1-compiler tool, run the following command:
 // `cargo run --bin asn1-compiler`
 
-#[rustfmt::skip]
+"#;
+
+/// The attributes for the generated Rust module.
+const MODULE_ATTRIBUTES: &str = r#"#[rustfmt::skip]
 #[allow(
 	missing_docs,
 	clippy::must_use_candidate,
@@ -33,22 +44,27 @@ fn main() -> Result<(), Box<dyn Error>> {
 	// Remove this after the next release https://github.com/librasn/compiler/commit/8219278295ab4f907b48f3dda42980032bfc9a59
 
 	// let files = ["presentation", "acse", "mms"];
-	let files = ["mms"];
+	let files = [
+		(BASE_PATH, "mms", ""),
+		(GOOSE_BASE_PATH, "goose", MMS_IMPORTS),
+		(SV_BASE_PATH, "sv", MMS_IMPORTS),
+	];
 
-	for file in &files {
+	for (base_path, file, imports) in files {
 		let config = RasnConfig { generate_from_impls: true, ..Default::default() };
 		let result = Compiler::<RasnBackend, _>::new_with_config(config)
-			.add_asn_by_path(ans1_file_path(file))
+			.add_asn_by_path(ans1_file_path(base_path, file))
 			.compile_to_string()
 			.unwrap_or_else(|e| {
-				panic!("Error compiling asn1 file {}: \n{e}", ans1_file_path(file))
+				panic!("Error compiling asn1 file {}: \n{e}", ans1_file_path(base_path, file))
 			});
 		#[allow(clippy::print_stdout)]
 		for warning in result.warnings {
 			println!("warning: {:?}", warning);
 		}
-		let rs_file_path = rs_file_path(file);
-		let generated_code = format!("{FILE_HEADER}{}", result.generated);
+		let rs_file_path = rs_file_path(base_path, file);
+		let generated_code =
+			format!("{FILE_HEADER}{imports}{MODULE_ATTRIBUTES}{}", result.generated);
 		std::fs::write(rs_file_path.clone(), generated_code)
 			.unwrap_or_else(|_| panic!("Error writing Rust module file {rs_file_path}"));
 	}
@@ -57,11 +73,11 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 /// Returns the path to the ASN.1 file for the given file name.
-fn ans1_file_path(file: &str) -> String {
-	format!("{BASE_PATH}/{}.asn", file)
+fn ans1_file_path(base_path: &str, file: &str) -> String {
+	format!("{base_path}/{}.asn", file)
 }
 
 /// Returns the path to the Rust module file for the given file name.
-fn rs_file_path(file: &str) -> String {
-	format!("{BASE_PATH}/{}.rs", file)
+fn rs_file_path(base_path: &str, file: &str) -> String {
+	format!("{base_path}/{}.rs", file)
 }
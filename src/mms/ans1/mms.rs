@@ -213,9 +213,55 @@ pub mod asn1 {
 			Self::selectAccess(value)
 		}
 	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct ApplicationReference {
+		#[rasn(tag(explicit(context, 0)), identifier = "ap-title")]
+		pub ap_title: Option<ObjectIdentifier>,
+		#[rasn(tag(explicit(context, 1)), identifier = "ap-invocation-id")]
+		pub ap_invocation_id: Option<Integer>,
+		#[rasn(tag(explicit(context, 2)), identifier = "ae-qualifier")]
+		pub ae_qualifier: Option<Integer>,
+		#[rasn(tag(explicit(context, 3)), identifier = "ae-invocation-id")]
+		pub ae_invocation_id: Option<Integer>,
+	}
+	impl ApplicationReference {
+		pub fn new(
+			ap_title: Option<ObjectIdentifier>,
+			ap_invocation_id: Option<Integer>,
+			ae_qualifier: Option<Integer>,
+			ae_invocation_id: Option<Integer>,
+		) -> Self {
+			Self { ap_title, ap_invocation_id, ae_qualifier, ae_invocation_id }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "Cancel-ErrorPDU")]
+	pub struct CancelErrorPDU {
+		#[rasn(tag(context, 0), identifier = "originalInvokeID")]
+		pub original_invoke_id: Unsigned32,
+		#[rasn(tag(context, 1), identifier = "serviceError")]
+		pub service_error: ServiceError,
+	}
+	impl CancelErrorPDU {
+		pub fn new(original_invoke_id: Unsigned32, service_error: ServiceError) -> Self {
+			Self { original_invoke_id, service_error }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Cancel-RequestPDU")]
+	pub struct CancelRequestPDU(pub Unsigned32);
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Cancel-ResponsePDU")]
+	pub struct CancelResponsePDU(pub Unsigned32);
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Conclude-ErrorPDU")]
+	pub struct ConcludeErrorPDU(pub ServiceError);
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash, Copy)]
 	#[rasn(delegate, identifier = "Conclude-RequestPDU")]
 	pub struct ConcludeRequestPDU(pub ());
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash, Copy)]
+	#[rasn(delegate, identifier = "Conclude-ResponsePDU")]
+	pub struct ConcludeResponsePDU(pub ());
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(identifier = "Confirmed-ErrorPDU")]
 	pub struct ConfirmedErrorPDU {
@@ -276,6 +322,12 @@ pub mod asn1 {
 		getNamedVariableListAttributes(GetNamedVariableListAttributesRequest),
 		#[rasn(tag(context, 13))]
 		deleteNamedVariableList(DeleteNamedVariableListRequest),
+		#[rasn(tag(context, 65))]
+		readJournal(ReadJournalRequest),
+		#[rasn(tag(context, 67))]
+		initializeJournal(InitializeJournalRequest),
+		#[rasn(tag(context, 68))]
+		reportJournalStatus(ReportJournalStatusRequest),
 		#[rasn(tag(context, 72))]
 		fileOpen(FileOpenRequest),
 		#[rasn(tag(context, 73))]
@@ -322,6 +374,21 @@ pub mod asn1 {
 			Self::deleteNamedVariableList(value)
 		}
 	}
+	impl From<ReadJournalRequest> for ConfirmedServiceRequest {
+		fn from(value: ReadJournalRequest) -> Self {
+			Self::readJournal(value)
+		}
+	}
+	impl From<InitializeJournalRequest> for ConfirmedServiceRequest {
+		fn from(value: InitializeJournalRequest) -> Self {
+			Self::initializeJournal(value)
+		}
+	}
+	impl From<ReportJournalStatusRequest> for ConfirmedServiceRequest {
+		fn from(value: ReportJournalStatusRequest) -> Self {
+			Self::reportJournalStatus(value)
+		}
+	}
 	impl From<FileOpenRequest> for ConfirmedServiceRequest {
 		fn from(value: FileOpenRequest) -> Self {
 			Self::fileOpen(value)
@@ -364,6 +431,12 @@ pub mod asn1 {
 		getNamedVariableListAttributes(GetNamedVariableListAttributesResponse),
 		#[rasn(tag(context, 13))]
 		deleteNamedVariableList(DeleteNamedVariableListResponse),
+		#[rasn(tag(context, 65))]
+		readJournal(ReadJournalResponse),
+		#[rasn(tag(context, 67))]
+		initializeJournal(InitializeJournalResponse),
+		#[rasn(tag(context, 68))]
+		reportJournalStatus(ReportJournalStatusResponse),
 		#[rasn(tag(context, 72))]
 		fileOpen(FileOpenResponse),
 		#[rasn(tag(context, 73))]
@@ -410,6 +483,21 @@ pub mod asn1 {
 			Self::deleteNamedVariableList(value)
 		}
 	}
+	impl From<ReadJournalResponse> for ConfirmedServiceResponse {
+		fn from(value: ReadJournalResponse) -> Self {
+			Self::readJournal(value)
+		}
+	}
+	impl From<InitializeJournalResponse> for ConfirmedServiceResponse {
+		fn from(value: InitializeJournalResponse) -> Self {
+			Self::initializeJournal(value)
+		}
+	}
+	impl From<ReportJournalStatusResponse> for ConfirmedServiceResponse {
+		fn from(value: ReportJournalStatusResponse) -> Self {
+			Self::reportJournalStatus(value)
+		}
+	}
 	impl From<FileOpenResponse> for ConfirmedServiceResponse {
 		fn from(value: FileOpenResponse) -> Self {
 			Self::fileOpen(value)
@@ -573,6 +661,66 @@ pub mod asn1 {
 			Self { file_name, file_attributes }
 		}
 	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct EntryContentEntryFormDataEvent {
+		#[rasn(tag(context, 0), identifier = "eventConditionName")]
+		pub event_condition_name: ObjectName,
+		#[rasn(tag(context, 1), identifier = "currentState")]
+		pub current_state: Integer,
+	}
+	impl EntryContentEntryFormDataEvent {
+		pub fn new(event_condition_name: ObjectName, current_state: Integer) -> Self {
+			Self { event_condition_name, current_state }
+		}
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct EntryContentEntryFormData {
+		#[rasn(tag(context, 0))]
+		pub event: Option<EntryContentEntryFormDataEvent>,
+		#[rasn(tag(context, 1), identifier = "listOfVariables")]
+		pub list_of_variables: Option<SequenceOf<JournalVariable>>,
+	}
+	impl EntryContentEntryFormData {
+		pub fn new(
+			event: Option<EntryContentEntryFormDataEvent>,
+			list_of_variables: Option<SequenceOf<JournalVariable>>,
+		) -> Self {
+			Self { event, list_of_variables }
+		}
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice)]
+	pub enum EntryContentEntryForm {
+		#[rasn(tag(context, 2))]
+		data(EntryContentEntryFormData),
+		#[rasn(tag(explicit(context, 3)))]
+		annotation(MMSString),
+	}
+	impl From<EntryContentEntryFormData> for EntryContentEntryForm {
+		fn from(value: EntryContentEntryFormData) -> Self {
+			Self::data(value)
+		}
+	}
+	impl From<MMSString> for EntryContentEntryForm {
+		fn from(value: MMSString) -> Self {
+			Self::annotation(value)
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct EntryContent {
+		#[rasn(tag(context, 0), identifier = "occurrenceTime")]
+		pub occurrence_time: TimeOfDay,
+		#[rasn(identifier = "entryForm")]
+		pub entry_form: EntryContentEntryForm,
+	}
+	impl EntryContent {
+		pub fn new(occurrence_time: TimeOfDay, entry_form: EntryContentEntryForm) -> Self {
+			Self { occurrence_time, entry_form }
+		}
+	}
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	pub struct FileAttributes {
 		#[rasn(tag(context, 0), identifier = "sizeOfFile")]
@@ -808,6 +956,38 @@ pub mod asn1 {
 			Self { variable_access_specification, list_of_access_result }
 		}
 	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct InitializeJournalRequestLimitSpecification {
+		#[rasn(tag(context, 0), identifier = "limitingTime")]
+		pub limiting_time: TimeOfDay,
+		#[rasn(tag(context, 1), identifier = "limitingEntry")]
+		pub limiting_entry: Option<OctetString>,
+	}
+	impl InitializeJournalRequestLimitSpecification {
+		pub fn new(limiting_time: TimeOfDay, limiting_entry: Option<OctetString>) -> Self {
+			Self { limiting_time, limiting_entry }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "InitializeJournal-Request")]
+	pub struct InitializeJournalRequest {
+		#[rasn(tag(context, 0), identifier = "journalName")]
+		pub journal_name: ObjectName,
+		#[rasn(tag(context, 1), identifier = "limitSpecification")]
+		pub limit_specification: Option<InitializeJournalRequestLimitSpecification>,
+	}
+	impl InitializeJournalRequest {
+		pub fn new(
+			journal_name: ObjectName,
+			limit_specification: Option<InitializeJournalRequestLimitSpecification>,
+		) -> Self {
+			Self { journal_name, limit_specification }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "InitializeJournal-Response")]
+	pub struct InitializeJournalResponse(pub Unsigned32);
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate, identifier = "Initiate-ErrorPDU")]
 	pub struct InitiateErrorPDU(pub ServiceError);
@@ -921,6 +1101,39 @@ pub mod asn1 {
 	#[rasn(delegate, value("-128..=127"))]
 	pub struct Integer8(pub i8);
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct JournalEntry {
+		#[rasn(tag(context, 0), identifier = "entryIdentifier")]
+		pub entry_identifier: OctetString,
+		#[rasn(tag(explicit(context, 1)), identifier = "originatingApplication")]
+		pub originating_application: ApplicationReference,
+		#[rasn(tag(context, 2), identifier = "entryContent")]
+		pub entry_content: EntryContent,
+	}
+	impl JournalEntry {
+		pub fn new(
+			entry_identifier: OctetString,
+			originating_application: ApplicationReference,
+			entry_content: EntryContent,
+		) -> Self {
+			Self { entry_identifier, originating_application, entry_content }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct JournalVariable {
+		#[rasn(tag(context, 0), identifier = "variableTag")]
+		pub variable_tag: MMS255String,
+		#[rasn(tag(context, 1), identifier = "valueSpecification")]
+		pub value_specification: Data,
+	}
+	impl JournalVariable {
+		pub fn new(variable_tag: MMS255String, value_specification: Data) -> Self {
+			Self { variable_tag, value_specification }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, size("1..=255"))]
+	pub struct MMS255String(pub VisibleString);
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate)]
 	pub struct MMSString(pub VisibleString);
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
@@ -936,6 +1149,12 @@ pub mod asn1 {
 		unconfirmed_PDU(UnconfirmedPDU),
 		#[rasn(tag(context, 4))]
 		rejectPDU(RejectPDU),
+		#[rasn(tag(context, 5), identifier = "cancel-RequestPDU")]
+		cancel_RequestPDU(CancelRequestPDU),
+		#[rasn(tag(context, 6), identifier = "cancel-ResponsePDU")]
+		cancel_ResponsePDU(CancelResponsePDU),
+		#[rasn(tag(context, 7), identifier = "cancel-ErrorPDU")]
+		cancel_ErrorPDU(CancelErrorPDU),
 		#[rasn(tag(context, 8), identifier = "initiate-RequestPDU")]
 		initiate_RequestPDU(InitiateRequestPDU),
 		#[rasn(tag(context, 9), identifier = "initiate-ResponsePDU")]
@@ -944,6 +1163,10 @@ pub mod asn1 {
 		initiate_ErrorPDU(InitiateErrorPDU),
 		#[rasn(tag(context, 11), identifier = "conclude-RequestPDU")]
 		conclude_RequestPDU(ConcludeRequestPDU),
+		#[rasn(tag(context, 12), identifier = "conclude-ResponsePDU")]
+		conclude_ResponsePDU(ConcludeResponsePDU),
+		#[rasn(tag(context, 13), identifier = "conclude-ErrorPDU")]
+		conclude_ErrorPDU(ConcludeErrorPDU),
 	}
 	impl From<ConfirmedRequestPDU> for MMSpdu {
 		fn from(value: ConfirmedRequestPDU) -> Self {
@@ -970,6 +1193,21 @@ pub mod asn1 {
 			Self::rejectPDU(value)
 		}
 	}
+	impl From<CancelRequestPDU> for MMSpdu {
+		fn from(value: CancelRequestPDU) -> Self {
+			Self::cancel_RequestPDU(value)
+		}
+	}
+	impl From<CancelResponsePDU> for MMSpdu {
+		fn from(value: CancelResponsePDU) -> Self {
+			Self::cancel_ResponsePDU(value)
+		}
+	}
+	impl From<CancelErrorPDU> for MMSpdu {
+		fn from(value: CancelErrorPDU) -> Self {
+			Self::cancel_ErrorPDU(value)
+		}
+	}
 	impl From<InitiateRequestPDU> for MMSpdu {
 		fn from(value: InitiateRequestPDU) -> Self {
 			Self::initiate_RequestPDU(value)
@@ -990,6 +1228,16 @@ pub mod asn1 {
 			Self::conclude_RequestPDU(value)
 		}
 	}
+	impl From<ConcludeResponsePDU> for MMSpdu {
+		fn from(value: ConcludeResponsePDU) -> Self {
+			Self::conclude_ResponsePDU(value)
+		}
+	}
+	impl From<ConcludeErrorPDU> for MMSpdu {
+		fn from(value: ConcludeErrorPDU) -> Self {
+			Self::conclude_ErrorPDU(value)
+		}
+	}
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(choice)]
 	pub enum ObjectClass {
@@ -1074,6 +1322,108 @@ pub mod asn1 {
 	#[doc = " Inner type "]
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(choice)]
+	pub enum ReadJournalRequestRangeStartSpecification {
+		#[rasn(tag(context, 0))]
+		startingTime(TimeOfDay),
+		#[rasn(tag(context, 1))]
+		startingEntry(OctetString),
+	}
+	impl From<TimeOfDay> for ReadJournalRequestRangeStartSpecification {
+		fn from(value: TimeOfDay) -> Self {
+			Self::startingTime(value)
+		}
+	}
+	impl From<OctetString> for ReadJournalRequestRangeStartSpecification {
+		fn from(value: OctetString) -> Self {
+			Self::startingEntry(value)
+		}
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice)]
+	pub enum ReadJournalRequestRangeStopSpecification {
+		#[rasn(tag(context, 0))]
+		endingTime(TimeOfDay),
+		#[rasn(tag(context, 1))]
+		numberOfEntries(Integer32),
+	}
+	impl From<TimeOfDay> for ReadJournalRequestRangeStopSpecification {
+		fn from(value: TimeOfDay) -> Self {
+			Self::endingTime(value)
+		}
+	}
+	impl From<Integer32> for ReadJournalRequestRangeStopSpecification {
+		fn from(value: Integer32) -> Self {
+			Self::numberOfEntries(value)
+		}
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct ReadJournalRequestEntryToStartAfter {
+		#[rasn(tag(context, 0), identifier = "timeSpecification")]
+		pub time_specification: TimeOfDay,
+		#[rasn(tag(context, 1), identifier = "entrySpecification")]
+		pub entry_specification: OctetString,
+	}
+	impl ReadJournalRequestEntryToStartAfter {
+		pub fn new(time_specification: TimeOfDay, entry_specification: OctetString) -> Self {
+			Self { time_specification, entry_specification }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "ReadJournal-Request")]
+	pub struct ReadJournalRequest {
+		#[rasn(tag(context, 0), identifier = "journalName")]
+		pub journal_name: ObjectName,
+		#[rasn(tag(context, 1), identifier = "rangeStartSpecification")]
+		pub range_start_specification: Option<ReadJournalRequestRangeStartSpecification>,
+		#[rasn(tag(context, 2), identifier = "rangeStopSpecification")]
+		pub range_stop_specification: Option<ReadJournalRequestRangeStopSpecification>,
+		#[rasn(tag(context, 4), identifier = "listOfVariables")]
+		pub list_of_variables: Option<SequenceOf<VisibleString>>,
+		#[rasn(tag(context, 5), identifier = "entryToStartAfter")]
+		pub entry_to_start_after: Option<ReadJournalRequestEntryToStartAfter>,
+	}
+	impl ReadJournalRequest {
+		pub fn new(
+			journal_name: ObjectName,
+			range_start_specification: Option<ReadJournalRequestRangeStartSpecification>,
+			range_stop_specification: Option<ReadJournalRequestRangeStopSpecification>,
+			list_of_variables: Option<SequenceOf<VisibleString>>,
+			entry_to_start_after: Option<ReadJournalRequestEntryToStartAfter>,
+		) -> Self {
+			Self {
+				journal_name,
+				range_start_specification,
+				range_stop_specification,
+				list_of_variables,
+				entry_to_start_after,
+			}
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "ReadJournal-Response")]
+	pub struct ReadJournalResponse {
+		#[rasn(tag(context, 0), identifier = "listOfJournalEntry")]
+		pub list_of_journal_entry: SequenceOf<JournalEntry>,
+		#[rasn(
+			tag(context, 1),
+			default = "read_journal_response_more_follows_default",
+			identifier = "moreFollows"
+		)]
+		pub more_follows: bool,
+	}
+	impl ReadJournalResponse {
+		pub fn new(list_of_journal_entry: SequenceOf<JournalEntry>, more_follows: bool) -> Self {
+			Self { list_of_journal_entry, more_follows }
+		}
+	}
+	fn read_journal_response_more_follows_default() -> bool {
+		false
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice)]
 	pub enum RejectPDURejectReason {
 		#[rasn(tag(context, 1), identifier = "confirmed-requestPDU")]
 		confirmed_requestPDU(Integer),
@@ -1113,6 +1463,22 @@ pub mod asn1 {
 			Self { original_invoke_id, reject_reason }
 		}
 	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "ReportJournalStatus-Request")]
+	pub struct ReportJournalStatusRequest(pub ObjectName);
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "ReportJournalStatus-Response")]
+	pub struct ReportJournalStatusResponse {
+		#[rasn(tag(context, 0), identifier = "currentEntries")]
+		pub current_entries: Unsigned32,
+		#[rasn(tag(context, 1), identifier = "mmsDeletable")]
+		pub mms_deletable: bool,
+	}
+	impl ReportJournalStatusResponse {
+		pub fn new(current_entries: Unsigned32, mms_deletable: bool) -> Self {
+			Self { current_entries, mms_deletable }
+		}
+	}
 	#[doc = " Inner type "]
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(choice)]
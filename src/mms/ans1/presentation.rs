@@ -27,6 +27,70 @@ pub mod asn1 {
 
 	use rasn::prelude::*;
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "ARP-PPDU")]
+	pub struct ARPPPDU {
+		#[rasn(tag(context, 0), identifier = "provider-reason")]
+		pub provider_reason: Option<AbortReason>,
+		#[rasn(tag(context, 1), identifier = "event-identifier")]
+		pub event_identifier: Option<EventIdentifier>,
+	}
+	impl ARPPPDU {
+		pub fn new(
+			provider_reason: Option<AbortReason>,
+			event_identifier: Option<EventIdentifier>,
+		) -> Self {
+			Self { provider_reason, event_identifier }
+		}
+	}
+	#[doc = " Inner type "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct ARUPPDUNormalModeParameters {
+		#[rasn(tag(context, 0), identifier = "presentation-context-identifier-list")]
+		pub presentation_context_identifier_list: Option<PresentationContextIdentifierList>,
+		#[rasn(identifier = "user-data")]
+		pub user_data: Option<UserData>,
+	}
+	impl ARUPPDUNormalModeParameters {
+		pub fn new(
+			presentation_context_identifier_list: Option<PresentationContextIdentifierList>,
+			user_data: Option<UserData>,
+		) -> Self {
+			Self { presentation_context_identifier_list, user_data }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice, identifier = "ARU-PPDU")]
+	pub enum ARUPPDU {
+		#[rasn(tag(context, 0), identifier = "normal-mode-parameters")]
+		normal_mode_parameters(ARUPPDUNormalModeParameters),
+	}
+	impl From<ARUPPDUNormalModeParameters> for ARUPPDU {
+		fn from(value: ARUPPDUNormalModeParameters) -> Self {
+			Self::normal_mode_parameters(value)
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Abort-reason")]
+	pub struct AbortReason(pub Integer);
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice, identifier = "Abort-type")]
+	pub enum AbortType {
+		#[rasn(identifier = "aru-ppdu")]
+		aru_ppdu(ARUPPDU),
+		#[rasn(identifier = "arp-ppdu")]
+		arp_ppdu(ARPPPDU),
+	}
+	impl From<ARUPPDU> for AbortType {
+		fn from(value: ARUPPDU) -> Self {
+			Self::aru_ppdu(value)
+		}
+	}
+	impl From<ARPPPDU> for AbortType {
+		fn from(value: ARPPPDU) -> Self {
+			Self::arp_ppdu(value)
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate, identifier = "Abstract-syntax-name")]
 	pub struct AbstractSyntaxName(pub ObjectIdentifier);
 	#[doc = " Inner type "]
@@ -205,6 +269,9 @@ pub mod asn1 {
 			Self { abstract_syntax_name, transfer_syntax_name }
 		}
 	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Event-identifier")]
+	pub struct EventIdentifier(pub Integer);
 	#[doc = "  See 8.4.1."]
 	#[doc = "Fully-encoded-data ::= SEQUENCE SIZE (1, ..., 2..MAX) OF PDV-list"]
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
@@ -278,6 +345,28 @@ pub mod asn1 {
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate, identifier = "Presentation-context-identifier")]
 	pub struct PresentationContextIdentifier(pub Integer);
+	#[doc = " Anonymous SEQUENCE OF member "]
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(identifier = "SEQUENCE")]
+	pub struct AnonymousPresentationContextIdentifierList {
+		#[rasn(identifier = "presentation-context-identifier")]
+		pub presentation_context_identifier: PresentationContextIdentifier,
+		#[rasn(identifier = "transfer-syntax-name")]
+		pub transfer_syntax_name: TransferSyntaxName,
+	}
+	impl AnonymousPresentationContextIdentifierList {
+		pub fn new(
+			presentation_context_identifier: PresentationContextIdentifier,
+			transfer_syntax_name: TransferSyntaxName,
+		) -> Self {
+			Self { presentation_context_identifier, transfer_syntax_name }
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(delegate, identifier = "Presentation-context-identifier-list")]
+	pub struct PresentationContextIdentifierList(
+		pub SequenceOf<AnonymousPresentationContextIdentifierList>,
+	);
 	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
 	#[rasn(delegate, identifier = "Presentation-requirements")]
 	pub struct PresentationRequirements(pub BitString);
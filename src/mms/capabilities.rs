@@ -0,0 +1,342 @@
+//! The capabilities of an MMS server.
+//!
+//! The server announces the services and parameters it supports in the
+//! initiate response. They are decoded here so that the client can fail fast
+//! instead of sending requests the server does not support.
+
+use std::fmt;
+
+use rasn::types::BitString;
+
+use crate::mms::ans1::mms::asn1::ConfirmedServiceRequest;
+
+/// An MMS confirmed or unconfirmed service.
+/// The value of every service is its bit in the services supported bitstring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum MmsService {
+	Status = 0,
+	GetNameList = 1,
+	Identify = 2,
+	Rename = 3,
+	Read = 4,
+	Write = 5,
+	GetVariableAccessAttributes = 6,
+	DefineNamedVariable = 7,
+	DefineScatteredAccess = 8,
+	GetScatteredAccessAttributes = 9,
+	DeleteVariableAccess = 10,
+	DefineNamedVariableList = 11,
+	GetNamedVariableListAttributes = 12,
+	DeleteNamedVariableList = 13,
+	DefineNamedType = 14,
+	GetNamedTypeAttributes = 15,
+	DeleteNamedType = 16,
+	Input = 17,
+	Output = 18,
+	TakeControl = 19,
+	RelinquishControl = 20,
+	DefineSemaphore = 21,
+	DeleteSemaphore = 22,
+	ReportSemaphoreStatus = 23,
+	ReportPoolSemaphoreStatus = 24,
+	ReportSemaphoreEntryStatus = 25,
+	InitiateDownloadSequence = 26,
+	DownloadSegment = 27,
+	TerminateDownloadSequence = 28,
+	InitiateUploadSequence = 29,
+	UploadSegment = 30,
+	TerminateUploadSequence = 31,
+	RequestDomainDownload = 32,
+	RequestDomainUpload = 33,
+	LoadDomainContent = 34,
+	StoreDomainContent = 35,
+	DeleteDomain = 36,
+	GetDomainAttributes = 37,
+	CreateProgramInvocation = 38,
+	DeleteProgramInvocation = 39,
+	Start = 40,
+	Stop = 41,
+	Resume = 42,
+	Reset = 43,
+	Kill = 44,
+	GetProgramInvocationAttributes = 45,
+	ObtainFile = 46,
+	DefineEventCondition = 47,
+	DeleteEventCondition = 48,
+	GetEventConditionAttributes = 49,
+	ReportEventConditionStatus = 50,
+	AlterEventConditionMonitoring = 51,
+	TriggerEvent = 52,
+	DefineEventAction = 53,
+	DeleteEventAction = 54,
+	GetEventActionAttributes = 55,
+	ReportEventActionStatus = 56,
+	DefineEventEnrollment = 57,
+	DeleteEventEnrollment = 58,
+	AlterEventEnrollment = 59,
+	ReportEventEnrollmentStatus = 60,
+	GetEventEnrollmentAttributes = 61,
+	AcknowledgeEventNotification = 62,
+	GetAlarmSummary = 63,
+	GetAlarmEnrollmentSummary = 64,
+	ReadJournal = 65,
+	WriteJournal = 66,
+	InitializeJournal = 67,
+	ReportJournalStatus = 68,
+	CreateJournal = 69,
+	DeleteJournal = 70,
+	GetCapabilityList = 71,
+	FileOpen = 72,
+	FileRead = 73,
+	FileClose = 74,
+	FileRename = 75,
+	FileDelete = 76,
+	FileDirectory = 77,
+	UnsolicitedStatus = 78,
+	InformationReport = 79,
+	EventNotification = 80,
+	AttachToEventCondition = 81,
+	AttachToSemaphore = 82,
+	Conclude = 83,
+	Cancel = 84,
+}
+
+impl MmsService {
+	/// All the services, ordered by their bit in the services supported
+	/// bitstring.
+	pub const ALL: [Self; 85] = [
+		Self::Status,
+		Self::GetNameList,
+		Self::Identify,
+		Self::Rename,
+		Self::Read,
+		Self::Write,
+		Self::GetVariableAccessAttributes,
+		Self::DefineNamedVariable,
+		Self::DefineScatteredAccess,
+		Self::GetScatteredAccessAttributes,
+		Self::DeleteVariableAccess,
+		Self::DefineNamedVariableList,
+		Self::GetNamedVariableListAttributes,
+		Self::DeleteNamedVariableList,
+		Self::DefineNamedType,
+		Self::GetNamedTypeAttributes,
+		Self::DeleteNamedType,
+		Self::Input,
+		Self::Output,
+		Self::TakeControl,
+		Self::RelinquishControl,
+		Self::DefineSemaphore,
+		Self::DeleteSemaphore,
+		Self::ReportSemaphoreStatus,
+		Self::ReportPoolSemaphoreStatus,
+		Self::ReportSemaphoreEntryStatus,
+		Self::InitiateDownloadSequence,
+		Self::DownloadSegment,
+		Self::TerminateDownloadSequence,
+		Self::InitiateUploadSequence,
+		Self::UploadSegment,
+		Self::TerminateUploadSequence,
+		Self::RequestDomainDownload,
+		Self::RequestDomainUpload,
+		Self::LoadDomainContent,
+		Self::StoreDomainContent,
+		Self::DeleteDomain,
+		Self::GetDomainAttributes,
+		Self::CreateProgramInvocation,
+		Self::DeleteProgramInvocation,
+		Self::Start,
+		Self::Stop,
+		Self::Resume,
+		Self::Reset,
+		Self::Kill,
+		Self::GetProgramInvocationAttributes,
+		Self::ObtainFile,
+		Self::DefineEventCondition,
+		Self::DeleteEventCondition,
+		Self::GetEventConditionAttributes,
+		Self::ReportEventConditionStatus,
+		Self::AlterEventConditionMonitoring,
+		Self::TriggerEvent,
+		Self::DefineEventAction,
+		Self::DeleteEventAction,
+		Self::GetEventActionAttributes,
+		Self::ReportEventActionStatus,
+		Self::DefineEventEnrollment,
+		Self::DeleteEventEnrollment,
+		Self::AlterEventEnrollment,
+		Self::ReportEventEnrollmentStatus,
+		Self::GetEventEnrollmentAttributes,
+		Self::AcknowledgeEventNotification,
+		Self::GetAlarmSummary,
+		Self::GetAlarmEnrollmentSummary,
+		Self::ReadJournal,
+		Self::WriteJournal,
+		Self::InitializeJournal,
+		Self::ReportJournalStatus,
+		Self::CreateJournal,
+		Self::DeleteJournal,
+		Self::GetCapabilityList,
+		Self::FileOpen,
+		Self::FileRead,
+		Self::FileClose,
+		Self::FileRename,
+		Self::FileDelete,
+		Self::FileDirectory,
+		Self::UnsolicitedStatus,
+		Self::InformationReport,
+		Self::EventNotification,
+		Self::AttachToEventCondition,
+		Self::AttachToSemaphore,
+		Self::Conclude,
+		Self::Cancel,
+	];
+}
+
+impl From<&ConfirmedServiceRequest> for MmsService {
+	fn from(value: &ConfirmedServiceRequest) -> Self {
+		match value {
+			ConfirmedServiceRequest::getNameList(_) => Self::GetNameList,
+			ConfirmedServiceRequest::read(_) => Self::Read,
+			ConfirmedServiceRequest::write(_) => Self::Write,
+			ConfirmedServiceRequest::getVariableAccessAttributes(_) => {
+				Self::GetVariableAccessAttributes
+			}
+			ConfirmedServiceRequest::defineNamedVariableList(_) => Self::DefineNamedVariableList,
+			ConfirmedServiceRequest::getNamedVariableListAttributes(_) => {
+				Self::GetNamedVariableListAttributes
+			}
+			ConfirmedServiceRequest::deleteNamedVariableList(_) => Self::DeleteNamedVariableList,
+			ConfirmedServiceRequest::readJournal(_) => Self::ReadJournal,
+			ConfirmedServiceRequest::initializeJournal(_) => Self::InitializeJournal,
+			ConfirmedServiceRequest::reportJournalStatus(_) => Self::ReportJournalStatus,
+			ConfirmedServiceRequest::fileOpen(_) => Self::FileOpen,
+			ConfirmedServiceRequest::fileRead(_) => Self::FileRead,
+			ConfirmedServiceRequest::fileClose(_) => Self::FileClose,
+			ConfirmedServiceRequest::fileDelete(_) => Self::FileDelete,
+			ConfirmedServiceRequest::fileDirectory(_) => Self::FileDirectory,
+		}
+	}
+}
+
+impl fmt::Display for MmsService {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+
+/// An MMS parameter conformance building block.
+/// The value of every parameter is its bit in the parameter support bitstring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MmsParameter {
+	/// Arrays.
+	Str1 = 0,
+	/// Structures.
+	Str2 = 1,
+	/// Named variables.
+	Vnam = 2,
+	/// Alternate access.
+	Valt = 3,
+	/// Unnamed variables.
+	Vadr = 4,
+	/// Scattered access.
+	Vsca = 5,
+	/// Third party operations.
+	Tpy = 6,
+	/// Named variable lists.
+	Vlis = 7,
+	/// Real numbers.
+	Real = 8,
+	/// Condition event information.
+	Cei = 10,
+}
+
+impl MmsParameter {
+	/// All the parameters, ordered by their bit in the parameter support
+	/// bitstring.
+	pub const ALL: [Self; 10] = [
+		Self::Str1,
+		Self::Str2,
+		Self::Vnam,
+		Self::Valt,
+		Self::Vadr,
+		Self::Vsca,
+		Self::Tpy,
+		Self::Vlis,
+		Self::Real,
+		Self::Cei,
+	];
+}
+
+/// The services and parameters supported by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerCapabilities {
+	/// The services supported by the server.
+	services: BitString,
+	/// The parameters supported by the server.
+	parameters: BitString,
+}
+
+impl ServerCapabilities {
+	/// Create the capabilities from the bitstrings of the initiate response.
+	#[must_use]
+	pub const fn new(services: BitString, parameters: BitString) -> Self {
+		Self { services, parameters }
+	}
+
+	/// Check if the server supports a service.
+	#[must_use]
+	pub fn supports(&self, service: MmsService) -> bool {
+		self.services.get(service as usize).is_some_and(|bit| *bit)
+	}
+
+	/// Check if the server supports a parameter.
+	#[must_use]
+	pub fn supports_parameter(&self, parameter: MmsParameter) -> bool {
+		self.parameters.get(parameter as usize).is_some_and(|bit| *bit)
+	}
+
+	/// Get the services supported by the server.
+	pub fn services(&self) -> impl Iterator<Item = MmsService> + '_ {
+		MmsService::ALL.into_iter().filter(|service| self.supports(*service))
+	}
+
+	/// Get the parameters supported by the server.
+	pub fn parameters(&self) -> impl Iterator<Item = MmsParameter> + '_ {
+		MmsParameter::ALL.into_iter().filter(|parameter| self.supports_parameter(*parameter))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_server_capabilities() {
+		let mut services = BitString::from_slice(&[
+			0xee, 0x1c, 0x00, 0x00, 0x04, 0x08, 0x00, 0x00, 0x79, 0xef, 0x18,
+		]);
+		services.truncate(85);
+		let mut parameters = BitString::from_slice(&[0xf1, 0x00]);
+		parameters.truncate(11);
+		let capabilities = ServerCapabilities::new(services, parameters);
+
+		assert!(capabilities.supports(MmsService::Read));
+		assert!(capabilities.supports(MmsService::DefineNamedVariableList));
+		assert!(capabilities.supports(MmsService::FileOpen));
+		assert!(!capabilities.supports(MmsService::Rename));
+		assert!(capabilities.supports(MmsService::Cancel));
+		assert!(!capabilities.supports(MmsService::ObtainFile));
+		assert_eq!(
+			capabilities.parameters().collect::<Vec<_>>(),
+			vec![
+				MmsParameter::Str1,
+				MmsParameter::Str2,
+				MmsParameter::Vnam,
+				MmsParameter::Valt,
+				MmsParameter::Vlis
+			]
+		);
+	}
+}
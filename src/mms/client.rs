@@ -1,21 +1,28 @@
 //! MMS client implementation.
 
-use std::collections::HashMap;
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::Arc,
+	time::Duration,
+};
 
 use rasn::{ber, prelude::*};
 use snafu::{ResultExt as _, Snafu};
 use tokio::{
 	select,
-	sync::{mpsc, oneshot},
+	sync::{broadcast, mpsc, oneshot, watch},
+	time::{self, Instant},
 };
 use tracing::instrument;
 
 use crate::{
-	iec61850::report::Report,
+	iec61850::{control::ControlEvent, report::Report},
 	mms::{
-		ClientConfig, ReadHalfConnection, ReportCallback, SpanTraceWrapper, WriteHalfConnection,
-		acse::{Acse, AcseError, AcseReadHalf, AcseWriteHalf},
+		ClientConfig, ReportCallback, SpanTraceWrapper, WriteHalfConnection,
+		acse::{Abort, Acse, AcseError, AcseEvent, AcseReadHalf, AcseWriteHalf},
 		ans1::mms::asn1::{self, *},
+		capabilities::{MmsService, ServerCapabilities},
+		error::{DataAccessError, RejectReason, ServiceError},
 	},
 };
 
@@ -28,17 +35,69 @@ const SERVICE_SUPPORT_OPTIONS: [u8; 11] =
 	[0xee, 0x1c, 0x00, 0x00, 0x04, 0x08, 0x00, 0x00, 0x79, 0xef, 0x18];
 /// The parameter support options.
 const PARAMETER_SUPPORT_OPTIONS: [u8; 2] = [0xf1, 0x00];
+/// The capacity of the control event channel.
+const CONTROL_EVENT_CAPACITY: usize = 64;
+
+/// The result of a confirmed service request.
+type ServiceResult = Result<ConfirmedServiceResponse, MmsClientError>;
+/// A confirmed service request, its timeout and the sender for its result.
+type RequestMessage = (ConfirmedServiceRequest, Option<Duration>, oneshot::Sender<ServiceResult>);
+
+/// The state of the connection to the server.
+#[derive(Debug, Clone)]
+pub enum ConnectionState {
+	/// The association is established.
+	Connected,
+	/// The association is established but in trouble: the server rejected a
+	/// PDU without telling which request it was. Back to
+	/// [`ConnectionState::Connected`] on the next response of the server.
+	Troubled(RejectReason),
+	/// The association is being released.
+	Closing,
+	/// The association was closed.
+	Closed,
+	/// The association was lost.
+	Aborted(Arc<MmsClientError>),
+}
+
+impl ConnectionState {
+	/// Check if the association is established.
+	#[must_use]
+	pub const fn is_connected(&self) -> bool {
+		matches!(self, Self::Connected | Self::Troubled(_))
+	}
+}
 
 /// The MMS client.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MmsClient {
-	// TODO: Do we need to store these values?
-	// max_serv_outstanding_calling: i16,
-	// max_serv_outstanding_called: i16,
-	// data_structure_nesting_level: i8,
-	// max_pdu_size: i32,
 	/// The sender for the confirmed service requests.
-	tx: mpsc::Sender<(ConfirmedServiceRequest, oneshot::Sender<ConfirmedServiceResponse>)>,
+	tx: mpsc::Sender<RequestMessage>,
+	/// The sender for the commands to the connection handler.
+	commands: mpsc::Sender<Command>,
+	/// How long to wait for the response of a request.
+	request_timeout: Option<Duration>,
+	/// The state of the connection.
+	state: watch::Receiver<ConnectionState>,
+	/// The parameters negotiated with the server.
+	parameters: NegotiatedParameters,
+	/// The services and parameters supported by the server.
+	capabilities: Arc<ServerCapabilities>,
+	/// The sender for the control events.
+	control_events: broadcast::WeakSender<ControlEvent>,
+}
+
+/// The parameters negotiated with the server during the MMS initiate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedParameters {
+	/// The maximum size of a PDU accepted by the server.
+	pub max_pdu_size: i32,
+	/// The maximum number of requests the client can have outstanding.
+	pub max_serv_outstanding_calling: i16,
+	/// The maximum number of requests the server can have outstanding.
+	pub max_serv_outstanding_called: i16,
+	/// The maximum nesting level of the data structures.
+	pub data_structure_nesting_level: i8,
 }
 
 impl MmsClient {
@@ -70,8 +129,12 @@ impl MmsClient {
 		let response = acse.connect(data).await?;
 		let response: MMSpdu = ber::decode(&response).context(DecodeResponse)?;
 
-		let MMSpdu::initiate_ResponsePDU(response) = response else {
-			return UnexpectedServiceResponse.fail();
+		let response = match response {
+			MMSpdu::initiate_ResponsePDU(response) => response,
+			MMSpdu::initiate_ErrorPDU(error) => {
+				return Service { error: ServiceError::from(error.0) }.fail();
+			}
+			_ => return UnexpectedServiceResponse.fail(),
 		};
 
 		if response.init_response_detail.negotiated_version_number != Integer16(VERSION_NUMBER) {
@@ -94,43 +157,148 @@ impl MmsClient {
 			return DataStructureNestingLevelExceeded.fail();
 		}
 
-		// TODO: Check if the services supported by the server are supported by the
-		// client
+		let capabilities = Arc::new(ServerCapabilities::new(
+			response.init_response_detail.services_supported_called.0,
+			response.init_response_detail.negotiated_parameter_cbb.0,
+		));
+		tracing::debug!(
+			"Services supported by the server: {:?}",
+			capabilities.services().collect::<Vec<_>>()
+		);
 
-		// max_serv_outstanding_called =
-		// response.negotiated_max_serv_outstanding_called.0;
-		// max_serv_outstanding_calling =
-		// response.negotiated_max_serv_outstanding_calling.0; if let Some(level) =
-		// response.negotiated_data_structure_nesting_level {
-		// 	data_structure_nesting_level = level.0;
-		// }
-		// if let Some(size) = response.local_detail_called {
-		// 	max_pdu_size = size.0;
-		// }
+		let parameters = NegotiatedParameters {
+			max_pdu_size: response.local_detail_called.map_or(max_pdu_size, |size| size.0),
+			max_serv_outstanding_calling: response.negotiated_max_serv_outstanding_calling.0,
+			max_serv_outstanding_called: response.negotiated_max_serv_outstanding_called.0,
+			data_structure_nesting_level: response
+				.negotiated_data_structure_nesting_level
+				.map_or(data_structure_nesting_level, |level| level.0),
+		};
+		tracing::debug!("Negotiated parameters: {:?}", parameters);
 
-		let (read_half, write_half) = acse.split();
 		let (tx, rx) = mpsc::channel(100);
-		let handler = ConnectionHandler::new(read_half, write_half, rx, report_callback);
+		let (commands, commands_rx) = mpsc::channel(1);
+		let (state_tx, state) = watch::channel(ConnectionState::Connected);
+		let handler = ConnectionHandler::new(
+			acse,
+			rx,
+			commands_rx,
+			report_callback,
+			config.connection.cancel_on_timeout && capabilities.supports(MmsService::Cancel),
+			parameters,
+			state_tx,
+		);
+		let control_events = handler.control_events.downgrade();
 		tokio::spawn(handler.handle_connection());
 
 		Ok(Self {
 			tx,
-			// max_serv_outstanding_calling,
-			// max_serv_outstanding_called,
-			// data_structure_nesting_level,
-			// max_pdu_size,
+			commands,
+			request_timeout: config.connection.request_timeout_ms.map(Duration::from_millis),
+			state,
+			parameters,
+			capabilities,
+			control_events,
 		})
 	}
 
+	/// Get a client that shares this connection but uses a different request
+	/// timeout. `None` waits forever.
+	#[must_use]
+	pub fn with_request_timeout(&self, timeout: Option<Duration>) -> Self {
+		Self {
+			tx: self.tx.clone(),
+			commands: self.commands.clone(),
+			request_timeout: timeout,
+			state: self.state.clone(),
+			parameters: self.parameters,
+			capabilities: self.capabilities.clone(),
+			control_events: self.control_events.clone(),
+		}
+	}
+
+	/// Abort the association.
+	/// Pending requests fail and the connection is closed without waiting for
+	/// the server.
+	#[instrument(skip(self))]
+	pub async fn abort(&self) -> Result<(), MmsClientError> {
+		let (sender, rx) = oneshot::channel();
+		self.commands.send(Command::Abort { sender }).await.map_err(|_| ConnectionLost.build())?;
+		rx.await.context(ReceiveResponse)?
+	}
+
+	/// Gracefully release the association.
+	/// Concludes the MMS context, releases the ACSE association and closes the
+	/// connection. Fails if the server refuses to conclude.
+	#[instrument(skip(self))]
+	pub async fn conclude(&self) -> Result<(), MmsClientError> {
+		let (sender, rx) = oneshot::channel();
+		self.commands
+			.send(Command::Conclude { timeout: self.request_timeout, sender })
+			.await
+			.map_err(|_| ConnectionLost.build())?;
+		rx.await.context(ReceiveResponse)?
+	}
+
+	/// Get a receiver for the connection state.
+	/// The receiver is notified every time the state changes.
+	#[must_use]
+	pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+		self.state.clone()
+	}
+
+	/// Check if the association is established.
+	#[must_use]
+	pub fn is_connected(&self) -> bool {
+		self.state.borrow().is_connected()
+	}
+
+	/// Get the request timeout.
+	#[must_use]
+	pub const fn request_timeout(&self) -> Option<Duration> {
+		self.request_timeout
+	}
+
+	/// Get the parameters negotiated with the server.
+	#[must_use]
+	pub const fn negotiated_parameters(&self) -> NegotiatedParameters {
+		self.parameters
+	}
+
+	/// Get the services and parameters supported by the server.
+	#[must_use]
+	pub fn server_capabilities(&self) -> &ServerCapabilities {
+		&self.capabilities
+	}
+
+	/// Subscribe to the `LastApplError` and `CommandTermination` reports of
+	/// the control model.
+	/// Only the events received after subscribing are delivered.
+	pub fn subscribe_control_events(
+		&self,
+	) -> Result<broadcast::Receiver<ControlEvent>, MmsClientError> {
+		self.control_events
+			.upgrade()
+			.map(|sender| sender.subscribe())
+			.ok_or_else(|| ConnectionLost.build())
+	}
+
 	/// Send a confirmed service request.
 	#[instrument(skip(self))]
 	async fn send_request(
 		&self,
 		request: ConfirmedServiceRequest,
 	) -> Result<ConfirmedServiceResponse, MmsClientError> {
+		let service = MmsService::from(&request);
+		if !self.capabilities.supports(service) {
+			return ServiceNotSupported { service }.fail();
+		}
 		let (tx, rx) = oneshot::channel();
-		self.tx.send((request, tx)).await.context(SendRequest)?;
-		rx.await.context(ReceiveResponse)
+		self.tx
+			.send((request, self.request_timeout, tx))
+			.await
+			.map_err(|_| ConnectionLost.build())?;
+		rx.await.context(ReceiveResponse)?
 	}
 
 	/// Get the name list.
@@ -166,12 +334,29 @@ impl MmsClient {
 	}
 
 	/// Read data from the MMS server.
+	/// Fails on the first variable that could not be read. Use
+	/// [`Self::read_with_results`] to get the result of every variable.
 	#[instrument(skip(self))]
 	pub async fn read(
 		&self,
 		variable_access_specification: VariableAccessSpecification,
 		specification_with_result: bool,
 	) -> Result<Vec<Data>, MmsClientError> {
+		self.read_with_results(variable_access_specification, specification_with_result)
+			.await?
+			.into_iter()
+			.map(|result| result.map_err(|error| DataAccess { error }.build()))
+			.collect::<Result<Vec<Data>, MmsClientError>>()
+	}
+
+	/// Read data from the MMS server.
+	/// Returns the result of every variable, in the order they were requested.
+	#[instrument(skip(self))]
+	pub async fn read_with_results(
+		&self,
+		variable_access_specification: VariableAccessSpecification,
+		specification_with_result: bool,
+	) -> Result<Vec<Result<Data, DataAccessError>>, MmsClientError> {
 		let request = ConfirmedServiceRequest::read(ReadRequest::new(
 			specification_with_result,
 			variable_access_specification,
@@ -181,23 +366,24 @@ impl MmsClient {
 		let ConfirmedServiceResponse::read(response) = response else {
 			return UnexpectedServiceResponse.fail();
 		};
-		response
+		Ok(response
 			.list_of_access_result
 			.into_iter()
 			.map(|result| match result {
 				AccessResult::success(data) => Ok(data),
-				AccessResult::failure(error) => DataAccess { error: error.0 }.fail(),
+				AccessResult::failure(error) => Err(error.into()),
 			})
-			.collect::<Result<Vec<Data>, MmsClientError>>()
+			.collect())
 	}
 
 	/// Write data to the MMS server.
+	/// Returns the result of every variable, in the order they were written.
 	#[instrument(skip(self))]
 	pub async fn write(
 		&self,
 		variable_access_specification: VariableAccessSpecification,
 		list_of_data: Vec<Data>,
-	) -> Result<(), MmsClientError> {
+	) -> Result<Vec<Result<(), DataAccessError>>, MmsClientError> {
 		let request = ConfirmedServiceRequest::write(WriteRequest::new(
 			variable_access_specification,
 			list_of_data,
@@ -207,16 +393,14 @@ impl MmsClient {
 			return UnexpectedServiceResponse.fail();
 		};
 
-		response
+		Ok(response
 			.0
 			.into_iter()
-			.find_map(|result| match result {
-				AnonymousWriteResponse::success(()) => None,
-				AnonymousWriteResponse::failure(error) => {
-					Some(DataAccess { error: error.0 }.fail())
-				}
+			.map(|result| match result {
+				AnonymousWriteResponse::success(()) => Ok(()),
+				AnonymousWriteResponse::failure(error) => Err(error.into()),
 			})
-			.unwrap_or(Ok(()))
+			.collect())
 	}
 
 	/// Get the variable access attributes.
@@ -295,6 +479,64 @@ impl MmsClient {
 		Ok(response)
 	}
 
+	/// Read entries from a journal.
+	#[instrument(skip(self))]
+	pub async fn read_journal(
+		&self,
+		journal_name: ObjectName,
+		range_start_specification: Option<ReadJournalRequestRangeStartSpecification>,
+		range_stop_specification: Option<ReadJournalRequestRangeStopSpecification>,
+		entry_to_start_after: Option<ReadJournalRequestEntryToStartAfter>,
+	) -> Result<ReadJournalResponse, MmsClientError> {
+		let request = ConfirmedServiceRequest::readJournal(ReadJournalRequest::new(
+			journal_name,
+			range_start_specification,
+			range_stop_specification,
+			None,
+			entry_to_start_after,
+		));
+		let response = self.send_request(request).await?;
+		let ConfirmedServiceResponse::readJournal(response) = response else {
+			return UnexpectedServiceResponse.fail();
+		};
+		Ok(response)
+	}
+
+	/// Delete the entries of a journal.
+	/// Without a limit, every entry is deleted. Returns the number of deleted
+	/// entries.
+	#[instrument(skip(self))]
+	pub async fn initialize_journal(
+		&self,
+		journal_name: ObjectName,
+		limit_specification: Option<InitializeJournalRequestLimitSpecification>,
+	) -> Result<u32, MmsClientError> {
+		let request = ConfirmedServiceRequest::initializeJournal(InitializeJournalRequest::new(
+			journal_name,
+			limit_specification,
+		));
+		let response = self.send_request(request).await?;
+		let ConfirmedServiceResponse::initializeJournal(response) = response else {
+			return UnexpectedServiceResponse.fail();
+		};
+		Ok(response.0.0)
+	}
+
+	/// Get the status of a journal.
+	#[instrument(skip(self))]
+	pub async fn report_journal_status(
+		&self,
+		journal_name: ObjectName,
+	) -> Result<ReportJournalStatusResponse, MmsClientError> {
+		let request =
+			ConfirmedServiceRequest::reportJournalStatus(ReportJournalStatusRequest(journal_name));
+		let response = self.send_request(request).await?;
+		let ConfirmedServiceResponse::reportJournalStatus(response) = response else {
+			return UnexpectedServiceResponse.fail();
+		};
+		Ok(response)
+	}
+
 	/// Open a file.
 	#[instrument(skip(self))]
 	pub async fn file_open(
@@ -412,6 +654,48 @@ impl MmsClient {
 	}
 }
 
+/// A command sent to the connection handler.
+enum Command {
+	/// Release the association.
+	Conclude {
+		/// How long to wait for the release to complete.
+		timeout: Option<Duration>,
+		/// The sender for the result.
+		sender: oneshot::Sender<Result<(), MmsClientError>>,
+	},
+	/// Abort the association.
+	Abort {
+		/// The sender for the result.
+		sender: oneshot::Sender<Result<(), MmsClientError>>,
+	},
+}
+
+/// A request waiting for its response.
+struct PendingRequest {
+	/// The sender for the result.
+	sender: oneshot::Sender<ServiceResult>,
+	/// When the request times out.
+	deadline: Option<Instant>,
+}
+
+/// A request waiting for a free slot to be sent.
+struct QueuedRequest {
+	/// The request.
+	request: ConfirmedServiceRequest,
+	/// The sender for the result.
+	sender: oneshot::Sender<ServiceResult>,
+	/// When the request times out.
+	deadline: Option<Instant>,
+}
+
+/// A release of the association waiting to complete.
+struct PendingRelease {
+	/// The sender for the result.
+	sender: oneshot::Sender<Result<(), MmsClientError>>,
+	/// When the release times out.
+	deadline: Option<Instant>,
+}
+
 /// The handler for the MMS connection.
 struct ConnectionHandler {
 	/// The read half.
@@ -419,130 +703,456 @@ struct ConnectionHandler {
 	/// The write half.
 	write_half: AcseWriteHalf,
 	/// The receiver for the confirmed service requests.
-	rx: mpsc::Receiver<(ConfirmedServiceRequest, oneshot::Sender<ConfirmedServiceResponse>)>,
-	/// The map of the response senders.
-	response_map: HashMap<u32, oneshot::Sender<ConfirmedServiceResponse>>,
+	rx: mpsc::Receiver<RequestMessage>,
+	/// The receiver for the commands.
+	commands: mpsc::Receiver<Command>,
+	/// The map of the pending requests.
+	response_map: HashMap<u32, PendingRequest>,
+	/// The requests waiting for a free slot, in arrival order.
+	queue: VecDeque<QueuedRequest>,
+	/// The invoke ID of the next request.
+	invoke_id: u32,
+	/// The release in progress, if any.
+	release: Option<PendingRelease>,
 	/// The report callback.
 	report_callback: Box<dyn ReportCallback + Send + Sync>,
+	/// The sender for the control events.
+	control_events: broadcast::Sender<ControlEvent>,
+	/// Send a cancel request when a request times out.
+	cancel_on_timeout: bool,
+	/// The parameters negotiated with the server.
+	parameters: NegotiatedParameters,
+	/// The sender for the connection state.
+	state: watch::Sender<ConnectionState>,
 }
 
 impl ConnectionHandler {
 	/// Create a new connection handler.
 	#[must_use]
 	pub fn new(
-		read_half: AcseReadHalf,
-		write_half: AcseWriteHalf,
-		rx: mpsc::Receiver<(ConfirmedServiceRequest, oneshot::Sender<ConfirmedServiceResponse>)>,
+		acse: Acse,
+		rx: mpsc::Receiver<RequestMessage>,
+		commands: mpsc::Receiver<Command>,
 		report_callback: Box<dyn ReportCallback + Send + Sync>,
+		cancel_on_timeout: bool,
+		parameters: NegotiatedParameters,
+		state: watch::Sender<ConnectionState>,
 	) -> Self {
-		Self { read_half, write_half, rx, response_map: HashMap::new(), report_callback }
+		let (read_half, write_half) = acse.split();
+		Self {
+			read_half,
+			write_half,
+			rx,
+			commands,
+			response_map: HashMap::new(),
+			queue: VecDeque::new(),
+			invoke_id: 0,
+			release: None,
+			report_callback,
+			control_events: broadcast::channel(CONTROL_EVENT_CAPACITY).0,
+			cancel_on_timeout,
+			parameters,
+			state,
+		}
 	}
 
 	/// Handle the MMS connection.
 	/// This is the main loop for the MMS connection.
 	#[instrument(skip(self))]
 	async fn handle_connection(mut self) {
-		let mut invoke_id = 0;
 		loop {
-			select! {
-				data = self.read_half.receive_data() => {
-					let data = match data {
-						Ok(data) => data,
-						Err(e) => {
-							tracing::error!("Error receiving data: {:?}", e);
-							// TODO: Handle error better
-							break;
-						}
-					};
-
-					let response: MMSpdu = match ber::decode(&data).context(DecodeResponse) {
-						Ok(response) => response,
-						Err(e) => {
-							tracing::error!("Error decoding response: {:?}", e);
-							// TODO: Handle error better
-							continue;
-						}
-					};
-					match response {
-						MMSpdu::confirmed_ResponsePDU(response) => {
-							self.handle_confirmed_response(response).await;
-						},
-						MMSpdu::confirmed_ErrorPDU(response) => {
-							self.handle_confirmed_error(response).await;
-						}
-						MMSpdu::unconfirmed_PDU(response) => {
-							match response.service {
-								UnconfirmedService::informationReport(report) => {
-									let report = match Report::try_from(report){
-										Ok(report) => report,
-										Err(e) => {
-											tracing::error!("Error decoding report: {:?}", e);
-											continue;
-										}
-									};
-									// TODO: Should we spawn a task here?
-									self.report_callback.on_report(report).await;
-								}
-							}
-						}
-						MMSpdu::rejectPDU(response) => {
-							self.handle_rejected_pdu(response).await;
-						}
-						MMSpdu::initiate_ResponsePDU(response) => {
-							tracing::info!("Initiate Response PDU: {:?}", response);
-						}
-						MMSpdu::initiate_ErrorPDU(response) => {
-							tracing::info!("Initiate Error PDU: {:?}", response);
-						}
-						MMSpdu::conclude_RequestPDU(response) => {
-							tracing::info!("Conclude Request PDU: {:?}", response);
-						}
-						_ => {
-							tracing::error!("Unexpected service response. Response: {:?}", response);
-							continue;
-						}
-					}
-				},
+			let next_deadline = self.next_deadline();
+			let running = select! {
+				event = self.read_half.receive_event() => self.handle_event(event).await,
 				request = self.rx.recv() => {
-					match request {
-						Some((request, sender)) => {
-							let data = match prepare_request(invoke_id, request) {
-								Ok(data) => data,
-								Err(e) => {
-									tracing::error!("Error preparing request: {:?}", e);
-									// TODO: Handle error better
-									continue;
-								}
-							};
-							if let Err(e) = self.write_half.send_data(data).await {
-								tracing::error!("Error sending data: {:?}", e);
-								// TODO: Handle error better
-								continue;
-							}
-							self.response_map.insert(invoke_id, sender);
-							invoke_id += 1;
-						}
-						None => {
-							tracing::info!("No more requests to send");
-							break;
-						}
+					if let Some(request) = request {
+						self.handle_request(request).await;
+						true
+					} else {
+						tracing::info!("Client dropped, closing the connection");
+						self.close(ConnectionState::Closed);
+						false
 					}
 				},
+				Some(command) = self.commands.recv() => self.handle_command(command).await,
+				() = time::sleep_until(next_deadline.unwrap_or_else(Instant::now)),
+					if next_deadline.is_some() => self.handle_timeouts().await,
+			};
+			if !running {
+				break;
+			}
+			self.send_queued().await;
+		}
+	}
+
+	/// Get the earliest deadline of the pending requests and release.
+	fn next_deadline(&self) -> Option<Instant> {
+		self.response_map
+			.values()
+			.filter_map(|pending| pending.deadline)
+			.chain(self.queue.iter().filter_map(|queued| queued.deadline))
+			.chain(self.release.as_ref().and_then(|release| release.deadline))
+			.min()
+	}
+
+	/// Close the connection handler.
+	/// Every pending and queued request is failed with a connection lost error
+	/// and the new state is published.
+	fn close(&mut self, state: ConnectionState) {
+		for (_, pending) in self.response_map.drain() {
+			let _ = pending.sender.send(ConnectionLost.fail());
+		}
+		for queued in self.queue.drain(..) {
+			let _ = queued.sender.send(ConnectionLost.fail());
+		}
+		self.rx.close();
+		while let Ok((_, _, sender)) = self.rx.try_recv() {
+			let _ = sender.send(ConnectionLost.fail());
+		}
+		if let Some(release) = self.release.take() {
+			let result = if matches!(state, ConnectionState::Closed) {
+				Ok(())
+			} else {
+				ConnectionLost.fail()
+			};
+			let _ = release.sender.send(result);
+		}
+		self.state.send_replace(state);
+	}
+
+	/// Handle an event received from the server.
+	/// Returns false when the connection is closed.
+	#[instrument(skip(self))]
+	async fn handle_event(&mut self, event: Result<AcseEvent, AcseError>) -> bool {
+		match event {
+			Ok(AcseEvent::Data(data)) => {
+				match ber::decode(&data).context(DecodeResponse) {
+					Ok(pdu) => self.handle_pdu(pdu).await,
+					Err(e) => tracing::error!("Error decoding response: {:?}", e),
+				}
+				true
+			}
+			Ok(AcseEvent::ReleaseRequest) => {
+				tracing::info!("Server requested the release of the association");
+				if let Err(e) = self.write_half.send_release_response().await {
+					tracing::error!("Error sending release response: {:?}", e);
+				}
+				self.close(ConnectionState::Closed);
+				false
+			}
+			Ok(AcseEvent::ReleaseResponse) => {
+				tracing::info!("Association released");
+				self.close(ConnectionState::Closed);
+				false
+			}
+			Ok(AcseEvent::Abort(abort)) => {
+				tracing::warn!("Association {abort}");
+				self.close(ConnectionState::Aborted(Arc::new(
+					AssociationAborted { abort }.build(),
+				)));
+				false
+			}
+			Err(e) => {
+				tracing::error!("Error receiving data: {:?}", e);
+				self.close(ConnectionState::Aborted(Arc::new(e.into())));
+				false
+			}
+		}
+	}
+
+	/// Handle a confirmed service request from the client.
+	/// The request is queued if the maximum number of outstanding requests is
+	/// reached.
+	#[instrument(skip(self))]
+	async fn handle_request(&mut self, (request, timeout, sender): RequestMessage) {
+		if self.release.is_some() {
+			let _ = sender.send(ReleaseInProgress.fail());
+			return;
+		}
+		let deadline = timeout.map(|timeout| Instant::now() + timeout);
+		self.queue.push_back(QueuedRequest { request, sender, deadline });
+		self.send_queued().await;
+	}
+
+	/// Send the queued requests while the number of outstanding requests is
+	/// below the negotiated maximum.
+	async fn send_queued(&mut self) {
+		let max_outstanding = usize::try_from(self.parameters.max_serv_outstanding_calling)
+			.unwrap_or_default()
+			.max(1);
+		while self.response_map.len() < max_outstanding {
+			let Some(queued) = self.queue.pop_front() else {
+				break;
+			};
+			self.send_request(queued).await;
+		}
+	}
+
+	/// Send a confirmed service request to the server.
+	#[instrument(skip(self))]
+	async fn send_request(&mut self, QueuedRequest { request, sender, deadline }: QueuedRequest) {
+		let data = match prepare_request(self.invoke_id, request) {
+			Ok(data) => data,
+			Err(e) => {
+				tracing::error!("Error preparing request: {:?}", e);
+				let _ = sender.send(Err(e));
+				return;
+			}
+		};
+		if usize::try_from(self.parameters.max_pdu_size).is_ok_and(|max| data.len() > max) {
+			let _ = sender.send(
+				PduTooLarge { size: data.len(), max_size: self.parameters.max_pdu_size }.fail(),
+			);
+			return;
+		}
+		if let Err(e) = self.write_half.send_data(data).await {
+			tracing::error!("Error sending data: {:?}", e);
+			let _ = sender.send(Err(e.into()));
+			return;
+		}
+		self.response_map.insert(self.invoke_id, PendingRequest { sender, deadline });
+		self.invoke_id = self.invoke_id.wrapping_add(1);
+	}
+
+	/// Handle a command from the client.
+	/// Returns false when the connection is closed.
+	#[instrument(skip_all)]
+	async fn handle_command(&mut self, command: Command) -> bool {
+		match command {
+			Command::Conclude { timeout, sender } => {
+				if self.release.is_some() {
+					let _ = sender.send(ReleaseInProgress.fail());
+					return true;
+				}
+				for queued in self.queue.drain(..) {
+					let _ = queued.sender.send(ReleaseInProgress.fail());
+				}
+				let request = MMSpdu::conclude_RequestPDU(ConcludeRequestPDU(()));
+				let data = match ber::encode(&request).context(EncodeRequest) {
+					Ok(data) => data,
+					Err(e) => {
+						let _ = sender.send(Err(e));
+						return true;
+					}
+				};
+				if let Err(e) = self.write_half.send_data(data).await {
+					let _ = sender.send(Err(e.into()));
+					return true;
+				}
+				self.state.send_replace(ConnectionState::Closing);
+				let deadline = timeout.map(|timeout| Instant::now() + timeout);
+				self.release = Some(PendingRelease { sender, deadline });
+				true
+			}
+			Command::Abort { sender } => {
+				let result = self.write_half.send_abort().await.map_err(MmsClientError::from);
+				if let Some(release) = self.release.take() {
+					let _ = release.sender.send(ConnectionLost.fail());
+				}
+				self.close(ConnectionState::Closed);
+				let _ = sender.send(result);
+				false
+			}
+		}
+	}
+
+	/// Handle an information report received from the server.
+	/// The `LastApplError` and `CommandTermination` reports are sent to the
+	/// control event subscribers. The reports sent by an RCB use a named
+	/// variable list and go to the report callback.
+	async fn handle_information_report(&mut self, report: InformationReport) {
+		if let Some(event) = ControlEvent::parse(&report) {
+			if self.control_events.send(event).is_err() {
+				tracing::debug!("Control event dropped, no subscribers");
+			}
+			return;
+		}
+		if !matches!(
+			report.variable_access_specification,
+			VariableAccessSpecification::variableListName(_)
+		) {
+			tracing::warn!("Unhandled information report: {:?}", report);
+			return;
+		}
+		let report = match Report::try_from(report) {
+			Ok(report) => report,
+			Err(e) => {
+				tracing::error!("Error decoding report: {:?}", e);
+				return;
+			}
+		};
+		// TODO: Should we spawn a task here?
+		self.report_callback.on_report(report).await;
+	}
+
+	/// Handle a PDU received from the server.
+	#[instrument(skip(self))]
+	async fn handle_pdu(&mut self, pdu: MMSpdu) {
+		match pdu {
+			MMSpdu::confirmed_ResponsePDU(response) => {
+				self.handle_confirmed_response(response).await;
+			}
+			MMSpdu::confirmed_ErrorPDU(response) => {
+				self.handle_confirmed_error(response).await;
+			}
+			MMSpdu::unconfirmed_PDU(response) => match response.service {
+				UnconfirmedService::informationReport(report) => {
+					self.handle_information_report(report).await;
+				}
+			},
+			MMSpdu::rejectPDU(response) => {
+				self.handle_rejected_pdu(response).await;
+			}
+			MMSpdu::initiate_ResponsePDU(response) => {
+				tracing::info!("Initiate Response PDU: {:?}", response);
+			}
+			MMSpdu::initiate_ErrorPDU(response) => {
+				tracing::info!("Initiate Error PDU: {:?}", response);
+			}
+			MMSpdu::conclude_RequestPDU(_) => {
+				self.handle_conclude_request().await;
+			}
+			MMSpdu::conclude_ResponsePDU(_) => {
+				self.handle_conclude_response().await;
+			}
+			MMSpdu::conclude_ErrorPDU(error) => {
+				self.handle_conclude_error(error);
+			}
+			MMSpdu::cancel_ResponsePDU(response) => {
+				tracing::info!("Request with invoke ID {} cancelled", response.0.0);
+			}
+			MMSpdu::cancel_ErrorPDU(response) => {
+				tracing::warn!(
+					"Error cancelling request with invoke ID {}: {}",
+					response.original_invoke_id.0,
+					ServiceError::from(response.service_error)
+				);
+			}
+			response => {
+				tracing::error!("Unexpected service response. Response: {:?}", response);
 			}
 		}
 	}
 
+	/// Handle a conclude request from the server.
+	/// The server will then request the release of the association.
+	#[instrument(skip(self))]
+	async fn handle_conclude_request(&mut self) {
+		tracing::info!("Server requested to conclude the association");
+		let response = MMSpdu::conclude_ResponsePDU(ConcludeResponsePDU(()));
+		let data = match ber::encode(&response).context(EncodeRequest) {
+			Ok(data) => data,
+			Err(e) => {
+				tracing::error!("Error encoding conclude response: {:?}", e);
+				return;
+			}
+		};
+		if let Err(e) = self.write_half.send_data(data).await {
+			tracing::error!("Error sending conclude response: {:?}", e);
+			return;
+		}
+		self.state.send_replace(ConnectionState::Closing);
+	}
+
+	/// Handle a conclude response by requesting the release of the association.
+	#[instrument(skip(self))]
+	async fn handle_conclude_response(&mut self) {
+		if self.release.is_none() {
+			tracing::error!("Unexpected conclude response");
+			return;
+		}
+		if let Err(e) = self.write_half.send_release_request().await {
+			tracing::error!("Error sending release request: {:?}", e);
+			if let Some(release) = self.release.take() {
+				let _ = release.sender.send(Err(e.into()));
+			}
+			self.state.send_replace(ConnectionState::Connected);
+		}
+	}
+
+	/// Handle a conclude error. The association stays established.
+	#[instrument(skip(self))]
+	fn handle_conclude_error(&mut self, error: ConcludeErrorPDU) {
+		let error = ServiceError::from(error.0);
+		tracing::warn!("Server refused to conclude the association: {error}");
+		if let Some(release) = self.release.take() {
+			let _ = release.sender.send(Service { error }.fail());
+		}
+		self.state.send_replace(ConnectionState::Connected);
+	}
+
+	/// Fail the requests whose deadline has passed and optionally ask the
+	/// server to cancel them.
+	/// Returns false when the connection is closed.
+	#[instrument(skip(self))]
+	async fn handle_timeouts(&mut self) -> bool {
+		let now = Instant::now();
+		if self
+			.release
+			.as_ref()
+			.is_some_and(|release| release.deadline.is_some_and(|deadline| deadline <= now))
+		{
+			tracing::warn!("Release of the association timed out, closing the connection");
+			if let Some(release) = self.release.take() {
+				let _ = release.sender.send(ReleaseTimeout.fail());
+			}
+			self.close(ConnectionState::Closed);
+			return false;
+		}
+
+		let (expired, queue): (VecDeque<_>, _) = self
+			.queue
+			.drain(..)
+			.partition(|queued| queued.deadline.is_some_and(|deadline| deadline <= now));
+		self.queue = queue;
+		for queued in expired {
+			tracing::warn!("Queued request timed out before being sent");
+			let _ = queued.sender.send(QueueTimeout.fail());
+		}
+
+		let expired = self
+			.response_map
+			.iter()
+			.filter(|(_, pending)| pending.deadline.is_some_and(|deadline| deadline <= now))
+			.map(|(invoke_id, _)| *invoke_id)
+			.collect::<Vec<_>>();
+
+		for invoke_id in expired {
+			let Some(pending) = self.response_map.remove(&invoke_id) else {
+				continue;
+			};
+			tracing::warn!("Request with invoke ID {invoke_id} timed out");
+			let _ = pending.sender.send(Timeout { invoke_id }.fail());
+
+			if self.cancel_on_timeout {
+				let request = MMSpdu::cancel_RequestPDU(CancelRequestPDU(Unsigned32(invoke_id)));
+				let data = match ber::encode(&request).context(EncodeRequest) {
+					Ok(data) => data,
+					Err(e) => {
+						tracing::error!("Error encoding cancel request: {:?}", e);
+						continue;
+					}
+				};
+				if let Err(e) = self.write_half.send_data(data).await {
+					tracing::error!("Error sending cancel request: {:?}", e);
+				}
+			}
+		}
+		true
+	}
+
 	/// Handle a confirmed response.
 	#[instrument(skip(self))]
 	async fn handle_confirmed_response(&mut self, response: ConfirmedResponsePDU) {
 		let invoke_id = response.invoke_id;
 		let response = response.service;
-		let Some(sender) = self.response_map.remove(&invoke_id.0) else {
+		self.clear_trouble();
+		let Some(PendingRequest { sender, .. }) = self.response_map.remove(&invoke_id.0) else {
 			tracing::error!("No sender found for invoke ID: {}", invoke_id.0);
 			return;
 		};
 
-		let _ = sender.send(response).inspect_err(|e| {
+		let _ = sender.send(Ok(response)).inspect_err(|e| {
 			tracing::error!("Error sending response: {:?}", e);
 			// TODO: Handle error better
 		});
@@ -551,21 +1161,55 @@ impl ConnectionHandler {
 	/// Handle a confirmed error.
 	#[instrument(skip(self))]
 	async fn handle_confirmed_error(&mut self, response: ConfirmedErrorPDU) {
-		let invoke_id = response.invoke_id;
-		// Dropping the sender will close the channel.
-		// TODO: Forward back the error to the caller.
-		let _ = self.response_map.remove(&invoke_id.0);
+		self.clear_trouble();
+		let Some(PendingRequest { sender, .. }) = self.response_map.remove(&response.invoke_id.0)
+		else {
+			tracing::error!("No sender found for invoke ID: {}", response.invoke_id.0);
+			return;
+		};
+
+		let error = Service { error: ServiceError::from(response) }.build();
+		let _ = sender.send(Err(error)).inspect_err(|e| {
+			tracing::error!("Error sending service error: {:?}", e);
+		});
+	}
+
+	/// Set the state back to connected once the server answers a request
+	/// after a reject without invoke ID.
+	fn clear_trouble(&self) {
+		self.state.send_if_modified(|state| {
+			let troubled = matches!(state, ConnectionState::Troubled(_));
+			if troubled {
+				*state = ConnectionState::Connected;
+			}
+			troubled
+		});
 	}
 
 	/// Handle a rejected PDU.
+	/// A reject without an invoke ID can't be matched to a request. The
+	/// application is told that the association is in trouble through
+	/// [`ConnectionState::Troubled`], and no request is failed: the pending
+	/// requests are left to their timeout, or wait for the connection to close
+	/// when they have none.
 	#[instrument(skip(self))]
 	async fn handle_rejected_pdu(&mut self, response: RejectPDU) {
-		tracing::info!("Rejected PDU: {:?}", response);
-		if let Some(invoke_id) = response.original_invoke_id {
-			// Dropping the sender will close the channel.
-			// TODO: Forward back the error to the caller.
-			let _ = self.response_map.remove(&invoke_id.0);
-		}
+		let reason = RejectReason::from(response.reject_reason);
+		tracing::info!("Rejected PDU: {reason}");
+
+		let Some(invoke_id) = response.original_invoke_id else {
+			tracing::warn!("Reject without invoke ID, the association is in trouble: {reason}");
+			self.state.send_replace(ConnectionState::Troubled(reason));
+			return;
+		};
+
+		let Some(PendingRequest { sender, .. }) = self.response_map.remove(&invoke_id.0) else {
+			tracing::error!("No sender found for invoke ID: {}", invoke_id.0);
+			return;
+		};
+		let _ = sender.send(Rejected { reason, invoke_id: invoke_id.0 }.fail()).inspect_err(|e| {
+			tracing::error!("Error sending reject: {:?}", e);
+		});
 	}
 }
 
@@ -604,16 +1248,68 @@ pub enum MmsClientError {
 	},
 	#[snafu(display("Data access error: {}", error))]
 	DataAccessError {
-		error: Integer,
+		error: DataAccessError,
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
-	#[snafu(display("Error sending request"))]
-	SendRequest {
-		source: mpsc::error::SendError<(
-			ConfirmedServiceRequest,
-			oneshot::Sender<ConfirmedServiceResponse>,
-		)>,
+	#[snafu(display("Service error: {}", error))]
+	ServiceError {
+		error: ServiceError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Request rejected: {}", reason))]
+	Rejected {
+		reason: RejectReason,
+		invoke_id: u32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Request with invoke ID {} timed out", invoke_id))]
+	Timeout {
+		invoke_id: u32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Connection lost"))]
+	ConnectionLost {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Association {abort}"))]
+	AssociationAborted {
+		abort: Abort,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display(
+		"The request size of {size} bytes exceeds the negotiated maximum PDU size of {max_size} \
+		 bytes"
+	))]
+	PduTooLarge {
+		size: usize,
+		max_size: i32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The request timed out while waiting to be sent"))]
+	QueueTimeout {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The service {service} is not supported by the server"))]
+	ServiceNotSupported {
+		service: MmsService,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The release of the association is in progress"))]
+	ReleaseInProgress {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The release of the association timed out"))]
+	ReleaseTimeout {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
@@ -693,9 +1389,18 @@ impl MmsClientError {
 			MmsClientError::VersionMismatch { context } => context,
 			MmsClientError::DecodeResponse { context, .. } => context,
 			MmsClientError::EncodeRequest { context, .. } => context,
-			MmsClientError::SendRequest { context, .. } => context,
 			MmsClientError::ReceiveResponse { context, .. } => context,
 			MmsClientError::DataAccessError { context, .. } => context,
+			MmsClientError::ServiceError { context, .. } => context,
+			MmsClientError::Rejected { context, .. } => context,
+			MmsClientError::Timeout { context, .. } => context,
+			MmsClientError::ConnectionLost { context } => context,
+			MmsClientError::AssociationAborted { context, .. } => context,
+			MmsClientError::PduTooLarge { context, .. } => context,
+			MmsClientError::QueueTimeout { context } => context,
+			MmsClientError::ServiceNotSupported { context, .. } => context,
+			MmsClientError::ReleaseInProgress { context } => context,
+			MmsClientError::ReleaseTimeout { context } => context,
 			MmsClientError::VisibleStringConversion { context, .. } => context,
 		}
 	}
@@ -803,6 +1508,23 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn test_encode_cancel_request() {
+		let request = MMSpdu::cancel_RequestPDU(CancelRequestPDU(Unsigned32(5)));
+		let data = ber::encode(&request).expect("Failed to encode cancel request");
+		assert_eq!(data, vec![0x85, 0x01, 0x05]);
+	}
+
+	#[test]
+	fn test_conclude_roundtrip() {
+		let request = MMSpdu::conclude_RequestPDU(ConcludeRequestPDU(()));
+		let data = ber::encode(&request).expect("Failed to encode conclude request");
+		assert_eq!(data, vec![0x8b, 0x00]);
+		let response: MMSpdu =
+			ber::decode(&[0x8c, 0x00]).expect("Failed to decode conclude response");
+		assert!(matches!(response, MMSpdu::conclude_ResponsePDU(_)));
+	}
+
 	struct TestReportCallback;
 
 	#[async_trait::async_trait]
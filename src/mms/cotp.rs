@@ -1,12 +1,12 @@
 //! COTP and RFC1006 implementation.
 
-use std::{pin::Pin, time::Duration};
+use std::{net::SocketAddr, pin::Pin, time::Duration};
 
 use async_trait::async_trait;
 use snafu::{OptionExt as _, ResultExt as _, Snafu, whatever};
 use tokio::{
 	io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
-	net::TcpStream,
+	net::{TcpListener, TcpStream},
 };
 use tokio_native_tls::{
 	TlsConnector, TlsStream,
@@ -15,7 +15,8 @@ use tokio_native_tls::{
 use tracing::instrument;
 
 use crate::mms::{
-	ClientConfig, ReadHalfConnection, SpanTraceWrapper, TlsClientConfig, WriteHalfConnection,
+	ClientConfig, ReadHalfConnection, ServerConfig, SpanTraceWrapper, TlsClientConfig,
+	WriteHalfConnection,
 };
 
 /// The version of the TPKT protocol.
@@ -26,6 +27,10 @@ pub(super) const COTP_MAX_TPDU_SIZE: u32 = 8192;
 pub(super) const COTP_DT_HEADER_SIZE: usize = 3;
 /// The size of the TPKT header.
 pub(super) const TPKT_HEADER_SIZE: usize = 4;
+/// The TPDU size used when the CR TPDU does not propose one.
+const COTP_DEFAULT_TPDU_SIZE: u32 = 128;
+/// The reference of the local end of the connection.
+const LOCAL_REF: u16 = 1;
 
 /// The COTP connection.
 #[derive(Debug)]
@@ -57,9 +62,7 @@ impl CotpConnection {
 			CotpOptions::TSelSrc(TselSrc { value: config.connection.local_t_sel.clone() }),
 		];
 
-		let local_ref = 1;
-
-		let tpkt = Tpkt::from_cotp(Cotp::Cr(CrTpdu::new(0, local_ref, options)));
+		let tpkt = Tpkt::from_cotp(Cotp::Cr(CrTpdu::new(0, LOCAL_REF, options)));
 		connection
 			.write_all(&tpkt.to_bytes())
 			.await
@@ -72,29 +75,54 @@ impl CotpConnection {
 		}
 
 		if let Cotp::Cc(cc_tpdu) = &tpkt.cotp
-			&& cc_tpdu.dst_ref == local_ref
+			&& cc_tpdu.dst_ref == LOCAL_REF
 		{
-			let tpdu_size = cc_tpdu
-				.options
-				.iter()
-				.find_map(|option| {
-					if let CotpOptions::TpduSize(tpdu_size) = option {
-						Some(tpdu_size.get_value())
-					} else {
-						None
-					}
-				})
-				.unwrap_or(COTP_MAX_TPDU_SIZE);
-			let (read_half, write_half) = tokio::io::split(connection);
-			return Ok(Self {
-				read_connection: CotpReadHalf { connection: read_half },
-				write_connection: CotpWriteHalf { connection: write_half, tpdu_size },
-			});
+			let tpdu_size = find_tpdu_size(&cc_tpdu.options).unwrap_or(COTP_MAX_TPDU_SIZE);
+			return Ok(Self::from_connection(connection, tpdu_size));
 		}
 
 		ConnectionFailed.fail()
 	}
 
+	/// Accept the connection request of a client and negotiate the connection
+	/// parameters. The TPDU size is the smallest of the proposed and the
+	/// configured one.
+	#[instrument(skip(config))]
+	pub async fn accept(stream: TcpStream, config: &ServerConfig) -> Result<Self, CotpError> {
+		let mut connection = Connection::Tcp(stream);
+		let tpkt = CotpReadHalf::read_tpkt(&mut connection).await?;
+		let Cotp::Cr(cr_tpdu) = tpkt.cotp else {
+			return WrongCotpType.fail();
+		};
+
+		let tpdu_size = find_tpdu_size(&cr_tpdu.options)
+			.unwrap_or(COTP_DEFAULT_TPDU_SIZE)
+			.min(TpduSize::new(config.tpdu_size).get_value());
+		let mut options = vec![CotpOptions::TpduSize(TpduSize::new(tpdu_size))];
+		options.extend(
+			cr_tpdu.options.into_iter().filter(|option| {
+				matches!(option, CotpOptions::TSelDst(_) | CotpOptions::TSelSrc(_))
+			}),
+		);
+
+		let tpkt = Tpkt::from_cotp(Cotp::Cc(CcTpdu::new(cr_tpdu.src_ref, LOCAL_REF, options)));
+		connection
+			.write_all(&tpkt.to_bytes())
+			.await
+			.whatever_context("Error writing to connection")?;
+
+		Ok(Self::from_connection(connection, tpdu_size))
+	}
+
+	/// Create the COTP connection of an established connection.
+	fn from_connection(connection: Connection, tpdu_size: u32) -> Self {
+		let (read_half, write_half) = tokio::io::split(connection);
+		Self {
+			read_connection: CotpReadHalf { connection: read_half },
+			write_connection: CotpWriteHalf { connection: write_half, tpdu_size },
+		}
+	}
+
 	/// Split the connection into a read half and a write half.
 	#[must_use]
 	pub fn split(self) -> (CotpReadHalf, CotpWriteHalf) {
@@ -122,6 +150,37 @@ impl WriteHalfConnection for CotpConnection {
 	}
 }
 
+/// A listener for the COTP connections of the clients.
+#[derive(Debug)]
+pub struct CotpListener {
+	/// The TCP listener.
+	listener: TcpListener,
+}
+
+impl CotpListener {
+	/// Bind a listener to the address and port of the configuration.
+	#[instrument]
+	pub async fn bind(config: &ServerConfig) -> Result<Self, CotpError> {
+		let listener = TcpListener::bind(format!("{}:{}", config.address, config.port))
+			.await
+			.whatever_context("Error binding listener")?;
+		Ok(Self { listener })
+	}
+
+	/// Get the local address of the listener.
+	pub fn local_addr(&self) -> Result<SocketAddr, CotpError> {
+		self.listener.local_addr().whatever_context("Error getting local address")
+	}
+
+	/// Accept the TCP connection of a client.
+	/// The COTP connection is then established by [`CotpConnection::accept`],
+	/// so that a slow client does not block the listener.
+	#[instrument(skip(self))]
+	pub async fn accept(&self) -> Result<(TcpStream, SocketAddr), CotpError> {
+		self.listener.accept().await.whatever_context("Error accepting connection")
+	}
+}
+
 /// The read half of the COTP connection.
 #[derive(Debug)]
 pub struct CotpReadHalf {
@@ -409,7 +468,6 @@ struct CcTpdu {
 impl CcTpdu {
 	/// Create a new CC TPDU.
 	#[must_use]
-	#[allow(dead_code)]
 	fn new(dst_ref: u16, src_ref: u16, options: Vec<CotpOptions>) -> Self {
 		Self {
 			li: (options.iter().map(CotpOptions::len).sum::<usize>() + 6) as u8,
@@ -583,6 +641,17 @@ fn bytes_to_options(bytes: &[u8]) -> Result<Vec<CotpOptions>, CotpError> {
 	Ok(options)
 }
 
+/// Find the TPDU size in the COTP options.
+fn find_tpdu_size(options: &[CotpOptions]) -> Option<u32> {
+	options.iter().find_map(|option| {
+		if let CotpOptions::TpduSize(tpdu_size) = option {
+			Some(tpdu_size.get_value())
+		} else {
+			None
+		}
+	})
+}
+
 /// Convert a vector of COTP options to a byte array.
 fn options_to_bytes(options: &[CotpOptions]) -> Vec<u8> {
 	let mut bytes = Vec::new();
@@ -846,6 +915,7 @@ enum Connection {
 	Tls(TlsStream<TcpStream>),
 }
 
+/// Make a TCP or TLS connection to the server.
 #[instrument(level = "debug")]
 async fn make_connection(config: &ClientConfig) -> Result<Connection, CotpError> {
 	let stream = tokio::time::timeout(
@@ -0,0 +1,882 @@
+//! Typed MMS error codes.
+//!
+//! The MMS ASN.1 definition only carries these codes as plain integers (the
+//! named values are commented out in `mms.asn`), so they are decoded here into
+//! rust enums.
+
+use std::fmt;
+
+use num_traits::ToPrimitive as _;
+use rasn::types::Integer;
+
+use crate::mms::ans1::mms::asn1;
+
+/// An error returned by the server for a confirmed service request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceError {
+	/// The class and code of the error.
+	pub class: ServiceErrorClass,
+	/// A server specific error code.
+	pub additional_code: Option<i64>,
+	/// A server specific description of the error.
+	pub additional_description: Option<String>,
+	/// The position of the modifier that caused the error, if any.
+	pub modifier_position: Option<u32>,
+}
+
+impl From<asn1::ServiceError> for ServiceError {
+	fn from(value: asn1::ServiceError) -> Self {
+		Self {
+			class: value.error_class.into(),
+			additional_code: value.additional_code.as_ref().map(to_code),
+			additional_description: value
+				.additional_description
+				.map(|description| description.to_string()),
+			modifier_position: None,
+		}
+	}
+}
+
+impl From<asn1::ConfirmedErrorPDU> for ServiceError {
+	fn from(value: asn1::ConfirmedErrorPDU) -> Self {
+		Self {
+			modifier_position: value.modifier_position.map(|position| position.0),
+			..value.service_error.into()
+		}
+	}
+}
+
+impl fmt::Display for ServiceError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.class)?;
+		if let Some(code) = self.additional_code {
+			write!(f, " (additional code: {code})")?;
+		}
+		if let Some(description) = &self.additional_description {
+			write!(f, ": {description}")?;
+		}
+		if let Some(position) = self.modifier_position {
+			write!(f, " at modifier position {position}")?;
+		}
+		Ok(())
+	}
+}
+
+/// The class of a service error together with its code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ServiceErrorClass {
+	VmdState(VmdStateError),
+	ApplicationReference(ApplicationReferenceError),
+	Definition(DefinitionError),
+	Resource(ResourceError),
+	Service(ServiceProblem),
+	ServicePreempt(ServicePreemptError),
+	TimeResolution(TimeResolutionError),
+	Access(AccessError),
+	Initiate(InitiateError),
+	Conclude(ConcludeError),
+	Cancel(CancelError),
+	File(FileError),
+	Others(i64),
+}
+
+impl From<asn1::ServiceErrorErrorClass> for ServiceErrorClass {
+	fn from(value: asn1::ServiceErrorErrorClass) -> Self {
+		use asn1::ServiceErrorErrorClass as Class;
+		match value {
+			Class::vmd_state(code) => Self::VmdState(to_code(&code).into()),
+			Class::application_reference(code) => Self::ApplicationReference(to_code(&code).into()),
+			Class::definition(code) => Self::Definition(to_code(&code).into()),
+			Class::resource(code) => Self::Resource(to_code(&code).into()),
+			Class::service(code) => Self::Service(to_code(&code).into()),
+			Class::service_preempt(code) => Self::ServicePreempt(to_code(&code).into()),
+			Class::time_resolution(code) => Self::TimeResolution(to_code(&code).into()),
+			Class::access(code) => Self::Access(to_code(&code).into()),
+			Class::initiate(code) => Self::Initiate(to_code(&code).into()),
+			Class::conclude(code) => Self::Conclude(to_code(&code).into()),
+			Class::cancel(code) => Self::Cancel(to_code(&code).into()),
+			Class::file(code) => Self::File(to_code(&code).into()),
+			Class::others(code) => Self::Others(to_code(&code)),
+		}
+	}
+}
+
+/// Errors of the vmd-state class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum VmdStateError {
+	Other,
+	VmdStateConflict,
+	VmdOperationalProblem,
+	DomainTransferProblem,
+	StateMachineIdInvalid,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for VmdStateError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::VmdStateConflict,
+			2 => Self::VmdOperationalProblem,
+			3 => Self::DomainTransferProblem,
+			4 => Self::StateMachineIdInvalid,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the application-reference class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ApplicationReferenceError {
+	Other,
+	ApplicationUnreachable,
+	ConnectionLost,
+	ApplicationReferenceInvalid,
+	ContextUnsupported,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ApplicationReferenceError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::ApplicationUnreachable,
+			2 => Self::ConnectionLost,
+			3 => Self::ApplicationReferenceInvalid,
+			4 => Self::ContextUnsupported,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the definition class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum DefinitionError {
+	Other,
+	ObjectUndefined,
+	InvalidAddress,
+	TypeUnsupported,
+	TypeInconsistent,
+	ObjectExists,
+	ObjectAttributeInconsistent,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for DefinitionError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::ObjectUndefined,
+			2 => Self::InvalidAddress,
+			3 => Self::TypeUnsupported,
+			4 => Self::TypeInconsistent,
+			5 => Self::ObjectExists,
+			6 => Self::ObjectAttributeInconsistent,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the resource class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ResourceError {
+	Other,
+	MemoryUnavailable,
+	ProcessorResourceUnavailable,
+	MassStorageUnavailable,
+	CapabilityUnavailable,
+	CapabilityUnknown,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ResourceError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::MemoryUnavailable,
+			2 => Self::ProcessorResourceUnavailable,
+			3 => Self::MassStorageUnavailable,
+			4 => Self::CapabilityUnavailable,
+			5 => Self::CapabilityUnknown,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the service class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ServiceProblem {
+	Other,
+	PrimitivesOutOfSequence,
+	ObjectStateConflict,
+	PduSize,
+	ContinuationInvalid,
+	ObjectConstraintConflict,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ServiceProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::PrimitivesOutOfSequence,
+			2 => Self::ObjectStateConflict,
+			3 => Self::PduSize,
+			4 => Self::ContinuationInvalid,
+			5 => Self::ObjectConstraintConflict,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the service-preempt class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ServicePreemptError {
+	Other,
+	Timeout,
+	Deadlock,
+	Cancel,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ServicePreemptError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::Timeout,
+			2 => Self::Deadlock,
+			3 => Self::Cancel,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the time-resolution class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum TimeResolutionError {
+	Other,
+	UnsupportableTimeResolution,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for TimeResolutionError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::UnsupportableTimeResolution,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the access class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum AccessError {
+	Other,
+	ObjectAccessUnsupported,
+	ObjectNonExistent,
+	ObjectAccessDenied,
+	ObjectInvalidated,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for AccessError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::ObjectAccessUnsupported,
+			2 => Self::ObjectNonExistent,
+			3 => Self::ObjectAccessDenied,
+			4 => Self::ObjectInvalidated,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the initiate class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum InitiateError {
+	Other,
+	VersionIncompatible,
+	MaxSegmentInsufficient,
+	MaxServicesOutstandingCallingInsufficient,
+	MaxServicesOutstandingCalledInsufficient,
+	ServiceCbbInsufficient,
+	ParameterCbbInsufficient,
+	NestingLevelInsufficient,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for InitiateError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::VersionIncompatible,
+			2 => Self::MaxSegmentInsufficient,
+			3 => Self::MaxServicesOutstandingCallingInsufficient,
+			4 => Self::MaxServicesOutstandingCalledInsufficient,
+			5 => Self::ServiceCbbInsufficient,
+			6 => Self::ParameterCbbInsufficient,
+			7 => Self::NestingLevelInsufficient,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the conclude class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConcludeError {
+	Other,
+	FurtherCommunicationRequired,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConcludeError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::FurtherCommunicationRequired,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the cancel class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CancelError {
+	Other,
+	InvokeIdUnknown,
+	CancelNotPossible,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for CancelError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvokeIdUnknown,
+			2 => Self::CancelNotPossible,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Errors of the file class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum FileError {
+	Other,
+	FilenameAmbiguous,
+	FileBusy,
+	FilenameSyntaxError,
+	ContentTypeInvalid,
+	PositionInvalid,
+	FileAccessDenied,
+	FileNonExistent,
+	DuplicateFilename,
+	InsufficientSpaceInFilestore,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for FileError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::FilenameAmbiguous,
+			2 => Self::FileBusy,
+			3 => Self::FilenameSyntaxError,
+			4 => Self::ContentTypeInvalid,
+			5 => Self::PositionInvalid,
+			6 => Self::FileAccessDenied,
+			7 => Self::FileNonExistent,
+			8 => Self::DuplicateFilename,
+			9 => Self::InsufficientSpaceInFilestore,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// The reason a PDU was rejected by the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum RejectReason {
+	ConfirmedRequest(ConfirmedRequestProblem),
+	ConfirmedResponse(ConfirmedResponseProblem),
+	ConfirmedError(ConfirmedErrorProblem),
+	Unconfirmed(UnconfirmedProblem),
+	PduError(PduProblem),
+	CancelRequest(CancelRequestProblem),
+	CancelResponse(CancelResponseProblem),
+	CancelError(CancelErrorProblem),
+	ConcludeRequest(ConcludeRequestProblem),
+	ConcludeResponse(ConcludeResponseProblem),
+	ConcludeError(ConcludeErrorProblem),
+}
+
+impl From<asn1::RejectPDURejectReason> for RejectReason {
+	fn from(value: asn1::RejectPDURejectReason) -> Self {
+		use asn1::RejectPDURejectReason as Reason;
+		match value {
+			Reason::confirmed_requestPDU(code) => Self::ConfirmedRequest(to_code(&code).into()),
+			Reason::confirmed_responsePDU(code) => Self::ConfirmedResponse(to_code(&code).into()),
+			Reason::confirmed_errorPDU(code) => Self::ConfirmedError(to_code(&code).into()),
+			Reason::unconfirmedPDU(code) => Self::Unconfirmed(to_code(&code).into()),
+			Reason::pdu_error(code) => Self::PduError(to_code(&code).into()),
+			Reason::cancel_requestPDU(code) => Self::CancelRequest(to_code(&code).into()),
+			Reason::cancel_responsePDU(code) => Self::CancelResponse(to_code(&code).into()),
+			Reason::cancel_errorPDU(code) => Self::CancelError(to_code(&code).into()),
+			Reason::conclude_requestPDU(code) => Self::ConcludeRequest(to_code(&code).into()),
+			Reason::conclude_responsePDU(code) => Self::ConcludeResponse(to_code(&code).into()),
+			Reason::conclude_errorPDU(code) => Self::ConcludeError(to_code(&code).into()),
+		}
+	}
+}
+
+impl fmt::Display for RejectReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+
+/// Reject codes for a confirmed-requestPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConfirmedRequestProblem {
+	Other,
+	UnrecognizedService,
+	UnrecognizedModifier,
+	InvalidInvokeId,
+	InvalidArgument,
+	InvalidModifier,
+	MaxServOutstandingExceeded,
+	MaxRecursionExceeded,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConfirmedRequestProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::UnrecognizedService,
+			2 => Self::UnrecognizedModifier,
+			3 => Self::InvalidInvokeId,
+			4 => Self::InvalidArgument,
+			5 => Self::InvalidModifier,
+			6 => Self::MaxServOutstandingExceeded,
+			8 => Self::MaxRecursionExceeded,
+			9 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a confirmed-responsePDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConfirmedResponseProblem {
+	Other,
+	UnrecognizedService,
+	InvalidInvokeId,
+	InvalidResult,
+	MaxRecursionExceeded,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConfirmedResponseProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::UnrecognizedService,
+			2 => Self::InvalidInvokeId,
+			3 => Self::InvalidResult,
+			5 => Self::MaxRecursionExceeded,
+			6 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a confirmed-errorPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConfirmedErrorProblem {
+	Other,
+	UnrecognizedService,
+	InvalidInvokeId,
+	InvalidServiceError,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConfirmedErrorProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::UnrecognizedService,
+			2 => Self::InvalidInvokeId,
+			3 => Self::InvalidServiceError,
+			4 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a unconfirmedPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum UnconfirmedProblem {
+	Other,
+	UnrecognizedService,
+	InvalidArgument,
+	MaxRecursionExceeded,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for UnconfirmedProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::UnrecognizedService,
+			2 => Self::InvalidArgument,
+			3 => Self::MaxRecursionExceeded,
+			4 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a pdu-error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum PduProblem {
+	UnknownPduType,
+	InvalidPdu,
+	IllegalAcseMapping,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for PduProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::UnknownPduType,
+			1 => Self::InvalidPdu,
+			2 => Self::IllegalAcseMapping,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a cancel-requestPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CancelRequestProblem {
+	Other,
+	InvalidInvokeId,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for CancelRequestProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidInvokeId,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a cancel-responsePDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CancelResponseProblem {
+	Other,
+	InvalidInvokeId,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for CancelResponseProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidInvokeId,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a cancel-errorPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CancelErrorProblem {
+	Other,
+	InvalidInvokeId,
+	InvalidServiceError,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for CancelErrorProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidInvokeId,
+			2 => Self::InvalidServiceError,
+			3 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a conclude-requestPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConcludeRequestProblem {
+	Other,
+	InvalidArgument,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConcludeRequestProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidArgument,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a conclude-responsePDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConcludeResponseProblem {
+	Other,
+	InvalidResult,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConcludeResponseProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidResult,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// Reject codes for a conclude-errorPDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ConcludeErrorProblem {
+	Other,
+	InvalidServiceError,
+	ValueOutOfRange,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for ConcludeErrorProblem {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::Other,
+			1 => Self::InvalidServiceError,
+			2 => Self::ValueOutOfRange,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+/// The reason the server could not access a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum DataAccessError {
+	ObjectInvalidated,
+	HardwareFault,
+	TemporarilyUnavailable,
+	ObjectAccessDenied,
+	ObjectUndefined,
+	InvalidAddress,
+	TypeUnsupported,
+	TypeInconsistent,
+	ObjectAttributeInconsistent,
+	ObjectAccessUnsupported,
+	ObjectNonExistent,
+	ObjectValueInvalid,
+	/// A code not defined by the standard.
+	Unknown(i64),
+}
+
+impl From<i64> for DataAccessError {
+	fn from(value: i64) -> Self {
+		match value {
+			0 => Self::ObjectInvalidated,
+			1 => Self::HardwareFault,
+			2 => Self::TemporarilyUnavailable,
+			3 => Self::ObjectAccessDenied,
+			4 => Self::ObjectUndefined,
+			5 => Self::InvalidAddress,
+			6 => Self::TypeUnsupported,
+			7 => Self::TypeInconsistent,
+			8 => Self::ObjectAttributeInconsistent,
+			9 => Self::ObjectAccessUnsupported,
+			10 => Self::ObjectNonExistent,
+			11 => Self::ObjectValueInvalid,
+			_ => Self::Unknown(value),
+		}
+	}
+}
+
+impl From<asn1::DataAccessError> for DataAccessError {
+	fn from(value: asn1::DataAccessError) -> Self {
+		to_code(&value.0).into()
+	}
+}
+
+impl From<DataAccessError> for i64 {
+	fn from(value: DataAccessError) -> Self {
+		match value {
+			DataAccessError::ObjectInvalidated => 0,
+			DataAccessError::HardwareFault => 1,
+			DataAccessError::TemporarilyUnavailable => 2,
+			DataAccessError::ObjectAccessDenied => 3,
+			DataAccessError::ObjectUndefined => 4,
+			DataAccessError::InvalidAddress => 5,
+			DataAccessError::TypeUnsupported => 6,
+			DataAccessError::TypeInconsistent => 7,
+			DataAccessError::ObjectAttributeInconsistent => 8,
+			DataAccessError::ObjectAccessUnsupported => 9,
+			DataAccessError::ObjectNonExistent => 10,
+			DataAccessError::ObjectValueInvalid => 11,
+			DataAccessError::Unknown(code) => code,
+		}
+	}
+}
+
+impl From<DataAccessError> for asn1::DataAccessError {
+	fn from(value: DataAccessError) -> Self {
+		Self(Integer::from(i64::from(value)))
+	}
+}
+
+impl fmt::Display for DataAccessError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+
+/// Convert an ASN.1 integer into an error code.
+/// Values that do not fit in an i64 can not be valid codes, so they are mapped
+/// to `i64::MAX` which always ends up as an unknown code.
+fn to_code(value: &Integer) -> i64 {
+	value.to_i64().unwrap_or(i64::MAX)
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use rasn::{ber, prelude::VisibleString};
+
+	use super::*;
+	use crate::mms::ans1::mms::asn1::{ConfirmedErrorPDU, MMSpdu, RejectPDU, Unsigned32};
+
+	#[test]
+	fn test_confirmed_error_pdu_to_service_error() {
+		let pdu = ConfirmedErrorPDU::new(
+			Unsigned32(7),
+			Some(Unsigned32(1)),
+			asn1::ServiceError::new(
+				asn1::ServiceErrorErrorClass::access(Integer::from(3)),
+				Some(Integer::from(42)),
+				Some(VisibleString::from_iso646_bytes(b"denied").unwrap()),
+			),
+		);
+		let data = ber::encode(&MMSpdu::confirmed_ErrorPDU(pdu)).unwrap();
+		let MMSpdu::confirmed_ErrorPDU(pdu) = ber::decode(&data).unwrap() else {
+			panic!("Expected confirmed_ErrorPDU");
+		};
+
+		let error = ServiceError::from(pdu);
+		assert_eq!(error.class, ServiceErrorClass::Access(AccessError::ObjectAccessDenied));
+		assert_eq!(error.additional_code, Some(42));
+		assert_eq!(error.additional_description.as_deref(), Some("denied"));
+		assert_eq!(error.modifier_position, Some(1));
+	}
+
+	#[test]
+	fn test_unknown_error_code() {
+		let class = ServiceErrorClass::from(asn1::ServiceErrorErrorClass::file(Integer::from(99)));
+		assert_eq!(class, ServiceErrorClass::File(FileError::Unknown(99)));
+	}
+
+	#[test]
+	fn test_reject_pdu_to_reject_reason() {
+		let pdu = RejectPDU::new(
+			Some(Unsigned32(3)),
+			asn1::RejectPDURejectReason::confirmed_requestPDU(Integer::from(6)),
+		);
+		let data = ber::encode(&MMSpdu::rejectPDU(pdu)).unwrap();
+		let MMSpdu::rejectPDU(pdu) = ber::decode(&data).unwrap() else {
+			panic!("Expected rejectPDU");
+		};
+
+		assert_eq!(pdu.original_invoke_id, Some(Unsigned32(3)));
+		assert_eq!(
+			RejectReason::from(pdu.reject_reason),
+			RejectReason::ConfirmedRequest(ConfirmedRequestProblem::MaxServOutstandingExceeded)
+		);
+		assert_eq!(
+			RejectReason::from(asn1::RejectPDURejectReason::pdu_error(Integer::from(7))),
+			RejectReason::PduError(PduProblem::Unknown(7))
+		);
+	}
+
+	#[test]
+	fn test_data_access_error() {
+		assert_eq!(
+			DataAccessError::from(asn1::DataAccessError(Integer::from(10))),
+			DataAccessError::ObjectNonExistent
+		);
+		assert_eq!(
+			DataAccessError::from(asn1::DataAccessError(Integer::from(12))),
+			DataAccessError::Unknown(12)
+		);
+	}
+}
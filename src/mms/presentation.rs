@@ -4,12 +4,13 @@ use async_trait::async_trait;
 use lazy_static::lazy_static;
 use rasn::{ber, prelude::*};
 use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use tokio::net::TcpStream;
 use tracing::instrument;
 
 use crate::mms::{
-	ClientConfig, ReadHalfConnection, SpanTraceWrapper, WriteHalfConnection,
+	ClientConfig, ReadHalfConnection, ServerConfig, SpanTraceWrapper, WriteHalfConnection,
 	ans1::presentation::asn1::*,
-	session::{Session, SessionError, SessionReadHalf, SessionWriteHalf},
+	session::{Session, SessionError, SessionEvent, SessionReadHalf, SessionWriteHalf},
 };
 
 /// The ACSE OID.
@@ -24,6 +25,17 @@ const ACSE_CONTEXT_ID: u64 = 1;
 /// The MMS context ID.
 const MMS_CONTEXT_ID: u64 = 3;
 
+/// The acceptance result of a presentation context.
+const RESULT_ACCEPTANCE: u8 = 0;
+/// The provider rejection result of a presentation context.
+const RESULT_PROVIDER_REJECTION: u8 = 2;
+/// The provider reason of a rejected context with an unsupported abstract
+/// syntax.
+const REASON_ABSTRACT_SYNTAX_NOT_SUPPORTED: u8 = 1;
+/// The provider reason of a rejected context with unsupported transfer
+/// syntaxes.
+const REASON_TRANSFER_SYNTAXES_NOT_SUPPORTED: u8 = 2;
+
 lazy_static! {
 	static ref BER_OID_OBJECT_IDENTIFIER: ObjectIdentifier = #[allow(clippy::expect_used)]
 	ObjectIdentifier::new(&BER_OID)
@@ -55,6 +67,17 @@ lazy_static! {
 				)],
 			},
 		]));
+	static ref PRESENTATION_CONTEXT_IDENTIFIER_LIST: PresentationContextIdentifierList =
+		PresentationContextIdentifierList(vec![
+			AnonymousPresentationContextIdentifierList::new(
+				PresentationContextIdentifier(Integer::from(ACSE_CONTEXT_ID)),
+				TransferSyntaxName(BER_OID_OBJECT_IDENTIFIER.clone()),
+			),
+			AnonymousPresentationContextIdentifierList::new(
+				PresentationContextIdentifier(Integer::from(MMS_CONTEXT_ID)),
+				TransferSyntaxName(BER_OID_OBJECT_IDENTIFIER.clone()),
+			),
+		]);
 }
 
 /// Presentation layer.
@@ -63,9 +86,12 @@ pub struct Presentation {
 	/// The session connection.
 	session: Session,
 	/// The local presentation selector.
-	local_p_sel: CallingPresentationSelector,
+	local_p_sel: PresentationSelector,
 	/// The remote presentation selector.
-	remote_p_sel: CalledPresentationSelector,
+	remote_p_sel: PresentationSelector,
+	/// The results of the presentation contexts proposed by the client, sent
+	/// in the CPA PPDU.
+	context_definition_results: Option<PresentationContextDefinitionResultList>,
 }
 
 impl Presentation {
@@ -75,22 +101,75 @@ impl Presentation {
 		let session = Session::new(config).await.context(CreateSession)?;
 		Ok(Self {
 			session,
-			local_p_sel: CallingPresentationSelector(PresentationSelector(OctetString::from(
+			local_p_sel: PresentationSelector(OctetString::from(
 				config.connection.local_p_sel.as_ref(),
-			))),
-			remote_p_sel: CalledPresentationSelector(PresentationSelector(OctetString::from(
+			)),
+			remote_p_sel: PresentationSelector(OctetString::from(
 				config.connection.remote_p_sel.as_ref(),
-			))),
+			)),
+			context_definition_results: None,
 		})
 	}
 
+	/// Accept the connection of a client and wait for the CP PPDU of the
+	/// remote presentation.
+	/// Returns the presentation and the ACSE data of the CP PPDU.
+	#[instrument(skip_all)]
+	pub async fn receive_connect(
+		stream: TcpStream,
+		config: &ServerConfig,
+	) -> std::result::Result<(Self, Vec<u8>), PresentationError> {
+		let (session, data) =
+			Session::receive_connect(stream, config).await.context(CreateSession)?;
+		let cp: CPType = ber::decode(&data).context(DecodeCp)?;
+		let parameters = cp.normal_mode_parameters.context(MissingNormalModeParameters)?;
+		let (data, _context_id) = read_user_data(parameters.user_data.context(MissingUserData)?)?;
+
+		let presentation = Self {
+			session,
+			local_p_sel: PresentationSelector(OctetString::from(config.local_p_sel.as_ref())),
+			remote_p_sel: parameters.calling_presentation_selector.map_or_else(
+				|| PresentationSelector(OctetString::default()),
+				|selector| selector.0,
+			),
+			context_definition_results: Some(make_result_list(
+				parameters.presentation_context_definition_list,
+			)),
+		};
+		Ok((presentation, data))
+	}
+
+	/// Accept the connection of the remote presentation with a CPA PPDU
+	/// carrying the ACSE data.
+	#[instrument(skip(self))]
+	pub async fn accept(&mut self, data: Vec<u8>) -> std::result::Result<(), PresentationError> {
+		let cpa = CPAPPDU::new(
+			ModeSelector::new(Integer::from(1)),
+			Some(CPAPPDUNormalModeParameters::new(
+				ProtocolVersion([true].into_iter().collect()),
+				Some(RespondingPresentationSelector(self.local_p_sel.clone())),
+				self.context_definition_results.take(),
+				None,
+				None,
+				Some(make_user_data(ACSE_CONTEXT_ID, data)),
+			)),
+		);
+		let cpa_bytes = ber::encode(&cpa).context(EncodeCpa)?;
+		self.session.accept(&cpa_bytes).await?;
+		Ok(())
+	}
+
 	/// Connect to the remote presentation.
 	#[instrument(skip(self))]
 	pub async fn connect(
 		&mut self,
 		data: Vec<u8>,
 	) -> std::result::Result<(Vec<u8>, u64), PresentationError> {
-		let cp = Self::make_cp_ppdu(self.local_p_sel.clone(), self.remote_p_sel.clone(), data);
+		let cp = Self::make_cp_ppdu(
+			CallingPresentationSelector(self.local_p_sel.clone()),
+			CalledPresentationSelector(self.remote_p_sel.clone()),
+			data,
+		);
 		let cp_bytes = ber::encode(&cp).context(EncodeCp)?;
 		let response = self.session.connect(&cp_bytes).await?;
 		let cpa: CPAPPDU = ber::decode(&response).context(DecodeCpa)?;
@@ -205,15 +284,47 @@ impl PresentationWriteHalf {
 		session_connection: &mut T,
 		data: Vec<u8>,
 	) -> std::result::Result<(), PresentationError> {
-		let data = UserData::fully_encoded_data(FullyEncodedData(vec![PDVList::new(
-			None,
-			PresentationContextIdentifier(Integer::from(MMS_CONTEXT_ID)),
-			PDVListPresentationDataValues::from(Any::from(data.clone())),
-		)]));
-		let data = ber::encode(&data).context(EncodeData)?;
+		let data = encode_user_data(MMS_CONTEXT_ID, data)?;
 		session_connection.send_data(data).await?;
 		Ok(())
 	}
+
+	/// Send a release request carrying the ACSE data.
+	#[instrument(skip(self))]
+	pub async fn send_release_request(
+		&mut self,
+		data: Vec<u8>,
+	) -> std::result::Result<(), PresentationError> {
+		let data = encode_user_data(ACSE_CONTEXT_ID, data)?;
+		self.session_connection.send_finish(data).await?;
+		Ok(())
+	}
+
+	/// Send a release response carrying the ACSE data.
+	#[instrument(skip(self))]
+	pub async fn send_release_response(
+		&mut self,
+		data: Vec<u8>,
+	) -> std::result::Result<(), PresentationError> {
+		let data = encode_user_data(ACSE_CONTEXT_ID, data)?;
+		self.session_connection.send_disconnect(data).await?;
+		Ok(())
+	}
+
+	/// Send a user abort carrying the ACSE data.
+	#[instrument(skip(self))]
+	pub async fn send_abort(
+		&mut self,
+		data: Vec<u8>,
+	) -> std::result::Result<(), PresentationError> {
+		let aru = AbortType::from(ARUPPDU::from(ARUPPDUNormalModeParameters::new(
+			Some(PRESENTATION_CONTEXT_IDENTIFIER_LIST.clone()),
+			Some(make_user_data(ACSE_CONTEXT_ID, data)),
+		)));
+		let data = ber::encode(&aru).context(EncodeData)?;
+		self.session_connection.send_abort(data).await?;
+		Ok(())
+	}
 }
 
 /// Presentation read half.
@@ -242,32 +353,158 @@ impl PresentationReadHalf {
 		session_connection: &mut R,
 	) -> std::result::Result<(Vec<u8>, u64), PresentationError> {
 		let data = session_connection.receive_data().await?;
-		let data: UserData = ber::decode(&data).context(DecodeData)?;
-		let mut pdvs = match data {
-			UserData::fully_encoded_data(data) => data.0,
-			UserData::simply_encoded_data(_) => {
-				return UnsupportedUserData.fail();
+		decode_user_data(&data)
+	}
+
+	/// Receive an event from the remote presentation.
+	#[instrument(skip(self))]
+	pub async fn receive_event(
+		&mut self,
+	) -> std::result::Result<PresentationEvent, PresentationError> {
+		Ok(match self.session_connection.receive_event().await? {
+			SessionEvent::Data(data) => {
+				let (data, context_id) = decode_user_data(&data)?;
+				PresentationEvent::Data { data, context_id }
 			}
-		};
-		//TODO: Do I need to look at all the PDVs?
-		let pdv = pdvs.pop().context(MissingPdv)?;
-		if pdv.transfer_syntax_name.is_some_and(|tsn| tsn.0 != *BER_OID_OBJECT_IDENTIFIER) {
-			return UnsupportedTransferSyntax.fail();
+			SessionEvent::Finish(data) => {
+				PresentationEvent::ReleaseRequest(decode_user_data(&data)?.0)
+			}
+			SessionEvent::Disconnect(data) => {
+				PresentationEvent::ReleaseResponse(decode_user_data(&data)?.0)
+			}
+			SessionEvent::Abort(data) => decode_abort(&data)?,
+		})
+	}
+}
+
+/// An event received from the remote presentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresentationEvent {
+	/// User data.
+	Data {
+		/// The data.
+		data: Vec<u8>,
+		/// The presentation context of the data.
+		context_id: u64,
+	},
+	/// The remote requested the release of the connection.
+	ReleaseRequest(Vec<u8>),
+	/// The remote accepted the release of the connection.
+	ReleaseResponse(Vec<u8>),
+	/// The remote user aborted the connection, with the ACSE data if any.
+	UserAbort(Option<Vec<u8>>),
+	/// The connection was aborted by the presentation or session provider.
+	ProviderAbort {
+		/// The presentation abort reason, if any.
+		reason: Option<i64>,
+	},
+}
+
+/// Make fully encoded user data of a presentation context.
+fn make_user_data(context_id: u64, data: Vec<u8>) -> UserData {
+	UserData::fully_encoded_data(FullyEncodedData(vec![PDVList::new(
+		None,
+		PresentationContextIdentifier(Integer::from(context_id)),
+		PDVListPresentationDataValues::from(Any::from(data)),
+	)]))
+}
+
+/// Make the results of the presentation contexts proposed by the client.
+/// Only the ACSE and MMS contexts with the identifiers used by this
+/// implementation and the BER transfer syntax are accepted.
+fn make_result_list(
+	list: Option<PresentationContextDefinitionList>,
+) -> PresentationContextDefinitionResultList {
+	let contexts = list.map(|list| list.0.0).unwrap_or_default();
+	let results = contexts
+		.into_iter()
+		.map(|context| {
+			let id = context.presentation_context_identifier.0;
+			let abstract_syntax = context.abstract_syntax_name.0;
+			let supported = (id == Integer::from(ACSE_CONTEXT_ID)
+				&& abstract_syntax == *ACSE_OID_OBJECT_IDENTIFIER)
+				|| (id == Integer::from(MMS_CONTEXT_ID)
+					&& abstract_syntax == *MMS_OID_OBJECT_IDENTIFIER);
+			let reason = if !supported {
+				REASON_ABSTRACT_SYNTAX_NOT_SUPPORTED
+			} else if !context
+				.transfer_syntax_name_list
+				.iter()
+				.any(|name| name.0 == *BER_OID_OBJECT_IDENTIFIER)
+			{
+				REASON_TRANSFER_SYNTAXES_NOT_SUPPORTED
+			} else {
+				return AnonymousResultList::new(
+					Result(Integer::from(RESULT_ACCEPTANCE)),
+					Some(TransferSyntaxName(BER_OID_OBJECT_IDENTIFIER.clone())),
+					None,
+				);
+			};
+			AnonymousResultList::new(
+				Result(Integer::from(RESULT_PROVIDER_REJECTION)),
+				None,
+				Some(Integer::from(reason)),
+			)
+		})
+		.collect();
+	PresentationContextDefinitionResultList(ResultList(results))
+}
+
+/// Encode the data as fully encoded user data of a presentation context.
+fn encode_user_data(
+	context_id: u64,
+	data: Vec<u8>,
+) -> std::result::Result<Vec<u8>, PresentationError> {
+	ber::encode(&make_user_data(context_id, data)).context(EncodeData)
+}
+
+/// Decode an abort PPDU.
+/// An abort without user data is a session provider abort.
+fn decode_abort(data: &[u8]) -> std::result::Result<PresentationEvent, PresentationError> {
+	if data.is_empty() {
+		return Ok(PresentationEvent::ProviderAbort { reason: None });
+	}
+	Ok(match ber::decode(data).context(DecodeData)? {
+		AbortType::aru_ppdu(ARUPPDU::normal_mode_parameters(parameters)) => {
+			let data = parameters.user_data.map(read_user_data).transpose()?.map(|(data, _)| data);
+			PresentationEvent::UserAbort(data)
 		}
+		AbortType::arp_ppdu(arp) => PresentationEvent::ProviderAbort {
+			reason: arp.provider_reason.and_then(|reason| reason.0.try_into().ok()),
+		},
+	})
+}
 
-		let context_id = pdv
-			.presentation_context_identifier
-			.0
-			.try_into()
-			.map_err(|_| InvalidContextId.build())?;
+/// Decode fully encoded user data.
+/// Returns the data and its presentation context.
+fn decode_user_data(data: &[u8]) -> std::result::Result<(Vec<u8>, u64), PresentationError> {
+	read_user_data(ber::decode(data).context(DecodeData)?)
+}
 
-		let user_data = pdv.presentation_data_values;
-		match user_data {
-			PDVListPresentationDataValues::single_ASN1_type(data) => {
-				Ok((data.into_bytes(), context_id))
-			}
-			_ => UnsupportedPresentationDataValues.fail(),
+/// Read the data of fully encoded user data.
+/// Returns the data and its presentation context.
+fn read_user_data(data: UserData) -> std::result::Result<(Vec<u8>, u64), PresentationError> {
+	let mut pdvs = match data {
+		UserData::fully_encoded_data(data) => data.0,
+		UserData::simply_encoded_data(_) => {
+			return UnsupportedUserData.fail();
+		}
+	};
+	//TODO: Do I need to look at all the PDVs?
+	let pdv = pdvs.pop().context(MissingPdv)?;
+	if pdv.transfer_syntax_name.is_some_and(|tsn| tsn.0 != *BER_OID_OBJECT_IDENTIFIER) {
+		return UnsupportedTransferSyntax.fail();
+	}
+
+	let context_id =
+		pdv.presentation_context_identifier.0.try_into().map_err(|_| InvalidContextId.build())?;
+
+	let user_data = pdv.presentation_data_values;
+	match user_data {
+		PDVListPresentationDataValues::single_ASN1_type(data) => {
+			Ok((data.into_bytes(), context_id))
 		}
+		_ => UnsupportedPresentationDataValues.fail(),
 	}
 }
 
@@ -352,6 +589,18 @@ pub enum PresentationError {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("Error decoding CP"))]
+	DecodeCp {
+		source: ber::de::DecodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error encoding CPA"))]
+	EncodeCpa {
+		source: rasn::der::enc::EncodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
 }
 
 impl PresentationError {
@@ -373,6 +622,8 @@ impl PresentationError {
 			PresentationError::CreateObjectIdentifier { context } => context,
 			PresentationError::EncodeCp { context, .. } => context,
 			PresentationError::DecodeCpa { context, .. } => context,
+			PresentationError::DecodeCp { context, .. } => context,
+			PresentationError::EncodeCpa { context, .. } => context,
 		}
 	}
 }
@@ -385,3 +636,32 @@ impl From<SessionError> for PresentationError {
 		}
 	}
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_user_abort() {
+		let aru = AbortType::from(ARUPPDU::from(ARUPPDUNormalModeParameters::new(
+			Some(PRESENTATION_CONTEXT_IDENTIFIER_LIST.clone()),
+			Some(make_user_data(ACSE_CONTEXT_ID, vec![0x64, 0x03, 0x80, 0x01, 0x00])),
+		)));
+		let data = ber::encode(&aru).unwrap();
+		assert_eq!(data[0], 0xa0);
+		assert_eq!(
+			decode_abort(&data).unwrap(),
+			PresentationEvent::UserAbort(Some(vec![0x64, 0x03, 0x80, 0x01, 0x00]))
+		);
+	}
+
+	#[test]
+	fn test_decode_provider_abort() {
+		assert_eq!(
+			decode_abort(&[0x30, 0x03, 0x80, 0x01, 0x02]).unwrap(),
+			PresentationEvent::ProviderAbort { reason: Some(2) }
+		);
+		assert_eq!(decode_abort(&[]).unwrap(), PresentationEvent::ProviderAbort { reason: None });
+	}
+}
@@ -0,0 +1,959 @@
+//! MMS server implementation.
+//!
+//! The server accepts the associations of the MMS clients and negotiates the
+//! initiate parameters. Every confirmed service request is handled by an
+//! [`MmsServerHandler`] in its own task, so a slow request doesn't block the
+//! other ones, and answered with the invoke ID of the request.
+
+use std::{collections::HashMap, fmt, net::SocketAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use rasn::{ber, prelude::*};
+use snafu::{ResultExt as _, Snafu};
+use tokio::{net::TcpStream, select, sync::mpsc, task::AbortHandle, time};
+use tracing::instrument;
+
+use crate::mms::{
+	ServerConfig, SpanTraceWrapper, WriteHalfConnection,
+	acse::{Acse, AcseError, AcseEvent, AcseWriteHalf},
+	ans1::mms::asn1::*,
+	capabilities::{MmsParameter, MmsService, ServerCapabilities},
+	client::NegotiatedParameters,
+	cotp::{CotpError, CotpListener},
+};
+
+/// The MMS version number.
+const VERSION_NUMBER: i16 = 1;
+/// The number of services in the service support options.
+const SERVICE_SUPPORT_LENGTH: usize = 85;
+/// The number of parameters in the parameter support options.
+const PARAMETER_SUPPORT_LENGTH: usize = 11;
+/// The parameters supported by the server.
+const SUPPORTED_PARAMETERS: [MmsParameter; 5] = [
+	MmsParameter::Str1,
+	MmsParameter::Str2,
+	MmsParameter::Vnam,
+	MmsParameter::Valt,
+	MmsParameter::Vlis,
+];
+/// The time a client has to establish the association.
+const ASSOCIATION_TIMEOUT: Duration = Duration::from_secs(10);
+/// The capacity of the information report channel of a connection.
+const REPORT_CAPACITY: usize = 64;
+
+/// The unknown PDU type problem of a rejected PDU.
+const PDU_PROBLEM_UNKNOWN_PDU_TYPE: u8 = 0;
+/// The invalid PDU problem of a rejected PDU.
+const PDU_PROBLEM_INVALID_PDU: u8 = 1;
+/// The other problem of a rejected confirmed request.
+const REQUEST_PROBLEM_OTHER: u8 = 0;
+/// The unrecognized service problem of a rejected confirmed request.
+const REQUEST_PROBLEM_UNRECOGNIZED_SERVICE: u8 = 1;
+/// The invalid invoke ID problem of a rejected confirmed request.
+const REQUEST_PROBLEM_INVALID_INVOKE_ID: u8 = 3;
+/// The max serv outstanding exceeded problem of a rejected confirmed request.
+const REQUEST_PROBLEM_MAX_SERV_OUTSTANDING_EXCEEDED: u8 = 6;
+/// The cancel code of the service preempt error class.
+const SERVICE_PREEMPT_CANCEL: u8 = 3;
+/// The invalid invoke ID code of the cancel error class.
+const CANCEL_INVALID_INVOKE_ID: u8 = 1;
+/// The further communication required code of the conclude error class.
+const CONCLUDE_FURTHER_COMMUNICATION_REQUIRED: u8 = 1;
+/// The memory unavailable code of the resource error class.
+const RESOURCE_MEMORY_UNAVAILABLE: u8 = 1;
+
+/// The services supported by default, besides conclude and cancel.
+pub const DEFAULT_SERVICES: [MmsService; 8] = [
+	MmsService::GetNameList,
+	MmsService::Read,
+	MmsService::Write,
+	MmsService::GetVariableAccessAttributes,
+	MmsService::DefineNamedVariableList,
+	MmsService::GetNamedVariableListAttributes,
+	MmsService::DeleteNamedVariableList,
+	MmsService::InformationReport,
+];
+
+/// The result of a confirmed service request handled by the server.
+type RequestResult = Result<ConfirmedServiceResponse, ServiceError>;
+/// The invoke ID, sequence number and outcome of a handled request.
+type ResponseMessage = (u32, u64, RequestOutcome);
+
+/// The outcome of a confirmed service request: its result and the
+/// information reports bound to it.
+#[derive(Debug)]
+pub struct RequestOutcome {
+	/// The response or the error sent to the client.
+	pub result: Result<ConfirmedServiceResponse, ServiceError>,
+	/// The reports sent right before the response, e.g. the `LastApplError`
+	/// of a rejected control.
+	pub reports_before: Vec<InformationReport>,
+	/// The reports sent right after the response, e.g. the
+	/// `CommandTermination` of a control.
+	pub reports_after: Vec<InformationReport>,
+}
+
+impl From<Result<ConfirmedServiceResponse, ServiceError>> for RequestOutcome {
+	fn from(result: Result<ConfirmedServiceResponse, ServiceError>) -> Self {
+		Self { result, reports_before: Vec::new(), reports_after: Vec::new() }
+	}
+}
+
+/// A trait for handling the requests of the MMS clients.
+#[async_trait]
+pub trait MmsServerHandler: Send + Sync {
+	/// The services announced to the clients. Conclude and cancel are always
+	/// supported. The requests of the other services are rejected without
+	/// reaching the handler.
+	fn supported_services(&self) -> Vec<MmsService> {
+		DEFAULT_SERVICES.to_vec()
+	}
+
+	/// Called once the association with a client is established.
+	async fn on_connect(&self, _connection: &ServerConnection) {}
+
+	/// Handle a confirmed service request of a client.
+	async fn handle_request(
+		&self,
+		connection: &ServerConnection,
+		request: ConfirmedServiceRequest,
+	) -> RequestOutcome;
+
+	/// Called once the association with a client is closed.
+	async fn on_disconnect(&self, _connection: &ServerConnection) {}
+}
+
+/// An association with a client.
+#[derive(Debug, Clone)]
+pub struct ServerConnection {
+	/// The id of the connection, unique for the server.
+	id: u64,
+	/// The address of the client.
+	peer_addr: SocketAddr,
+	/// The negotiated parameters.
+	parameters: NegotiatedParameters,
+	/// The sender for the information reports.
+	reports: mpsc::Sender<InformationReport>,
+}
+
+impl ServerConnection {
+	/// The id of the connection, unique for the server.
+	#[must_use]
+	pub const fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// The address of the client.
+	#[must_use]
+	pub const fn peer_addr(&self) -> SocketAddr {
+		self.peer_addr
+	}
+
+	/// The parameters negotiated with the client.
+	#[must_use]
+	pub const fn negotiated_parameters(&self) -> NegotiatedParameters {
+		self.parameters
+	}
+
+	/// Whether the association is closed.
+	#[must_use]
+	pub fn is_closed(&self) -> bool {
+		self.reports.is_closed()
+	}
+
+	/// Send an information report to the client.
+	pub async fn send_information_report(
+		&self,
+		report: InformationReport,
+	) -> Result<(), MmsServerError> {
+		self.reports.send(report).await.map_err(|_| ConnectionClosed.build())
+	}
+
+	/// Queue an information report without waiting. The reports queued by
+	/// one task are sent in order, so a caller holding a lock keeps the order
+	/// of the reports it makes under that lock.
+	pub fn try_send_information_report(
+		&self,
+		report: InformationReport,
+	) -> Result<(), MmsServerError> {
+		self.reports.try_send(report).map_err(|e| match e {
+			mpsc::error::TrySendError::Full(_) => ReportQueueFull.build(),
+			mpsc::error::TrySendError::Closed(_) => ConnectionClosed.build(),
+		})
+	}
+}
+
+/// An MMS server.
+pub struct MmsServer {
+	/// The listener for the client connections.
+	listener: CotpListener,
+	/// The server configuration.
+	config: Arc<ServerConfig>,
+	/// The handler for the requests.
+	handler: Arc<dyn MmsServerHandler>,
+}
+
+impl fmt::Debug for MmsServer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MmsServer")
+			.field("listener", &self.listener)
+			.field("config", &self.config)
+			.finish()
+	}
+}
+
+impl MmsServer {
+	/// Bind the server to the address of the configuration.
+	#[instrument(skip(handler))]
+	pub async fn bind(
+		config: ServerConfig,
+		handler: Arc<dyn MmsServerHandler>,
+	) -> Result<Self, MmsServerError> {
+		let listener = CotpListener::bind(&config).await?;
+		Ok(Self { listener, config: Arc::new(config), handler })
+	}
+
+	/// The local address of the server.
+	pub fn local_addr(&self) -> Result<SocketAddr, MmsServerError> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// Accept the clients until the listener fails. Every client is served
+	/// in its own task.
+	#[instrument(skip(self))]
+	pub async fn run(self) -> Result<(), MmsServerError> {
+		let mut next_id: u64 = 0;
+		loop {
+			let (stream, peer_addr) = self.listener.accept().await?;
+			let id = next_id;
+			next_id = next_id.wrapping_add(1);
+			tracing::info!("Client {peer_addr} connected");
+
+			let config = self.config.clone();
+			let handler = self.handler.clone();
+			tokio::spawn(async move {
+				if let Err(e) = serve(stream, peer_addr, id, &config, handler).await {
+					tracing::warn!(
+						"Error serving client {peer_addr}: {}",
+						snafu::Report::from_error(&e)
+					);
+				}
+				tracing::info!("Client {peer_addr} disconnected");
+			});
+		}
+	}
+}
+
+/// Establish the association with a client and handle its requests until the
+/// association is closed.
+#[instrument(skip(stream, config, handler))]
+async fn serve(
+	stream: TcpStream,
+	peer_addr: SocketAddr,
+	id: u64,
+	config: &ServerConfig,
+	handler: Arc<dyn MmsServerHandler>,
+) -> Result<(), MmsServerError> {
+	let capabilities = make_capabilities(&handler.supported_services());
+	let (acse, parameters) =
+		time::timeout(ASSOCIATION_TIMEOUT, associate(stream, config, &capabilities))
+			.await
+			.context(AssociationTimeout)??;
+	tracing::debug!("Negotiated parameters: {:?}", parameters);
+
+	let (reports, reports_rx) = mpsc::channel(REPORT_CAPACITY);
+	let connection = ServerConnection { id, peer_addr, parameters, reports };
+	handler.on_connect(&connection).await;
+	ConnectionHandler::new(acse, connection.clone(), handler.clone(), capabilities, reports_rx)
+		.handle_connection()
+		.await;
+	handler.on_disconnect(&connection).await;
+	Ok(())
+}
+
+/// Accept the association of a client and answer its initiate request.
+async fn associate(
+	stream: TcpStream,
+	config: &ServerConfig,
+	capabilities: &ServerCapabilities,
+) -> Result<(Acse, NegotiatedParameters), MmsServerError> {
+	let (mut acse, data) = Acse::receive_connect(stream, config).await?;
+	let Ok(MMSpdu::initiate_RequestPDU(request)) = ber::decode(&data) else {
+		acse.reject().await?;
+		return InvalidInitiateRequest.fail();
+	};
+
+	let (response, parameters) = negotiate(&request, config, capabilities);
+	let data = ber::encode(&MMSpdu::initiate_ResponsePDU(response)).context(EncodeResponse)?;
+	acse.accept(data).await?;
+	Ok((acse, parameters))
+}
+
+/// Negotiate the parameters of an initiate request. Every parameter is the
+/// smallest of the proposed and the configured one.
+fn negotiate(
+	request: &InitiateRequestPDU,
+	config: &ServerConfig,
+	capabilities: &ServerCapabilities,
+) -> (InitiateResponsePDU, NegotiatedParameters) {
+	let parameters = NegotiatedParameters {
+		max_pdu_size: request
+			.local_detail_calling
+			.as_ref()
+			.map_or(config.max_pdu_size, |size| size.0.min(config.max_pdu_size)),
+		max_serv_outstanding_calling: request
+			.proposed_max_serv_outstanding_calling
+			.0
+			.min(config.max_serv_outstanding_calling),
+		max_serv_outstanding_called: request
+			.proposed_max_serv_outstanding_called
+			.0
+			.min(config.max_serv_outstanding_called),
+		data_structure_nesting_level: request
+			.proposed_data_structure_nesting_level
+			.as_ref()
+			.map_or(config.data_structure_nesting_level, |level| {
+				level.0.min(config.data_structure_nesting_level)
+			}),
+	};
+
+	let detail = &request.init_request_detail;
+	let proposed_parameters = &detail.proposed_parameter_cbb.0;
+	let parameter_cbb = make_bitstring(
+		PARAMETER_SUPPORT_LENGTH,
+		capabilities
+			.parameters()
+			.map(|parameter| parameter as usize)
+			.filter(|&parameter| proposed_parameters.get(parameter).is_some_and(|bit| *bit)),
+	);
+	let services = make_bitstring(
+		SERVICE_SUPPORT_LENGTH,
+		capabilities.services().map(|service| service as usize),
+	);
+
+	let response = InitiateResponsePDU::new(
+		Some(Integer32(parameters.max_pdu_size)),
+		Integer16(parameters.max_serv_outstanding_calling),
+		Integer16(parameters.max_serv_outstanding_called),
+		Some(Integer8(parameters.data_structure_nesting_level)),
+		InitiateResponsePDUInitResponseDetail::new(
+			Integer16(detail.proposed_version_number.0.min(VERSION_NUMBER)),
+			ParameterSupportOptions(parameter_cbb),
+			ServiceSupportOptions(services),
+		),
+	);
+	(response, parameters)
+}
+
+/// Make the capabilities of the server from the services of the handler.
+fn make_capabilities(services: &[MmsService]) -> ServerCapabilities {
+	let services = services
+		.iter()
+		.chain(&[MmsService::Conclude, MmsService::Cancel])
+		.map(|&service| service as usize);
+	ServerCapabilities::new(
+		make_bitstring(SERVICE_SUPPORT_LENGTH, services),
+		make_bitstring(
+			PARAMETER_SUPPORT_LENGTH,
+			SUPPORTED_PARAMETERS.iter().map(|&parameter| parameter as usize),
+		),
+	)
+}
+
+/// Make a bitstring of the given length with the given bits set.
+fn make_bitstring(length: usize, bits: impl IntoIterator<Item = usize>) -> BitString {
+	let mut bitstring = BitString::repeat(false, length);
+	for bit in bits {
+		bitstring.set(bit, true);
+	}
+	bitstring
+}
+
+/// Make a service error without additional information.
+pub(crate) fn service_error(class: ServiceErrorErrorClass) -> ServiceError {
+	ServiceError::new(class, None, None)
+}
+
+/// A confirmed service request being handled.
+#[derive(Debug)]
+struct PendingRequest {
+	/// The sequence number of the request. It tells apart the requests reusing
+	/// an invoke ID once the previous one is answered.
+	sequence: u64,
+	/// The handle to abort the task handling the request.
+	task: AbortHandle,
+}
+
+/// The handler of an established association.
+struct ConnectionHandler {
+	/// The events received from the client.
+	events: mpsc::Receiver<Result<AcseEvent, AcseError>>,
+	/// The handle to abort the task reading the events.
+	reader: AbortHandle,
+	/// The write half of the association.
+	write_half: AcseWriteHalf,
+	/// The association.
+	connection: ServerConnection,
+	/// The handler for the requests.
+	handler: Arc<dyn MmsServerHandler>,
+	/// The capabilities of the server.
+	capabilities: ServerCapabilities,
+	/// The information reports to send.
+	reports: mpsc::Receiver<InformationReport>,
+	/// The sender for the results of the requests.
+	responses_tx: mpsc::Sender<ResponseMessage>,
+	/// The results of the requests.
+	responses: mpsc::Receiver<ResponseMessage>,
+	/// The requests being handled by invoke ID.
+	pending: HashMap<u32, PendingRequest>,
+	/// The sequence number of the next request.
+	sequence: u64,
+	/// Whether the client concluded the association.
+	concluded: bool,
+}
+
+impl ConnectionHandler {
+	/// Create a new connection handler.
+	fn new(
+		acse: Acse,
+		connection: ServerConnection,
+		handler: Arc<dyn MmsServerHandler>,
+		capabilities: ServerCapabilities,
+		reports: mpsc::Receiver<InformationReport>,
+	) -> Self {
+		let (mut read_half, write_half) = acse.split();
+		let (events_tx, events) = mpsc::channel(1);
+		// The events are read in their own task since receiving an event is not
+		// cancel safe.
+		let reader = tokio::spawn(async move {
+			loop {
+				let event = read_half.receive_event().await;
+				let closed = !matches!(event, Ok(AcseEvent::Data(_)));
+				if events_tx.send(event).await.is_err() || closed {
+					break;
+				}
+			}
+		})
+		.abort_handle();
+
+		let capacity = usize::try_from(connection.parameters.max_serv_outstanding_calling)
+			.unwrap_or_default()
+			.max(1);
+		let (responses_tx, responses) = mpsc::channel(capacity);
+		Self {
+			events,
+			reader,
+			write_half,
+			connection,
+			handler,
+			capabilities,
+			reports,
+			responses_tx,
+			responses,
+			pending: HashMap::new(),
+			sequence: 0,
+			concluded: false,
+		}
+	}
+
+	/// Handle the association until it is closed.
+	#[instrument(skip(self), fields(peer_addr = %self.connection.peer_addr))]
+	async fn handle_connection(mut self) {
+		loop {
+			let running = select! {
+				event = self.events.recv() => match event {
+					Some(event) => self.handle_event(event).await,
+					None => false,
+				},
+				Some(response) = self.responses.recv() => {
+					self.handle_response(response).await;
+					true
+				}
+				Some(report) = self.reports.recv() => {
+					self.send_report(report).await;
+					true
+				}
+			};
+			if !running {
+				break;
+			}
+		}
+
+		self.reader.abort();
+		for (_, request) in self.pending.drain() {
+			request.task.abort();
+		}
+	}
+
+	/// Handle an event of the association. Returns whether the association is
+	/// still open.
+	async fn handle_event(&mut self, event: Result<AcseEvent, AcseError>) -> bool {
+		match event {
+			Ok(AcseEvent::Data(data)) => {
+				match ber::decode(&data) {
+					Ok(pdu) => self.handle_pdu(pdu).await,
+					Err(e) => {
+						tracing::warn!("Error decoding PDU: {e}");
+						self.send_reject(
+							None,
+							RejectPDURejectReason::pdu_error(Integer::from(
+								PDU_PROBLEM_INVALID_PDU,
+							)),
+						)
+						.await;
+					}
+				}
+				true
+			}
+			Ok(AcseEvent::ReleaseRequest) => {
+				tracing::debug!("Release requested");
+				if let Err(e) = self.write_half.send_release_response().await {
+					tracing::warn!("Error sending the release response: {e}");
+				}
+				false
+			}
+			Ok(AcseEvent::ReleaseResponse) => {
+				tracing::warn!("Unexpected release response");
+				false
+			}
+			Ok(AcseEvent::Abort(abort)) => {
+				tracing::info!("Association {abort}");
+				false
+			}
+			Err(e) => {
+				tracing::warn!("Error receiving event: {}", snafu::Report::from_error(&e));
+				false
+			}
+		}
+	}
+
+	/// Handle a PDU of the client.
+	async fn handle_pdu(&mut self, pdu: MMSpdu) {
+		match pdu {
+			MMSpdu::confirmed_RequestPDU(request) => self.handle_request(request).await,
+			MMSpdu::cancel_RequestPDU(request) => self.handle_cancel(request.0.0).await,
+			MMSpdu::conclude_RequestPDU(_) => self.handle_conclude().await,
+			pdu => {
+				tracing::warn!("Unexpected PDU: {:?}", pdu);
+				self.send_reject(
+					None,
+					RejectPDURejectReason::pdu_error(Integer::from(PDU_PROBLEM_UNKNOWN_PDU_TYPE)),
+				)
+				.await;
+			}
+		}
+	}
+
+	/// Handle a confirmed service request in its own task, or reject it.
+	async fn handle_request(&mut self, request: ConfirmedRequestPDU) {
+		let invoke_id = request.invoke_id.0;
+		let max_outstanding =
+			usize::try_from(self.connection.parameters.max_serv_outstanding_calling)
+				.unwrap_or_default();
+		let problem = if self.concluded {
+			Some(REQUEST_PROBLEM_OTHER)
+		} else if self.pending.contains_key(&invoke_id) {
+			Some(REQUEST_PROBLEM_INVALID_INVOKE_ID)
+		} else if !self.capabilities.supports(MmsService::from(&request.service)) {
+			Some(REQUEST_PROBLEM_UNRECOGNIZED_SERVICE)
+		} else if self.pending.len() >= max_outstanding {
+			Some(REQUEST_PROBLEM_MAX_SERV_OUTSTANDING_EXCEEDED)
+		} else {
+			None
+		};
+		if let Some(problem) = problem {
+			tracing::debug!("Rejecting request {invoke_id} with problem {problem}");
+			self.send_reject(
+				Some(invoke_id),
+				RejectPDURejectReason::confirmed_requestPDU(Integer::from(problem)),
+			)
+			.await;
+			return;
+		}
+
+		let sequence = self.sequence;
+		self.sequence = self.sequence.wrapping_add(1);
+		let handler = self.handler.clone();
+		let connection = self.connection.clone();
+		let responses = self.responses_tx.clone();
+		let task = tokio::spawn(async move {
+			let result = handler.handle_request(&connection, request.service).await;
+			// The connection handler is gone if the association is closed.
+			let _ = responses.send((invoke_id, sequence, result)).await;
+		})
+		.abort_handle();
+		self.pending.insert(invoke_id, PendingRequest { sequence, task });
+	}
+
+	/// Send the result of a request between its reports. A response exceeding
+	/// the negotiated maximum PDU size is replaced by a resource error.
+	async fn handle_response(&mut self, (invoke_id, sequence, outcome): ResponseMessage) {
+		// The request may have been cancelled in the meantime.
+		if self.pending.get(&invoke_id).is_none_or(|request| request.sequence != sequence) {
+			return;
+		}
+		self.pending.remove(&invoke_id);
+
+		for report in outcome.reports_before {
+			self.send_report(report).await;
+		}
+		self.send_result(invoke_id, outcome.result).await;
+		for report in outcome.reports_after {
+			self.send_report(report).await;
+		}
+	}
+
+	/// Send the result of a request.
+	async fn send_result(&mut self, invoke_id: u32, result: RequestResult) {
+		let pdu = match result {
+			Ok(response) => MMSpdu::confirmed_ResponsePDU(ConfirmedResponsePDU::new(
+				Unsigned32(invoke_id),
+				response,
+			)),
+			Err(error) => MMSpdu::confirmed_ErrorPDU(ConfirmedErrorPDU::new(
+				Unsigned32(invoke_id),
+				None,
+				error,
+			)),
+		};
+		let max_size = usize::try_from(self.connection.parameters.max_pdu_size).unwrap_or_default();
+		match ber::encode(&pdu) {
+			Ok(data) if data.len() <= max_size => self.send_data(data).await,
+			result => {
+				match result {
+					Ok(data) => tracing::warn!(
+						"The response to request {invoke_id} of {} bytes exceeds the maximum PDU \
+						 size of {max_size} bytes",
+						data.len()
+					),
+					Err(e) => {
+						tracing::error!("Error encoding the response to request {invoke_id}: {e}");
+					}
+				}
+				self.send_pdu(MMSpdu::confirmed_ErrorPDU(ConfirmedErrorPDU::new(
+					Unsigned32(invoke_id),
+					None,
+					service_error(ServiceErrorErrorClass::resource(Integer::from(
+						RESOURCE_MEMORY_UNAVAILABLE,
+					))),
+				)))
+				.await;
+			}
+		}
+	}
+
+	/// Send an information report.
+	async fn send_report(&mut self, report: InformationReport) {
+		self.send_pdu(MMSpdu::unconfirmed_PDU(UnconfirmedPDU::new(
+			UnconfirmedService::informationReport(report),
+		)))
+		.await;
+	}
+
+	/// Cancel a pending request. The request is answered with a service
+	/// preempt error before the cancel response.
+	async fn handle_cancel(&mut self, invoke_id: u32) {
+		let Some(request) = self.pending.remove(&invoke_id) else {
+			self.send_pdu(MMSpdu::cancel_ErrorPDU(CancelErrorPDU::new(
+				Unsigned32(invoke_id),
+				service_error(ServiceErrorErrorClass::cancel(Integer::from(
+					CANCEL_INVALID_INVOKE_ID,
+				))),
+			)))
+			.await;
+			return;
+		};
+
+		request.task.abort();
+		self.send_pdu(MMSpdu::confirmed_ErrorPDU(ConfirmedErrorPDU::new(
+			Unsigned32(invoke_id),
+			None,
+			service_error(ServiceErrorErrorClass::service_preempt(Integer::from(
+				SERVICE_PREEMPT_CANCEL,
+			))),
+		)))
+		.await;
+		self.send_pdu(MMSpdu::cancel_ResponsePDU(CancelResponsePDU(Unsigned32(invoke_id)))).await;
+	}
+
+	/// Conclude the association. Refused while requests are pending.
+	async fn handle_conclude(&mut self) {
+		if !self.pending.is_empty() {
+			self.send_pdu(MMSpdu::conclude_ErrorPDU(ConcludeErrorPDU(service_error(
+				ServiceErrorErrorClass::conclude(Integer::from(
+					CONCLUDE_FURTHER_COMMUNICATION_REQUIRED,
+				)),
+			))))
+			.await;
+			return;
+		}
+
+		self.concluded = true;
+		self.send_pdu(MMSpdu::conclude_ResponsePDU(ConcludeResponsePDU(()))).await;
+	}
+
+	/// Send a reject PDU.
+	async fn send_reject(&mut self, invoke_id: Option<u32>, reason: RejectPDURejectReason) {
+		self.send_pdu(MMSpdu::rejectPDU(RejectPDU::new(invoke_id.map(Unsigned32), reason))).await;
+	}
+
+	/// Encode and send a PDU.
+	async fn send_pdu(&mut self, pdu: MMSpdu) {
+		match ber::encode(&pdu) {
+			Ok(data) => self.send_data(data).await,
+			Err(e) => tracing::error!("Error encoding PDU: {e}"),
+		}
+	}
+
+	/// Send encoded data. A failure closes the association, which is noticed
+	/// when receiving the next event.
+	async fn send_data(&mut self, data: Vec<u8>) {
+		if let Err(e) = self.write_half.send_data(data).await {
+			tracing::warn!("Error sending data: {}", snafu::Report::from_error(&e));
+		}
+	}
+}
+
+/// The error type for the MMS server.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum MmsServerError {
+	#[snafu(display("Error in cotp layer"))]
+	CotpLayer {
+		source: CotpError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error in acse layer"))]
+	AcseLayer {
+		source: AcseError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The association was not established in time"))]
+	AssociationTimeout {
+		source: time::error::Elapsed,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Expected an initiate request"))]
+	InvalidInitiateRequest {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error encoding response"))]
+	EncodeResponse {
+		source: ber::enc::EncodeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Connection closed"))]
+	ConnectionClosed {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The report queue of the connection is full"))]
+	ReportQueueFull {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
+
+impl MmsServerError {
+	/// Get the context of the MMS server error.
+	#[must_use]
+	pub fn get_context(&self) -> &SpanTraceWrapper {
+		match self {
+			MmsServerError::CotpLayer { context, .. } => context,
+			MmsServerError::AcseLayer { context, .. } => context,
+			MmsServerError::AssociationTimeout { context, .. } => context,
+			MmsServerError::InvalidInitiateRequest { context } => context,
+			MmsServerError::EncodeResponse { context, .. } => context,
+			MmsServerError::ConnectionClosed { context } => context,
+			MmsServerError::ReportQueueFull { context } => context,
+		}
+	}
+}
+
+impl From<CotpError> for MmsServerError {
+	fn from(error: CotpError) -> Self {
+		MmsServerError::CotpLayer {
+			context: Box::new((*error.get_context()).clone()),
+			source: error,
+		}
+	}
+}
+
+impl From<AcseError> for MmsServerError {
+	fn from(error: AcseError) -> Self {
+		MmsServerError::AcseLayer {
+			context: Box::new((*error.get_context()).clone()),
+			source: error,
+		}
+	}
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use tokio::sync::Notify;
+
+	use super::*;
+	use crate::{
+		iec61850::report::Report,
+		mms::{
+			ClientConfig, MmsObjectClass, ReportCallback,
+			ans1::mms::asn1,
+			client::{MmsClient, MmsClientError},
+			error::{AccessError, ServiceErrorClass},
+		},
+	};
+
+	/// A handler serving one logical device and one integer variable.
+	struct TestHandler {
+		/// Notified when a client disconnects.
+		disconnected: Arc<Notify>,
+	}
+
+	#[async_trait]
+	impl MmsServerHandler for TestHandler {
+		async fn handle_request(
+			&self,
+			_connection: &ServerConnection,
+			request: ConfirmedServiceRequest,
+		) -> RequestOutcome {
+			let result = match request {
+				ConfirmedServiceRequest::getNameList(_) => {
+					Ok(ConfirmedServiceResponse::getNameList(GetNameListResponse::new(
+						vec![asn1::Identifier(VisibleString::try_from("LD0").unwrap())],
+						false,
+					)))
+				}
+				ConfirmedServiceRequest::read(_) => {
+					// Slow enough for the reads to overlap.
+					time::sleep(Duration::from_millis(20)).await;
+					Ok(ConfirmedServiceResponse::read(ReadResponse::new(
+						None,
+						vec![AccessResult::success(Data::integer(Integer::from(42)))],
+					)))
+				}
+				_ => Err(service_error(ServiceErrorErrorClass::access(Integer::from(3)))),
+			};
+			result.into()
+		}
+
+		async fn on_disconnect(&self, _connection: &ServerConnection) {
+			self.disconnected.notify_one();
+		}
+	}
+
+	/// A report callback ignoring the reports.
+	struct IgnoreReports;
+
+	#[async_trait]
+	impl ReportCallback for IgnoreReports {
+		async fn on_report(&self, _report: Report) {}
+	}
+
+	fn initiate_request() -> InitiateRequestPDU {
+		InitiateRequestPDU::new(
+			Some(Integer32(65_000)),
+			Integer16(5),
+			Integer16(20),
+			Some(Integer8(4)),
+			InitiateRequestPDUInitRequestDetail::new(
+				Integer16(2),
+				ParameterSupportOptions(make_bitstring(PARAMETER_SUPPORT_LENGTH, [0, 2, 8])),
+				ServiceSupportOptions(make_bitstring(SERVICE_SUPPORT_LENGTH, 0..85)),
+			),
+		)
+	}
+
+	#[test]
+	fn test_negotiate() {
+		let capabilities = make_capabilities(&[MmsService::Read]);
+		let (response, parameters) =
+			negotiate(&initiate_request(), &ServerConfig::default(), &capabilities);
+		assert_eq!(
+			parameters,
+			NegotiatedParameters {
+				max_pdu_size: 8192,
+				max_serv_outstanding_calling: 5,
+				max_serv_outstanding_called: 10,
+				data_structure_nesting_level: 4,
+			}
+		);
+
+		let detail = response.init_response_detail;
+		assert_eq!(detail.negotiated_version_number, Integer16(1));
+		let negotiated = ServerCapabilities::new(
+			detail.services_supported_called.0,
+			detail.negotiated_parameter_cbb.0,
+		);
+		assert_eq!(
+			negotiated.services().collect::<Vec<_>>(),
+			[MmsService::Read, MmsService::Conclude, MmsService::Cancel]
+		);
+		// Only the proposed parameters supported by the server are kept.
+		assert_eq!(
+			negotiated.parameters().collect::<Vec<_>>(),
+			[MmsParameter::Str1, MmsParameter::Vnam]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_client_server() {
+		let disconnected = Arc::new(Notify::new());
+		let handler = Arc::new(TestHandler { disconnected: disconnected.clone() });
+		let config =
+			ServerConfig { address: "127.0.0.1".to_owned(), port: 0, ..ServerConfig::default() };
+		let server = MmsServer::bind(config, handler).await.unwrap();
+		let port = server.local_addr().unwrap().port();
+		tokio::spawn(server.run());
+
+		let config =
+			ClientConfig { address: "127.0.0.1".to_owned(), port, ..ClientConfig::default() };
+		let client = MmsClient::connect(&config, Box::new(IgnoreReports)).await.unwrap();
+		assert_eq!(client.negotiated_parameters().max_pdu_size, 8192);
+
+		let devices = client
+			.get_name_list(
+				MmsObjectClass::Domain as u8,
+				GetNameListRequestObjectScope::vmdSpecific(()),
+			)
+			.await
+			.unwrap();
+		assert_eq!(devices, ["LD0"]);
+
+		let variable = || {
+			VariableAccessSpecification::listOfVariable(VariableDefs(vec![
+				AnonymousVariableDefs::new(
+					VariableSpecification::name(ObjectName::vmd_specific(asn1::Identifier(
+						VisibleString::try_from("Var").unwrap(),
+					))),
+					None,
+				),
+			]))
+		};
+		let (first, second) =
+			tokio::join!(client.read(variable(), false), client.read(variable(), false));
+		assert_eq!(first.unwrap(), [Data::integer(Integer::from(42))]);
+		assert_eq!(second.unwrap(), [Data::integer(Integer::from(42))]);
+
+		let error = client.write(variable(), vec![Data::bool(true)]).await.unwrap_err();
+		assert!(matches!(
+			error,
+			MmsClientError::ServiceError { error, .. }
+				if error.class == ServiceErrorClass::Access(AccessError::ObjectAccessDenied)
+		));
+		// Services not announced by the server fail without a request.
+		assert!(matches!(
+			client.file_delete(vec!["file".to_owned()]).await,
+			Err(MmsClientError::ServiceNotSupported { service: MmsService::FileDelete, .. })
+		));
+
+		client.conclude().await.unwrap();
+		time::timeout(Duration::from_secs(1), disconnected.notified()).await.unwrap();
+	}
+}
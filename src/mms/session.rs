@@ -2,10 +2,11 @@
 
 use async_trait::async_trait;
 use snafu::{OptionExt as _, Snafu};
+use tokio::net::TcpStream;
 use tracing::instrument;
 
 use crate::mms::{
-	ClientConfig, ReadHalfConnection, SpanTraceWrapper, WriteHalfConnection,
+	ClientConfig, ReadHalfConnection, ServerConfig, SpanTraceWrapper, WriteHalfConnection,
 	cotp::{CotpConnection, CotpError, CotpReadHalf, CotpWriteHalf},
 };
 
@@ -52,6 +53,43 @@ impl Session {
 			InvalidCotpResponse.fail()
 		}
 	}
+	/// Accept the COTP connection of a client and wait for the connect SPDU of
+	/// the remote session.
+	/// Returns the session and the user data of the connect SPDU.
+	#[instrument(skip_all)]
+	pub async fn receive_connect(
+		stream: TcpStream,
+		config: &ServerConfig,
+	) -> Result<(Self, Vec<u8>), SessionError> {
+		let mut cotp_connection = CotpConnection::accept(stream, config).await?;
+		let request = cotp_connection.receive_data().await?;
+		let Spdu::Connect(connect_spdu) = Spdu::from_bytes(&request)? else {
+			return InvalidCotpResponse.fail();
+		};
+		let session = Self {
+			cotp_connection,
+			local_s_sel: SSelector::from_bytes(&config.local_s_sel)?,
+			remote_s_sel: connect_spdu
+				.calling_session_selector
+				.unwrap_or(SSelector { value: Vec::new() }),
+		};
+		Ok((session, connect_spdu.data))
+	}
+
+	/// Accept the connection of the remote session.
+	#[instrument(skip(self))]
+	pub async fn accept(&mut self, data: &[u8]) -> Result<(), SessionError> {
+		let spdu =
+			AcceptSpdu::new(self.local_s_sel.clone(), SessionRequirement::Duplex, 0, data.to_vec());
+		let spdu_bytes = spdu.to_bytes();
+		// The length of the accept SPDU is encoded in a single byte.
+		if spdu_bytes.len() - 2 > usize::from(u8::MAX) {
+			return PayloadTooLarge.fail();
+		}
+		self.cotp_connection.send_data(spdu_bytes).await?;
+		Ok(())
+	}
+
 	/// Split the connection into a read half and a write half.
 	#[must_use]
 	pub fn split(self) -> (SessionReadHalf, SessionWriteHalf) {
@@ -98,6 +136,27 @@ impl SessionWriteHalf {
 		cotp_write.send_data(spdu_bytes).await?;
 		Ok(())
 	}
+
+	/// Send a Finish SPDU requesting the release of the connection.
+	#[instrument(skip(self))]
+	pub async fn send_finish(&mut self, data: Vec<u8>) -> Result<(), SessionError> {
+		self.cotp_write.send_data(FinishSpdu::new(data).to_bytes()).await?;
+		Ok(())
+	}
+
+	/// Send a Disconnect SPDU accepting the release of the connection.
+	#[instrument(skip(self))]
+	pub async fn send_disconnect(&mut self, data: Vec<u8>) -> Result<(), SessionError> {
+		self.cotp_write.send_data(DisconnectSpdu::new(data).to_bytes()).await?;
+		Ok(())
+	}
+
+	/// Send an Abort SPDU aborting the connection.
+	#[instrument(skip(self))]
+	pub async fn send_abort(&mut self, data: Vec<u8>) -> Result<(), SessionError> {
+		self.cotp_write.send_data(AbortSpdu::new(data).to_bytes()).await?;
+		Ok(())
+	}
 }
 
 #[async_trait]
@@ -123,14 +182,45 @@ impl SessionReadHalf {
 	async fn receive_data_internal<R: ReadHalfConnection<Error = CotpError>>(
 		cotp_read: &mut R,
 	) -> Result<Vec<u8>, SessionError> {
+		match Self::receive_event_internal(cotp_read).await? {
+			SessionEvent::Data(data) => Ok(data),
+			_ => InvalidCotpResponse.fail(),
+		}
+	}
+
+	/// Receive an event from the remote session.
+	#[instrument(skip_all)]
+	async fn receive_event_internal<R: ReadHalfConnection<Error = CotpError>>(
+		cotp_read: &mut R,
+	) -> Result<SessionEvent, SessionError> {
 		let response = cotp_read.receive_data().await?;
-		let response_spdu = Spdu::from_bytes(&response)?;
-		if let Spdu::Data(data_spdu) = response_spdu {
-			Ok(data_spdu.data)
-		} else {
-			InvalidCotpResponse.fail()
+		match Spdu::from_bytes(&response)? {
+			Spdu::Data(spdu) => Ok(SessionEvent::Data(spdu.data)),
+			Spdu::Finish(spdu) => Ok(SessionEvent::Finish(spdu.data)),
+			Spdu::Disconnect(spdu) => Ok(SessionEvent::Disconnect(spdu.user_data)),
+			Spdu::Abort(spdu) => Ok(SessionEvent::Abort(spdu.user_data)),
+			_ => InvalidCotpResponse.fail(),
 		}
 	}
+
+	/// Receive an event from the remote session.
+	#[instrument(skip(self))]
+	pub async fn receive_event(&mut self) -> Result<SessionEvent, SessionError> {
+		Self::receive_event_internal(&mut self.cotp_read).await
+	}
+}
+
+/// An event received from the remote session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+	/// User data.
+	Data(Vec<u8>),
+	/// The remote requested the release of the connection.
+	Finish(Vec<u8>),
+	/// The remote accepted the release of the connection.
+	Disconnect(Vec<u8>),
+	/// The remote aborted the connection.
+	Abort(Vec<u8>),
 }
 
 #[async_trait]
@@ -660,7 +750,6 @@ pub struct AcceptSpdu {
 }
 
 impl AcceptSpdu {
-	#[allow(dead_code)]
 	/// Create a new Accept SPDU.
 	#[must_use]
 	const fn new(
@@ -784,7 +873,6 @@ pub struct FinishSpdu {
 }
 
 impl FinishSpdu {
-	#[allow(dead_code)]
 	/// Create a new Finish SPDU.
 	#[must_use]
 	const fn new(user_data: Vec<u8>) -> Self {
@@ -884,7 +972,6 @@ pub struct AbortSpdu {
 }
 
 impl AbortSpdu {
-	#[allow(dead_code)]
 	/// Create a new Abort SPDU.
 	#[must_use]
 	const fn new(user_data: Vec<u8>) -> Self {
@@ -894,11 +981,9 @@ impl AbortSpdu {
 	/// Parse an Abort SPDU from bytes.
 	#[instrument(skip_all)]
 	fn from_bytes(bytes: &[u8]) -> Result<Self, SessionError> {
-		if bytes.len() < 7 {
-			return MessageTooShort.fail();
-		}
-		// Skip transport disconnect parameters and extract user data
-		Ok(Self { user_data: bytes[7..].to_vec() })
+		// The user data is optional, an abort may only carry the transport
+		// disconnect parameter
+		FinishSpdu::from_bytes(bytes).map(|f| Self { user_data: f.data })
 	}
 
 	/// Encode an Abort SPDU to bytes.
@@ -1179,6 +1264,24 @@ mod tests {
 		assert_eq!(parsed.data, user_data);
 	}
 
+	#[test]
+	fn test_disconnect_spdu_roundtrip() {
+		let user_data = b"Released".to_vec();
+		let bytes = DisconnectSpdu::new(user_data.clone()).to_bytes();
+
+		let Spdu::Disconnect(parsed) = Spdu::from_bytes(&bytes).unwrap() else {
+			panic!("Expected a Disconnect SPDU");
+		};
+		assert_eq!(parsed.user_data, user_data);
+	}
+
+	#[test]
+	fn test_abort_spdu_without_user_data() {
+		let bytes = [SpduType::Abort as u8, 3, Pgi::TransportDisconnect as u8, 1, 11];
+		let parsed = AbortSpdu::from_bytes(&bytes).unwrap();
+		assert!(parsed.user_data.is_empty());
+	}
+
 	#[test]
 	fn test_abort_spdu_roundtrip() {
 		let user_data = b"Error".to_vec();
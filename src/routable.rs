@@ -0,0 +1,452 @@
+//! Routable GOOSE and SV (IEC 61850-90-5) implementation.
+//!
+//! The routable messages are sent over UDP, see [`udp`]. Each datagram
+//! carries a session protocol data unit (SPDU) with the following layout:
+//! | SPDU ID (1) | Header length (1) | 0x80 (1) | Common header length (1) |
+//! SPDU length (4) | SPDU number (4) | Version (2) | Security information (12)
+//! | Payload length (4) | Payloads | Signature |
+//!
+//! Each payload has the following layout:
+//! | Payload type (1) | Simulation (1) | APPID (2) | APDU length (2) | APDU |
+//!
+//! The SPDU length counts the bytes after the SPDU length field and the
+//! payload length the bytes of the payloads.
+
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
+
+use crate::{
+	goose::{GOOSE_ETHER_TYPE, GooseError, GooseMessage, frame::EthernetFrame},
+	sv::{self, SV_ETHER_TYPE, SvAsdu, SvError},
+};
+
+pub mod udp;
+
+/// The default version of the session protocol.
+pub const SESSION_PROTOCOL_VERSION: u16 = 1;
+/// The length of the session header after the SPDU ID and length fields.
+const SESSION_HEADER_LENGTH: u8 = 0x18;
+/// The tag of the common session header.
+const COMMON_HEADER_TAG: u8 = 0x80;
+/// The length of the common session header after its tag and length fields.
+const COMMON_HEADER_LENGTH: u8 = 0x16;
+/// The size of the fields before the payloads.
+const HEADER_SIZE: usize = 30;
+/// The size of the header of a payload.
+const PAYLOAD_HEADER_SIZE: usize = 6;
+/// The tag of the signature.
+const SIGNATURE_TAG: u8 = 0x85;
+
+/// The SPDU ID, i.e. the kind of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SpduId {
+	/// Tunnelled ethernet frames.
+	Tunnelled = 0xa0,
+	/// GOOSE messages.
+	Goose = 0xa1,
+	/// Sampled values messages.
+	SampledValues = 0xa2,
+	/// Management messages.
+	Management = 0xa3,
+}
+
+impl TryFrom<u8> for SpduId {
+	type Error = SessionError;
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			0xa0 => Ok(Self::Tunnelled),
+			0xa1 => Ok(Self::Goose),
+			0xa2 => Ok(Self::SampledValues),
+			0xa3 => Ok(Self::Management),
+			id => UnknownSpduId { id }.fail(),
+		}
+	}
+}
+
+/// The type of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PayloadType {
+	/// A GOOSE PDU.
+	Goose = 0x81,
+	/// A sampled values PDU.
+	SampledValues = 0x82,
+	/// A tunnelled ethernet frame.
+	Tunnelled = 0x83,
+	/// A management PDU.
+	Management = 0x84,
+}
+
+impl TryFrom<u8> for PayloadType {
+	type Error = SessionError;
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			0x81 => Ok(Self::Goose),
+			0x82 => Ok(Self::SampledValues),
+			0x83 => Ok(Self::Tunnelled),
+			0x84 => Ok(Self::Management),
+			tag => UnknownPayloadType { tag }.fail(),
+		}
+	}
+}
+
+impl From<PayloadType> for SpduId {
+	fn from(value: PayloadType) -> Self {
+		match value {
+			PayloadType::Goose => Self::Goose,
+			PayloadType::SampledValues => Self::SampledValues,
+			PayloadType::Tunnelled => Self::Tunnelled,
+			PayloadType::Management => Self::Management,
+		}
+	}
+}
+
+/// The security information of the session. All zeroes when the messages are
+/// neither encrypted nor signed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecurityInformation {
+	/// The time the current key was issued, in seconds since the epoch.
+	pub time_of_current_key: u32,
+	/// The time until the next key is used, in minutes.
+	pub time_to_next_key: u16,
+	/// The encryption algorithm.
+	pub encryption_algorithm: u8,
+	/// The signature (MAC) algorithm.
+	pub signature_algorithm: u8,
+	/// The id of the current key.
+	pub key_id: u32,
+}
+
+/// A payload of a session protocol data unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payload {
+	/// The type of the payload.
+	pub payload_type: PayloadType,
+	/// Whether the message is a simulated one.
+	pub simulation: bool,
+	/// The application id.
+	pub app_id: u16,
+	/// The application protocol data unit.
+	pub apdu: Vec<u8>,
+}
+
+impl Payload {
+	/// Create a payload from a GOOSE message.
+	pub fn goose(app_id: u16, message: GooseMessage) -> Result<Self, SessionError> {
+		Ok(Self {
+			payload_type: PayloadType::Goose,
+			simulation: message.simulation,
+			app_id,
+			apdu: message.encode().context(Goose)?,
+		})
+	}
+
+	/// Create a payload from the ASDUs of a sampled values message.
+	pub fn sampled_values(
+		app_id: u16,
+		simulation: bool,
+		asdus: Vec<SvAsdu>,
+	) -> Result<Self, SessionError> {
+		Ok(Self {
+			payload_type: PayloadType::SampledValues,
+			simulation,
+			app_id,
+			apdu: sv::encode_pdu(asdus).context(SampledValues)?,
+		})
+	}
+
+	/// Decode the GOOSE message of the payload.
+	pub fn goose_message(&self) -> Result<GooseMessage, SessionError> {
+		ensure!(
+			self.payload_type == PayloadType::Goose,
+			UnexpectedPayloadType { payload_type: self.payload_type }
+		);
+		let mut message = GooseMessage::decode(&self.apdu).context(Goose)?;
+		message.simulation |= self.simulation;
+		Ok(message)
+	}
+
+	/// Decode the ASDUs of the sampled values payload.
+	pub fn sv_asdus(&self) -> Result<Vec<SvAsdu>, SessionError> {
+		ensure!(
+			self.payload_type == PayloadType::SampledValues,
+			UnexpectedPayloadType { payload_type: self.payload_type }
+		);
+		sv::decode_pdu(&self.apdu).context(SampledValues)
+	}
+
+	/// Convert a GOOSE or sampled values payload into an ethernet frame, so it
+	/// can be handled like a layer 2 message. The MAC addresses are unknown
+	/// and left as zeroes. Returns `None` for the other payload types.
+	#[must_use]
+	pub fn into_ethernet_frame(self) -> Option<EthernetFrame> {
+		let ether_type = match self.payload_type {
+			PayloadType::Goose => GOOSE_ETHER_TYPE,
+			PayloadType::SampledValues => SV_ETHER_TYPE,
+			PayloadType::Tunnelled | PayloadType::Management => return None,
+		};
+		let mut frame = EthernetFrame {
+			destination: [0; 6],
+			source: [0; 6],
+			vlan: None,
+			ether_type,
+			app_id: self.app_id,
+			reserved1: 0,
+			reserved2: 0,
+			apdu: self.apdu,
+		};
+		frame.set_simulation(self.simulation);
+		Some(frame)
+	}
+}
+
+impl TryFrom<EthernetFrame> for Payload {
+	type Error = SessionError;
+	fn try_from(value: EthernetFrame) -> Result<Self, Self::Error> {
+		let payload_type = match value.ether_type {
+			GOOSE_ETHER_TYPE => PayloadType::Goose,
+			SV_ETHER_TYPE => PayloadType::SampledValues,
+			ether_type => return NotRoutable { ether_type }.fail(),
+		};
+		Ok(Self {
+			payload_type,
+			simulation: value.simulation(),
+			app_id: value.app_id,
+			apdu: value.apdu,
+		})
+	}
+}
+
+/// A session protocol data unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spdu {
+	/// The kind of the session.
+	pub spdu_id: SpduId,
+	/// The number of the SPDU, incremented for every SPDU sent.
+	pub spdu_number: u32,
+	/// The version of the session protocol.
+	pub version: u16,
+	/// The security information.
+	pub security: SecurityInformation,
+	/// The payloads.
+	pub payloads: Vec<Payload>,
+	/// The signature of the SPDU. Empty if the SPDU is not signed.
+	pub signature: Vec<u8>,
+}
+
+impl Spdu {
+	/// Decode a session protocol data unit.
+	pub fn decode(bytes: &[u8]) -> Result<Self, SessionError> {
+		ensure!(bytes.len() >= HEADER_SIZE, TooShort { length: bytes.len() });
+		let spdu_id = SpduId::try_from(bytes[0])?;
+		ensure!(
+			bytes[1] == SESSION_HEADER_LENGTH
+				&& bytes[2] == COMMON_HEADER_TAG
+				&& bytes[3] == COMMON_HEADER_LENGTH,
+			InvalidHeader
+		);
+		let spdu_length = read_u32(bytes, 4)? as usize;
+		ensure!(8 + spdu_length <= bytes.len(), InvalidLength { field: "spdu_length" });
+		let bytes = &bytes[..8 + spdu_length];
+
+		let spdu_number = read_u32(bytes, 8)?;
+		let version = read_u16(bytes, 12)?;
+		let security = SecurityInformation {
+			time_of_current_key: read_u32(bytes, 14)?,
+			time_to_next_key: read_u16(bytes, 18)?,
+			encryption_algorithm: bytes[20],
+			signature_algorithm: bytes[21],
+			key_id: read_u32(bytes, 22)?,
+		};
+
+		let payload_length = read_u32(bytes, 26)? as usize;
+		let signature_offset = HEADER_SIZE + payload_length;
+		ensure!(signature_offset <= bytes.len(), InvalidLength { field: "payload_length" });
+		let mut payloads = Vec::new();
+		let mut offset = HEADER_SIZE;
+		while offset < signature_offset {
+			let payload_type = PayloadType::try_from(bytes[offset])?;
+			let simulation = *bytes.get(offset + 1).context(TooShort { length: bytes.len() })? != 0;
+			let app_id = read_u16(bytes, offset + 2)?;
+			let apdu_length = usize::from(read_u16(bytes, offset + 4)?);
+			offset += PAYLOAD_HEADER_SIZE;
+			ensure!(
+				offset + apdu_length <= signature_offset,
+				InvalidLength { field: "apdu_length" }
+			);
+			payloads.push(Payload {
+				payload_type,
+				simulation,
+				app_id,
+				apdu: bytes[offset..offset + apdu_length].to_vec(),
+			});
+			offset += apdu_length;
+		}
+
+		let signature = match &bytes[signature_offset..] {
+			[] => Vec::new(),
+			[SIGNATURE_TAG, length, signature @ ..] if usize::from(*length) == signature.len() => {
+				signature.to_vec()
+			}
+			_ => return InvalidSignature.fail(),
+		};
+
+		Ok(Self { spdu_id, spdu_number, version, security, payloads, signature })
+	}
+
+	/// Encode the session protocol data unit.
+	pub fn encode(&self) -> Result<Vec<u8>, SessionError> {
+		let mut payloads = Vec::new();
+		for payload in &self.payloads {
+			let apdu_length = u16::try_from(payload.apdu.len())
+				.ok()
+				.context(InvalidLength { field: "apdu_length" })?;
+			payloads.push(payload.payload_type as u8);
+			payloads.push(u8::from(payload.simulation));
+			payloads.extend_from_slice(&payload.app_id.to_be_bytes());
+			payloads.extend_from_slice(&apdu_length.to_be_bytes());
+			payloads.extend_from_slice(&payload.apdu);
+		}
+		let signature_length = u8::try_from(self.signature.len())
+			.ok()
+			.context(InvalidLength { field: "signature" })?;
+		let payload_length = u32::try_from(payloads.len())
+			.ok()
+			.context(InvalidLength { field: "payload_length" })?;
+		let spdu_length = u32::try_from(
+			HEADER_SIZE - 8
+				+ payloads.len()
+				+ (if self.signature.is_empty() { 0 } else { 2 })
+				+ self.signature.len(),
+		)
+		.ok()
+		.context(InvalidLength { field: "spdu_length" })?;
+
+		let mut bytes = Vec::with_capacity(8 + spdu_length as usize);
+		bytes.extend_from_slice(&[
+			self.spdu_id as u8,
+			SESSION_HEADER_LENGTH,
+			COMMON_HEADER_TAG,
+			COMMON_HEADER_LENGTH,
+		]);
+		bytes.extend_from_slice(&spdu_length.to_be_bytes());
+		bytes.extend_from_slice(&self.spdu_number.to_be_bytes());
+		bytes.extend_from_slice(&self.version.to_be_bytes());
+		bytes.extend_from_slice(&self.security.time_of_current_key.to_be_bytes());
+		bytes.extend_from_slice(&self.security.time_to_next_key.to_be_bytes());
+		bytes.push(self.security.encryption_algorithm);
+		bytes.push(self.security.signature_algorithm);
+		bytes.extend_from_slice(&self.security.key_id.to_be_bytes());
+		bytes.extend_from_slice(&payload_length.to_be_bytes());
+		bytes.extend_from_slice(&payloads);
+		if !self.signature.is_empty() {
+			bytes.extend_from_slice(&[SIGNATURE_TAG, signature_length]);
+			bytes.extend_from_slice(&self.signature);
+		}
+		Ok(bytes)
+	}
+}
+
+/// Read a big endian u16 at the given offset.
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, SessionError> {
+	bytes
+		.get(offset..)
+		.and_then(<[u8]>::first_chunk)
+		.map(|value| u16::from_be_bytes(*value))
+		.context(TooShort { length: bytes.len() })
+}
+
+/// Read a big endian u32 at the given offset.
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, SessionError> {
+	bytes
+		.get(offset..)
+		.and_then(<[u8]>::first_chunk)
+		.map(|value| u32::from_be_bytes(*value))
+		.context(TooShort { length: bytes.len() })
+}
+
+/// The error type for the session protocol.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum SessionError {
+	#[snafu(display("SPDU too short. Length: {}", length))]
+	TooShort { length: usize },
+	#[snafu(display("Invalid session header"))]
+	InvalidHeader,
+	#[snafu(display("Unknown SPDU ID: {:#04x}", id))]
+	UnknownSpduId { id: u8 },
+	#[snafu(display("Unknown payload type: {:#04x}", tag))]
+	UnknownPayloadType { tag: u8 },
+	#[snafu(display("Unexpected payload type: {:?}", payload_type))]
+	UnexpectedPayloadType { payload_type: PayloadType },
+	#[snafu(display("Invalid length for field: {}", field))]
+	InvalidLength { field: String },
+	#[snafu(display("Invalid signature"))]
+	InvalidSignature,
+	#[snafu(display("Not a routable message. EtherType: {:#06x}", ether_type))]
+	NotRoutable { ether_type: u16 },
+	#[snafu(display("Invalid GOOSE payload"))]
+	Goose { source: GooseError },
+	#[snafu(display("Invalid sampled values payload"))]
+	SampledValues { source: SvError },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use time::OffsetDateTime;
+
+	use super::*;
+	use crate::iec61850::data::Iec61850Data;
+
+	#[test]
+	fn test_spdu_roundtrip() {
+		let message = GooseMessage {
+			gocb_ref: "LD/LLN0$GO$gcb1".to_owned(),
+			time_allowed_to_live: 2000,
+			dataset: "LD/LLN0$DS1".to_owned(),
+			go_id: None,
+			timestamp: OffsetDateTime::UNIX_EPOCH,
+			st_num: 1,
+			sq_num: 0,
+			simulation: false,
+			config_rev: 1,
+			needs_commissioning: false,
+			number_of_entries: 1,
+			data: vec![Iec61850Data::Bool(true)],
+		};
+		let payload = Payload::goose(0x1000, message.clone()).unwrap();
+		let spdu = Spdu {
+			spdu_id: SpduId::Goose,
+			spdu_number: 7,
+			version: SESSION_PROTOCOL_VERSION,
+			security: SecurityInformation::default(),
+			payloads: vec![payload.clone()],
+			signature: Vec::new(),
+		};
+		let bytes = spdu.encode().unwrap();
+		let apdu_length = payload.apdu.len();
+		assert_eq!(bytes.len(), HEADER_SIZE + PAYLOAD_HEADER_SIZE + apdu_length);
+		assert_eq!(bytes[..4], [0xa1, 0x18, 0x80, 0x16]);
+		assert_eq!(read_u32(&bytes, 4).unwrap() as usize, bytes.len() - 8);
+		assert_eq!(read_u32(&bytes, 8).unwrap(), 7);
+		assert_eq!(bytes[30..34], [0x81, 0x00, 0x10, 0x00]);
+
+		let decoded = Spdu::decode(&bytes).unwrap();
+		assert_eq!(decoded, spdu);
+		assert_eq!(decoded.payloads[0].goose_message().unwrap(), message);
+		assert!(matches!(
+			decoded.payloads[0].sv_asdus(),
+			Err(SessionError::UnexpectedPayloadType { payload_type: PayloadType::Goose })
+		));
+
+		// A signed SPDU.
+		let spdu = Spdu { signature: vec![1, 2, 3, 4], ..spdu };
+		assert_eq!(Spdu::decode(&spdu.encode().unwrap()).unwrap(), spdu);
+		assert!(matches!(
+			Spdu::decode(&bytes[..bytes.len() - 1]),
+			Err(SessionError::InvalidLength { .. })
+		));
+	}
+}
@@ -0,0 +1,263 @@
+//! UDP transport of the routable GOOSE and SV messages.
+//!
+//! The [`RoutablePublisher`] is a [`FrameSink`], so the GOOSE publisher can
+//! send its messages over UDP. The [`RoutableSubscriber`] is a
+//! [`FrameSource`], so the GOOSE and SV subscribers can supervise the received
+//! messages.
+
+use std::{
+	collections::VecDeque,
+	io,
+	net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use async_trait::async_trait;
+use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use tokio::net::UdpSocket;
+use tracing::instrument;
+
+use crate::{
+	goose::{frame::EthernetFrame, publisher::FrameSink, subscriber::FrameSource},
+	routable::{Payload, SESSION_PROTOCOL_VERSION, SecurityInformation, SessionError, Spdu},
+};
+
+/// The UDP port of the routable GOOSE and SV messages.
+pub const ROUTABLE_PORT: u16 = 102;
+/// The maximum size of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65_535;
+
+/// A publisher of routable messages.
+#[derive(Debug)]
+pub struct RoutablePublisher {
+	/// The UDP socket.
+	socket: UdpSocket,
+	/// The destination of the messages, a unicast or multicast address.
+	destination: SocketAddr,
+	/// The number of the next SPDU.
+	spdu_number: u32,
+	/// The security information sent with every SPDU.
+	security: SecurityInformation,
+}
+
+impl RoutablePublisher {
+	/// Bind a publisher to a local address that sends the messages to the
+	/// destination.
+	pub async fn bind(local: SocketAddr, destination: SocketAddr) -> io::Result<Self> {
+		Ok(Self {
+			socket: UdpSocket::bind(local).await?,
+			destination,
+			spdu_number: 0,
+			security: SecurityInformation::default(),
+		})
+	}
+
+	/// Set the time to live of the multicast messages, i.e. the number of
+	/// routers they can cross.
+	pub fn set_multicast_ttl(&self, ttl: u32) -> io::Result<()> {
+		self.socket.set_multicast_ttl_v4(ttl)
+	}
+
+	/// Set the security information sent with every SPDU. The messages are
+	/// neither encrypted nor signed by the publisher.
+	pub const fn set_security(&mut self, security: SecurityInformation) {
+		self.security = security;
+	}
+
+	/// The number of the next SPDU.
+	#[must_use]
+	pub const fn spdu_number(&self) -> u32 {
+		self.spdu_number
+	}
+
+	/// Send the payloads in one SPDU. The kind of the session is given by the
+	/// type of the first payload.
+	#[instrument(skip_all)]
+	pub async fn send(&mut self, payloads: Vec<Payload>) -> Result<(), RoutableError> {
+		let spdu_id = payloads.first().context(EmptySpdu)?.payload_type.into();
+		let spdu = Spdu {
+			spdu_id,
+			spdu_number: self.spdu_number,
+			version: SESSION_PROTOCOL_VERSION,
+			security: self.security,
+			payloads,
+			signature: Vec::new(),
+		};
+		self.socket
+			.send_to(&spdu.encode().context(Session)?, self.destination)
+			.await
+			.context(Io)?;
+		self.spdu_number = self.spdu_number.wrapping_add(1);
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl FrameSink for RoutablePublisher {
+	async fn send_frame(&mut self, frame: &[u8]) -> Result<(), io::Error> {
+		let frame = EthernetFrame::decode(frame).map_err(io::Error::other)?;
+		let payload = Payload::try_from(frame).map_err(io::Error::other)?;
+		self.send(vec![payload]).await.map_err(io::Error::other)
+	}
+}
+
+/// A subscriber of routable messages.
+#[derive(Debug)]
+pub struct RoutableSubscriber {
+	/// The UDP socket.
+	socket: UdpSocket,
+	/// The payloads of the last SPDU not delivered yet as frames.
+	pending: VecDeque<Payload>,
+}
+
+impl RoutableSubscriber {
+	/// Bind a subscriber to a local address, e.g. `0.0.0.0:102`.
+	pub async fn bind(local: SocketAddr) -> io::Result<Self> {
+		Ok(Self { socket: UdpSocket::bind(local).await?, pending: VecDeque::new() })
+	}
+
+	/// The local address of the subscriber.
+	pub fn local_addr(&self) -> io::Result<SocketAddr> {
+		self.socket.local_addr()
+	}
+
+	/// Join a multicast group. IPv4 groups are joined on the default
+	/// interface and IPv6 groups on the interface with the given index, 0
+	/// being the default one.
+	pub fn join_multicast(&self, group: IpAddr, interface: u32) -> io::Result<()> {
+		match group {
+			IpAddr::V4(group) => self.socket.join_multicast_v4(group, Ipv4Addr::UNSPECIFIED),
+			IpAddr::V6(group) => self.socket.join_multicast_v6(&group, interface),
+		}
+	}
+
+	/// Leave a multicast group.
+	pub fn leave_multicast(&self, group: IpAddr, interface: u32) -> io::Result<()> {
+		match group {
+			IpAddr::V4(group) => self.socket.leave_multicast_v4(group, Ipv4Addr::UNSPECIFIED),
+			IpAddr::V6(group) => self.socket.leave_multicast_v6(&group, interface),
+		}
+	}
+
+	/// Receive the next SPDU and the address of its publisher.
+	pub async fn recv(&self) -> Result<(Spdu, SocketAddr), RoutableError> {
+		let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+		let (length, address) = self.socket.recv_from(&mut buffer).await.context(Io)?;
+		Ok((Spdu::decode(&buffer[..length]).context(Session)?, address))
+	}
+}
+
+#[async_trait]
+impl FrameSource for RoutableSubscriber {
+	async fn recv_frame(&mut self) -> Result<Option<Vec<u8>>, io::Error> {
+		loop {
+			if let Some(payload) = self.pending.pop_front() {
+				if let Some(frame) = payload.into_ethernet_frame() {
+					return frame.encode().map(Some).map_err(io::Error::other);
+				}
+				continue;
+			}
+			match self.recv().await {
+				Ok((spdu, _)) => self.pending.extend(spdu.payloads),
+				Err(RoutableError::Io { source }) => return Err(source),
+				Err(e) => tracing::warn!("Invalid SPDU: {e}"),
+			}
+		}
+	}
+}
+
+/// The error type for the UDP transport of the routable messages.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum RoutableError {
+	#[snafu(display("IO error"))]
+	Io { source: io::Error },
+	#[snafu(display("Invalid SPDU"))]
+	Session { source: SessionError },
+	#[snafu(display("An SPDU needs at least one payload"))]
+	EmptySpdu,
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use time::OffsetDateTime;
+
+	use super::*;
+	use crate::{
+		goose::{
+			GooseFrame,
+			publisher::{GoosePublisher, GoosePublisherConfig, RetransmissionCurve},
+		},
+		iec61850::{data::Iec61850Data, gocb::DestinationAddress},
+		routable::{PayloadType, SpduId},
+		sv::{SampleSynchronization, SvAsdu, SvFrame},
+	};
+
+	#[tokio::test]
+	async fn test_loopback() {
+		let loopback = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+		let mut subscriber = RoutableSubscriber::bind(loopback).await.unwrap();
+		let destination = subscriber.local_addr().unwrap();
+		let publisher = RoutablePublisher::bind(loopback, destination).await.unwrap();
+
+		// A GOOSE publisher sending over UDP.
+		let config = GoosePublisherConfig {
+			gocb_ref: "LD/LLN0$GO$gcb1".to_owned(),
+			dataset: "LD/LLN0$DS1".to_owned(),
+			go_id: None,
+			destination: DestinationAddress {
+				mac_address: [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01],
+				priority: 4,
+				vlan_id: 0,
+				app_id: 0x1000,
+			},
+			source: [0; 6],
+			config_rev: 1,
+			needs_commissioning: false,
+			simulation: true,
+			curve: RetransmissionCurve::default(),
+		};
+		let mut goose_publisher = GoosePublisher::new(config, publisher, Vec::new());
+		goose_publisher.publish(vec![Iec61850Data::Integer(5)]).await.unwrap();
+		let frame = GooseFrame::decode(&subscriber.recv_frame().await.unwrap().unwrap()).unwrap();
+		assert_eq!(frame.app_id, 0x1000);
+		assert_eq!(frame.message.st_num, 1);
+		assert!(frame.message.simulation);
+		assert_eq!(frame.message.data, [Iec61850Data::Integer(5)]);
+
+		// Sampled values with two ASDUs sent directly.
+		let mut publisher = RoutablePublisher::bind(loopback, destination).await.unwrap();
+		let asdu = SvAsdu {
+			sv_id: "MU01".to_owned(),
+			dataset: None,
+			sample_count: 1,
+			config_rev: 1,
+			refresh_time: Some(OffsetDateTime::UNIX_EPOCH),
+			sample_synchronization: SampleSynchronization::Global,
+			sample_rate: None,
+			seq_data: vec![0; 64],
+			sample_mode: None,
+			grandmaster_identity: None,
+		};
+		let asdus = vec![asdu.clone(), SvAsdu { sample_count: 2, ..asdu }];
+		publisher
+			.send(vec![Payload::sampled_values(0x4000, false, asdus.clone()).unwrap()])
+			.await
+			.unwrap();
+		assert_eq!(publisher.spdu_number(), 1);
+		let (spdu, _) = subscriber.recv().await.unwrap();
+		assert_eq!(spdu.spdu_id, SpduId::SampledValues);
+		assert_eq!(spdu.payloads[0].payload_type, PayloadType::SampledValues);
+		assert_eq!(spdu.payloads[0].sv_asdus().unwrap(), asdus);
+
+		publisher
+			.send(vec![Payload::sampled_values(0x4000, false, asdus.clone()).unwrap()])
+			.await
+			.unwrap();
+		let frame = SvFrame::decode(&subscriber.recv_frame().await.unwrap().unwrap()).unwrap();
+		assert_eq!(frame.app_id, 0x4000);
+		assert_eq!(frame.asdus, asdus);
+		assert!(matches!(publisher.send(Vec::new()).await, Err(RoutableError::EmptySpdu)));
+	}
+}
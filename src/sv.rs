@@ -0,0 +1,449 @@
+//! Sampled values (IEC 61850-9-2) implementation.
+//!
+//! The sampled values are published directly over ethernet with the same
+//! frame layout as the GOOSE messages, see [`crate::goose::frame`]. The
+//! samples of the 9-2LE profile can be decoded with [`Le92Sample`] and the
+//! [`subscriber`] supervises the received samples.
+
+use rasn::{ber, error::strings::PermittedAlphabetError, types::VisibleString};
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
+use time::OffsetDateTime;
+
+use crate::{
+	goose::{
+		frame::{EthernetFrame, FrameError, VlanTag},
+		pcap::{self, PcapError},
+	},
+	iec61850::{data::Iec61850DataError, svcb::SampleMode},
+	sv::ans1::sv::iec61850_9_2::{ASDU, SVpdu, SavPdu},
+};
+
+pub mod ans1;
+pub mod subscriber;
+
+/// The EtherType of the sampled values messages.
+pub const SV_ETHER_TYPE: u16 = 0x88ba;
+
+/// The length of the sample data of the 9-2LE profile.
+pub const LE92_SEQ_DATA_LENGTH: usize = 64;
+
+/// The synchronization of the samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleSynchronization {
+	/// The samples are not synchronized.
+	Unsynchronized,
+	/// The samples are synchronized by a local clock.
+	Local,
+	/// The samples are synchronized by a global clock.
+	Global,
+	/// The samples are synchronized by the time source with the given id.
+	Identified(u8),
+}
+
+impl From<u8> for SampleSynchronization {
+	fn from(value: u8) -> Self {
+		match value {
+			0 => Self::Unsynchronized,
+			1 => Self::Local,
+			2 => Self::Global,
+			id => Self::Identified(id),
+		}
+	}
+}
+
+impl From<SampleSynchronization> for u8 {
+	fn from(value: SampleSynchronization) -> Self {
+		match value {
+			SampleSynchronization::Unsynchronized => 0,
+			SampleSynchronization::Local => 1,
+			SampleSynchronization::Global => 2,
+			SampleSynchronization::Identified(id) => id,
+		}
+	}
+}
+
+/// An ASDU (Application Service Data Unit) of a sampled values message. Each
+/// ASDU carries one sample of the dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvAsdu {
+	/// The id of the sampled value control block.
+	pub sv_id: String,
+	/// The reference of the published dataset.
+	pub dataset: Option<String>,
+	/// The sample counter.
+	pub sample_count: u16,
+	/// The configuration revision of the sampled value control block.
+	pub config_rev: u32,
+	/// The time the sample buffer was last refreshed.
+	pub refresh_time: Option<OffsetDateTime>,
+	/// The synchronization of the sample.
+	pub sample_synchronization: SampleSynchronization,
+	/// The sample rate.
+	pub sample_rate: Option<u16>,
+	/// The encoded values of the dataset, see [`Le92Sample`] for the 9-2LE
+	/// profile.
+	pub seq_data: Vec<u8>,
+	/// The unit of the sample rate.
+	pub sample_mode: Option<SampleMode>,
+	/// The identity of the grandmaster clock the samples are synchronized to.
+	pub grandmaster_identity: Option<[u8; 8]>,
+}
+
+impl TryFrom<ASDU> for SvAsdu {
+	type Error = SvError;
+	fn try_from(value: ASDU) -> Result<Self, Self::Error> {
+		Ok(Self {
+			sv_id: value.sv_id.to_string(),
+			dataset: value.datset.map(|dataset| dataset.to_string()),
+			sample_count: u16::from_be_bytes(to_array(&value.smp_cnt, "sample_count")?),
+			config_rev: u32::from_be_bytes(to_array(&value.conf_rev, "config_rev")?),
+			refresh_time: value.refr_tm.map(TryInto::try_into).transpose().context(InvalidData)?,
+			sample_synchronization: u8::from_be_bytes(to_array(
+				&value.smp_synch,
+				"sample_synchronization",
+			)?)
+			.into(),
+			sample_rate: value
+				.smp_rate
+				.map(|rate| to_array(&rate, "sample_rate").map(u16::from_be_bytes))
+				.transpose()?,
+			seq_data: value.seq_data.to_vec(),
+			sample_mode: value
+				.smp_mod
+				.map(|mode| sample_mode_from_bytes(to_array(&mode, "sample_mode")?))
+				.transpose()?,
+			grandmaster_identity: value
+				.gm_identity
+				.map(|identity| to_array(&identity, "grandmaster_identity"))
+				.transpose()?,
+		})
+	}
+}
+
+impl TryFrom<SvAsdu> for ASDU {
+	type Error = SvError;
+	fn try_from(value: SvAsdu) -> Result<Self, Self::Error> {
+		Ok(Self::new(
+			to_visible_string(&value.sv_id, "sv_id")?,
+			value
+				.dataset
+				.as_deref()
+				.map(|dataset| to_visible_string(dataset, "dataset"))
+				.transpose()?,
+			value.sample_count.to_be_bytes().to_vec().into(),
+			value.config_rev.to_be_bytes().to_vec().into(),
+			value.refresh_time.map(TryInto::try_into).transpose().context(InvalidData)?,
+			vec![value.sample_synchronization.into()].into(),
+			value.sample_rate.map(|rate| rate.to_be_bytes().to_vec().into()),
+			value.seq_data.into(),
+			value.sample_mode.map(|mode| sample_mode_to_bytes(mode).to_vec().into()),
+			value.grandmaster_identity.map(|identity| identity.to_vec().into()),
+		))
+	}
+}
+
+/// A sampled values message with the ethernet header it was sent with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvFrame {
+	/// The destination MAC address.
+	pub destination: [u8; 6],
+	/// The source MAC address.
+	pub source: [u8; 6],
+	/// The VLAN tag, if present.
+	pub vlan: Option<VlanTag>,
+	/// The application id.
+	pub app_id: u16,
+	/// Whether the message is a simulated one.
+	pub simulation: bool,
+	/// The ASDUs of the message.
+	pub asdus: Vec<SvAsdu>,
+}
+
+impl SvFrame {
+	/// Decode a sampled values frame from the bytes of an ethernet frame.
+	pub fn decode(bytes: &[u8]) -> Result<Self, SvError> {
+		EthernetFrame::decode(bytes).context(Frame)?.try_into()
+	}
+
+	/// Encode the sampled values frame into the bytes of an ethernet frame.
+	pub fn encode(self) -> Result<Vec<u8>, SvError> {
+		EthernetFrame::try_from(self)?.encode().context(Frame)
+	}
+}
+
+impl TryFrom<EthernetFrame> for SvFrame {
+	type Error = SvError;
+	fn try_from(value: EthernetFrame) -> Result<Self, Self::Error> {
+		ensure!(
+			value.ether_type == SV_ETHER_TYPE,
+			NotSampledValues { ether_type: value.ether_type }
+		);
+		Ok(Self {
+			destination: value.destination,
+			source: value.source,
+			vlan: value.vlan,
+			app_id: value.app_id,
+			simulation: value.simulation(),
+			asdus: decode_pdu(&value.apdu)?,
+		})
+	}
+}
+
+impl TryFrom<SvFrame> for EthernetFrame {
+	type Error = SvError;
+	fn try_from(value: SvFrame) -> Result<Self, Self::Error> {
+		let mut frame = Self {
+			destination: value.destination,
+			source: value.source,
+			vlan: value.vlan,
+			ether_type: SV_ETHER_TYPE,
+			app_id: value.app_id,
+			reserved1: 0,
+			reserved2: 0,
+			apdu: encode_pdu(value.asdus)?,
+		};
+		frame.set_simulation(value.simulation);
+		Ok(frame)
+	}
+}
+
+/// A measured value of the 9-2LE profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+	/// The scaled value: 1 mA for the currents and 10 mV for the voltages.
+	pub value: i32,
+	/// The quality of the value.
+	pub quality: u32,
+}
+
+impl Measurement {
+	/// Whether the validity of the quality is good.
+	#[must_use]
+	pub const fn is_good(&self) -> bool {
+		self.quality & 0b11 == 0
+	}
+}
+
+/// A sample of the 9-2LE profile: the currents and voltages of the three
+/// phases and the neutral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Le92Sample {
+	/// The currents of the phases A, B, C and the neutral.
+	pub currents: [Measurement; 4],
+	/// The voltages of the phases A, B, C and the neutral.
+	pub voltages: [Measurement; 4],
+}
+
+impl Le92Sample {
+	/// Decode a sample from the sample data of an ASDU.
+	pub fn decode(seq_data: &[u8]) -> Result<Self, SvError> {
+		let values: &[u8; LE92_SEQ_DATA_LENGTH] =
+			seq_data.try_into().ok().context(InvalidSeqData { length: seq_data.len() })?;
+		let measurement = |index: usize| {
+			let (value, quality) = values[index * 8..].split_at(4);
+			Measurement {
+				value: i32::from_be_bytes([value[0], value[1], value[2], value[3]]),
+				quality: u32::from_be_bytes([quality[0], quality[1], quality[2], quality[3]]),
+			}
+		};
+		Ok(Self {
+			currents: [0, 1, 2, 3].map(measurement),
+			voltages: [4, 5, 6, 7].map(measurement),
+		})
+	}
+
+	/// Encode the sample into the sample data of an ASDU.
+	#[must_use]
+	pub fn encode(&self) -> Vec<u8> {
+		self.currents
+			.iter()
+			.chain(&self.voltages)
+			.flat_map(|measurement| {
+				[measurement.value.to_be_bytes(), measurement.quality.to_be_bytes()]
+			})
+			.flatten()
+			.collect()
+	}
+
+	/// The currents in amperes.
+	#[must_use]
+	pub fn currents_in_amperes(&self) -> [f64; 4] {
+		self.currents.map(|current| f64::from(current.value) / 1000.0)
+	}
+
+	/// The voltages in volts.
+	#[must_use]
+	pub fn voltages_in_volts(&self) -> [f64; 4] {
+		self.voltages.map(|voltage| f64::from(voltage.value) / 100.0)
+	}
+}
+
+/// Read the sampled values frames of a pcap capture. The frames that are not
+/// sampled values messages are skipped.
+pub fn read_pcap(bytes: &[u8]) -> Result<Vec<SvFrame>, SvError> {
+	pcap::read_pcap_ether_type(bytes, SV_ETHER_TYPE)
+		.context(Pcap)?
+		.into_iter()
+		.map(|packet| EthernetFrame::decode(&packet.data).context(Frame)?.try_into())
+		.collect()
+}
+
+/// Decode the ASDUs of a sampled values PDU.
+pub fn decode_pdu(bytes: &[u8]) -> Result<Vec<SvAsdu>, SvError> {
+	let SVpdu::savPdu(pdu) = ber::decode(bytes).context(Decode)?;
+	ensure!(
+		usize::from(pdu.no_asdu) == pdu.asdu.len(),
+		AsduCountMismatch { expected: pdu.no_asdu, received: pdu.asdu.len() }
+	);
+	pdu.asdu.into_iter().map(TryInto::try_into).collect()
+}
+
+/// Encode ASDUs into the bytes of a sampled values PDU.
+pub fn encode_pdu(asdus: Vec<SvAsdu>) -> Result<Vec<u8>, SvError> {
+	let no_asdu = u16::try_from(asdus.len()).ok().context(TooManyAsdus { count: asdus.len() })?;
+	let pdu = SVpdu::savPdu(SavPdu::new(
+		no_asdu,
+		asdus.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+	));
+	ber::encode(&pdu).context(Encode)
+}
+
+/// Convert a fixed size octet string field of the ASDU.
+fn to_array<const N: usize>(value: &[u8], field: &str) -> Result<[u8; N], SvError> {
+	value.try_into().ok().context(InvalidLength { field, length: value.len() })
+}
+
+/// Convert a string field of the ASDU.
+fn to_visible_string(value: &str, field: &str) -> Result<VisibleString, SvError> {
+	VisibleString::from_iso646_bytes(value.as_bytes()).context(InvalidString { field })
+}
+
+/// Convert the sample mode field of the ASDU.
+fn sample_mode_from_bytes(value: [u8; 2]) -> Result<SampleMode, SvError> {
+	match u16::from_be_bytes(value) {
+		0 => Ok(SampleMode::SamplesPerPeriod),
+		1 => Ok(SampleMode::SamplesPerSecond),
+		2 => Ok(SampleMode::SecondsPerSample),
+		value => InvalidSampleMode { value }.fail(),
+	}
+}
+
+/// Convert a sample mode into the sample mode field of the ASDU.
+const fn sample_mode_to_bytes(value: SampleMode) -> [u8; 2] {
+	match value {
+		SampleMode::SamplesPerPeriod => 0_u16,
+		SampleMode::SamplesPerSecond => 1,
+		SampleMode::SecondsPerSample => 2,
+	}
+	.to_be_bytes()
+}
+
+/// The error type for the sampled values messages.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum SvError {
+	#[snafu(display("Invalid ethernet frame"))]
+	Frame { source: FrameError },
+	#[snafu(display("Invalid pcap capture"))]
+	Pcap { source: PcapError },
+	#[snafu(display("Not a sampled values message. EtherType: {:#06x}", ether_type))]
+	NotSampledValues { ether_type: u16 },
+	#[snafu(display("Error decoding the sampled values PDU"))]
+	Decode { source: ber::de::DecodeError },
+	#[snafu(display("Error encoding the sampled values PDU"))]
+	Encode { source: ber::enc::EncodeError },
+	#[snafu(display("Number of ASDUs mismatch. Expected: {}, received: {}", expected, received))]
+	AsduCountMismatch { expected: u16, received: usize },
+	#[snafu(display("Too many ASDUs: {}", count))]
+	TooManyAsdus { count: usize },
+	#[snafu(display("Invalid length for field: {}. Length: {}", field, length))]
+	InvalidLength { field: String, length: usize },
+	#[snafu(display("Invalid string for field: {}", field))]
+	InvalidString { field: String, source: PermittedAlphabetError },
+	#[snafu(display("Invalid sample mode: {}", value))]
+	InvalidSampleMode { value: u16 },
+	#[snafu(display("Invalid refresh time"))]
+	InvalidData { source: Iec61850DataError },
+	#[snafu(display("Invalid 9-2LE sample data length: {}", length))]
+	InvalidSeqData { length: usize },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use super::{pcap::PcapPacket, *};
+
+	/// A 9-2LE sampled values frame with one ASDU. The phase A current is 1 A
+	/// and the phase A voltage is 100 V with an invalid quality.
+	const SV_FRAME: [u8; 116] = [
+		0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01, 0x00, 0x1a, 0xb6, 0x03, 0x2f, 0x1c, 0x88, 0xba, 0x40,
+		0x00, 0x00, 0x66, 0x00, 0x00, 0x00, 0x00, 0x60, 0x5c, 0x80, 0x01, 0x01, 0xa2, 0x57, 0x30,
+		0x55, 0x80, 0x04, 0x4d, 0x55, 0x30, 0x31, 0x82, 0x02, 0x0f, 0x9f, 0x83, 0x04, 0x00, 0x00,
+		0x00, 0x01, 0x85, 0x01, 0x02, 0x87, 0x40, 0x00, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x00, 0x00,
+		0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+		0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x27, 0x10, 0x00, 0x00,
+		0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+		0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	];
+
+	#[test]
+	fn test_decode_sv_frame() {
+		let frame = SvFrame::decode(&SV_FRAME).unwrap();
+		assert_eq!(frame.destination, [0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01]);
+		assert_eq!(frame.vlan, None);
+		assert_eq!(frame.app_id, 0x4000);
+		assert!(!frame.simulation);
+		assert_eq!(frame.asdus.len(), 1);
+
+		let asdu = &frame.asdus[0];
+		assert_eq!(asdu.sv_id, "MU01");
+		assert_eq!(asdu.dataset, None);
+		assert_eq!(asdu.sample_count, 3999);
+		assert_eq!(asdu.config_rev, 1);
+		assert_eq!(asdu.sample_synchronization, SampleSynchronization::Global);
+		assert_eq!(asdu.sample_rate, None);
+
+		let sample = Le92Sample::decode(&asdu.seq_data).unwrap();
+		assert_eq!(sample.currents.map(|current| current.value), [1000, 0, 0, 0]);
+		assert_eq!(sample.voltages.map(|voltage| voltage.value), [10000, 0, 0, 0]);
+		assert!((sample.currents_in_amperes()[0] - 1.0).abs() < f64::EPSILON);
+		assert!((sample.voltages_in_volts()[0] - 100.0).abs() < f64::EPSILON);
+		assert!(sample.currents[0].is_good());
+		assert!(!sample.voltages[0].is_good());
+		assert_eq!(sample.encode(), asdu.seq_data);
+		assert!(matches!(
+			Le92Sample::decode(&asdu.seq_data[1..]),
+			Err(SvError::InvalidSeqData { length: 63 })
+		));
+
+		// The encoded frame must be the same as the original one.
+		assert_eq!(frame.clone().encode().unwrap(), SV_FRAME);
+	}
+
+	#[test]
+	fn test_read_sv_pcap() {
+		let mut asdu = SvFrame::decode(&SV_FRAME).unwrap().asdus.remove(0);
+		asdu.dataset = Some("MU01LD0/LLN0$PhsMeas1".to_owned());
+		asdu.refresh_time = Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap());
+		asdu.sample_rate = Some(80);
+		asdu.sample_mode = Some(SampleMode::SamplesPerPeriod);
+		let frame = SvFrame {
+			destination: [0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01],
+			source: [0; 6],
+			vlan: Some(VlanTag { priority: 4, drop_eligible: false, vlan_id: 10 }),
+			app_id: 0x4000,
+			simulation: true,
+			asdus: vec![asdu.clone(), SvAsdu { sample_count: 0, ..asdu }],
+		};
+		let mut other = SV_FRAME.to_vec();
+		// GOOSE EtherType
+		other[13] = 0xb8;
+		// An IPv4 header, whose bytes are not a valid frame length.
+		let mut ipv4 = SV_FRAME[..12].to_vec();
+		ipv4.extend_from_slice(&[0x08, 0x00, 0x45, 0x00, 0x05, 0xdc, 0x00, 0x00, 0x40, 0x00]);
+		let packets = [frame.clone().encode().unwrap(), ipv4, other]
+			.map(|data| PcapPacket { timestamp: OffsetDateTime::UNIX_EPOCH, data });
+		assert_eq!(read_pcap(&pcap::write_pcap(&packets)).unwrap(), [frame]);
+	}
+}
@@ -0,0 +1,8 @@
+//! ANS.1 module for the IEC61850 sampled values part of the protocol.
+//! The rust module is generated using the asn1-compiler tool.
+//! For running the asn1-compiler tool, run the following command:
+//! `cargo run --bin asn1-compiler`
+
+#![allow(missing_docs)]
+
+pub mod sv;
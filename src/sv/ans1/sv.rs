@@ -0,0 +1,107 @@
+// This file was generated by the asn1-compiler tool.
+// *DO NOT EDIT THIS FILE MANUALLY*.
+// The asn1-compiler tool is used to compile the ASN.1 files into Rust modules
+// using the rasn-compiler crate.
+// The asn1-compiler tool is located in the src/mms/ans1/asn1-compiler.rs file.
+// For running the asn1-compiler tool, run the following command:
+// `cargo run --bin asn1-compiler`
+
+use crate::mms::ans1::mms::asn1;
+
+#[rustfmt::skip]
+#[allow(
+	missing_docs,
+	clippy::must_use_candidate,
+	clippy::missing_const_for_fn,
+	clippy::missing_docs_in_private_items,
+	clippy::unseparated_literal_suffix
+)]
+#[allow(
+	non_camel_case_types,
+	non_snake_case,
+	non_upper_case_globals,
+	unused,
+	clippy::too_many_arguments
+)]
+pub mod iec61850_9_2 {
+	extern crate alloc;
+	use core::borrow::Borrow;
+	use std::sync::LazyLock;
+
+	use rasn::prelude::*;
+
+	use super::asn1::UtcTime;
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct ASDU {
+		#[rasn(tag(context, 0), identifier = "svID")]
+		pub sv_id: VisibleString,
+		#[rasn(tag(context, 1))]
+		pub datset: Option<VisibleString>,
+		#[rasn(size("2"), tag(context, 2), identifier = "smpCnt")]
+		pub smp_cnt: OctetString,
+		#[rasn(size("4"), tag(context, 3), identifier = "confRev")]
+		pub conf_rev: OctetString,
+		#[rasn(tag(context, 4), identifier = "refrTm")]
+		pub refr_tm: Option<UtcTime>,
+		#[rasn(size("1"), tag(context, 5), identifier = "smpSynch")]
+		pub smp_synch: OctetString,
+		#[rasn(size("2"), tag(context, 6), identifier = "smpRate")]
+		pub smp_rate: Option<OctetString>,
+		#[rasn(tag(context, 7), identifier = "seqData")]
+		pub seq_data: OctetString,
+		#[rasn(size("2"), tag(context, 8), identifier = "smpMod")]
+		pub smp_mod: Option<OctetString>,
+		#[rasn(size("8"), tag(context, 9), identifier = "gmIdentity")]
+		pub gm_identity: Option<OctetString>,
+	}
+	impl ASDU {
+		pub fn new(
+			sv_id: VisibleString,
+			datset: Option<VisibleString>,
+			smp_cnt: OctetString,
+			conf_rev: OctetString,
+			refr_tm: Option<UtcTime>,
+			smp_synch: OctetString,
+			smp_rate: Option<OctetString>,
+			seq_data: OctetString,
+			smp_mod: Option<OctetString>,
+			gm_identity: Option<OctetString>,
+		) -> Self {
+			Self {
+				sv_id,
+				datset,
+				smp_cnt,
+				conf_rev,
+				refr_tm,
+				smp_synch,
+				smp_rate,
+				seq_data,
+				smp_mod,
+				gm_identity,
+			}
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	#[rasn(choice)]
+	pub enum SVpdu {
+		#[rasn(tag(application, 0))]
+		savPdu(SavPdu),
+	}
+	impl From<SavPdu> for SVpdu {
+		fn from(value: SavPdu) -> Self {
+			Self::savPdu(value)
+		}
+	}
+	#[derive(AsnType, Debug, Clone, Decode, Encode, PartialEq, Eq, Hash)]
+	pub struct SavPdu {
+		#[rasn(value("1..=65535"), tag(context, 0), identifier = "noASDU")]
+		pub no_asdu: u16,
+		#[rasn(tag(context, 2))]
+		pub asdu: SequenceOf<ASDU>,
+	}
+	impl SavPdu {
+		pub fn new(no_asdu: u16, asdu: SequenceOf<ASDU>) -> Self {
+			Self { no_asdu, asdu }
+		}
+	}
+}
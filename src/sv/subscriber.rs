@@ -0,0 +1,422 @@
+//! Sampled values subscriber.
+//!
+//! The subscriber receives ethernet frames from a [`FrameSource`], delivers
+//! the samples of every subscribed sampled value control block and supervises
+//! the stream: lost, duplicated and out of order samples, changes of the
+//! synchronization and the configuration revision.
+
+use std::io;
+
+use async_trait::async_trait;
+use snafu::{ResultExt as _, Snafu};
+use tracing::instrument;
+
+use crate::{
+	goose::{
+		frame::EthernetFrame,
+		subscriber::{FrameSource, Sequence},
+	},
+	iec61850::svcb::SampleMode,
+	sv::{SV_ETHER_TYPE, SampleSynchronization, SvAsdu, SvFrame},
+};
+
+/// A trait for reacting to the sampled values events.
+#[async_trait]
+#[allow(missing_docs)]
+pub trait SvCallback {
+	async fn on_event(&self, event: SvEvent);
+}
+
+/// An event of a sampled values subscription.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvEvent {
+	/// A sample was received.
+	Sample(SvAsdu),
+	/// Samples were lost between the last received sample and this one.
+	SampleLoss {
+		/// The id of the sampled value control block.
+		sv_id: String,
+		/// The expected sample counter.
+		expected: u16,
+		/// The received sample counter.
+		received: u16,
+	},
+	/// The sample was already received. It is discarded.
+	Duplicate {
+		/// The id of the sampled value control block.
+		sv_id: String,
+		/// The sample counter of the received sample.
+		sample_count: u16,
+	},
+	/// The sample is older than the last received sample. It is discarded.
+	OutOfOrder {
+		/// The id of the sampled value control block.
+		sv_id: String,
+		/// The sample counter of the received sample.
+		sample_count: u16,
+	},
+	/// The synchronization of the samples changed.
+	SynchronizationChange {
+		/// The id of the sampled value control block.
+		sv_id: String,
+		/// The synchronization of the last received sample.
+		previous: SampleSynchronization,
+		/// The synchronization of the received sample.
+		current: SampleSynchronization,
+	},
+	/// The configuration revision of the sample is not the expected one. The
+	/// sample is discarded.
+	ConfigRevisionMismatch {
+		/// The id of the sampled value control block.
+		sv_id: String,
+		/// The expected configuration revision.
+		expected: u32,
+		/// The received configuration revision.
+		received: u32,
+	},
+}
+
+/// A subscription to a sampled value control block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvSubscription {
+	/// The id of the sampled value control block.
+	pub sv_id: String,
+	/// The expected application id. Any application id is accepted if `None`.
+	pub app_id: Option<u16>,
+	/// The expected destination MAC address. Any address is accepted if
+	/// `None`.
+	pub destination: Option<[u8; 6]>,
+	/// The expected configuration revision. Any revision is accepted if
+	/// `None`.
+	pub config_rev: Option<u32>,
+	/// The number of samples per second, at which the sample counter rolls
+	/// over to 0. If `None`, the `smpRate` of the samples is used when they
+	/// give it in samples per second.
+	pub samples_per_second: Option<u16>,
+}
+
+impl SvSubscription {
+	/// Create a subscription to a sampled value control block that accepts any
+	/// application id, destination and configuration revision.
+	#[must_use]
+	pub fn new(sv_id: impl Into<String>) -> Self {
+		Self {
+			sv_id: sv_id.into(),
+			app_id: None,
+			destination: None,
+			config_rev: None,
+			samples_per_second: None,
+		}
+	}
+
+	/// The value at which the sample counter of an ASDU rolls over, if
+	/// known.
+	fn counter_modulus(&self, asdu: &SvAsdu) -> Option<u32> {
+		let sample_rate = match (asdu.sample_rate, asdu.sample_mode) {
+			(Some(rate), Some(SampleMode::SamplesPerSecond)) => Some(rate),
+			_ => None,
+		};
+		self.samples_per_second.or(sample_rate).filter(|rate| *rate > 0).map(u32::from)
+	}
+
+	/// Whether the ASDU of the frame belongs to the subscription.
+	fn matches(&self, frame: &SvFrame, asdu: &SvAsdu) -> bool {
+		self.sv_id == asdu.sv_id
+			&& self.app_id.is_none_or(|app_id| app_id == frame.app_id)
+			&& self.destination.is_none_or(|destination| destination == frame.destination)
+	}
+}
+
+/// The state of a subscription.
+#[derive(Debug)]
+struct SubscriptionState {
+	/// The subscription.
+	subscription: SvSubscription,
+	/// The sample counter and synchronization of the last accepted sample.
+	last: Option<(u16, SampleSynchronization)>,
+}
+
+impl SubscriptionState {
+	/// Supervise a sample of the subscription and return its events.
+	fn handle_sample(&mut self, asdu: SvAsdu) -> Vec<SvEvent> {
+		let sv_id = asdu.sv_id.clone();
+		if let Some(expected) = self.subscription.config_rev
+			&& expected != asdu.config_rev
+		{
+			return vec![SvEvent::ConfigRevisionMismatch {
+				sv_id,
+				expected,
+				received: asdu.config_rev,
+			}];
+		}
+
+		let (count, synchronization) = (asdu.sample_count, asdu.sample_synchronization);
+		let mut events = Vec::new();
+		if let Some((last_count, last_synchronization)) = self.last {
+			let modulus = self.subscription.counter_modulus(&asdu);
+			match compare(count, last_count, modulus) {
+				Sequence::Next => {}
+				Sequence::Gap => {
+					let expected = match modulus {
+						Some(modulus) => {
+							u16::try_from((u32::from(last_count) + 1) % modulus).unwrap_or_default()
+						}
+						None => last_count.wrapping_add(1),
+					};
+					events.push(SvEvent::SampleLoss {
+						sv_id: sv_id.clone(),
+						expected,
+						received: count,
+					});
+				}
+				Sequence::Duplicate => {
+					return vec![SvEvent::Duplicate { sv_id, sample_count: count }];
+				}
+				Sequence::Older => {
+					return vec![SvEvent::OutOfOrder { sv_id, sample_count: count }];
+				}
+			}
+			if synchronization != last_synchronization {
+				events.push(SvEvent::SynchronizationChange {
+					sv_id,
+					previous: last_synchronization,
+					current: synchronization,
+				});
+			}
+		}
+
+		self.last = Some((count, synchronization));
+		events.push(SvEvent::Sample(asdu));
+		events
+	}
+}
+
+/// Compare a sample counter with the last accepted one. The counter rolls
+/// over to 0 at the modulus. Without the modulus, the counter may roll over
+/// at any value: a 0 is the next sample and an older sample can't be told
+/// from a sample after a rollover, so it is a loss.
+fn compare(received: u16, last: u16, modulus: Option<u32>) -> Sequence {
+	let (received, last) = (u32::from(received), u32::from(last));
+	let Some(modulus) = modulus.filter(|modulus| received < *modulus && last < *modulus) else {
+		return match received {
+			_ if received == last => Sequence::Duplicate,
+			0 => Sequence::Next,
+			_ if received == last + 1 => Sequence::Next,
+			_ => Sequence::Gap,
+		};
+	};
+	match (received + modulus - last) % modulus {
+		0 => Sequence::Duplicate,
+		1 => Sequence::Next,
+		difference if difference <= modulus / 2 => Sequence::Gap,
+		_ => Sequence::Older,
+	}
+}
+
+/// A sampled values subscriber.
+pub struct SvSubscriber {
+	/// The state of the subscriptions.
+	subscriptions: Vec<SubscriptionState>,
+	/// The callback for the events.
+	callback: Box<dyn SvCallback + Send + Sync>,
+}
+
+impl std::fmt::Debug for SvSubscriber {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("SvSubscriber").field("subscriptions", &self.subscriptions).finish()
+	}
+}
+
+impl SvSubscriber {
+	/// Create a new sampled values subscriber.
+	#[must_use]
+	pub fn new(callback: Box<dyn SvCallback + Send + Sync>) -> Self {
+		Self { subscriptions: Vec::new(), callback }
+	}
+
+	/// Subscribe to a sampled value control block.
+	pub fn subscribe(&mut self, subscription: SvSubscription) {
+		self.subscriptions.push(SubscriptionState { subscription, last: None });
+	}
+
+	/// Receive frames from the source until it is exhausted.
+	#[instrument(skip_all)]
+	pub async fn run(&mut self, mut source: impl FrameSource) -> Result<(), SvSubscriberError> {
+		while let Some(frame) = source.recv_frame().await.context(Source)? {
+			self.handle_frame(&frame).await;
+		}
+		Ok(())
+	}
+
+	/// Handle a received ethernet frame. Frames that are not sampled values
+	/// messages and ASDUs that don't belong to any subscription are ignored.
+	pub async fn handle_frame(&mut self, bytes: &[u8]) {
+		let frame = match EthernetFrame::decode(bytes) {
+			Ok(frame) if frame.ether_type == SV_ETHER_TYPE => frame,
+			Ok(_) => return,
+			Err(e) => {
+				tracing::debug!("Invalid ethernet frame: {e}");
+				return;
+			}
+		};
+		let mut frame = match SvFrame::try_from(frame) {
+			Ok(frame) => frame,
+			Err(e) => {
+				tracing::warn!("Invalid sampled values message: {e}");
+				return;
+			}
+		};
+
+		for asdu in std::mem::take(&mut frame.asdus) {
+			let Some(state) = self
+				.subscriptions
+				.iter_mut()
+				.find(|state| state.subscription.matches(&frame, &asdu))
+			else {
+				continue;
+			};
+			for event in state.handle_sample(asdu) {
+				self.callback.on_event(event).await;
+			}
+		}
+	}
+}
+
+/// The error type for the sampled values subscriber.
+#[allow(missing_docs)]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum SvSubscriberError {
+	#[snafu(display("Error receiving a frame"))]
+	Source { source: io::Error },
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use time::OffsetDateTime;
+	use tokio::sync::Mutex;
+
+	use super::*;
+	use crate::goose::{
+		pcap::{self, PcapPacket},
+		subscriber::PcapFrameSource,
+	};
+
+	/// A callback that stores the received events.
+	struct TestCallback(Arc<Mutex<Vec<SvEvent>>>);
+
+	#[async_trait]
+	impl SvCallback for TestCallback {
+		async fn on_event(&self, event: SvEvent) {
+			self.0.lock().await.push(event);
+		}
+	}
+
+	fn asdu(sample_count: u16, sample_synchronization: SampleSynchronization) -> SvAsdu {
+		SvAsdu {
+			sv_id: "MU01".to_owned(),
+			dataset: None,
+			sample_count,
+			config_rev: 1,
+			refresh_time: None,
+			sample_synchronization,
+			sample_rate: None,
+			seq_data: vec![0; 64],
+			sample_mode: None,
+			grandmaster_identity: None,
+		}
+	}
+
+	fn frame(asdus: Vec<SvAsdu>) -> Vec<u8> {
+		SvFrame {
+			destination: [0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01],
+			source: [0; 6],
+			vlan: None,
+			app_id: 0x4000,
+			simulation: false,
+			asdus,
+		}
+		.encode()
+		.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_subscriber_supervision() {
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let mut subscriber = SvSubscriber::new(Box::new(TestCallback(events.clone())));
+		subscriber.subscribe(SvSubscription { config_rev: Some(1), ..SvSubscription::new("MU01") });
+
+		let global = SampleSynchronization::Global;
+		let local = SampleSynchronization::Local;
+		let mut other = asdu(0, global);
+		other.sv_id = "MU02".to_owned();
+		let mut wrong_revision = asdu(4002, local);
+		wrong_revision.config_rev = 2;
+		let frames = [
+			frame(vec![asdu(3998, global), asdu(3999, global)]),
+			frame(vec![other, asdu(0, global)]),
+			frame(vec![asdu(1, global), asdu(4000, local), wrong_revision, asdu(4001, local)]),
+		];
+		let packets = frames.map(|data| PcapPacket { timestamp: OffsetDateTime::UNIX_EPOCH, data });
+		subscriber.run(PcapFrameSource::new(&pcap::write_pcap(&packets)).unwrap()).await.unwrap();
+
+		let sv_id = "MU01".to_owned();
+		assert_eq!(
+			*events.lock().await,
+			[
+				SvEvent::Sample(asdu(3998, global)),
+				SvEvent::Sample(asdu(3999, global)),
+				SvEvent::Sample(asdu(0, global)),
+				SvEvent::Sample(asdu(1, global)),
+				SvEvent::SampleLoss { sv_id: sv_id.clone(), expected: 2, received: 4000 },
+				SvEvent::SynchronizationChange {
+					sv_id: sv_id.clone(),
+					previous: global,
+					current: local
+				},
+				SvEvent::Sample(asdu(4000, local)),
+				SvEvent::ConfigRevisionMismatch { sv_id, expected: 1, received: 2 },
+				SvEvent::Sample(asdu(4001, local)),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_sample_counter() {
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let mut subscriber = SvSubscriber::new(Box::new(TestCallback(events.clone())));
+		subscriber.subscribe(SvSubscription::new("MU01"));
+
+		// The sample rate gives the rollover of the counter.
+		let global = SampleSynchronization::Global;
+		let sample = |sample_count| SvAsdu {
+			sample_rate: Some(4000),
+			sample_mode: Some(SampleMode::SamplesPerSecond),
+			..asdu(sample_count, global)
+		};
+		let frames = [
+			frame(vec![sample(3998), sample(0), sample(0)]),
+			frame(vec![sample(3999), sample(1), sample(2000)]),
+		];
+		let packets = frames.map(|data| PcapPacket { timestamp: OffsetDateTime::UNIX_EPOCH, data });
+		subscriber.run(PcapFrameSource::new(&pcap::write_pcap(&packets)).unwrap()).await.unwrap();
+
+		let sv_id = "MU01".to_owned();
+		assert_eq!(
+			*events.lock().await,
+			[
+				SvEvent::Sample(sample(3998)),
+				SvEvent::SampleLoss { sv_id: sv_id.clone(), expected: 3999, received: 0 },
+				SvEvent::Sample(sample(0)),
+				SvEvent::Duplicate { sv_id: sv_id.clone(), sample_count: 0 },
+				SvEvent::OutOfOrder { sv_id: sv_id.clone(), sample_count: 3999 },
+				SvEvent::Sample(sample(1)),
+				SvEvent::SampleLoss { sv_id, expected: 2, received: 2000 },
+				SvEvent::Sample(sample(2000)),
+			]
+		);
+	}
+}